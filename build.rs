@@ -0,0 +1,4 @@
+fn main() {
+    #[cfg(feature = "nodejs")]
+    napi_build::setup();
+}
@@ -0,0 +1,46 @@
+/* Generated with cbindgen from src/ffi.rs: cbindgen --config cbindgen.toml --output include/lindera_neologd.h */
+
+#ifndef LINDERA_NEOLOGD_H
+#define LINDERA_NEOLOGD_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define LINDERA_NEOLOGD_OK 0
+
+#define LINDERA_NEOLOGD_INVALID_ARGUMENT 1
+
+#define LINDERA_NEOLOGD_INVALID_OPTIONS 2
+
+#define LINDERA_NEOLOGD_BUILD_ERROR 3
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Builds the dictionary from `input_dir` into `output_dir`.
+ *
+ * `options_json` is a JSON object with the fields of `BuilderOptions`, or NULL for the
+ * defaults. Returns `LINDERA_NEOLOGD_OK` on success; on failure, the error message can be
+ * retrieved with `lindera_neologd_last_error`.
+ *
+ * # Safety
+ *
+ * All arguments must be NULL or point to NUL-terminated strings.
+ */
+int lindera_neologd_build(const char *input_dir, const char *output_dir, const char *options_json);
+
+/**
+ * Returns the message of the last error that occurred on the calling thread, or NULL.
+ * The returned string is owned by the library and valid until the next call on this thread.
+ */
+const char *lindera_neologd_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* LINDERA_NEOLOGD_H */
@@ -0,0 +1,146 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+use lindera_core::dictionary_builder::DictionaryBuilder;
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::IpadicNeologdBuilder;
+
+/// Source files that must exist before a build starts.
+const REQUIRED_FILES: [&str; 3] = ["char.def", "unk.def", "matrix.def"];
+
+/// Runs `f` with the builder on the blocking thread pool of the runtime.
+async fn run_blocking<T, F>(builder: &Arc<IpadicNeologdBuilder>, f: F) -> LinderaResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&IpadicNeologdBuilder) -> LinderaResult<T> + Send + 'static,
+{
+    let builder = Arc::clone(builder);
+    tokio::task::spawn_blocking(move || f(&builder))
+        .await
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?
+}
+
+impl IpadicNeologdBuilder {
+    /// Builds the dictionary like `build_dictionary` inside a tokio runtime. The source
+    /// files are checked with async file I/O, and the locking of the output directory and
+    /// each build phase run on the blocking thread pool, so the runtime is never blocked.
+    pub async fn build_dictionary_async(
+        self: Arc<Self>,
+        input_dir: PathBuf,
+        output_dir: PathBuf,
+    ) -> LinderaResult<()> {
+        let start = Instant::now();
+        for name in REQUIRED_FILES {
+            tokio::fs::metadata(input_dir.join(name))
+                .await
+                .map_err(|err| {
+                    LinderaErrorKind::Io.with_error(anyhow::anyhow!("{}: {}", name, err))
+                })?;
+        }
+        let output = output_dir.clone();
+        let _locks =
+            run_blocking(&self, move |builder| builder.prepare_outputs(&[&output])).await?;
+
+        let (input, output) = (input_dir.clone(), output_dir.clone());
+        let chardef = run_blocking(&self, move |builder| {
+            builder.open_build_log(&output)?;
+            builder.measure_phase("chardef", &builder.char_def_files(&input), &output, || {
+                builder.build_chardef(&input, &output)
+            })
+        })
+        .await?;
+
+        let (input, output) = (input_dir.clone(), output_dir.clone());
+        run_blocking(&self, move |builder| {
+            builder.measure_phase("unk", &[input.join("unk.def")], &output, || {
+                builder.build_unk(&input, &chardef, &output)
+            })
+        })
+        .await?;
+
+        let (input, output) = (input_dir.clone(), output_dir.clone());
+        let seed_dates = run_blocking(&self, move |builder| {
+            let csv_files: Vec<PathBuf> = builder
+                .csv_sources(&input)?
+                .into_iter()
+                .map(|(_, filename)| filename)
+                .collect();
+            let seed_dates = builder.check_seed_dates(&csv_files)?;
+            builder.measure_phase("dict", &csv_files, &output, || {
+                builder.build_dict(&input, &output)
+            })?;
+            builder.report_entry_stats();
+            Ok(seed_dates)
+        })
+        .await?;
+
+        let (input, output) = (input_dir.clone(), output_dir.clone());
+        run_blocking(&self, move |builder| {
+            builder.measure_phase("matrix", &[input.join("matrix.def")], &output, || {
+                builder.build_cost_matrix(&input, &output)
+            })
+        })
+        .await?;
+
+        run_blocking(&self, move |builder| {
+            builder.finish_build(&input_dir, &[&output_dir], seed_dates, start)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::Arc;
+
+    use lindera_core::dictionary_builder::DictionaryBuilder;
+
+    use crate::fixtures::write_fixture;
+    use crate::layout::DictionaryLayout;
+    use crate::IpadicNeologdBuilder;
+
+    #[test]
+    fn builds_the_same_dictionary_as_the_blocking_build() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-async-build-{}",
+            std::process::id()
+        ));
+        let input_dir = base_dir.join("input");
+        let (output_dir, async_output_dir) = (base_dir.join("output"), base_dir.join("async"));
+        write_fixture(&input_dir).unwrap();
+        IpadicNeologdBuilder::new()
+            .build_dictionary(&input_dir, &output_dir)
+            .unwrap();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let builder = Arc::new(IpadicNeologdBuilder::new());
+        runtime
+            .block_on(
+                Arc::clone(&builder)
+                    .build_dictionary_async(input_dir.clone(), async_output_dir.clone()),
+            )
+            .unwrap();
+        for name in DictionaryLayout::default().artifact_names() {
+            assert_eq!(
+                fs::read(output_dir.join(name)).unwrap(),
+                fs::read(async_output_dir.join(name)).unwrap(),
+                "{}",
+                name
+            );
+        }
+
+        fs::remove_file(input_dir.join("matrix.def")).unwrap();
+        let err = runtime
+            .block_on(builder.build_dictionary_async(input_dir, base_dir.join("missing")))
+            .unwrap_err();
+        assert!(err.to_string().contains("matrix.def"), "{}", err);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
@@ -0,0 +1,207 @@
+use std::fs;
+use std::path::Path;
+
+use yada::DoubleArray;
+
+use lindera_core::error::{LinderaError, LinderaErrorKind};
+use lindera_core::word_entry::WordEntry;
+use lindera_core::LinderaResult;
+
+use crate::error::BuildError;
+use crate::packing::{
+    CompactPacker, IndirectPacker, ValuePacker, ValuePacking, VALUE_TABLE_FILENAME,
+};
+use crate::IpadicNeologdBuilder;
+
+/// A surface of the keyset, as checked by `IpadicNeologdBuilder::audit_lexicon`.
+pub(crate) struct AuditedSurface<'a> {
+    pub(crate) surface: &'a [u8],
+    /// The number of entries of the surface.
+    pub(crate) entries: u32,
+    /// The entries written for the surface, if they are still in memory.
+    pub(crate) word_entries: Option<&'a [WordEntry]>,
+}
+
+fn audit_error(message: String) -> LinderaError {
+    BuildError::Validation(format!("audit failed: {}", message)).into()
+}
+
+fn same_entry(a: &WordEntry, b: &WordEntry) -> bool {
+    a.word_id.0 == b.word_id.0 && a.word_cost == b.word_cost && a.cost_id == b.cost_id
+}
+
+impl IpadicNeologdBuilder {
+    /// Checks `dict.da` and `dict.vals`, as just written to `output_dir`, against the keyset:
+    /// the offsets of the entries of the surfaces are re-derived from their counts in keyset
+    /// order, and each surface must map in the double array to its offset and count, its
+    /// entries must be the ones written for it when they are given, and the entries must
+    /// fill `dict.vals` exactly.
+    pub(crate) fn audit_lexicon<'a, I>(&self, output_dir: &Path, keyset: I) -> LinderaResult<()>
+    where
+        I: IntoIterator<Item = AuditedSurface<'a>>,
+    {
+        let layout = &self.options.layout;
+        let read = |name: &str| -> LinderaResult<Vec<u8>> {
+            fs::read(output_dir.join(name))
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))
+        };
+        self.print("auditing dict.da and dict.vals");
+        let da = DoubleArray::new(read(&layout.dict_da)?);
+        let vals = read(&layout.dict_vals)?;
+        let packer: Box<dyn ValuePacker> = match self.options.value_packing {
+            ValuePacking::Compact => Box::new(CompactPacker),
+            ValuePacking::Indirect => {
+                Box::new(IndirectPacker::from_table(&read(VALUE_TABLE_FILENAME)?)?)
+            }
+        };
+
+        let num_vals = (vals.len() / WordEntry::SERIALIZED_LEN) as u64;
+        let mut surfaces = 0;
+        let mut next_id = 0u64;
+        for audited in keyset {
+            let surface = String::from_utf8_lossy(audited.surface);
+            let value = da.exact_match_search(audited.surface).ok_or_else(|| {
+                audit_error(format!("{} is missing from {}", surface, layout.dict_da))
+            })?;
+            let (first_id, entries) = packer.unpack(value).ok_or_else(|| {
+                audit_error(format!(
+                    "{} maps to the invalid value {} in {}",
+                    surface, value, layout.dict_da
+                ))
+            })?;
+            if u64::from(first_id) != next_id || entries != audited.entries {
+                return Err(audit_error(format!(
+                    "{} maps to entries {}..{} in {}, expected {}..{}",
+                    surface,
+                    first_id,
+                    u64::from(first_id) + u64::from(entries),
+                    layout.dict_da,
+                    next_id,
+                    next_id + u64::from(audited.entries)
+                )));
+            }
+            if let Some(word_entries) = audited.word_entries {
+                for (idx, expected) in word_entries.iter().enumerate() {
+                    let start = (next_id as usize + idx) * WordEntry::SERIALIZED_LEN;
+                    let written = vals
+                        .get(start..start + WordEntry::SERIALIZED_LEN)
+                        .map(|data| WordEntry::deserialize(data, true));
+                    if !written.is_some_and(|written| same_entry(&written, expected)) {
+                        return Err(audit_error(format!(
+                            "entry {} of {} in {} is not the entry written for it",
+                            idx, surface, layout.dict_vals
+                        )));
+                    }
+                }
+            }
+            next_id += u64::from(entries);
+            surfaces += 1;
+        }
+
+        if next_id != num_vals {
+            return Err(audit_error(format!(
+                "the surfaces have {} entries, but {} has {}",
+                next_id, layout.dict_vals, num_vals
+            )));
+        }
+        self.print(&format!(
+            "audited {} surfaces and {} entries",
+            surfaces, num_vals
+        ));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use lindera_core::word_entry::WordEntry;
+
+    use super::AuditedSurface;
+    use crate::fixtures::build_fixture;
+    use crate::options::BuilderOptions;
+    use crate::packing::ValuePacking;
+    use crate::reader::DictionaryReader;
+    use crate::IpadicNeologdBuilder;
+
+    const SURFACES: [&str; 5] = ["いく", "に", "東京", "東京スカイツリー", "行く"];
+
+    fn keyset<'a>(word_entries: &'a [Vec<WordEntry>]) -> Vec<AuditedSurface<'a>> {
+        SURFACES
+            .iter()
+            .zip(word_entries.iter())
+            .map(|(surface, word_entries)| AuditedSurface {
+                surface: surface.as_bytes(),
+                entries: word_entries.len() as u32,
+                word_entries: Some(word_entries),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn audits_the_written_lexicon() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-audit-{}",
+            std::process::id()
+        ));
+        for (idx, value_packing) in vec![ValuePacking::Compact, ValuePacking::Indirect]
+            .into_iter()
+            .enumerate()
+        {
+            let output_dir = base_dir.join(format!("output-{}", idx));
+            let options = BuilderOptions {
+                value_packing,
+                paranoid: true,
+                ..BuilderOptions::default()
+            };
+            build_fixture(&base_dir.join("input"), &output_dir, options.clone()).unwrap();
+            let builder = IpadicNeologdBuilder::with_options(options);
+
+            let reader = DictionaryReader::open(&output_dir).unwrap();
+            let mut word_entries: Vec<Vec<WordEntry>> = SURFACES
+                .iter()
+                .map(|surface| reader.lookup(surface))
+                .collect();
+            builder
+                .audit_lexicon(&output_dir, keyset(&word_entries))
+                .unwrap();
+
+            let err = builder
+                .audit_lexicon(&output_dir, keyset(&word_entries).into_iter().take(4))
+                .unwrap_err();
+            assert!(
+                err.to_string().contains("the surfaces have 4 entries"),
+                "{}",
+                err
+            );
+
+            let mut missing = keyset(&word_entries);
+            missing[2].surface = "東".as_bytes();
+            let err = builder.audit_lexicon(&output_dir, missing).unwrap_err();
+            assert!(err.to_string().contains("東 is missing"), "{}", err);
+
+            let mut miscounted = keyset(&word_entries);
+            miscounted[1].entries = 2;
+            let err = builder.audit_lexicon(&output_dir, miscounted).unwrap_err();
+            assert!(
+                err.to_string().contains("に maps to entries 1..2"),
+                "{}",
+                err
+            );
+
+            word_entries[3][0].word_cost += 1;
+            let err = builder
+                .audit_lexicon(&output_dir, keyset(&word_entries))
+                .unwrap_err();
+            assert!(
+                err.to_string()
+                    .contains("entry 0 of 東京スカイツリー in dict.vals"),
+                "{}",
+                err
+            );
+        }
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lindera_core::dictionary_builder::DictionaryBuilder;
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::disk_space::DiskUsageEstimate;
+use crate::error::ErrorCollector;
+use crate::{parse_files, CsvRow, IpadicNeologdBuilder, SeedDates};
+
+impl IpadicNeologdBuilder {
+    /// Builds the dictionary of a combined IPADIC and NEologd source tree to `output_dir`
+    /// and, from the same pass over the sources, the dictionary without the NEologd seed
+    /// files to `base_output_dir`, for comparing the tokenization of both. Seed files are
+    /// the CSV files whose names carry a NEologd snapshot date.
+    pub fn build_with_base_dictionary(
+        &self,
+        input_dir: &Path,
+        output_dir: &Path,
+        base_output_dir: &Path,
+    ) -> LinderaResult<()> {
+        self.run_build(input_dir, &[output_dir, base_output_dir], || {
+            self.build_with_base_phases(input_dir, output_dir, base_output_dir)
+        })
+    }
+
+    /// The phases of `build_with_base_dictionary`, run with both output directories locked.
+    fn build_with_base_phases(
+        &self,
+        input_dir: &Path,
+        output_dir: &Path,
+        base_output_dir: &Path,
+    ) -> LinderaResult<Option<SeedDates>> {
+        self.preflight(input_dir)?;
+        // The base dictionary holds fewer entries, but is estimated like the full one.
+        let estimate = self.estimate_disk_usage(input_dir)?;
+        let base_estimate = DiskUsageEstimate {
+            temporary: 0,
+            ..estimate
+        };
+        self.check_disk_space(&[
+            self.disk_need(output_dir, estimate),
+            self.disk_need(base_output_dir, base_estimate),
+        ])?;
+        let chardef =
+            self.measure_phase("chardef", &[input_dir.join("char.def")], output_dir, || {
+                self.build_chardef(input_dir, output_dir)
+            })?;
+        self.measure_phase("unk", &[input_dir.join("unk.def")], output_dir, || {
+            self.build_unk(input_dir, &chardef, output_dir)
+        })?;
+        let layout = &self.options.layout;
+        self.copy_artifacts(
+            &[&layout.char_def, &layout.unk],
+            output_dir,
+            base_output_dir,
+        )?;
+
+        let csv_files: Vec<PathBuf> = self
+            .csv_sources(input_dir)?
+            .into_iter()
+            .map(|(_, filename)| filename)
+            .collect();
+        let seed_dates = self.check_seed_dates(&csv_files)?;
+        self.measure_phase("dict", &csv_files, output_dir, || {
+            self.print("BUILD DICT");
+            let files = self.read_csv_files(input_dir)?;
+            let patches = self.load_patches()?;
+            let mut errors = ErrorCollector::new(self.options.max_errors);
+            let rows = parse_files(&files, &mut errors)?;
+            errors.finish()?;
+            self.entry_stats.record_parsed(&rows);
+            let rows = self.apply_patches(&patches, rows)?;
+            let rows = self.apply_term_frequencies(rows)?;
+            let rows = self.apply_preferred_terms(rows)?;
+            let mut rows = self.drop_reference_entries(rows)?;
+            let mut base_rows: Vec<CsvRow> = rows
+                .iter()
+                .filter(|row| !row.is_seed_row())
+                .cloned()
+                .collect();
+            self.print(&format!(
+                "{} entries, {} without the NEologd seed files",
+                rows.len(),
+                base_rows.len()
+            ));
+            self.annotate_compounds(&mut rows);
+            self.annotate_compounds(&mut base_rows);
+
+            let matrix_data_path = input_dir.join("matrix.def");
+            self.check_context_ids(&rows, &matrix_data_path)?;
+            let rows = self.apply_size_budget(rows, output_dir, &matrix_data_path)?;
+            let base_rows =
+                self.apply_size_budget(base_rows, base_output_dir, &matrix_data_path)?;
+            self.write_dict(rows, output_dir)?;
+            self.write_dict(base_rows, base_output_dir)
+        })?;
+        self.report_entry_stats();
+
+        self.measure_phase(
+            "matrix",
+            &[input_dir.join("matrix.def")],
+            output_dir,
+            || self.build_cost_matrix(input_dir, output_dir),
+        )?;
+        self.copy_artifacts(&[&layout.matrix], output_dir, base_output_dir)?;
+        Ok(seed_dates)
+    }
+
+    /// Copies the artifacts shared by both dictionaries instead of building them twice.
+    fn copy_artifacts(&self, names: &[&str], from_dir: &Path, to_dir: &Path) -> LinderaResult<()> {
+        for name in names {
+            let to_path = self.options.layout.artifact_path(to_dir, name)?;
+            self.print(&format!("creating {:?}", to_path));
+            fs::copy(from_dir.join(name), to_path)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::fixtures::{write_fixture, FIXTURE_ROWS};
+    use crate::reader::DictionaryReader;
+    use crate::IpadicNeologdBuilder;
+
+    #[test]
+    fn builds_the_base_dictionary_without_seed_files() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-base-only-{}",
+            std::process::id()
+        ));
+        let input_dir = base_dir.join("input");
+        let (output_dir, base_output_dir) = (base_dir.join("output"), base_dir.join("base"));
+        write_fixture(&input_dir).unwrap();
+        fs::write(
+            input_dir.join("neologd-noun-dict-seed.20200910.csv"),
+            "渋谷,1,1,3000,名詞,固有名詞,地域,一般,*,*,渋谷,シブヤ,シブヤ\n",
+        )
+        .unwrap();
+        IpadicNeologdBuilder::new()
+            .build_with_base_dictionary(&input_dir, &output_dir, &base_output_dir)
+            .unwrap();
+
+        let reader = DictionaryReader::open(&output_dir).unwrap();
+        assert_eq!(reader.num_words(), FIXTURE_ROWS.len() + 1);
+        assert_eq!(reader.lookup("渋谷").len(), 1);
+        let base_reader = DictionaryReader::open(&base_output_dir).unwrap();
+        assert_eq!(base_reader.num_words(), FIXTURE_ROWS.len());
+        assert!(base_reader.lookup("渋谷").is_empty());
+        assert_eq!(base_reader.lookup("東京").len(), 1);
+        for name in ["char_def.bin", "unk.bin", "matrix.mtx"] {
+            assert_eq!(
+                fs::read(output_dir.join(name)).unwrap(),
+                fs::read(base_output_dir.join(name)).unwrap()
+            );
+        }
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use lindera_core::dictionary_builder::DictionaryBuilder;
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::chardef;
+use crate::endian::Endianness;
+use crate::header::ArtifactHeader;
+use crate::options::BuilderOptions;
+use crate::unk::{EffectiveUnkCost, UnkCostAdjustment};
+use crate::IpadicNeologdBuilder;
+
+/// A dictionary built by `build_many`: the builder configured for it and its output
+/// directory.
+pub struct BuildJob {
+    pub builder: IpadicNeologdBuilder,
+    pub output_dir: PathBuf,
+}
+
+impl BuildJob {
+    pub fn new(options: BuilderOptions, output_dir: &Path) -> Self {
+        BuildJob {
+            builder: IpadicNeologdBuilder::with_options(options),
+            output_dir: output_dir.to_path_buf(),
+        }
+    }
+}
+
+/// How the finish phase of a job rewrites its artifacts in place. The artifacts are copied
+/// from the output directory of an earlier job once it is finished, so they are only
+/// shared between the jobs rewriting them alike.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ArtifactFormat {
+    endianness: Endianness,
+    /// The header prepended to the artifacts, if `BuilderOptions::artifact_headers` is set.
+    header: Option<ArtifactHeader>,
+}
+
+impl ArtifactFormat {
+    fn of(builder: &IpadicNeologdBuilder) -> Self {
+        let options = &builder.options;
+        ArtifactFormat {
+            endianness: options.endianness,
+            header: if options.artifact_headers {
+                Some(ArtifactHeader::new(
+                    options.endianness,
+                    options.detail_format,
+                ))
+            } else {
+                None
+            },
+        }
+    }
+}
+
+/// The `char.def` and `unk.def` artifacts built by a job, copied by the jobs of the same
+/// character overrides, unk costs and artifact format.
+struct CharDefArtifacts {
+    char_overrides: Option<PathBuf>,
+    unk_costs: BTreeMap<String, UnkCostAdjustment>,
+    format: ArtifactFormat,
+    char_def_path: PathBuf,
+    unk_path: PathBuf,
+    /// The unk costs adjusted in `unk_path`, reported by the jobs copying it as well.
+    effective_unk_costs: Vec<EffectiveUnkCost>,
+}
+
+/// The `matrix.def` artifact built by a job, copied by the jobs of the same artifact format.
+struct MatrixArtifact {
+    format: ArtifactFormat,
+    matrix_path: PathBuf,
+}
+
+/// Whether the job builds its artifacts with the default phase builders, so that they can be
+/// shared with the other jobs.
+fn shares_char_def(builder: &IpadicNeologdBuilder) -> bool {
+    builder.char_def_builder.is_none()
+}
+
+fn shares_matrix(builder: &IpadicNeologdBuilder) -> bool {
+    builder.matrix_builder.is_none()
+}
+
+/// Builds several dictionaries of the same sources in `input_dir`, such as a pruned and a
+/// full one, or compressed and raw ones. `matrix.def` is parsed once for each artifact
+/// format, the endianness and the artifact headers, and its artifact copied to the other
+/// jobs, as are the artifacts of `char.def` and `unk.def` between the jobs of the same
+/// character overrides, unk costs and artifact format. The entries are built for each job,
+/// since most options change them.
+pub fn build_many(input_dir: &Path, jobs: &[BuildJob]) -> LinderaResult<()> {
+    // Every output directory stays locked until the last job ends, since the jobs copy the
+    // artifacts of the earlier ones.
+    let mut locks = Vec::new();
+    for job in jobs {
+        locks.extend(job.builder.prepare_outputs(&[&job.output_dir])?);
+    }
+    let mut disk_needs = Vec::new();
+    for job in jobs {
+        job.builder.preflight(input_dir)?;
+        let estimate = job.builder.estimate_disk_usage(input_dir)?;
+        disk_needs.push(job.builder.disk_need(&job.output_dir, estimate));
+    }
+    // The outputs of all the jobs are kept, so their estimates add up; the disk space check
+    // of the first job applies to the whole batch.
+    if let Some(job) = jobs.first() {
+        job.builder.check_disk_space(&disk_needs)?;
+    }
+
+    let mut char_defs: Vec<CharDefArtifacts> = Vec::new();
+    let mut matrices: Vec<MatrixArtifact> = Vec::new();
+
+    for (job_idx, job) in jobs.iter().enumerate() {
+        let builder = &job.builder;
+        let output_dir = job.output_dir.as_path();
+        let layout = &builder.options.layout;
+        let format = ArtifactFormat::of(builder);
+        let start = Instant::now();
+        builder.print(&format!(
+            "BUILD JOB {}/{}: {:?}",
+            job_idx + 1,
+            jobs.len(),
+            output_dir
+        ));
+        builder.open_build_log(output_dir)?;
+
+        let shared_char_def = char_defs.iter().find(|char_def| {
+            shares_char_def(builder)
+                && char_def.char_overrides == builder.options.char_overrides
+                && char_def.unk_costs == builder.options.unk_costs
+                && char_def.format == format
+        });
+        match shared_char_def {
+            Some(char_def) => builder.measure_phase("chardef", &[], output_dir, || {
+                // The shared artifacts were built by a job which may not generate numeric
+                // entries, so the check of the numeric category is not inherited.
+                if builder.options.numeric_entries {
+                    builder.check_numeric_category(&chardef::read_char_def(
+                        input_dir,
+                        builder.options.char_overrides.as_deref(),
+                    )?);
+                }
+                builder.record_effective_unk_costs(char_def.effective_unk_costs.clone());
+                copy_artifact(
+                    builder,
+                    &char_def.char_def_path,
+                    output_dir,
+                    &layout.char_def,
+                )?;
+                copy_artifact(builder, &char_def.unk_path, output_dir, &layout.unk)
+            })?,
+            None => {
+                let chardef = builder.measure_phase(
+                    "chardef",
+                    &builder.char_def_files(input_dir),
+                    output_dir,
+                    || builder.build_chardef(input_dir, output_dir),
+                )?;
+                builder.measure_phase("unk", &[input_dir.join("unk.def")], output_dir, || {
+                    builder.build_unk(input_dir, &chardef, output_dir)
+                })?;
+                if shares_char_def(builder) {
+                    char_defs.push(CharDefArtifacts {
+                        char_overrides: builder.options.char_overrides.clone(),
+                        unk_costs: builder.options.unk_costs.clone(),
+                        format: format.clone(),
+                        char_def_path: layout.artifact_path(output_dir, &layout.char_def)?,
+                        unk_path: layout.artifact_path(output_dir, &layout.unk)?,
+                        effective_unk_costs: builder.effective_unk_costs(),
+                    });
+                }
+            }
+        }
+
+        let csv_files: Vec<PathBuf> = builder
+            .csv_sources(input_dir)?
+            .into_iter()
+            .map(|(_, filename)| filename)
+            .collect();
+        let seed_dates = builder.check_seed_dates(&csv_files)?;
+        builder.measure_phase("dict", &csv_files, output_dir, || {
+            builder.build_dict(input_dir, output_dir)
+        })?;
+        builder.report_entry_stats();
+
+        let shared_matrix = matrices
+            .iter()
+            .find(|matrix| shares_matrix(builder) && matrix.format == format);
+        match shared_matrix {
+            Some(matrix) => builder.measure_phase("matrix", &[], output_dir, || {
+                copy_artifact(builder, &matrix.matrix_path, output_dir, &layout.matrix)
+            })?,
+            None => {
+                builder.measure_phase(
+                    "matrix",
+                    &[input_dir.join("matrix.def")],
+                    output_dir,
+                    || builder.build_cost_matrix(input_dir, output_dir),
+                )?;
+                if shares_matrix(builder) {
+                    matrices.push(MatrixArtifact {
+                        format,
+                        matrix_path: layout.artifact_path(output_dir, &layout.matrix)?,
+                    });
+                }
+            }
+        }
+
+        builder.finish_build(input_dir, &[output_dir], seed_dates, start)?;
+    }
+    Ok(())
+}
+
+/// Copies an artifact built by another job to the artifact `name` of `output_dir`.
+fn copy_artifact(
+    builder: &IpadicNeologdBuilder,
+    from_path: &Path,
+    output_dir: &Path,
+    name: &str,
+) -> LinderaResult<()> {
+    let to_path = builder.options.layout.artifact_path(output_dir, name)?;
+    builder.print(&format!("creating {:?}", to_path));
+    fs::copy(from_path, to_path)
+        .map(|_| ())
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{build_many, BuildJob};
+    use crate::build_log::BUILD_LOG_FILENAME;
+    use crate::fixtures::{write_fixture, FIXTURE_CHAR_DEF};
+    use crate::options::BuilderOptions;
+
+    #[test]
+    fn jobs_sharing_char_def_check_the_numeric_category() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-batch-numeric-{}",
+            std::process::id()
+        ));
+        let input_dir = base_dir.join("input");
+        write_fixture(&input_dir).unwrap();
+        // A NUMERIC category which does not group digits.
+        fs::write(
+            input_dir.join("char.def"),
+            FIXTURE_CHAR_DEF.replace("NUMERIC 1 1 0", "NUMERIC 1 0 0"),
+        )
+        .unwrap();
+
+        let jobs = [
+            BuildJob::new(BuilderOptions::default(), &base_dir.join("plain")),
+            BuildJob::new(
+                BuilderOptions {
+                    numeric_entries: true,
+                    build_log: true,
+                    ..BuilderOptions::default()
+                },
+                &base_dir.join("numeric"),
+            ),
+        ];
+        build_many(&input_dir, &jobs).unwrap();
+
+        let build_log =
+            fs::read_to_string(base_dir.join("numeric").join(BUILD_LOG_FILENAME)).unwrap();
+        assert!(build_log.contains("does not group digits"));
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
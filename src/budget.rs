@@ -0,0 +1,287 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use lindera_core::word_entry::WordEntry;
+use lindera_core::LinderaResult;
+
+use crate::error::BuildError;
+use crate::manifest::Manifest;
+use crate::metrics::file_size;
+use crate::{matrix, CsvRow, IpadicNeologdBuilder};
+
+/// Approximate size of the double array per byte of the surfaces.
+const DA_BYTES_PER_KEY_BYTE: u64 = 8;
+
+/// Number of dropped entries printed when pruning.
+const PRINTED_DROPPED_ROWS: usize = 10;
+
+/// Number of artifacts listed when the size budget is exceeded.
+const LISTED_ARTIFACTS: usize = 5;
+
+/// Which entries are dropped first when the dictionary exceeds its size budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PruningStrategy {
+    /// Entries with the highest word cost, the least likely to be chosen, are dropped first.
+    HighestCost,
+    /// Entries with the longest surface are dropped first.
+    LongestSurface,
+}
+
+impl IpadicNeologdBuilder {
+    /// Drops the entries above the maximum word cost of the options, then entries with the
+    /// pruning strategy of the options until the estimated size of the dictionary fits the
+    /// size budget. Without a pruning strategy the rows are kept and the budget is only
+    /// enforced on the written artifacts.
+    pub(crate) fn apply_size_budget<'a>(
+        &self,
+        rows: Vec<CsvRow<'a>>,
+        output_dir: &Path,
+        matrix_data_path: &Path,
+    ) -> LinderaResult<Vec<CsvRow<'a>>> {
+        let rows = self.drop_rare_entries(rows);
+        let (size_budget, strategy) = match (self.options.size_budget, self.options.pruning) {
+            (Some(size_budget), Some(strategy)) => (size_budget, strategy),
+            _ => return Ok(rows),
+        };
+
+        let layout = &self.options.layout;
+        let (forward_size, backward_size) = matrix::read_header(matrix_data_path)?;
+        let fixed_size = file_size(&output_dir.join(&layout.char_def))
+            + file_size(&output_dir.join(&layout.unk))
+            + (2 + u64::from(forward_size) * u64::from(backward_size)) * 2;
+
+        let codec = self.options.detail_codec();
+        let detail_fields = self.options.detail_field_indices()?;
+        let mut words_buffer = Vec::new();
+        let mut row_sizes = Vec::with_capacity(rows.len());
+        for row in &rows {
+            codec.encode(
+                &self.word_details(row, detail_fields.as_deref()),
+                &mut words_buffer,
+            )?;
+            row_sizes.push(
+                words_buffer.len() as u64
+                    + 4
+                    + WordEntry::SERIALIZED_LEN as u64
+                    + row.surface_form.len() as u64 * DA_BYTES_PER_KEY_BYTE,
+            );
+            words_buffer.clear();
+        }
+        let estimated_size = fixed_size + row_sizes.iter().sum::<u64>();
+        if estimated_size <= size_budget {
+            return Ok(rows);
+        }
+
+        let mut order: Vec<usize> = (0..rows.len()).collect();
+        match strategy {
+            PruningStrategy::HighestCost => {
+                order.sort_by_key(|idx| std::cmp::Reverse(rows[*idx].word_cost))
+            }
+            PruningStrategy::LongestSurface => {
+                order.sort_by_key(|idx| std::cmp::Reverse(rows[*idx].surface_form.len()))
+            }
+        }
+        let mut dropped = vec![false; rows.len()];
+        let mut excess = estimated_size - size_budget;
+        for idx in order {
+            if excess == 0 {
+                break;
+            }
+            dropped[idx] = true;
+            excess = excess.saturating_sub(row_sizes[idx]);
+        }
+
+        let mut kept = Vec::with_capacity(rows.len());
+        let mut dropped_rows = 0;
+        for (row, dropped) in rows.into_iter().zip(dropped) {
+            if !dropped {
+                kept.push(row);
+                continue;
+            }
+            if dropped_rows < PRINTED_DROPPED_ROWS {
+                self.print(&format!(
+                    "dropping {} (cost {}) to fit the size budget",
+                    row.surface_form, row.word_cost
+                ));
+            }
+            self.log_skipped_row(&row, "size budget");
+            dropped_rows += 1;
+        }
+        self.print(&format!(
+            "dropped {} entries to fit the size budget of {} bytes (estimated {} bytes)",
+            dropped_rows, size_budget, estimated_size
+        ));
+        Ok(kept)
+    }
+
+    fn drop_rare_entries<'a>(&self, rows: Vec<CsvRow<'a>>) -> Vec<CsvRow<'a>> {
+        let max_word_cost = match self.options.max_word_cost {
+            Some(max_word_cost) => max_word_cost,
+            None => return rows,
+        };
+        let (kept, dropped_rows) = self.retain_common_entries(max_word_cost, rows);
+        self.print(&format!(
+            "dropped {} entries with a word cost above {}",
+            dropped_rows, max_word_cost
+        ));
+        kept
+    }
+
+    /// Keeps the rows with a word cost up to `max_word_cost`, returning them with the number
+    /// of rows dropped.
+    pub(crate) fn retain_common_entries<'a>(
+        &self,
+        max_word_cost: i32,
+        rows: Vec<CsvRow<'a>>,
+    ) -> (Vec<CsvRow<'a>>, usize) {
+        let mut kept = Vec::with_capacity(rows.len());
+        let mut dropped_rows = 0;
+        for row in rows {
+            if row.word_cost <= max_word_cost {
+                kept.push(row);
+                continue;
+            }
+            self.log_skipped_row(&row, "max word cost");
+            dropped_rows += 1;
+        }
+        (kept, dropped_rows)
+    }
+}
+
+/// Fails if the artifacts of the manifest exceed `size_budget`, listing the largest ones.
+pub(crate) fn check_size_budget(manifest: &Manifest, size_budget: u64) -> LinderaResult<()> {
+    let total_size: u64 = manifest
+        .artifacts
+        .iter()
+        .map(|artifact| artifact.size)
+        .sum();
+    if total_size <= size_budget {
+        return Ok(());
+    }
+
+    let mut artifacts: Vec<_> = manifest.artifacts.iter().collect();
+    artifacts.sort_by_key(|artifact| std::cmp::Reverse(artifact.size));
+    let largest: Vec<String> = artifacts
+        .iter()
+        .take(LISTED_ARTIFACTS)
+        .map(|artifact| format!("{} {} bytes", artifact.name, artifact.size))
+        .collect();
+    Err(BuildError::Validation(format!(
+        "the dictionary is {} bytes, over the size budget of {} bytes; largest files: {}",
+        total_size,
+        size_budget,
+        largest.join(", ")
+    ))
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{check_size_budget, PruningStrategy};
+    use crate::fixtures::{write_fixture, FIXTURE_ROWS};
+    use crate::manifest::{ArtifactEntry, Manifest};
+    use crate::options::BuilderOptions;
+    use crate::{CsvRow, IpadicNeologdBuilder};
+
+    /// The surfaces kept by the size budget of a builder with `options`.
+    fn kept_surfaces(options: BuilderOptions, input_dir: &std::path::Path) -> Vec<String> {
+        let rows: Vec<CsvRow> = FIXTURE_ROWS
+            .iter()
+            .map(|line| CsvRow::from_line(line).unwrap())
+            .collect();
+        IpadicNeologdBuilder::with_options(options)
+            .apply_size_budget(rows, input_dir, &input_dir.join("matrix.def"))
+            .unwrap()
+            .iter()
+            .map(|row| row.surface_form.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn prunes_entries_by_strategy_to_fit_the_budget() {
+        let input_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-budget-{}",
+            std::process::id()
+        ));
+        write_fixture(&input_dir).unwrap();
+        let options = |size_budget: u64, strategy: PruningStrategy| BuilderOptions {
+            size_budget: Some(size_budget),
+            pruning: Some(strategy),
+            ..BuilderOptions::default()
+        };
+
+        // The smallest budget keeping every entry is the estimated size.
+        let (mut low, mut high) = (0, 1 << 20);
+        while low < high {
+            let mid = (low + high) / 2;
+            let kept = kept_surfaces(options(mid, PruningStrategy::HighestCost), &input_dir);
+            if kept.len() == FIXTURE_ROWS.len() {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        let estimated_size = low;
+        assert!(estimated_size > 0);
+
+        let kept = kept_surfaces(
+            options(estimated_size - 1, PruningStrategy::HighestCost),
+            &input_dir,
+        );
+        assert!(!kept.contains(&"いく".to_string()));
+        assert_eq!(kept.len(), FIXTURE_ROWS.len() - 1);
+        let kept = kept_surfaces(
+            options(estimated_size - 1, PruningStrategy::LongestSurface),
+            &input_dir,
+        );
+        assert!(!kept.contains(&"東京スカイツリー".to_string()));
+        assert_eq!(kept.len(), FIXTURE_ROWS.len() - 1);
+
+        // Without a strategy, only the maximum word cost drops entries.
+        let max_word_cost = BuilderOptions {
+            size_budget: Some(0),
+            max_word_cost: Some(4000),
+            ..BuilderOptions::default()
+        };
+        assert_eq!(
+            kept_surfaces(max_word_cost, &input_dir),
+            vec!["東京", "東京スカイツリー", "に"]
+        );
+
+        fs::remove_dir_all(&input_dir).unwrap();
+    }
+
+    #[test]
+    fn lists_the_largest_artifacts_over_the_budget() {
+        let manifest = Manifest {
+            builder: "builder".to_string(),
+            builder_version: "0.1.0".to_string(),
+            dictionary_version: None,
+            seed_dates: None,
+            detail_format: Default::default(),
+            endianness: Default::default(),
+            artifact_headers: false,
+            lindera_target: Default::default(),
+            value_packing: Default::default(),
+            detail_fields: None,
+            artifacts: (1..=7)
+                .map(|size| ArtifactEntry {
+                    name: format!("file{}", size),
+                    size: size * 10,
+                })
+                .collect(),
+        };
+        check_size_budget(&manifest, 280).unwrap();
+        let message = check_size_budget(&manifest, 279).unwrap_err().to_string();
+        assert!(message.contains("the dictionary is 280 bytes, over the size budget of 279 bytes"));
+        assert!(message.contains(
+            "largest files: file7 70 bytes, file6 60 bytes, file5 50 bytes, file4 40 bytes, \
+             file3 30 bytes"
+        ));
+        assert!(!message.contains("file2"));
+    }
+}
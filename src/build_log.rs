@@ -0,0 +1,190 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+/// The JSONL event log written to the output directory with `BuilderOptions::build_log`.
+pub const BUILD_LOG_FILENAME: &str = "build.jsonl";
+
+/// An event of the build log, serialized with its kind in the `event` field.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum BuildEvent<'a> {
+    PhaseStart {
+        phase: &'a str,
+    },
+    PhaseEnd {
+        phase: &'a str,
+        duration_ms: u128,
+    },
+    Warning {
+        message: &'a str,
+    },
+    SkippedRow {
+        file: &'a str,
+        surface: &'a str,
+        reason: &'a str,
+    },
+    FileStats {
+        file: &'a str,
+        bytes: u64,
+        lines: usize,
+    },
+    FileEntries {
+        file: &'a str,
+        parsed: usize,
+        normalized: usize,
+        skipped: usize,
+        duplicates_seen: usize,
+        merged: usize,
+    },
+}
+
+#[derive(Serialize)]
+struct LogLine<'a> {
+    /// Milliseconds since the Unix epoch.
+    time: u128,
+    #[serde(flatten)]
+    event: &'a BuildEvent<'a>,
+}
+
+/// The event log of a build. Events are dropped until it is opened.
+#[derive(Default)]
+pub(crate) struct BuildLog {
+    wtr: Mutex<Option<io::BufWriter<File>>>,
+}
+
+impl BuildLog {
+    pub(crate) fn open(&self, output_dir: &Path) -> LinderaResult<()> {
+        let wtr_log_path = output_dir.join(BUILD_LOG_FILENAME);
+        println!("creating {:?}", wtr_log_path);
+        let wtr_log = io::BufWriter::new(
+            File::create(wtr_log_path)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
+        );
+        if let Ok(mut wtr) = self.wtr.lock() {
+            *wtr = Some(wtr_log);
+        }
+        Ok(())
+    }
+
+    /// Appends an event to the log, if it is open. Failures to write are reported but do
+    /// not fail the build.
+    pub(crate) fn log(&self, event: &BuildEvent) {
+        let mut wtr = match self.wtr.lock() {
+            Ok(wtr) => wtr,
+            Err(_err) => return,
+        };
+        let wtr = match wtr.as_mut() {
+            Some(wtr) => wtr,
+            None => return,
+        };
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis())
+            .unwrap_or_default();
+        let result = serde_json::to_writer(&mut *wtr, &LogLine { time, event })
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(wtr));
+        if let Err(err) = result {
+            println!("failed to write the build log: {}", err);
+        }
+    }
+
+    /// Flushes and closes the log.
+    pub(crate) fn close(&self) -> LinderaResult<()> {
+        let wtr = match self.wtr.lock() {
+            Ok(mut wtr) => wtr.take(),
+            Err(_err) => None,
+        };
+        if let Some(mut wtr) = wtr {
+            wtr.flush()
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::Value;
+
+    use super::{BuildEvent, BuildLog, BUILD_LOG_FILENAME};
+    use crate::fixtures::build_fixture;
+    use crate::options::BuilderOptions;
+
+    fn read_log(path: &std::path::Path) -> Vec<Value> {
+        fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn writes_an_event_per_line() {
+        let output_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-build-log-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&output_dir).unwrap();
+        let log = BuildLog::default();
+        log.log(&BuildEvent::PhaseStart { phase: "dropped" });
+        log.open(&output_dir).unwrap();
+        log.log(&BuildEvent::PhaseEnd {
+            phase: "dict",
+            duration_ms: 12,
+        });
+        log.log(&BuildEvent::SkippedRow {
+            file: "a.csv",
+            surface: "東京",
+            reason: "skip rule",
+        });
+        log.close().unwrap();
+        log.log(&BuildEvent::Warning { message: "dropped" });
+
+        let events = read_log(&output_dir.join(BUILD_LOG_FILENAME));
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["event"], "phase_end");
+        assert_eq!(events[0]["phase"], "dict");
+        assert_eq!(events[0]["duration_ms"], 12);
+        assert!(events[0]["time"].as_u64().unwrap() > 0);
+        assert_eq!(events[1]["event"], "skipped_row");
+        assert_eq!(events[1]["surface"], "東京");
+
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    fn builds_log_their_phases() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-build-log-phases-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        let options = BuilderOptions {
+            build_log: true,
+            ..BuilderOptions::default()
+        };
+        build_fixture(&input_dir, &output_dir, options).unwrap();
+
+        let events = read_log(&output_dir.join(BUILD_LOG_FILENAME));
+        let phases: Vec<&str> = events
+            .iter()
+            .filter(|event| event["event"] == "phase_start")
+            .map(|event| event["phase"].as_str().unwrap())
+            .collect();
+        assert_eq!(phases, vec!["chardef", "unk", "dict", "matrix", "finish"]);
+        assert!(events.iter().any(|event| event["event"] == "file_stats"));
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
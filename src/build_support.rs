@@ -0,0 +1,187 @@
+//! Helpers for building the dictionary from the `build.rs` of a downstream crate.
+//!
+//! ```no_run
+//! // build.rs
+//! use lindera_ipadic_neologd_builder::build_support::BuildSupport;
+//!
+//! fn main() {
+//!     BuildSupport::new("https://example.com/mecab-ipadic-neologd.tar.gz")
+//!         .run()
+//!         .unwrap();
+//! }
+//! ```
+//!
+//! The built dictionary directory is exported to the crate as the `LINDERA_IPADIC_NEOLOGD_DIR`
+//! environment variable, so it can be embedded with
+//! `include_bytes!(concat!(env!("LINDERA_IPADIC_NEOLOGD_DIR"), "/dict.da"))`.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
+
+use lindera_core::dictionary_builder::DictionaryBuilder;
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::checksum::verify_output;
+use crate::file_util::find_input_dir;
+use crate::options::BuilderOptions;
+use crate::IpadicNeologdBuilder;
+
+/// Overrides the source archive URL.
+pub const SOURCE_URL_ENV: &str = "LINDERA_IPADIC_NEOLOGD_SOURCE_URL";
+/// Overrides the cache directory, which defaults to `OUT_DIR`.
+pub const CACHE_DIR_ENV: &str = "LINDERA_IPADIC_NEOLOGD_CACHE_DIR";
+/// Exported to the downstream crate with the path of the built dictionary.
+pub const DICTIONARY_DIR_ENV: &str = "LINDERA_IPADIC_NEOLOGD_DIR";
+
+pub struct BuildSupport {
+    source_url: String,
+    cache_dir: Option<PathBuf>,
+    options: BuilderOptions,
+}
+
+impl BuildSupport {
+    /// `source_url` points to a `.tar.gz` of a NEologd source directory (the directory
+    /// containing the CSVs, `char.def`, `unk.def` and `matrix.def`).
+    pub fn new(source_url: &str) -> Self {
+        BuildSupport {
+            source_url: source_url.to_string(),
+            cache_dir: None,
+            options: BuilderOptions::default(),
+        }
+    }
+
+    pub fn cache_dir(mut self, cache_dir: &Path) -> Self {
+        self.cache_dir = Some(cache_dir.to_path_buf());
+        self
+    }
+
+    pub fn options(mut self, options: BuilderOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Builds the dictionary unless a verified build is already cached, prints the `cargo:`
+    /// directives and returns the path of the dictionary directory.
+    pub fn run(&self) -> LinderaResult<PathBuf> {
+        println!("cargo:rerun-if-env-changed={}", SOURCE_URL_ENV);
+        println!("cargo:rerun-if-env-changed={}", CACHE_DIR_ENV);
+
+        let source_url = env::var(SOURCE_URL_ENV).unwrap_or_else(|_| self.source_url.clone());
+        let cache_dir = match (env::var_os(CACHE_DIR_ENV), &self.cache_dir) {
+            (Some(cache_dir), _) => PathBuf::from(cache_dir),
+            (None, Some(cache_dir)) => cache_dir.clone(),
+            (None, None) => env::var_os("OUT_DIR").map(PathBuf::from).ok_or_else(|| {
+                LinderaErrorKind::Args.with_error(anyhow::anyhow!(
+                    "neither {} nor OUT_DIR is set",
+                    CACHE_DIR_ENV
+                ))
+            })?,
+        };
+
+        let key = cache_key(&source_url, &self.options)?;
+        let work_dir = cache_dir.join(format!("lindera-ipadic-neologd-{}", &key[..16]));
+        let dictionary_dir = work_dir.join("dictionary");
+
+        if verify_output(&dictionary_dir).is_err() {
+            let source_dir = work_dir.join("source");
+            if source_dir.exists() {
+                fs::remove_dir_all(&source_dir)
+                    .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            }
+            download_source(&source_url, &work_dir.join("source.tar.gz"), &source_dir)?;
+
+            let input_dir = find_input_dir(&source_dir)?;
+            let builder = IpadicNeologdBuilder::with_options(self.options.clone());
+            builder.build_dictionary(&input_dir, &dictionary_dir)?;
+        }
+
+        println!(
+            "cargo:rustc-env={}={}",
+            DICTIONARY_DIR_ENV,
+            dictionary_dir.display()
+        );
+
+        Ok(dictionary_dir)
+    }
+}
+
+/// The key of the cached build of `source_url` with `options`, so that a cached dictionary
+/// is only reused by the same version of the builder with the same options. Files named by
+/// the options, such as patch files, are keyed by their paths, not their contents.
+fn cache_key(source_url: &str, options: &BuilderOptions) -> LinderaResult<String> {
+    let options_json = serde_json::to_vec(options)
+        .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{}@{}", source_url, env!("CARGO_PKG_VERSION")).as_bytes());
+    hasher.update(&options_json);
+    // The signing key is not serialized with the options.
+    #[cfg(feature = "signing")]
+    if let Some(signing_key) = &options.signing_key {
+        hasher.update(&signing_key.0);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn download_source(source_url: &str, archive_path: &Path, source_dir: &Path) -> LinderaResult<()> {
+    if let Some(parent) = archive_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    }
+
+    let response = ureq::get(source_url)
+        .call()
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    let mut wtr_archive = io::BufWriter::new(
+        File::create(archive_path)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
+    );
+    io::copy(&mut response.into_reader(), &mut wtr_archive)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    wtr_archive
+        .flush()
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+    let archive = File::open(archive_path)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    tar::Archive::new(GzDecoder::new(archive))
+        .unpack(source_dir)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cache_key;
+    use crate::options::BuilderOptions;
+
+    const SOURCE_URL: &str = "https://example.com/mecab-ipadic-neologd.tar.gz";
+
+    #[test]
+    fn cache_keys_change_with_the_options() {
+        let key = cache_key(SOURCE_URL, &BuilderOptions::default()).unwrap();
+        assert_eq!(
+            key,
+            cache_key(SOURCE_URL, &BuilderOptions::default()).unwrap()
+        );
+        assert_ne!(
+            key,
+            cache_key(
+                "https://example.com/mecab-ipadic.tar.gz",
+                &BuilderOptions::default()
+            )
+            .unwrap()
+        );
+        let options = BuilderOptions {
+            max_word_cost: Some(8000),
+            ..BuilderOptions::default()
+        };
+        assert_ne!(key, cache_key(SOURCE_URL, &options).unwrap());
+    }
+}
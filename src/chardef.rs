@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::file_util::{read_source_file, source_lines};
+
+pub const CHAR_OVERRIDES_FILENAME: &str = "char_overrides.def";
+
+/// A code point range of a `char.def` range line, e.g. `0x30A1..0x30FF KATAKANA`.
+struct CharRange<'a> {
+    low: u32,
+    high: u32,
+    categories: &'a str,
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(pos) => line[..pos].trim(),
+        None => line.trim(),
+    }
+}
+
+fn parse_code_point(field: &str) -> Option<u32> {
+    u32::from_str_radix(field.trim_start_matches("0x"), 16).ok()
+}
+
+fn parse_range(line: &str) -> Option<CharRange<'_>> {
+    let (range, categories) = line.split_once(char::is_whitespace)?;
+    let (low, high) = match range.split_once("..") {
+        Some((low, high)) => (parse_code_point(low)?, parse_code_point(high)?),
+        None => {
+            let code_point = parse_code_point(range)?;
+            (code_point, code_point)
+        }
+    };
+    Some(CharRange {
+        low,
+        high,
+        categories: categories.trim(),
+    })
+}
+
+fn category_name(line: &str) -> &str {
+    line.split_whitespace().next().unwrap_or_default()
+}
+
+/// Checks the shape of every line of `char_def` before it is handed to the lindera-core
+/// parser: category lines need a name, two 0/1 flags and a length, and range lines need a
+/// valid ascending range and categories defined in the file.
+pub(crate) fn check_char_def(char_def: &str) -> LinderaResult<()> {
+    let mut categories = Vec::new();
+    let mut ranges = Vec::new();
+    for (line_num, line) in source_lines(char_def) {
+        let line = strip_comment(line);
+        if line.is_empty() {
+            continue;
+        }
+        let line_error = |message: String| {
+            LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                "char.def:{}: {}",
+                line_num,
+                message
+            ))
+        };
+        if line.starts_with("0x") {
+            let range =
+                parse_range(line).ok_or_else(|| line_error("invalid range line".to_string()))?;
+            if range.low > range.high || char::from_u32(range.high).is_none() {
+                return Err(line_error(format!(
+                    "invalid code point range 0x{:04X}..0x{:04X}",
+                    range.low, range.high
+                )));
+            }
+            ranges.push((line_num, range));
+        } else {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let valid = match fields.as_slice() {
+                [_, invoke, group, length] => {
+                    (*invoke == "0" || *invoke == "1")
+                        && (*group == "0" || *group == "1")
+                        && u32::from_str(length).is_ok()
+                }
+                _ => false,
+            };
+            if !valid {
+                return Err(line_error(format!("invalid category line {:?}", line)));
+            }
+            categories.push(fields[0]);
+        }
+    }
+
+    for (line_num, range) in ranges {
+        let mut range_categories = range.categories.split_whitespace().peekable();
+        if range_categories.peek().is_none() {
+            return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                "char.def:{}: range without categories",
+                line_num
+            )));
+        }
+        for category in range_categories {
+            if !categories.contains(&category) {
+                return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                    "char.def:{}: undefined category {}",
+                    line_num,
+                    category
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Merges `overrides` over the stock `char_def`.
+///
+/// Category definitions of the overrides replace the stock definitions of the same name and
+/// new categories are added. Range lines are appended after the stock ranges; since the
+/// categories of every range containing a character are combined, ranges overlapping stock
+/// ranges are reported. Returns the merged content and the report messages.
+pub(crate) fn merge_char_def(
+    char_def: &str,
+    overrides: &str,
+    overrides_path: &Path,
+) -> LinderaResult<(String, Vec<String>)> {
+    let mut messages = Vec::new();
+
+    let mut override_categories: HashMap<&str, (usize, &str)> = HashMap::new();
+    let mut override_category_order = Vec::new();
+    let mut override_ranges = Vec::new();
+    for (line_num, line) in source_lines(overrides) {
+        let line = strip_comment(line);
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with("0x") {
+            let range = parse_range(line).ok_or_else(|| {
+                LinderaErrorKind::Parse.with_error(anyhow::anyhow!(
+                    "{}:{}: invalid range line",
+                    overrides_path.display(),
+                    line_num
+                ))
+            })?;
+            override_ranges.push((line_num, line, range));
+        } else {
+            let name = category_name(line);
+            if let Some((prev_line_num, _)) = override_categories.insert(name, (line_num, line)) {
+                return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                    "{}:{}: category {} is already defined at line {}",
+                    overrides_path.display(),
+                    line_num,
+                    name,
+                    prev_line_num
+                )));
+            }
+            override_category_order.push(name);
+        }
+    }
+
+    let mut category_lines = Vec::new();
+    let mut range_lines = Vec::new();
+    let mut stock_ranges = Vec::new();
+    for (line_num, line) in source_lines(char_def) {
+        let line = strip_comment(line);
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with("0x") {
+            if let Some(range) = parse_range(line) {
+                stock_ranges.push((line_num, range));
+            }
+            range_lines.push(line.to_string());
+        } else {
+            let name = category_name(line);
+            match override_categories.remove(name) {
+                Some((override_line_num, override_line)) => {
+                    messages.push(format!(
+                        "{}:{}: category {} overrides char.def:{}",
+                        overrides_path.display(),
+                        override_line_num,
+                        name,
+                        line_num
+                    ));
+                    category_lines.push(override_line.to_string());
+                }
+                None => category_lines.push(line.to_string()),
+            }
+        }
+    }
+    for name in override_category_order {
+        if let Some((_, line)) = override_categories.remove(name) {
+            category_lines.push(line.to_string());
+        }
+    }
+
+    for (line_num, line, range) in override_ranges {
+        for (stock_line_num, stock_range) in &stock_ranges {
+            if range.low <= stock_range.high && stock_range.low <= range.high {
+                messages.push(format!(
+                    "{}:{}: range overlaps char.def:{}, categories {} are combined with {}",
+                    overrides_path.display(),
+                    line_num,
+                    stock_line_num,
+                    range.categories,
+                    stock_range.categories
+                ));
+            }
+        }
+        range_lines.push(line.to_string());
+    }
+
+    category_lines.extend(range_lines);
+    Ok((category_lines.join("\n"), messages))
+}
+
+/// Reads `char.def` of `input_dir`, merged with the overrides file if there is one.
+pub(crate) fn read_char_def(
+    input_dir: &Path,
+    overrides_path: Option<&Path>,
+) -> LinderaResult<String> {
+    let char_def = read_source_file(&input_dir.join("char.def"))?;
+
+    let default_overrides_path = input_dir.join(CHAR_OVERRIDES_FILENAME);
+    let overrides_path = match overrides_path {
+        Some(path) => path,
+        None if default_overrides_path.exists() => &default_overrides_path,
+        None => return Ok(char_def),
+    };
+
+    let overrides = read_source_file(overrides_path)?;
+    let (char_def, messages) = merge_char_def(&char_def, &overrides, overrides_path)?;
+    for message in messages {
+        println!("{}", message);
+    }
+    Ok(char_def)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{check_char_def, merge_char_def};
+    use crate::fixtures::FIXTURE_CHAR_DEF;
+
+    #[test]
+    fn checks_category_and_range_lines() {
+        check_char_def(FIXTURE_CHAR_DEF).unwrap();
+        check_char_def("DEFAULT 0 1 0 # comment\n\n0x0000..0x001F DEFAULT\n").unwrap();
+
+        let error = |char_def: &str| check_char_def(char_def).unwrap_err().to_string();
+        assert!(error("DEFAULT 2 1 0\n").contains("char.def:1: invalid category line"));
+        assert!(error("DEFAULT 0 1\n").contains("invalid category line"));
+        assert!(error("DEFAULT 0 1 0\n0x0039..0x0030 DEFAULT\n")
+            .contains("char.def:2: invalid code point range 0x0039..0x0030"));
+        assert!(error("DEFAULT 0 1 0\n0xD800 DEFAULT\n").contains("invalid code point range"));
+        assert!(error("DEFAULT 0 1 0\n0x0030\n").contains("char.def:2: invalid range line"));
+        assert!(error("DEFAULT 0 1 0\n0x0030 NUMERIC\n")
+            .contains("char.def:2: undefined category NUMERIC"));
+    }
+
+    #[test]
+    fn merges_overrides_over_the_stock_char_def() {
+        let overrides = "\
+KANJI 1 1 3
+EMOJI 0 0 1
+0x1F600..0x1F64F EMOJI
+0x4E00 KANJI EMOJI
+";
+        let (merged, messages) =
+            merge_char_def(FIXTURE_CHAR_DEF, overrides, Path::new("char_overrides.def")).unwrap();
+        check_char_def(&merged).unwrap();
+        let lines: Vec<&str> = merged.lines().collect();
+        assert_eq!(lines[2], "KANJI 1 1 3");
+        assert_eq!(lines[7], "EMOJI 0 0 1");
+        assert_eq!(
+            &lines[lines.len() - 2..],
+            &overrides.lines().collect::<Vec<_>>()[2..]
+        );
+        assert_eq!(
+            messages,
+            vec![
+                "char_overrides.def:1: category KANJI overrides char.def:3".to_string(),
+                "char_overrides.def:4: range overlaps char.def:14, categories KANJI EMOJI \
+                 are combined with KANJI"
+                    .to_string(),
+            ]
+        );
+
+        let err = merge_char_def(
+            FIXTURE_CHAR_DEF,
+            "EMOJI 0 0 1\nEMOJI 1 0 1\n",
+            Path::new("char_overrides.def"),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("char_overrides.def:2: category EMOJI is already defined at line 1"));
+    }
+}
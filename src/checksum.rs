@@ -0,0 +1,132 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::manifest::{Manifest, MANIFEST_FILENAME};
+
+pub const CHECKSUMS_FILENAME: &str = "checksums.txt";
+
+fn sha256_file(path: &Path) -> LinderaResult<String> {
+    let mut file =
+        File::open(path).map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        let len = file
+            .read(&mut buf)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        if len == 0 {
+            break;
+        }
+        hasher.update(&buf[..len]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Writes the SHA-256 checksums of the manifest and every artifact listed in it to
+/// `checksums.txt`, in the same format as `sha256sum`.
+pub fn write_checksums(output_dir: &Path) -> LinderaResult<()> {
+    let manifest = Manifest::load(output_dir)?;
+
+    let wtr_checksums_path = output_dir.join(Path::new(CHECKSUMS_FILENAME));
+    println!("creating {:?}", wtr_checksums_path);
+    let mut wtr_checksums = io::BufWriter::new(
+        File::create(wtr_checksums_path)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
+    );
+    let filenames = std::iter::once(MANIFEST_FILENAME).chain(
+        manifest
+            .artifacts
+            .iter()
+            .map(|artifact| artifact.name.as_str()),
+    );
+    for filename in filenames {
+        let checksum = sha256_file(&output_dir.join(filename))?;
+        writeln!(wtr_checksums, "{}  {}", checksum, filename)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    }
+    wtr_checksums
+        .flush()
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+    Ok(())
+}
+
+/// Recomputes the checksums of the files listed in `checksums.txt` and fails with the
+/// list of mismatching files if any of them differ.
+pub fn verify_output(output_dir: &Path) -> LinderaResult<()> {
+    let checksums_data = fs::read_to_string(output_dir.join(CHECKSUMS_FILENAME))
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+    let mut mismatches = Vec::new();
+    for line in checksums_data.lines() {
+        let mut fields = line.splitn(2, "  ");
+        let (expected, filename) = match (fields.next(), fields.next()) {
+            (Some(expected), Some(filename)) => (expected, filename),
+            _ => {
+                return Err(LinderaErrorKind::Content
+                    .with_error(anyhow::anyhow!("invalid checksum line: {}", line)))
+            }
+        };
+        if sha256_file(&output_dir.join(filename))? != expected {
+            mismatches.push(filename.to_string());
+        }
+    }
+
+    if !mismatches.is_empty() {
+        return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+            "checksum mismatch: {}",
+            mismatches.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{verify_output, CHECKSUMS_FILENAME};
+    use crate::fixtures::build_fixture;
+    use crate::manifest::MANIFEST_FILENAME;
+    use crate::options::BuilderOptions;
+
+    #[test]
+    fn verifies_and_detects_changed_artifacts() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-checksum-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        build_fixture(&input_dir, &output_dir, BuilderOptions::default()).unwrap();
+
+        let checksums = fs::read_to_string(output_dir.join(CHECKSUMS_FILENAME)).unwrap();
+        let first = checksums.lines().next().unwrap();
+        assert!(
+            first.ends_with(&format!("  {}", MANIFEST_FILENAME)),
+            "{}",
+            first
+        );
+        assert_eq!(first.find("  "), Some(64));
+        verify_output(&output_dir).unwrap();
+
+        let options = BuilderOptions::default();
+        let dict_vals = &options.layout.dict_vals;
+        fs::write(output_dir.join(dict_vals), b"changed").unwrap();
+        let message = verify_output(&output_dir).unwrap_err().to_string();
+        assert!(message.contains("checksum mismatch"), "{}", message);
+        assert!(message.contains(dict_vals.as_str()), "{}", message);
+
+        fs::write(output_dir.join(CHECKSUMS_FILENAME), "0123\n").unwrap();
+        let message = verify_output(&output_dir).unwrap_err().to_string();
+        assert!(message.contains("invalid checksum line"), "{}", message);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
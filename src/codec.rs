@@ -0,0 +1,263 @@
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+/// Encodes the details (POS, conjugation, base form, reading, pronunciation) of a word
+/// written to `dict.words`.
+pub trait DetailCodec {
+    /// Appends the encoded details to `buf`.
+    fn encode(&self, details: &[String], buf: &mut Vec<u8>) -> LinderaResult<()>;
+
+    /// Decodes the details at the start of `data`, ignoring any trailing bytes.
+    fn decode(&self, data: &[u8]) -> LinderaResult<Vec<String>>;
+}
+
+/// bincode-serialized `Vec<String>`, the format loaded by Lindera.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BincodeCodec;
+
+impl DetailCodec for BincodeCodec {
+    fn encode(&self, details: &[String], buf: &mut Vec<u8>) -> LinderaResult<()> {
+        bincode::serialize_into(buf, details)
+            .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))
+    }
+
+    fn decode(&self, data: &[u8]) -> LinderaResult<Vec<String>> {
+        bincode::deserialize(data)
+            .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))
+    }
+}
+
+/// The number of fields as one byte, followed by each field as a LEB128 length and its
+/// UTF-8 bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompactCodec;
+
+fn write_varint(buf: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Option<usize> {
+    let mut value = 0usize;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+        shift += 7;
+        if shift >= usize::BITS {
+            return None;
+        }
+    }
+}
+
+impl DetailCodec for CompactCodec {
+    fn encode(&self, details: &[String], buf: &mut Vec<u8>) -> LinderaResult<()> {
+        if details.len() > u8::MAX as usize {
+            return Err(LinderaErrorKind::Serialize
+                .with_error(anyhow::anyhow!("too many detail fields: {}", details.len())));
+        }
+        buf.push(details.len() as u8);
+        for field in details {
+            write_varint(buf, field.len());
+            buf.extend_from_slice(field.as_bytes());
+        }
+        Ok(())
+    }
+
+    fn decode(&self, data: &[u8]) -> LinderaResult<Vec<String>> {
+        let truncated =
+            || LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!("truncated word details"));
+        let fields_num = *data.first().ok_or_else(truncated)? as usize;
+        let mut pos = 1;
+        let mut details = Vec::with_capacity(fields_num);
+        for _ in 0..fields_num {
+            let len = read_varint(data, &mut pos).ok_or_else(truncated)?;
+            let field = data.get(pos..pos + len).ok_or_else(truncated)?;
+            pos += len;
+            details.push(
+                String::from_utf8(field.to_vec())
+                    .map_err(|err| LinderaErrorKind::Decode.with_error(anyhow::anyhow!(err)))?,
+            );
+        }
+        Ok(details)
+    }
+}
+
+/// A JSON array of strings, for debugging.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+impl DetailCodec for JsonCodec {
+    fn encode(&self, details: &[String], buf: &mut Vec<u8>) -> LinderaResult<()> {
+        serde_json::to_writer(buf, details)
+            .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))
+    }
+
+    fn decode(&self, data: &[u8]) -> LinderaResult<Vec<String>> {
+        serde_json::Deserializer::from_slice(data)
+            .into_iter::<Vec<String>>()
+            .next()
+            .ok_or_else(|| {
+                LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!("truncated word details"))
+            })?
+            .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))
+    }
+}
+
+/// The number of fields of the word details struct read by lindera 0.3.
+pub const STRUCT_DETAIL_FIELDS: usize = 9;
+
+/// bincode-serialized struct of nine `String` fields, without the length prefix of a
+/// vector, the format loaded by lindera 0.3. Missing fields are written as `*`, and entries
+/// of more fields, such as those with the tag of the POS mapping or appended columns,
+/// cannot be written.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StructCodec;
+
+impl DetailCodec for StructCodec {
+    fn encode(&self, details: &[String], buf: &mut Vec<u8>) -> LinderaResult<()> {
+        if details.len() > STRUCT_DETAIL_FIELDS {
+            return Err(LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(
+                "lindera 0.3 reads {} detail fields, but the entry has {}: {}",
+                STRUCT_DETAIL_FIELDS,
+                details.len(),
+                details.join(",")
+            )));
+        }
+        for idx in 0..STRUCT_DETAIL_FIELDS {
+            let field = details.get(idx).map(String::as_str).unwrap_or("*");
+            bincode::serialize_into(&mut *buf, field)
+                .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+        }
+        Ok(())
+    }
+
+    fn decode(&self, mut data: &[u8]) -> LinderaResult<Vec<String>> {
+        (0..STRUCT_DETAIL_FIELDS)
+            .map(|_| {
+                bincode::deserialize_from(&mut data)
+                    .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))
+            })
+            .collect()
+    }
+}
+
+/// The detail codecs built in, selectable in `BuilderOptions` and recorded in the manifest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DetailFormat {
+    #[default]
+    Bincode,
+    Compact,
+    Json,
+}
+
+impl DetailFormat {
+    pub fn codec(&self) -> Box<dyn DetailCodec + Send + Sync> {
+        match self {
+            DetailFormat::Bincode => Box::new(BincodeCodec),
+            DetailFormat::Compact => Box::new(CompactCodec),
+            DetailFormat::Json => Box::new(JsonCodec),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compat::LinderaTarget;
+
+    fn details() -> Vec<String> {
+        [
+            "名詞",
+            "固有名詞",
+            "地域",
+            "一般",
+            "*",
+            "*",
+            "東京",
+            "トウキョウ",
+            "トーキョー",
+        ]
+        .iter()
+        .map(|field| field.to_string())
+        .collect()
+    }
+
+    fn assert_round_trip(format: DetailFormat) {
+        let codec = format.codec();
+        for details in [details(), Vec::new(), vec![String::new(), "x".repeat(300)]].iter() {
+            let mut buf = Vec::new();
+            codec.encode(details, &mut buf).unwrap();
+            assert_eq!(&codec.decode(&buf).unwrap(), details, "{:?}", format);
+            // The details of the next word follow in dict.words.
+            codec.encode(&["next".to_string()], &mut buf).unwrap();
+            assert_eq!(&codec.decode(&buf).unwrap(), details, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn bincode_round_trips() {
+        assert_round_trip(DetailFormat::Bincode);
+    }
+
+    #[test]
+    fn compact_round_trips() {
+        assert_round_trip(DetailFormat::Compact);
+    }
+
+    #[test]
+    fn json_round_trips() {
+        assert_round_trip(DetailFormat::Json);
+    }
+
+    #[test]
+    fn struct_round_trips() {
+        let codec = LinderaTarget::V0_3.detail_codec(DetailFormat::default());
+        let mut buf = Vec::new();
+        codec.encode(&details(), &mut buf).unwrap();
+        assert_eq!(codec.decode(&buf).unwrap(), details());
+    }
+
+    #[test]
+    fn struct_fills_missing_fields() {
+        let mut buf = Vec::new();
+        StructCodec
+            .encode(&["記号".to_string(), "一般".to_string()], &mut buf)
+            .unwrap();
+        let decoded = StructCodec.decode(&buf).unwrap();
+        assert_eq!(decoded.len(), STRUCT_DETAIL_FIELDS);
+        assert_eq!(&decoded[..3], &["記号", "一般", "*"]);
+    }
+
+    #[test]
+    fn struct_rejects_extra_fields() {
+        let mut details = details();
+        details.push("トーキョー".to_string());
+        assert!(StructCodec.encode(&details, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn compact_rejects_truncated_details() {
+        let mut buf = Vec::new();
+        CompactCodec.encode(&details(), &mut buf).unwrap();
+        for len in 0..buf.len() {
+            assert!(CompactCodec.decode(&buf[..len]).is_err(), "length {}", len);
+        }
+    }
+
+    #[test]
+    fn compact_rejects_too_many_fields() {
+        let details = vec![String::new(); u8::MAX as usize + 1];
+        assert!(CompactCodec.encode(&details, &mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::manifest::Manifest;
+
+pub const CODEGEN_FILENAME: &str = "dictionary.rs";
+
+/// The gzip level of the compressed artifacts when `BuilderOptions::compression_level` is not
+/// set.
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 9;
+
+fn const_name(artifact_name: &str) -> String {
+    artifact_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>()
+        .to_uppercase()
+}
+
+fn create_parent_dir(path: &Path) -> LinderaResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    }
+    Ok(())
+}
+
+/// Writes `dictionary.rs` to `dest_dir`, embedding every artifact of the built dictionary in
+/// `output_dir` with `include_bytes!`, together with accessor functions returning the data.
+///
+/// With `compress`, the artifacts are gzip-compressed with `level` into `dest_dir` and the
+/// accessors decompress them on first use, which requires the embedding crate to depend on
+/// `flate2`. The generated file is meant to be pulled in with `include!`.
+pub fn generate_rust_source(
+    output_dir: &Path,
+    dest_dir: &Path,
+    compress: bool,
+    level: u32,
+) -> LinderaResult<()> {
+    fs::create_dir_all(dest_dir)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    let dest_dir = dest_dir
+        .canonicalize()
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    let manifest = Manifest::load(output_dir)?;
+
+    let wtr_source_path = dest_dir.join(CODEGEN_FILENAME);
+    println!("creating {:?}", wtr_source_path);
+    let mut wtr_source = io::BufWriter::new(
+        File::create(&wtr_source_path)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
+    );
+
+    let mut source = String::new();
+    source.push_str(&format!(
+        "// Generated by {} {}. Do not edit.\n\n",
+        manifest.builder, manifest.builder_version
+    ));
+    if let Some(dictionary_version) = &manifest.dictionary_version {
+        source.push_str(&format!(
+            "pub const DICTIONARY_VERSION: &str = {:?};\n\n",
+            dictionary_version
+        ));
+    }
+    if compress {
+        source.push_str(
+            "fn decompress(data: &[u8]) -> Vec<u8> {\n    \
+             use std::io::Read;\n    \
+             let mut buf = Vec::new();\n    \
+             flate2::read::GzDecoder::new(data)\n        \
+             .read_to_end(&mut buf)\n        \
+             .expect(\"corrupted embedded dictionary\");\n    \
+             buf\n\
+             }\n\n",
+        );
+    }
+
+    for artifact in &manifest.artifacts {
+        let const_name = const_name(&artifact.name);
+        let fn_name = const_name.to_lowercase();
+        let artifact_path = output_dir.join(&artifact.name);
+
+        if compress {
+            let embedded_path = dest_dir.join(format!("{}.gz", artifact.name));
+            create_parent_dir(&embedded_path)?;
+            let mut rdr_artifact = File::open(&artifact_path)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            let mut encoder = GzEncoder::new(
+                File::create(&embedded_path)
+                    .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
+                Compression::new(level),
+            );
+            io::copy(&mut rdr_artifact, &mut encoder)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            encoder
+                .finish()
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+            source.push_str(&format!(
+                "static {}: &[u8] = include_bytes!({:?});\n\n\
+                 pub fn {}() -> &'static [u8] {{\n    \
+                 static DATA: std::sync::OnceLock<Vec<u8>> = std::sync::OnceLock::new();\n    \
+                 DATA.get_or_init(|| decompress({}))\n\
+                 }}\n\n",
+                const_name,
+                embedded_path.display().to_string(),
+                fn_name,
+                const_name
+            ));
+        } else {
+            let embedded_path = dest_dir.join(&artifact.name);
+            create_parent_dir(&embedded_path)?;
+            // Generating into the output directory itself must not copy a file onto itself.
+            if artifact_path.canonicalize().ok().as_ref() != Some(&embedded_path) {
+                fs::copy(&artifact_path, &embedded_path)
+                    .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            }
+
+            source.push_str(&format!(
+                "static {}: &[u8] = include_bytes!({:?});\n\n\
+                 pub fn {}() -> &'static [u8] {{\n    \
+                 {}\n\
+                 }}\n\n",
+                const_name,
+                embedded_path.display().to_string(),
+                fn_name,
+                const_name
+            ));
+        }
+    }
+
+    wtr_source
+        .write_all(source.as_bytes())
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    wtr_source
+        .flush()
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::{const_name, generate_rust_source, CODEGEN_FILENAME, DEFAULT_COMPRESSION_LEVEL};
+    use crate::fixtures::build_fixture;
+    use crate::options::BuilderOptions;
+
+    #[test]
+    fn names_constants_after_the_artifacts() {
+        assert_eq!(const_name("dict.da"), "DICT_DA");
+        assert_eq!(const_name("columns/pos-1.bin"), "COLUMNS_POS_1_BIN");
+    }
+
+    #[test]
+    fn embeds_every_artifact() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-codegen-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        let options = BuilderOptions {
+            dictionary_version: Some("2020.01.01".to_string()),
+            ..BuilderOptions::default()
+        };
+        build_fixture(&input_dir, &output_dir, options).unwrap();
+        let dict_da = fs::read(output_dir.join("dict.da")).unwrap();
+
+        let plain_dir = base_dir.join("plain");
+        generate_rust_source(&output_dir, &plain_dir, false, DEFAULT_COMPRESSION_LEVEL).unwrap();
+        let source = fs::read_to_string(plain_dir.join(CODEGEN_FILENAME)).unwrap();
+        assert!(source.contains("pub const DICTIONARY_VERSION: &str = \"2020.01.01\";"));
+        assert!(source.contains("pub fn dict_da() -> &'static [u8] {\n    DICT_DA\n}"));
+        assert!(!source.contains("fn decompress"));
+        assert_eq!(fs::read(plain_dir.join("dict.da")).unwrap(), dict_da);
+
+        let compressed_dir = base_dir.join("compressed");
+        generate_rust_source(
+            &output_dir,
+            &compressed_dir,
+            true,
+            DEFAULT_COMPRESSION_LEVEL,
+        )
+        .unwrap();
+        let source = fs::read_to_string(compressed_dir.join(CODEGEN_FILENAME)).unwrap();
+        assert!(source.contains("fn decompress"));
+        assert!(source.contains("DATA.get_or_init(|| decompress(DICT_DA))"));
+        let mut decompressed = Vec::new();
+        GzDecoder::new(File::open(compressed_dir.join("dict.da.gz")).unwrap())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, dict_da);
+
+        // Generating into the output directory keeps the artifacts in place.
+        generate_rust_source(&output_dir, &output_dir, false, DEFAULT_COMPRESSION_LEVEL).unwrap();
+        assert_eq!(fs::read(output_dir.join("dict.da")).unwrap(), dict_da);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
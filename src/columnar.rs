@@ -0,0 +1,153 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::CsvRow;
+
+/// The directory, in the output directory, holding the columnar word details.
+pub const COLUMNS_DIRNAME: &str = "columns";
+
+/// The word detail fields, in the order of `dict.words` records.
+pub const DETAIL_FIELDS: [&str; 9] = [
+    "pos_level1",
+    "pos_level2",
+    "pos_level3",
+    "pos_level4",
+    "conjugation_type",
+    "conjugate_form",
+    "base_form",
+    "reading",
+    "pronunciation",
+];
+
+/// The names of the files of the columnar word details, relative to the output directory.
+pub fn column_filenames() -> Vec<String> {
+    DETAIL_FIELDS
+        .iter()
+        .flat_map(|field| {
+            vec![
+                format!("{}/{}.data", COLUMNS_DIRNAME, field),
+                format!("{}/{}.idx", COLUMNS_DIRNAME, field),
+            ]
+        })
+        .collect()
+}
+
+/// Writes each word detail field of the sorted `rows` to its own column: `<field>.data`
+/// holds the concatenated UTF-8 values in word_id order, and `<field>.idx` the `u32` start
+/// offsets of the values followed by the end offset of the last one. The files created are
+/// reported with `print`.
+pub(crate) fn write_columns(
+    output_dir: &Path,
+    rows: &[CsvRow],
+    print: &dyn Fn(&str),
+) -> LinderaResult<()> {
+    let columns_dir = output_dir.join(COLUMNS_DIRNAME);
+    fs::create_dir_all(&columns_dir)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+    for (field_idx, field) in DETAIL_FIELDS.iter().enumerate() {
+        let wtr_data_path = columns_dir.join(format!("{}.data", field));
+        print(&format!("creating {:?}", wtr_data_path));
+        let mut wtr_data = io::BufWriter::new(
+            File::create(wtr_data_path)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
+        );
+        let mut wtr_idx = io::BufWriter::new(
+            File::create(columns_dir.join(format!("{}.idx", field)))
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
+        );
+
+        let mut offset = 0u32;
+        for row in rows {
+            let value = row.detail_fields()[field_idx];
+            wtr_idx
+                .write_u32::<LittleEndian>(offset)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            wtr_data
+                .write_all(value.as_bytes())
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            offset += value.len() as u32;
+        }
+        wtr_idx
+            .write_u32::<LittleEndian>(offset)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+        wtr_data
+            .flush()
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        wtr_idx
+            .flush()
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use byteorder::{ByteOrder, LittleEndian};
+
+    use super::{column_filenames, COLUMNS_DIRNAME};
+    use crate::fixtures::build_fixture;
+    use crate::manifest::Manifest;
+    use crate::options::BuilderOptions;
+
+    #[test]
+    fn writes_a_column_per_detail_field() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-columnar-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        let options = BuilderOptions {
+            columnar_details: true,
+            ..BuilderOptions::default()
+        };
+        build_fixture(&input_dir, &output_dir, options).unwrap();
+
+        let columns_dir = output_dir.join(COLUMNS_DIRNAME);
+        let column = |field: &str| {
+            let data = fs::read_to_string(columns_dir.join(format!("{}.data", field))).unwrap();
+            let offsets: Vec<usize> = fs::read(columns_dir.join(format!("{}.idx", field)))
+                .unwrap()
+                .chunks(4)
+                .map(|bytes| LittleEndian::read_u32(bytes) as usize)
+                .collect();
+            assert_eq!(offsets.last(), Some(&data.len()));
+            offsets
+                .windows(2)
+                .map(|range| data[range[0]..range[1]].to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            column("reading"),
+            vec!["イク", "ニ", "トウキョウ", "トウキョウスカイツリー", "イク"]
+        );
+        assert_eq!(
+            column("pos_level1"),
+            vec!["動詞", "助詞", "名詞", "名詞", "動詞"]
+        );
+
+        let manifest = Manifest::load(&output_dir).unwrap();
+        for name in column_filenames() {
+            assert!(
+                manifest
+                    .artifacts
+                    .iter()
+                    .any(|artifact| artifact.name == name),
+                "{} is missing from the manifest",
+                name
+            );
+        }
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
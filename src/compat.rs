@@ -0,0 +1,68 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::{LinderaError, LinderaErrorKind};
+
+use crate::codec::{DetailCodec, DetailFormat, StructCodec};
+
+/// The lindera version the artifacts are written for, so that one builder release can
+/// serve deployments running older lindera versions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinderaTarget {
+    /// The lindera-core this builder is built with.
+    #[default]
+    #[serde(rename = "0.8")]
+    V0_8,
+    /// lindera 0.3, which reads the word details as a struct of nine fields rather than a
+    /// vector. The other artifacts are written as for 0.8: lindera-core 0.3 to 0.8 derive the
+    /// bincode serialization of `CharacterDefinitions` (`category_definitions`,
+    /// `category_names` and the `mapping` lookup table) and of `UnknownDictionary`
+    /// (`category_references` and `costs`) from the same fields, and write the word entries
+    /// and the cost matrix as the same little-endian integers, so `char_def.bin`, `unk.bin`,
+    /// `dict.vals` and `matrix.mtx` need no conversion.
+    #[serde(rename = "0.3")]
+    V0_3,
+}
+
+impl LinderaTarget {
+    /// The codec of the word details for this target. `detail_format` is only used by the
+    /// targets reading a vector of fields.
+    pub fn detail_codec(&self, detail_format: DetailFormat) -> Box<dyn DetailCodec + Send + Sync> {
+        match self {
+            LinderaTarget::V0_8 => detail_format.codec(),
+            LinderaTarget::V0_3 => Box::new(StructCodec),
+        }
+    }
+}
+
+impl FromStr for LinderaTarget {
+    type Err = LinderaError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "0.8" => Ok(LinderaTarget::V0_8),
+            "0.3" => Ok(LinderaTarget::V0_3),
+            _ => Err(LinderaErrorKind::Args
+                .with_error(anyhow::anyhow!("unsupported lindera target {}", value))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LinderaTarget;
+
+    #[test]
+    fn parses_lindera_targets() {
+        for (name, target) in [("0.8", LinderaTarget::V0_8), ("0.3", LinderaTarget::V0_3)] {
+            assert_eq!(name.parse::<LinderaTarget>().unwrap(), target);
+            assert_eq!(
+                serde_json::to_string(&target).unwrap(),
+                format!("\"{}\"", name)
+            );
+        }
+        assert!("0.9".parse::<LinderaTarget>().is_err());
+        assert_eq!(LinderaTarget::default(), LinderaTarget::V0_8);
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+
+use crate::CsvRow;
+
+/// Minimum number of characters of the entries a compound is split into, so that
+/// single-character entries do not split every word.
+const MIN_PART_CHARS: usize = 2;
+
+/// Minimum number of characters of a surface to look for split points in.
+const MIN_COMPOUND_CHARS: usize = 2 * MIN_PART_CHARS;
+
+/// Finds the split points of long surfaces that are sequences of shorter dictionary entries.
+pub(crate) struct CompoundSplitter<'a> {
+    surfaces: HashSet<&'a str>,
+    max_part_chars: usize,
+}
+
+impl<'a> CompoundSplitter<'a> {
+    pub(crate) fn new(rows: &[CsvRow<'a>]) -> Self {
+        let surfaces: HashSet<&'a str> = rows
+            .iter()
+            .map(|row| row.surface_form)
+            .filter(|surface| surface.chars().count() >= MIN_PART_CHARS)
+            .collect();
+        let max_part_chars = surfaces
+            .iter()
+            .map(|surface| surface.chars().count())
+            .max()
+            .unwrap_or(0);
+        CompoundSplitter {
+            surfaces,
+            max_part_chars,
+        }
+    }
+
+    /// The character offsets splitting `surface` into the fewest shorter entries, or an empty
+    /// list if it cannot be split.
+    pub(crate) fn split_points(&self, surface: &str) -> Vec<usize> {
+        let offsets: Vec<usize> = surface
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain(std::iter::once(surface.len()))
+            .collect();
+        let chars = offsets.len() - 1;
+        if chars < MIN_COMPOUND_CHARS {
+            return Vec::new();
+        }
+
+        // fewest[end] is the fewest entries covering the first `end` characters, with the
+        // start of the last one.
+        let mut fewest: Vec<Option<(usize, usize)>> = vec![None; chars + 1];
+        fewest[0] = Some((0, 0));
+        for end in MIN_PART_CHARS..=chars {
+            let min_start = end.saturating_sub(self.max_part_chars.min(chars - 1));
+            for start in min_start..=(end - MIN_PART_CHARS) {
+                let parts = match fewest[start] {
+                    Some((parts, _)) => parts + 1,
+                    None => continue,
+                };
+                if fewest[end].is_some_and(|(best, _)| best <= parts) {
+                    continue;
+                }
+                if self
+                    .surfaces
+                    .contains(&surface[offsets[start]..offsets[end]])
+                {
+                    fewest[end] = Some((parts, start));
+                }
+            }
+        }
+
+        let mut points = Vec::new();
+        let mut end = chars;
+        while end > 0 {
+            match fewest[end] {
+                Some((_, start)) => {
+                    if start > 0 {
+                        points.push(start);
+                    }
+                    end = start;
+                }
+                None => return Vec::new(),
+            }
+        }
+        points.reverse();
+        points
+    }
+}
+
+/// The split points as written to the word details: the character offsets joined with `/`,
+/// or `*` if there are none.
+pub(crate) fn format_split_points(points: &[usize]) -> String {
+    if points.is_empty() {
+        return "*".to_string();
+    }
+    points
+        .iter()
+        .map(|point| point.to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_split_points, CompoundSplitter};
+    use crate::CsvRow;
+
+    fn rows(surfaces: &[&str]) -> Vec<String> {
+        surfaces
+            .iter()
+            .map(|surface| {
+                format!(
+                    "{},1,1,3000,名詞,固有名詞,一般,*,*,*,{},*,*",
+                    surface, surface
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_into_the_fewest_entries() {
+        let lines = rows(&["東京", "スカイ", "ツリー", "東京スカイツリー", "ス"]);
+        let csv_rows: Vec<CsvRow> = lines
+            .iter()
+            .map(|line| CsvRow::from_line(line).unwrap())
+            .collect();
+        let splitter = CompoundSplitter::new(&csv_rows);
+        assert_eq!(splitter.split_points("東京スカイツリー"), vec![2, 5]);
+        assert_eq!(splitter.split_points("スカイツリー"), vec![3]);
+        assert!(splitter.split_points("東京タワー").is_empty());
+        assert!(splitter.split_points("東京").is_empty());
+
+        let lines = rows(&["東京", "スカイ", "ツリー", "スカイツリー"]);
+        let csv_rows: Vec<CsvRow> = lines
+            .iter()
+            .map(|line| CsvRow::from_line(line).unwrap())
+            .collect();
+        let splitter = CompoundSplitter::new(&csv_rows);
+        assert_eq!(splitter.split_points("東京スカイツリー"), vec![2]);
+    }
+
+    #[test]
+    fn formats_split_points() {
+        assert_eq!(format_split_points(&[2, 5]), "2/5");
+        assert_eq!(format_split_points(&[]), "*");
+    }
+}
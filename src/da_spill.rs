@@ -0,0 +1,502 @@
+//! Construction of the double array in a memory-mapped file, for keysets whose trie does not
+//! fit in memory.
+//!
+//! The units are written directly into the mapped file, so the page cache can write them
+//! back and evict them, and the working state of the construction is kept only for the last
+//! `NUM_EXTRA_BLOCKS` blocks, whose units may still be taken. Older blocks are closed and
+//! their unused units given labels no transition can match. The layout of the units is the
+//! one read by `yada::DoubleArray`.
+
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+use byteorder::{ByteOrder, LittleEndian};
+use memmap2::{Mmap, MmapMut};
+use yada::DoubleArray;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+const BLOCK_SIZE: u32 = 256;
+const NUM_EXTRA_BLOCKS: u32 = 16;
+const NUM_EXTRAS: usize = (BLOCK_SIZE * NUM_EXTRA_BLOCKS) as usize;
+
+const UPPER_MASK: u32 = 0xFF << 21;
+const LOWER_MASK: u32 = 0xFF;
+
+const UNIT_SIZE: usize = 4;
+
+/// The number of units the scratch file is first sized for.
+const INITIAL_UNITS: usize = 1 << 16;
+
+fn io_error(err: std::io::Error) -> lindera_core::error::LinderaError {
+    LinderaErrorKind::Io.with_error(anyhow::anyhow!(err))
+}
+
+fn set_has_leaf(unit: u32) -> u32 {
+    unit | (1 << 8)
+}
+
+fn set_value(value: u32) -> LinderaResult<u32> {
+    if value >= 1 << 31 {
+        return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+            "the value {} does not fit in 31 bits",
+            value
+        )));
+    }
+    Ok(value | (1 << 31))
+}
+
+fn set_label(unit: u32, label: u8) -> u32 {
+    (unit & !0xFF) | u32::from(label)
+}
+
+fn set_offset(unit: u32, offset: u32) -> LinderaResult<u32> {
+    if offset >= 1 << 29 {
+        return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+            "the double array offset {} does not fit in 29 bits",
+            offset
+        )));
+    }
+    let unit = unit & ((1 << 31) | (1 << 8) | 0xFF);
+    if offset < 1 << 21 {
+        Ok(unit | (offset << 10))
+    } else {
+        Ok(unit | (offset << 2) | (1 << 9))
+    }
+}
+
+/// The byte of `key` at `depth`, 0 past its end.
+fn key_label(key: &[u8], depth: usize) -> u8 {
+    key.get(depth).copied().unwrap_or(0)
+}
+
+/// The units of the double array, in a memory-mapped file grown as blocks are added.
+struct Units {
+    file: File,
+    mmap: MmapMut,
+    len: u32,
+}
+
+impl Units {
+    fn create(path: &Path) -> LinderaResult<Units> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .map_err(io_error)?;
+        file.set_len((INITIAL_UNITS * UNIT_SIZE) as u64)
+            .map_err(io_error)?;
+        let mmap = unsafe { MmapMut::map_mut(&file) }.map_err(io_error)?;
+        Ok(Units { file, mmap, len: 0 })
+    }
+
+    fn get(&self, id: u32) -> u32 {
+        let pos = id as usize * UNIT_SIZE;
+        LittleEndian::read_u32(&self.mmap[pos..pos + UNIT_SIZE])
+    }
+
+    fn set(&mut self, id: u32, unit: u32) {
+        let pos = id as usize * UNIT_SIZE;
+        LittleEndian::write_u32(&mut self.mmap[pos..pos + UNIT_SIZE], unit);
+    }
+
+    /// Grows the units to `len`, the new units being 0.
+    fn resize(&mut self, len: u32) -> LinderaResult<()> {
+        let bytes = len as usize * UNIT_SIZE;
+        if bytes > self.mmap.len() {
+            let capacity = bytes.max(self.mmap.len() * 2);
+            self.file.set_len(capacity as u64).map_err(io_error)?;
+            self.mmap = unsafe { MmapMut::map_mut(&self.file) }.map_err(io_error)?;
+        }
+        self.len = len;
+        Ok(())
+    }
+
+    /// Writes the units back and truncates the file to them.
+    fn finish(self) -> LinderaResult<()> {
+        self.mmap.flush().map_err(io_error)?;
+        drop(self.mmap);
+        self.file
+            .set_len((self.len as usize * UNIT_SIZE) as u64)
+            .map_err(io_error)?;
+        self.file.sync_all().map_err(io_error)
+    }
+}
+
+/// The construction state of a unit of the last blocks.
+#[derive(Debug, Clone, Copy, Default)]
+struct Extra {
+    prev: u32,
+    next: u32,
+    /// Whether the unit is taken.
+    is_fixed: bool,
+    /// Whether the unit is the offset of a node.
+    is_used: bool,
+}
+
+struct SpillBuilder {
+    units: Units,
+    extras: Vec<Extra>,
+    /// The first unit of the circular list of the units not taken, or `units.len` when
+    /// every unit is taken.
+    extras_head: u32,
+    labels: Vec<u8>,
+}
+
+impl SpillBuilder {
+    fn extra(&self, id: u32) -> &Extra {
+        &self.extras[id as usize % NUM_EXTRAS]
+    }
+
+    fn extra_mut(&mut self, id: u32) -> &mut Extra {
+        &mut self.extras[id as usize % NUM_EXTRAS]
+    }
+
+    fn build<K: AsRef<[u8]>>(&mut self, keyset: &[(K, u32)]) -> LinderaResult<()> {
+        self.reserve_id(0)?;
+        self.extra_mut(0).is_used = true;
+        let root = set_offset(self.units.get(0), 1)?;
+        self.units.set(0, set_label(root, 0));
+        if !keyset.is_empty() {
+            self.build_from_keyset(keyset, 0, keyset.len(), 0, 0)?;
+        }
+
+        let num_blocks = self.units.len / BLOCK_SIZE;
+        for block_id in num_blocks.saturating_sub(NUM_EXTRA_BLOCKS)..num_blocks {
+            self.fix_block(block_id)?;
+        }
+        Ok(())
+    }
+
+    fn build_from_keyset<K: AsRef<[u8]>>(
+        &mut self,
+        keyset: &[(K, u32)],
+        mut begin: usize,
+        end: usize,
+        depth: usize,
+        dic_id: u32,
+    ) -> LinderaResult<()> {
+        let offset = self.arrange_from_keyset(keyset, begin, end, depth, dic_id)?;
+
+        while begin < end && key_label(keyset[begin].0.as_ref(), depth) == 0 {
+            begin += 1;
+        }
+        if begin == end {
+            return Ok(());
+        }
+
+        let mut last_begin = begin;
+        let mut last_label = key_label(keyset[begin].0.as_ref(), depth);
+        for idx in begin + 1..end {
+            let label = key_label(keyset[idx].0.as_ref(), depth);
+            if label != last_label {
+                let child_id = offset ^ u32::from(last_label);
+                self.build_from_keyset(keyset, last_begin, idx, depth + 1, child_id)?;
+                last_begin = idx;
+                last_label = label;
+            }
+        }
+        let child_id = offset ^ u32::from(last_label);
+        self.build_from_keyset(keyset, last_begin, end, depth + 1, child_id)
+    }
+
+    /// Places the children of the node `dic_id`, the labels of the keys at `depth`, and
+    /// returns their offset.
+    fn arrange_from_keyset<K: AsRef<[u8]>>(
+        &mut self,
+        keyset: &[(K, u32)],
+        begin: usize,
+        end: usize,
+        depth: usize,
+        dic_id: u32,
+    ) -> LinderaResult<u32> {
+        self.labels.clear();
+        let mut value = None;
+        for (key, key_value) in &keyset[begin..end] {
+            let label = key_label(key.as_ref(), depth);
+            if label == 0 && value.is_none() {
+                value = Some(*key_value);
+            }
+            match self.labels.last() {
+                Some(last) if label == *last => (),
+                Some(last) if label < *last => {
+                    return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                        "the keys of the double array are not sorted at {:?}",
+                        String::from_utf8_lossy(key.as_ref())
+                    )));
+                }
+                _ => self.labels.push(label),
+            }
+        }
+
+        let offset = self.find_valid_offset(dic_id);
+        let unit = set_offset(self.units.get(dic_id), dic_id ^ offset)?;
+        self.units.set(dic_id, unit);
+
+        for idx in 0..self.labels.len() {
+            let label = self.labels[idx];
+            let child_id = offset ^ u32::from(label);
+            self.reserve_id(child_id)?;
+            if label == 0 {
+                let unit = set_has_leaf(self.units.get(dic_id));
+                self.units.set(dic_id, unit);
+                self.units
+                    .set(child_id, set_value(value.unwrap_or_default())?);
+            } else {
+                let unit = set_label(self.units.get(child_id), label);
+                self.units.set(child_id, unit);
+            }
+        }
+        self.extra_mut(offset).is_used = true;
+
+        Ok(offset)
+    }
+
+    fn find_valid_offset(&self, id: u32) -> u32 {
+        if self.extras_head >= self.units.len {
+            return self.units.len | (id & LOWER_MASK);
+        }
+
+        let mut unfixed_id = self.extras_head;
+        loop {
+            let offset = unfixed_id ^ u32::from(self.labels[0]);
+            if self.is_valid_offset(id, offset) {
+                return offset;
+            }
+            unfixed_id = self.extra(unfixed_id).next;
+            if unfixed_id == self.extras_head {
+                break;
+            }
+        }
+        self.units.len | (id & LOWER_MASK)
+    }
+
+    fn is_valid_offset(&self, id: u32, offset: u32) -> bool {
+        if self.extra(offset).is_used {
+            return false;
+        }
+        let relative_offset = id ^ offset;
+        if relative_offset & LOWER_MASK != 0 && relative_offset & UPPER_MASK != 0 {
+            return false;
+        }
+        self.labels[1..]
+            .iter()
+            .all(|label| !self.extra(offset ^ u32::from(*label)).is_fixed)
+    }
+
+    /// Takes the unit `id` out of the list of the units not taken.
+    fn reserve_id(&mut self, id: u32) -> LinderaResult<()> {
+        if id >= self.units.len {
+            self.expand_units()?;
+        }
+        if id == self.extras_head {
+            self.extras_head = self.extra(id).next;
+            if self.extras_head == id {
+                self.extras_head = self.units.len;
+            }
+        }
+        let Extra { prev, next, .. } = *self.extra(id);
+        self.extra_mut(prev).next = next;
+        self.extra_mut(next).prev = prev;
+        self.extra_mut(id).is_fixed = true;
+        Ok(())
+    }
+
+    /// Adds a block of units, closing the oldest block whose state is kept.
+    fn expand_units(&mut self) -> LinderaResult<()> {
+        let src_num_units = self.units.len;
+        let src_num_blocks = src_num_units / BLOCK_SIZE;
+        let dest_num_units = src_num_units + BLOCK_SIZE;
+        let dest_num_blocks = src_num_blocks + 1;
+
+        if dest_num_blocks > NUM_EXTRA_BLOCKS {
+            self.fix_block(src_num_blocks - NUM_EXTRA_BLOCKS)?;
+        }
+        self.units.resize(dest_num_units)?;
+        for id in src_num_units..dest_num_units {
+            *self.extra_mut(id) = Extra::default();
+        }
+
+        for id in src_num_units + 1..dest_num_units {
+            self.extra_mut(id - 1).next = id;
+            self.extra_mut(id).prev = id - 1;
+        }
+        self.extra_mut(src_num_units).prev = dest_num_units - 1;
+        self.extra_mut(dest_num_units - 1).next = src_num_units;
+
+        let head = self.extras_head;
+        let head_prev = self.extra(head).prev;
+        self.extra_mut(src_num_units).prev = head_prev;
+        self.extra_mut(dest_num_units - 1).next = head;
+        self.extra_mut(head_prev).next = src_num_units;
+        self.extra_mut(head).prev = dest_num_units - 1;
+        Ok(())
+    }
+
+    /// Takes the remaining units of a block, labelled so that no transition reaches them.
+    fn fix_block(&mut self, block_id: u32) -> LinderaResult<()> {
+        let begin = block_id * BLOCK_SIZE;
+        let end = begin + BLOCK_SIZE;
+
+        let unused_offset = (begin..end)
+            .find(|offset| !self.extra(*offset).is_used)
+            .unwrap_or(0);
+        for id in begin..end {
+            if !self.extra(id).is_fixed {
+                self.reserve_id(id)?;
+                let unit = set_label(self.units.get(id), (id ^ unused_offset) as u8);
+                self.units.set(id, unit);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds the double array of `keyset`, sorted by key, into the file at `path`, then checks
+/// that the written trie maps every key to its value.
+pub(crate) fn build_double_array<K: AsRef<[u8]>>(
+    keyset: &[(K, u32)],
+    path: &Path,
+) -> LinderaResult<()> {
+    let mut builder = SpillBuilder {
+        units: Units::create(path)?,
+        extras: vec![Extra::default(); NUM_EXTRAS],
+        extras_head: 0,
+        labels: Vec::new(),
+    };
+    builder.build(keyset)?;
+    builder.units.finish()?;
+
+    let file = File::open(path).map_err(io_error)?;
+    let da = DoubleArray::new(unsafe { Mmap::map(&file) }.map_err(io_error)?);
+    for (key, value) in keyset {
+        if da.exact_match_search(key.as_ref()) != Some(*value) {
+            return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                "the double array built in {} does not map {:?} to {}",
+                path.display(),
+                String::from_utf8_lossy(key.as_ref()),
+                value
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use yada::builder::DoubleArrayBuilder;
+    use yada::DoubleArray;
+
+    use super::{build_double_array, BLOCK_SIZE, NUM_EXTRA_BLOCKS, UNIT_SIZE};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-da-spill-{}-{}.da",
+            name,
+            std::process::id()
+        ))
+    }
+
+    /// Builds `keys`, sorted, with the double array of yada and the spilled one, and checks
+    /// that both find the same values for the keys and for `queries`. Returns the number of
+    /// units of the spilled double array.
+    fn assert_same_searches(name: &str, mut keys: Vec<Vec<u8>>, queries: &[&[u8]]) -> usize {
+        keys.sort();
+        keys.dedup();
+        let keyset: Vec<(&[u8], u32)> = keys
+            .iter()
+            .enumerate()
+            .map(|(idx, key)| (key.as_slice(), idx as u32))
+            .collect();
+
+        let path = temp_path(name);
+        build_double_array(&keyset, &path).unwrap();
+        let spilled_bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let spilled = DoubleArray::new(spilled_bytes.clone());
+        let expected = DoubleArray::new(DoubleArrayBuilder::build(&keyset).unwrap());
+
+        for query in keys
+            .iter()
+            .map(|key| key.as_slice())
+            .chain(queries.iter().copied())
+        {
+            assert_eq!(
+                spilled.exact_match_search(query),
+                expected.exact_match_search(query),
+                "exact match of {:?}",
+                query
+            );
+            assert_eq!(
+                spilled.common_prefix_search(query).collect::<Vec<_>>(),
+                expected.common_prefix_search(query).collect::<Vec<_>>(),
+                "common prefixes of {:?}",
+                query
+            );
+        }
+        spilled_bytes.len() / UNIT_SIZE
+    }
+
+    #[test]
+    fn builds_the_searches_of_yada_for_shared_prefixes() {
+        let keys = [
+            "東京",
+            "東京都",
+            "東京スカイツリー",
+            "東",
+            "に",
+            "行く",
+            "いく",
+            "a",
+            "ab",
+            "abc",
+        ]
+        .iter()
+        .map(|key| key.as_bytes().to_vec())
+        .collect();
+        let queries: [&[u8]; 5] = [
+            "東京スカイツリーに行く".as_bytes(),
+            "東京タワー".as_bytes(),
+            "大阪".as_bytes(),
+            b"abcd",
+            b"",
+        ];
+        assert_same_searches("prefixes", keys, &queries);
+    }
+
+    #[test]
+    fn builds_empty_and_single_key_keysets() {
+        let queries: [&[u8]; 3] = [b"a", b"ab", b""];
+        assert_same_searches("empty", Vec::new(), &queries);
+        assert_same_searches("single", vec![b"a".to_vec()], &queries);
+    }
+
+    #[test]
+    fn builds_keysets_spanning_more_blocks_than_kept() {
+        // Keys with the lowest and highest labels, and enough of them that the oldest
+        // blocks are closed while the trie grows.
+        let mut keys = Vec::new();
+        for n in 0..4000u32 {
+            keys.push(format!("{:05}", n.wrapping_mul(7919) % 100_000).into_bytes());
+            keys.push(vec![0x01, (n % 255) as u8 + 1, (n / 255) as u8 + 1]);
+            keys.push(vec![0xFF, 0xFF - (n % 255) as u8, (n / 255) as u8 + 1]);
+        }
+        let queries: [&[u8]; 3] = [b"000000", &[0x01, 0x01], &[0xFF, 0xFF, 0x01, 0x01]];
+        let num_units = assert_same_searches("blocks", keys, &queries);
+        assert!(num_units > (BLOCK_SIZE * NUM_EXTRA_BLOCKS) as usize);
+    }
+
+    #[test]
+    fn rejects_unsorted_keysets() {
+        let path = temp_path("unsorted");
+        let keyset: [(&[u8], u32); 2] = [(b"b", 0), (b"a", 1)];
+        assert!(build_double_array(&keyset, &path).is_err());
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use lindera_core::LinderaResult;
+
+use crate::reader::DictionaryReader;
+use crate::{CsvRow, IpadicNeologdBuilder};
+
+impl IpadicNeologdBuilder {
+    /// Drops the entries whose surface and POS are already in the reference dictionary of
+    /// the options, so that only the delta to it is built.
+    pub(crate) fn drop_reference_entries<'a>(
+        &self,
+        rows: Vec<CsvRow<'a>>,
+    ) -> LinderaResult<Vec<CsvRow<'a>>> {
+        let reference_dir = match &self.options.reference_dict_dir {
+            Some(reference_dir) => reference_dir,
+            None => return Ok(rows),
+        };
+        let reader = DictionaryReader::open(reference_dir)?;
+        let (kept, dropped_rows) = self.retain_new_entries(&reader, rows)?;
+        self.print(&format!(
+            "dropped {} entries already in {:?}, {} left",
+            dropped_rows,
+            reference_dir,
+            kept.len()
+        ));
+        Ok(kept)
+    }
+
+    /// Keeps the rows whose surface and POS are not in the reference dictionary `reader`,
+    /// returning them with the number of rows dropped.
+    pub(crate) fn retain_new_entries<'a>(
+        &self,
+        reader: &DictionaryReader,
+        rows: Vec<CsvRow<'a>>,
+    ) -> LinderaResult<(Vec<CsvRow<'a>>, usize)> {
+        // The POS levels of the reference entries of each surface, looked up once.
+        let mut reference_pos: HashMap<&str, Vec<Vec<String>>> = HashMap::new();
+        let mut kept = Vec::with_capacity(rows.len());
+        let mut dropped_rows = 0;
+        for row in rows {
+            if !reference_pos.contains_key(row.surface_form) {
+                let pos = reader
+                    .lookup(row.surface_form)
+                    .iter()
+                    .map(|word_entry| {
+                        let mut details = reader.details(word_entry.word_id.0)?;
+                        details.truncate(4);
+                        Ok(details)
+                    })
+                    .collect::<LinderaResult<Vec<_>>>()?;
+                reference_pos.insert(row.surface_form, pos);
+            }
+            let row_pos = [
+                row.pos_level1,
+                row.pos_level2,
+                row.pos_level3,
+                row.pos_level4,
+            ];
+            if reference_pos[row.surface_form]
+                .iter()
+                .any(|pos| pos.iter().map(String::as_str).eq(row_pos.iter().copied()))
+            {
+                self.log_skipped_row(&row, "reference dictionary");
+                dropped_rows += 1;
+                continue;
+            }
+            kept.push(row);
+        }
+        Ok((kept, dropped_rows))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use lindera_core::dictionary_builder::DictionaryBuilder;
+
+    use crate::fixtures::{build_fixture, FIXTURE_CSV_FILENAME, FIXTURE_ROWS};
+    use crate::options::BuilderOptions;
+    use crate::reader::DictionaryReader;
+    use crate::IpadicNeologdBuilder;
+
+    #[test]
+    fn builds_only_entries_missing_from_the_reference() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-delta-{}",
+            std::process::id()
+        ));
+        let (input_dir, reference_dir) = (base_dir.join("input"), base_dir.join("reference"));
+        build_fixture(&input_dir, &reference_dir, BuilderOptions::default()).unwrap();
+
+        let mut csv = format!("{}\n", FIXTURE_ROWS.join("\n"));
+        csv.push_str("東京,1,1,3500,名詞,固有名詞,人名,姓,*,*,東京,トウキョウ,トウキョウ\n");
+        csv.push_str("渋谷,1,1,3000,名詞,固有名詞,地域,一般,*,*,渋谷,シブヤ,シブヤ\n");
+        fs::write(input_dir.join(FIXTURE_CSV_FILENAME), csv).unwrap();
+        let output_dir = base_dir.join("output");
+        let options = BuilderOptions {
+            reference_dict_dir: Some(reference_dir),
+            ..BuilderOptions::default()
+        };
+        IpadicNeologdBuilder::with_options(options)
+            .build_dictionary(&input_dir, &output_dir)
+            .unwrap();
+
+        let reader = DictionaryReader::open(&output_dir).unwrap();
+        assert_eq!(reader.num_words(), 2);
+        let tokyo = reader.lookup("東京");
+        assert_eq!(tokyo.len(), 1);
+        assert_eq!(reader.details(tokyo[0].word_id.0).unwrap()[2], "人名");
+        assert_eq!(reader.lookup("渋谷").len(), 1);
+        assert!(reader.lookup("行く").is_empty());
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
@@ -0,0 +1,380 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::merge::SourceLayer;
+use crate::{csv_filenames, matrix, IpadicNeologdBuilder};
+
+/// Approximate ratio of the size of the lexicon artifacts to the size of the CSV files.
+const OUTPUT_SIZE_FACTOR: u64 = 2;
+
+/// Approximate ratio of the size of the externally sorted runs to the size of the CSV files.
+const RUNS_SIZE_FACTOR: u64 = 2;
+
+/// What a build does when the filesystem of the output directory lacks the estimated room.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiskSpaceCheck {
+    /// Fails before building anything.
+    #[default]
+    Fail,
+    /// Prints a warning and builds anyway.
+    Warn,
+    /// Does not estimate the disk usage.
+    Skip,
+}
+
+/// The disk space a build is estimated to need, in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DiskUsageEstimate {
+    /// The files left in the output directory.
+    pub output: u64,
+    /// The files removed before the build completes, such as the sorted runs.
+    pub temporary: u64,
+}
+
+impl DiskUsageEstimate {
+    pub fn total(&self) -> u64 {
+        self.output + self.temporary
+    }
+}
+
+/// The space available to unprivileged users on the filesystem of `path`, if it can be
+/// queried on this platform.
+#[cfg(target_os = "linux")]
+pub fn available_space(path: &Path) -> io::Result<Option<u64>> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(Some(stat.f_bavail as u64 * stat.f_frsize as u64))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn available_space(_path: &Path) -> io::Result<Option<u64>> {
+    Ok(None)
+}
+
+/// The device of the filesystem of `path`, telling apart the directories on different
+/// filesystems, if it can be queried on this platform.
+#[cfg(unix)]
+fn filesystem_id(path: &Path) -> io::Result<Option<u64>> {
+    use std::os::unix::fs::MetadataExt;
+
+    Ok(Some(fs::metadata(path)?.dev()))
+}
+
+#[cfg(not(unix))]
+fn filesystem_id(_path: &Path) -> io::Result<Option<u64>> {
+    Ok(None)
+}
+
+/// The disk space a build needs in its output directory and in the directory of its
+/// temporary files.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DiskNeed<'a> {
+    output_dir: &'a Path,
+    temporary_dir: &'a Path,
+    estimate: DiskUsageEstimate,
+}
+
+/// The disk space the builds need on a filesystem, in bytes.
+struct FilesystemNeed<'a> {
+    id: Option<u64>,
+    /// The first directory of the filesystem, where its available space is queried.
+    dir: &'a Path,
+    output: u64,
+    temporary: u64,
+    /// The artifacts of previous builds, overwritten by the builds.
+    reclaimable: u64,
+}
+
+impl<'a> FilesystemNeed<'a> {
+    /// Adds the need of `dir` to the filesystem it is on in `needs`.
+    fn add(
+        needs: &mut Vec<FilesystemNeed<'a>>,
+        dir: &'a Path,
+        output: u64,
+        temporary: u64,
+        reclaimable: u64,
+    ) -> io::Result<()> {
+        let id = filesystem_id(dir)?;
+        let position = needs
+            .iter()
+            .position(|need| need.dir == dir || (id.is_some() && need.id == id));
+        let need = match position {
+            Some(position) => &mut needs[position],
+            None => {
+                needs.push(FilesystemNeed {
+                    id,
+                    dir,
+                    output: 0,
+                    temporary: 0,
+                    reclaimable: 0,
+                });
+                needs.last_mut().unwrap()
+            }
+        };
+        need.output += output;
+        need.temporary += temporary;
+        need.reclaimable += reclaimable;
+        Ok(())
+    }
+}
+
+impl IpadicNeologdBuilder {
+    /// Estimates the disk space a build of `input_dir` needs from the sizes of its source
+    /// files and the header of `matrix.def`, without parsing them.
+    pub fn estimate_disk_usage(&self, input_dir: &Path) -> LinderaResult<DiskUsageEstimate> {
+        let csv_files: Vec<PathBuf> = self
+            .csv_sources(input_dir)?
+            .into_iter()
+            .map(|(_, filename)| filename)
+            .collect();
+        self.estimate_sources_disk_usage(&csv_files, &input_dir.join("matrix.def"))
+    }
+
+    /// Estimates the disk space a merged build of `layers` needs, like
+    /// `estimate_disk_usage`.
+    pub fn estimate_layers_disk_usage(
+        &self,
+        layers: &[SourceLayer],
+    ) -> LinderaResult<DiskUsageEstimate> {
+        let mut csv_files = Vec::new();
+        for layer in layers {
+            csv_files.extend(csv_filenames(&layer.input_dir)?);
+        }
+        match layers.first() {
+            Some(base_layer) => self
+                .estimate_sources_disk_usage(&csv_files, &base_layer.input_dir.join("matrix.def")),
+            None => Ok(DiskUsageEstimate::default()),
+        }
+    }
+
+    fn estimate_sources_disk_usage(
+        &self,
+        csv_files: &[PathBuf],
+        matrix_def: &Path,
+    ) -> LinderaResult<DiskUsageEstimate> {
+        let mut csv_bytes = 0;
+        for filename in csv_files {
+            csv_bytes += fs::metadata(filename)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?
+                .len();
+        }
+        let (forward_size, backward_size) = matrix::read_header(matrix_def)?;
+        let mut output = csv_bytes * OUTPUT_SIZE_FACTOR
+            + (2 + u64::from(forward_size) * u64::from(backward_size)) * 2;
+        // The container holds a copy of the artifacts, and the compressed package about half
+        // of one.
+        if self.options.mmap_container {
+            output *= 2;
+        }
+        if self.options.package {
+            output += output / 2;
+        }
+        let temporary = if self.options.memory_budget.is_some() && self.supports_external_sort() {
+            csv_bytes * RUNS_SIZE_FACTOR
+        } else {
+            0
+        };
+        Ok(DiskUsageEstimate { output, temporary })
+    }
+
+    /// The disk space `estimate` needs in `output_dir`, and in `BuilderOptions::scratch_dir`
+    /// for the temporary files if it is set.
+    pub(crate) fn disk_need<'a>(
+        &'a self,
+        output_dir: &'a Path,
+        estimate: DiskUsageEstimate,
+    ) -> DiskNeed<'a> {
+        DiskNeed {
+            output_dir,
+            temporary_dir: self.options.scratch_dir.as_deref().unwrap_or(output_dir),
+            estimate,
+        }
+    }
+
+    /// Compares the disk space of `needs`, summed over the directories on the same
+    /// filesystem, with the space left on each filesystem, failing or warning as
+    /// `BuilderOptions::disk_space_check` says when one lacks room.
+    pub(crate) fn check_disk_space(&self, needs: &[DiskNeed]) -> LinderaResult<()> {
+        if self.options.disk_space_check == DiskSpaceCheck::Skip {
+            return Ok(());
+        }
+        let mut filesystems = Vec::new();
+        for need in needs {
+            // The artifacts of a previous build in the output directory are overwritten.
+            let mut reclaimable = 0;
+            for name in self.options.layout.artifact_names().iter() {
+                if let Ok(metadata) = fs::metadata(need.output_dir.join(name)) {
+                    reclaimable += metadata.len();
+                }
+            }
+            FilesystemNeed::add(
+                &mut filesystems,
+                need.output_dir,
+                need.estimate.output,
+                0,
+                reclaimable,
+            )
+            .and_then(|_| {
+                FilesystemNeed::add(
+                    &mut filesystems,
+                    need.temporary_dir,
+                    0,
+                    need.estimate.temporary,
+                    0,
+                )
+            })
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        }
+        for filesystem in filesystems {
+            let needed = filesystem.output + filesystem.temporary;
+            let available = match available_space(filesystem.dir)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?
+            {
+                Some(available) => available + filesystem.reclaimable,
+                None => continue,
+            };
+            if needed <= available {
+                continue;
+            }
+            let message = format!(
+                "the build is estimated to need {} bytes ({} of output, {} temporary), but only {} are available in {}",
+                needed,
+                filesystem.output,
+                filesystem.temporary,
+                available,
+                filesystem.dir.display()
+            );
+            match self.options.disk_space_check {
+                DiskSpaceCheck::Fail => {
+                    return Err(LinderaErrorKind::Io.with_error(anyhow::anyhow!(message)))
+                }
+                _ => self.warn(&message),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{available_space, DiskSpaceCheck, DiskUsageEstimate};
+    use crate::fixtures::{write_fixture, FIXTURE_CSV_FILENAME};
+    use crate::options::BuilderOptions;
+    use crate::IpadicNeologdBuilder;
+
+    #[test]
+    fn estimates_the_disk_usage_of_the_sources() {
+        let input_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-disk-estimate-{}",
+            std::process::id()
+        ));
+        write_fixture(&input_dir).unwrap();
+        let csv_bytes = fs::metadata(input_dir.join(FIXTURE_CSV_FILENAME))
+            .unwrap()
+            .len();
+        // The CSV files twice, and the 3x3 matrix with its header as 16-bit integers.
+        let output = csv_bytes * 2 + 22;
+
+        let estimate = IpadicNeologdBuilder::new()
+            .estimate_disk_usage(&input_dir)
+            .unwrap();
+        assert_eq!(
+            estimate,
+            DiskUsageEstimate {
+                output,
+                temporary: 0
+            }
+        );
+        let options = BuilderOptions {
+            memory_budget: Some(1),
+            mmap_container: true,
+            package: true,
+            ..BuilderOptions::default()
+        };
+        let estimate = IpadicNeologdBuilder::with_options(options)
+            .estimate_disk_usage(&input_dir)
+            .unwrap();
+        assert_eq!(estimate.output, output * 3);
+        assert_eq!(estimate.temporary, csv_bytes * 2);
+        assert_eq!(estimate.total(), output * 3 + csv_bytes * 2);
+
+        fs::remove_dir_all(&input_dir).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn checks_the_space_left_on_the_filesystem() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-disk-check-{}",
+            std::process::id()
+        ));
+        let (output_dir, scratch_dir) = (base_dir.join("output"), base_dir.join("scratch"));
+        fs::create_dir_all(&output_dir).unwrap();
+        fs::create_dir_all(&scratch_dir).unwrap();
+        let available = available_space(&base_dir).unwrap().unwrap();
+        let estimate = DiskUsageEstimate {
+            output: available / 5 * 3,
+            temporary: 0,
+        };
+        let builder = |disk_space_check| {
+            IpadicNeologdBuilder::with_options(BuilderOptions {
+                disk_space_check,
+                scratch_dir: Some(scratch_dir.clone()),
+                ..BuilderOptions::default()
+            })
+        };
+
+        let fail = builder(DiskSpaceCheck::Fail);
+        fail.check_disk_space(&[fail.disk_need(&output_dir, estimate)])
+            .unwrap();
+        // The needs of directories on the same filesystem add up.
+        let needs = [
+            fail.disk_need(&output_dir, estimate),
+            fail.disk_need(&scratch_dir, estimate),
+        ];
+        let err = fail.check_disk_space(&needs).unwrap_err();
+        assert!(err.to_string().contains("estimated to need"), "{}", err);
+        let temporary = DiskUsageEstimate {
+            output: 0,
+            temporary: available / 5 * 3,
+        };
+        assert!(fail
+            .check_disk_space(&[
+                fail.disk_need(&output_dir, estimate),
+                fail.disk_need(&output_dir, temporary)
+            ])
+            .is_err());
+
+        let warn = builder(DiskSpaceCheck::Warn);
+        warn.check_disk_space(&[
+            warn.disk_need(&output_dir, estimate),
+            warn.disk_need(&scratch_dir, estimate),
+        ])
+        .unwrap();
+        let skip = builder(DiskSpaceCheck::Skip);
+        let huge = DiskUsageEstimate {
+            output: u64::MAX / 2,
+            temporary: 0,
+        };
+        skip.check_disk_space(&[skip.disk_need(&output_dir, huge)])
+            .unwrap();
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
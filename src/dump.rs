@@ -0,0 +1,219 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use lindera_core::character_definition::{CategoryId, CharacterDefinitions};
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::unknown_dictionary::UnknownDictionary;
+use lindera_core::LinderaResult;
+
+use crate::header::strip_header;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryDump {
+    pub name: String,
+    pub invoke: bool,
+    pub group: bool,
+    pub length: u32,
+}
+
+/// A code point range whose characters all belong to the same categories.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CharRangeDump {
+    pub low: u32,
+    pub high: u32,
+    pub categories: Vec<String>,
+}
+
+/// The content of `char_def.bin`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CharDefDump {
+    pub categories: Vec<CategoryDump>,
+    pub ranges: Vec<CharRangeDump>,
+}
+
+/// Prints the dump in the `char.def` format.
+impl fmt::Display for CharDefDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for category in &self.categories {
+            writeln!(
+                f,
+                "{} {} {} {}",
+                category.name, category.invoke as u8, category.group as u8, category.length
+            )?;
+        }
+        for range in &self.ranges {
+            if range.low == range.high {
+                write!(f, "0x{:04X}", range.low)?;
+            } else {
+                write!(f, "0x{:04X}..0x{:04X}", range.low, range.high)?;
+            }
+            writeln!(f, " {}", range.categories.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnkEntryDump {
+    pub category: String,
+    pub word_id: u32,
+    pub left_id: u32,
+    pub right_id: u32,
+    pub word_cost: i16,
+}
+
+/// The content of `unk.bin`, by character category.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnkDump {
+    pub entries: Vec<UnkEntryDump>,
+}
+
+/// Prints the dump in the `unk.def` format, without the word details `unk.bin` does not keep.
+impl fmt::Display for UnkDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "{},{},{},{}",
+                entry.category, entry.left_id, entry.right_id, entry.word_cost
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn load_chardef(char_def_file: &Path) -> LinderaResult<CharacterDefinitions> {
+    let data = fs::read(char_def_file)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    bincode::deserialize(strip_header(&data)?)
+        .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))
+}
+
+/// Loads `char_def.bin` and returns its categories and code point ranges.
+pub fn dump_chardef(char_def_file: &Path) -> LinderaResult<CharDefDump> {
+    let char_definitions = load_chardef(char_def_file)?;
+
+    let categories = (0..char_definitions.categories().len())
+        .map(|category_id| {
+            let definition = char_definitions.lookup_definition(CategoryId(category_id));
+            CategoryDump {
+                name: char_definitions
+                    .category_name(CategoryId(category_id))
+                    .to_string(),
+                invoke: definition.invoke,
+                group: definition.group,
+                length: definition.length,
+            }
+        })
+        .collect();
+
+    let mut ranges: Vec<CharRangeDump> = Vec::new();
+    for code_point in 0..=(char::MAX as u32) {
+        let c = match std::char::from_u32(code_point) {
+            Some(c) => c,
+            None => continue,
+        };
+        let categories: Vec<String> = char_definitions
+            .lookup_categories(c)
+            .iter()
+            .map(|category_id| char_definitions.category_name(*category_id).to_string())
+            .collect();
+        match ranges.last_mut() {
+            Some(range) if range.high + 1 == code_point && range.categories == categories => {
+                range.high = code_point;
+            }
+            _ => ranges.push(CharRangeDump {
+                low: code_point,
+                high: code_point,
+                categories,
+            }),
+        }
+    }
+
+    Ok(CharDefDump { categories, ranges })
+}
+
+/// Loads `unk.bin` and returns its entries, naming the categories after `char_def.bin`.
+pub fn dump_unk(unk_file: &Path, char_def_file: &Path) -> LinderaResult<UnkDump> {
+    let char_definitions = load_chardef(char_def_file)?;
+    let data =
+        fs::read(unk_file).map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    let unknown_dictionary: UnknownDictionary = bincode::deserialize(strip_header(&data)?)
+        .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))?;
+
+    let mut entries = Vec::new();
+    for (category_id, category) in char_definitions.categories().iter().enumerate() {
+        for word_id in unknown_dictionary.lookup_word_ids(CategoryId(category_id)) {
+            let word_entry = unknown_dictionary.word_entry(*word_id);
+            entries.push(UnkEntryDump {
+                category: category.clone(),
+                word_id: *word_id,
+                left_id: word_entry.left_id(),
+                right_id: word_entry.right_id(),
+                word_cost: word_entry.word_cost,
+            });
+        }
+    }
+
+    Ok(UnkDump { entries })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{dump_chardef, dump_unk};
+    use crate::fixtures::{build_fixture, FIXTURE_CHAR_DEF, FIXTURE_UNK_DEF};
+    use crate::options::BuilderOptions;
+
+    #[test]
+    fn dumps_in_the_source_formats() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-dump-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        build_fixture(&input_dir, &output_dir, BuilderOptions::default()).unwrap();
+        let (char_def_file, unk_file) =
+            (output_dir.join("char_def.bin"), output_dir.join("unk.bin"));
+
+        let char_def_dump = dump_chardef(&char_def_file).unwrap().to_string();
+        let category_lines: Vec<&str> = FIXTURE_CHAR_DEF
+            .lines()
+            .filter(|line| !line.starts_with("0x"))
+            .collect();
+        assert_eq!(
+            char_def_dump
+                .lines()
+                .take(category_lines.len())
+                .collect::<Vec<_>>(),
+            category_lines
+        );
+        for range_line in [
+            "0x0020 SPACE",
+            "0x0030..0x0039 NUMERIC",
+            "0x0041..0x005A ALPHA",
+            "0x3041..0x309F HIRAGANA",
+            "0x4E00..0x9FFF KANJI",
+        ] {
+            assert!(
+                char_def_dump.lines().any(|line| line == range_line),
+                "{} is missing",
+                range_line
+            );
+        }
+
+        let unk_dump = dump_unk(&unk_file, &char_def_file).unwrap();
+        let unk_lines: Vec<String> = FIXTURE_UNK_DEF
+            .lines()
+            .map(|line| line.split(',').take(4).collect::<Vec<_>>().join(","))
+            .collect();
+        assert_eq!(unk_dump.to_string().lines().collect::<Vec<_>>(), unk_lines);
+        assert_eq!(unk_dump.entries[2].category, "KANJI");
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
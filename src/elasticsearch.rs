@@ -0,0 +1,220 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::CsvRow;
+
+/// The directory, in the output directory, holding the Elasticsearch analyzer files.
+pub const ELASTICSEARCH_DIRNAME: &str = "elasticsearch";
+
+/// The kuromoji user dictionary, in the Elasticsearch directory.
+pub const USER_DICT_FILENAME: &str = "userdict_ja.txt";
+
+/// The synonym file, in the Elasticsearch directory.
+pub const SYNONYMS_FILENAME: &str = "synonyms_ja.txt";
+
+/// Which entries are exported for Elasticsearch analyzers.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElasticsearchExport {
+    /// Only the entries whose POS levels start with these, e.g. `["名詞", "固有名詞"]`.
+    #[serde(default)]
+    pub pos_prefix: Vec<String>,
+    /// Only the entries from NEologd seed files.
+    #[serde(default)]
+    pub neologd_only: bool,
+}
+
+impl ElasticsearchExport {
+    fn includes(&self, row: &CsvRow) -> bool {
+        let pos = [
+            row.pos_level1,
+            row.pos_level2,
+            row.pos_level3,
+            row.pos_level4,
+        ];
+        (!self.neologd_only || row.is_seed_row())
+            && self.pos_prefix.len() <= pos.len()
+            && self.pos_prefix.iter().zip(pos.iter()).all(|(a, b)| a == b)
+    }
+}
+
+/// The names of the files written by `write_elasticsearch_files`, relative to the output
+/// directory.
+pub fn elasticsearch_filenames() -> Vec<String> {
+    [USER_DICT_FILENAME, SYNONYMS_FILENAME]
+        .iter()
+        .map(|name| format!("{}/{}", ELASTICSEARCH_DIRNAME, name))
+        .collect()
+}
+
+/// Writes the entries of `rows` included by `export` as a kuromoji user dictionary and a
+/// synonym file for Elasticsearch.
+///
+/// The user dictionary has a `surface,surface,reading,pos` line for the lowest-cost entry
+/// of each surface with a reading, the POS levels joined with `-`. The synonym file has a
+/// line of the comma-separated surfaces of each base form written with several surfaces,
+/// for the entries without conjugation. The files created and their sizes are reported with
+/// `print`.
+pub(crate) fn write_elasticsearch_files(
+    output_dir: &Path,
+    rows: &[CsvRow],
+    export: &ElasticsearchExport,
+    print: &dyn Fn(&str),
+) -> LinderaResult<()> {
+    let mut user_dict: BTreeMap<&str, (i32, &str, String)> = BTreeMap::new();
+    let mut synonyms: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    // Commas would split the fields of both formats.
+    for row in rows
+        .iter()
+        .filter(|row| export.includes(row) && !row.surface_form.contains(','))
+    {
+        if row.reading != "*" {
+            let pos = [
+                row.pos_level1,
+                row.pos_level2,
+                row.pos_level3,
+                row.pos_level4,
+            ]
+            .iter()
+            .filter(|level| **level != "*")
+            .copied()
+            .collect::<Vec<_>>()
+            .join("-");
+            match user_dict.get(row.surface_form) {
+                Some((word_cost, _, _)) if *word_cost <= row.word_cost => {}
+                _ => {
+                    user_dict.insert(row.surface_form, (row.word_cost, row.reading, pos));
+                }
+            }
+        }
+        if row.conjugation_type == "*" && row.base_form != "*" {
+            synonyms
+                .entry(row.base_form)
+                .or_default()
+                .insert(row.surface_form);
+        }
+    }
+
+    let es_dir = output_dir.join(ELASTICSEARCH_DIRNAME);
+    fs::create_dir_all(&es_dir)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+    let user_dict_path = es_dir.join(USER_DICT_FILENAME);
+    print(&format!("creating {:?}", user_dict_path));
+    let write_user_dict = || -> io::Result<()> {
+        let mut wtr = BufWriter::new(File::create(&user_dict_path)?);
+        for (surface, (_, reading, pos)) in &user_dict {
+            writeln!(wtr, "{},{},{},{}", surface, surface, reading, pos)?;
+        }
+        wtr.flush()
+    };
+    write_user_dict().map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+    let synonyms_path = es_dir.join(SYNONYMS_FILENAME);
+    print(&format!("creating {:?}", synonyms_path));
+    let mut synonym_lines = 0;
+    let mut write_synonyms = || -> io::Result<()> {
+        let mut wtr = BufWriter::new(File::create(&synonyms_path)?);
+        for surfaces in synonyms.values().filter(|surfaces| surfaces.len() > 1) {
+            let surfaces: Vec<&str> = surfaces.iter().copied().collect();
+            writeln!(wtr, "{}", surfaces.join(","))?;
+            synonym_lines += 1;
+        }
+        wtr.flush()
+    };
+    write_synonyms().map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+    print(&format!(
+        "{} user dictionary entries, {} synonym groups",
+        user_dict.len(),
+        synonym_lines
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{
+        elasticsearch_filenames, write_elasticsearch_files, ElasticsearchExport,
+        ELASTICSEARCH_DIRNAME, SYNONYMS_FILENAME, USER_DICT_FILENAME,
+    };
+    use crate::fixtures::FIXTURE_ROWS;
+    use crate::CsvRow;
+
+    const EXTRA_ROWS: [&str; 2] = [
+        "とうきょう,1,1,2500,名詞,固有名詞,地域,一般,*,*,東京,トウキョウ,トーキョー",
+        "東京,1,1,4000,名詞,固有名詞,人名,姓,*,*,東京,ヒガシキョウ,ヒガシキョー",
+    ];
+
+    #[test]
+    fn writes_the_user_dictionary_and_synonyms() {
+        let output_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-elasticsearch-{}",
+            std::process::id()
+        ));
+        let rows: Vec<CsvRow> = FIXTURE_ROWS
+            .iter()
+            .chain(EXTRA_ROWS.iter())
+            .map(|line| {
+                let mut row = CsvRow::from_line(line).unwrap();
+                row.provenance = Some(if row.surface_form == "東京スカイツリー" {
+                    "neologd-seed-dict-20200910.csv"
+                } else {
+                    "base.csv"
+                });
+                row
+            })
+            .collect();
+        let es_dir = output_dir.join(ELASTICSEARCH_DIRNAME);
+        let read = |filename: &str| fs::read_to_string(es_dir.join(filename)).unwrap();
+
+        let export = ElasticsearchExport::default();
+        write_elasticsearch_files(&output_dir, &rows, &export, &|_| {}).unwrap();
+        assert_eq!(
+            read(USER_DICT_FILENAME),
+            "いく,いく,イク,動詞-自立\n\
+             とうきょう,とうきょう,トウキョウ,名詞-固有名詞-地域-一般\n\
+             に,に,ニ,助詞-格助詞-一般\n\
+             東京,東京,トウキョウ,名詞-固有名詞-地域-一般\n\
+             東京スカイツリー,東京スカイツリー,トウキョウスカイツリー,名詞-固有名詞-一般\n\
+             行く,行く,イク,動詞-自立\n"
+        );
+        assert_eq!(read(SYNONYMS_FILENAME), "とうきょう,東京\n");
+
+        let export = ElasticsearchExport {
+            pos_prefix: vec!["名詞".to_string(), "固有名詞".to_string()],
+            neologd_only: true,
+        };
+        write_elasticsearch_files(&output_dir, &rows, &export, &|_| {}).unwrap();
+        assert_eq!(
+            read(USER_DICT_FILENAME),
+            "東京スカイツリー,東京スカイツリー,トウキョウスカイツリー,名詞-固有名詞-一般\n"
+        );
+        assert_eq!(read(SYNONYMS_FILENAME), "");
+
+        let mut filenames = elasticsearch_filenames();
+        filenames.sort();
+        let mut written: Vec<String> = fs::read_dir(&es_dir)
+            .unwrap()
+            .map(|entry| {
+                format!(
+                    "{}/{}",
+                    ELASTICSEARCH_DIRNAME,
+                    entry.unwrap().file_name().to_string_lossy()
+                )
+            })
+            .collect();
+        written.sort();
+        assert_eq!(written, filenames);
+
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+}
@@ -0,0 +1,230 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::{LinderaError, LinderaErrorKind};
+use lindera_core::LinderaResult;
+
+use crate::columnar::{COLUMNS_DIRNAME, DETAIL_FIELDS};
+use crate::layout::DictionaryLayout;
+use crate::packing::VALUE_TABLE_FILENAME;
+use crate::reading_index::{READING_DA_FILENAME, READING_VALS_FILENAME};
+use crate::shard::{ShardIndex, SHARD_INDEX_FILENAME};
+
+/// The byte order of the integers of the binary artifacts. The Bincode-encoded
+/// `char_def.bin`, `unk.bin` and word details are little-endian either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Endianness {
+    /// The byte order loaded by Lindera.
+    #[default]
+    Little,
+    /// Network byte order, for consumers on big-endian platforms.
+    Big,
+}
+
+impl FromStr for Endianness {
+    type Err = LinderaError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "little" => Ok(Endianness::Little),
+            "big" => Ok(Endianness::Big),
+            _ => {
+                Err(LinderaErrorKind::Args
+                    .with_error(anyhow::anyhow!("unknown endianness {}", value)))
+            }
+        }
+    }
+}
+
+/// The integer widths of a record of the double arrays and of the `u32` indexes.
+pub const U32_RECORD: &[usize] = &[4];
+
+/// The integer widths of a record of `dict.vals`: word_id, word cost and context id.
+pub const WORD_ENTRY_RECORD: &[usize] = &[4, 2, 2];
+
+/// The integer widths of a record of `matrix.mtx`.
+pub const I16_RECORD: &[usize] = &[2];
+
+/// Reverses the byte order of each integer of `data`, a sequence of records of the integer
+/// widths `record`, converting it from one endianness to the other. A trailing partial
+/// record is left as it is.
+pub fn swap_byte_order(data: &mut [u8], record: &[usize]) {
+    let record_len: usize = record.iter().sum();
+    for chunk in data.chunks_exact_mut(record_len) {
+        let mut start = 0;
+        for width in record {
+            chunk[start..start + width].reverse();
+            start += width;
+        }
+    }
+}
+
+/// The artifacts of `output_dir` holding integers, with the widths of their records.
+fn integer_artifacts(
+    output_dir: &Path,
+    layout: &DictionaryLayout,
+) -> LinderaResult<Vec<(String, &'static [usize])>> {
+    let mut artifacts = vec![
+        (layout.dict_da.clone(), U32_RECORD),
+        (layout.dict_vals.clone(), WORD_ENTRY_RECORD),
+        (layout.dict_wordsidx.clone(), U32_RECORD),
+        (layout.matrix.clone(), I16_RECORD),
+    ];
+    if output_dir.join(VALUE_TABLE_FILENAME).exists() {
+        artifacts.push((VALUE_TABLE_FILENAME.to_string(), U32_RECORD));
+    }
+    if output_dir.join(READING_DA_FILENAME).exists() {
+        artifacts.push((READING_DA_FILENAME.to_string(), U32_RECORD));
+        artifacts.push((READING_VALS_FILENAME.to_string(), U32_RECORD));
+    }
+    if output_dir.join(SHARD_INDEX_FILENAME).exists() {
+        for shard in ShardIndex::load(output_dir)?.shards {
+            artifacts.push((shard.wordsidx, U32_RECORD));
+            artifacts.push((shard.vals, WORD_ENTRY_RECORD));
+        }
+    }
+    if output_dir.join(COLUMNS_DIRNAME).is_dir() {
+        for field in DETAIL_FIELDS.iter() {
+            artifacts.push((format!("{}/{}.idx", COLUMNS_DIRNAME, field), U32_RECORD));
+        }
+    }
+    Ok(artifacts)
+}
+
+/// Rewrites the integers of the artifacts of the little-endian build in `output_dir` in
+/// big-endian byte order.
+pub(crate) fn convert_to_big_endian(
+    output_dir: &Path,
+    layout: &DictionaryLayout,
+) -> LinderaResult<()> {
+    for (name, record) in integer_artifacts(output_dir, layout)? {
+        let path = output_dir.join(&name);
+        println!("converting {:?} to big-endian", path);
+        let mut data =
+            fs::read(&path).map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        swap_byte_order(&mut data, record);
+        fs::write(&path, data)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{BigEndian, ByteOrder, LittleEndian, WriteBytesExt};
+
+    use super::*;
+
+    /// Word entries as written to `dict.vals`: word_id, word cost and context id.
+    const WORD_ENTRIES: [(u32, i16, u16); 3] =
+        [(0, -1200, 1285), (1, 32767, 0), (70000, -32768, 65535)];
+
+    fn word_entries_le() -> Vec<u8> {
+        let mut data = Vec::new();
+        for (word_id, word_cost, context_id) in WORD_ENTRIES.iter() {
+            data.write_u32::<LittleEndian>(*word_id).unwrap();
+            data.write_i16::<LittleEndian>(*word_cost).unwrap();
+            data.write_u16::<LittleEndian>(*context_id).unwrap();
+        }
+        data
+    }
+
+    fn read_word_entries<B: ByteOrder>(data: &[u8]) -> Vec<(u32, i16, u16)> {
+        data.chunks_exact(8)
+            .map(|record| {
+                (
+                    B::read_u32(&record[0..4]),
+                    B::read_i16(&record[4..6]),
+                    B::read_u16(&record[6..8]),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn swapped_word_entries_read_back_in_both_byte_orders() {
+        let little = word_entries_le();
+        assert_eq!(read_word_entries::<LittleEndian>(&little), WORD_ENTRIES);
+
+        let mut big = little.clone();
+        swap_byte_order(&mut big, WORD_ENTRY_RECORD);
+        assert_eq!(read_word_entries::<BigEndian>(&big), WORD_ENTRIES);
+
+        swap_byte_order(&mut big, WORD_ENTRY_RECORD);
+        assert_eq!(big, little);
+    }
+
+    #[test]
+    fn trailing_partial_record_is_left_as_it_is() {
+        let mut data = vec![1, 2, 3, 4, 5, 6];
+        swap_byte_order(&mut data, U32_RECORD);
+        assert_eq!(data, vec![4, 3, 2, 1, 5, 6]);
+    }
+
+    #[test]
+    fn big_endian_artifacts_round_trip() {
+        let output_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-endian-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&output_dir).unwrap();
+        let layout = DictionaryLayout::default();
+
+        let mut u32s = Vec::new();
+        for value in [0u32, 1, 0x0102_0304, u32::MAX].iter() {
+            u32s.write_u32::<LittleEndian>(*value).unwrap();
+        }
+        let mut costs = Vec::new();
+        for cost in [2i16, 3, -4517, i16::MAX, i16::MIN].iter() {
+            costs.write_i16::<LittleEndian>(*cost).unwrap();
+        }
+        let vals = word_entries_le();
+        for (name, data) in [
+            (&layout.dict_da, &u32s),
+            (&layout.dict_vals, &vals),
+            (&layout.dict_wordsidx, &u32s),
+            (&layout.matrix, &costs),
+        ]
+        .iter()
+        {
+            fs::write(output_dir.join(name), data).unwrap();
+        }
+
+        convert_to_big_endian(&output_dir, &layout).unwrap();
+
+        let read = |name: &str| fs::read(output_dir.join(name)).unwrap();
+        assert_eq!(
+            read_word_entries::<BigEndian>(&read(&layout.dict_vals)),
+            WORD_ENTRIES
+        );
+        let matrix = read(&layout.matrix);
+        assert_eq!(BigEndian::read_i16(&matrix[4..6]), -4517);
+        // Swapped back as `DictionaryReader` does for big-endian builds.
+        for (name, record, original) in [
+            (&layout.dict_da, U32_RECORD, &u32s),
+            (&layout.dict_vals, WORD_ENTRY_RECORD, &vals),
+            (&layout.dict_wordsidx, U32_RECORD, &u32s),
+            (&layout.matrix, I16_RECORD, &costs),
+        ]
+        .iter()
+        {
+            let mut data = read(name);
+            assert_ne!(&data, *original);
+            swap_byte_order(&mut data, record);
+            assert_eq!(&data, *original);
+        }
+
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    fn parses_endianness() {
+        assert_eq!("little".parse::<Endianness>().unwrap(), Endianness::Little);
+        assert_eq!("big".parse::<Endianness>().unwrap(), Endianness::Big);
+        assert!("middle".parse::<Endianness>().is_err());
+    }
+}
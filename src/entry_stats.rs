@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::CsvRow;
+
+/// What happened to the entries of one source CSV file during a build.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FileEntryStats {
+    /// Entries parsed from the file.
+    pub parsed: usize,
+    /// Lines changed by the normalization of their fields.
+    pub normalized: usize,
+    /// Entries left out of the dictionary, as listed in `SKIP_WORDS` or pruned to fit the
+    /// size budget.
+    pub skipped: usize,
+    /// Entries identical to a previous entry of the same surface. They are reported as
+    /// duplicate entry warnings but kept in the dictionary, like the other entries.
+    pub duplicates_seen: usize,
+    /// Entries replaced by the entry of another layer when merging source layers.
+    pub merged: usize,
+}
+
+/// The entry statistics of the source files of a build, by file name.
+#[derive(Default)]
+pub(crate) struct EntryStatsCollector {
+    stats: Mutex<BTreeMap<String, FileEntryStats>>,
+}
+
+impl EntryStatsCollector {
+    /// Updates the statistics of `file` with `f`.
+    pub(crate) fn record<F: FnOnce(&mut FileEntryStats)>(&self, file: &str, f: F) {
+        if let Ok(mut stats) = self.stats.lock() {
+            match stats.get_mut(file) {
+                Some(file_stats) => f(file_stats),
+                None => f(stats.entry(file.to_string()).or_default()),
+            }
+        }
+    }
+
+    /// Counts the rows by their provenance as parsed.
+    pub(crate) fn record_parsed(&self, rows: &[CsvRow]) {
+        for (file, count) in count_by_provenance(rows) {
+            self.record(file, |stats| stats.parsed += count);
+        }
+    }
+
+    /// Counts the parsed rows of each file missing from the merged rows as merged.
+    pub(crate) fn record_merged(&self, merged: &[CsvRow]) {
+        let kept = count_by_provenance(merged);
+        if let Ok(mut stats) = self.stats.lock() {
+            for (file, file_stats) in stats.iter_mut() {
+                let kept = kept.get(file.as_str()).copied().unwrap_or(0);
+                file_stats.merged = file_stats.parsed.saturating_sub(kept);
+            }
+        }
+    }
+
+    pub(crate) fn stats(&self) -> BTreeMap<String, FileEntryStats> {
+        self.stats
+            .lock()
+            .map(|stats| stats.clone())
+            .unwrap_or_default()
+    }
+}
+
+pub(crate) fn count_by_provenance<'a>(rows: &[CsvRow<'a>]) -> BTreeMap<&'a str, usize> {
+    let mut counts = BTreeMap::new();
+    for row in rows {
+        *counts
+            .entry(row.provenance.unwrap_or_default())
+            .or_insert(0) += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EntryStatsCollector, FileEntryStats};
+    use crate::fixtures::FIXTURE_ROWS;
+    use crate::CsvRow;
+
+    #[test]
+    fn counts_the_entries_of_each_file() {
+        let rows: Vec<CsvRow> = FIXTURE_ROWS
+            .iter()
+            .enumerate()
+            .map(|(row_id, line)| {
+                let mut row = CsvRow::from_line(line).unwrap();
+                row.provenance = Some(if row_id < 3 { "base.csv" } else { "user.csv" });
+                row
+            })
+            .collect();
+        let collector = EntryStatsCollector::default();
+        collector.record_parsed(&rows);
+        collector.record("base.csv", |stats| stats.skipped += 1);
+        collector.record_merged(&rows[1..4]);
+
+        let stats = collector.stats();
+        assert_eq!(
+            stats["base.csv"],
+            FileEntryStats {
+                parsed: 3,
+                skipped: 1,
+                merged: 1,
+                ..FileEntryStats::default()
+            }
+        );
+        assert_eq!(
+            stats["user.csv"],
+            FileEntryStats {
+                parsed: 2,
+                merged: 1,
+                ..FileEntryStats::default()
+            }
+        );
+    }
+}
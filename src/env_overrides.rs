@@ -0,0 +1,96 @@
+use std::env;
+use std::str::FromStr;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::options::BuilderOptions;
+
+/// The input directory of the command line builds, when it is not given.
+pub const INPUT_DIR_ENV: &str = "LINDERA_NEOLOGD_INPUT_DIR";
+/// The output directory of the command line builds, when it is not given.
+pub const OUTPUT_DIR_ENV: &str = "LINDERA_NEOLOGD_OUTPUT_DIR";
+/// The config file of the command line builds, when it is not given.
+pub const CONFIG_ENV: &str = "LINDERA_NEOLOGD_CONFIG";
+/// Overrides `BuilderOptions::threads`.
+pub const THREADS_ENV: &str = "LINDERA_NEOLOGD_THREADS";
+/// Overrides `BuilderOptions::compression_level`.
+pub const COMPRESSION_LEVEL_ENV: &str = "LINDERA_NEOLOGD_COMPRESSION_LEVEL";
+
+/// The highest gzip compression level.
+pub const MAX_COMPRESSION_LEVEL: u32 = 9;
+
+fn parse_env<T: FromStr>(name: &str) -> LinderaResult<Option<T>> {
+    match env::var(name) {
+        Ok(value) => value.trim().parse().map(Some).map_err(|_| {
+            LinderaErrorKind::Args.with_error(anyhow::anyhow!("invalid {} {:?}", name, value))
+        }),
+        Err(_) => Ok(None),
+    }
+}
+
+impl BuilderOptions {
+    /// Overrides the options set by the `LINDERA_NEOLOGD_*` environment variables, so that
+    /// container builds can be tuned without editing the config file.
+    pub fn apply_env(&mut self) -> LinderaResult<()> {
+        if let Some(threads) = parse_env(THREADS_ENV)? {
+            self.threads = Some(threads);
+        }
+        if let Some(level) = parse_env::<u32>(COMPRESSION_LEVEL_ENV)? {
+            if level > MAX_COMPRESSION_LEVEL {
+                return Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!(
+                    "{} must be at most {} but is {}",
+                    COMPRESSION_LEVEL_ENV,
+                    MAX_COMPRESSION_LEVEL,
+                    level
+                )));
+            }
+            self.compression_level = Some(level);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::{COMPRESSION_LEVEL_ENV, THREADS_ENV};
+    use crate::options::BuilderOptions;
+
+    /// One test sets the variables, since they are shared by the threads of the tests.
+    #[test]
+    fn overrides_options_from_the_environment() {
+        let mut options = BuilderOptions {
+            threads: Some(2),
+            ..BuilderOptions::default()
+        };
+        env::remove_var(THREADS_ENV);
+        env::remove_var(COMPRESSION_LEVEL_ENV);
+        options.apply_env().unwrap();
+        assert_eq!(options.threads, Some(2));
+        assert_eq!(options.compression_level, None);
+
+        env::set_var(THREADS_ENV, " 8 ");
+        env::set_var(COMPRESSION_LEVEL_ENV, "9");
+        options.apply_env().unwrap();
+        assert_eq!(options.threads, Some(8));
+        assert_eq!(options.compression_level, Some(9));
+
+        env::set_var(COMPRESSION_LEVEL_ENV, "10");
+        assert!(options
+            .apply_env()
+            .unwrap_err()
+            .to_string()
+            .contains("must be at most 9 but is 10"));
+        env::set_var(THREADS_ENV, "many");
+        assert!(options
+            .apply_env()
+            .unwrap_err()
+            .to_string()
+            .contains("invalid LINDERA_NEOLOGD_THREADS \"many\""));
+
+        env::remove_var(THREADS_ENV);
+        env::remove_var(COMPRESSION_LEVEL_ENV);
+    }
+}
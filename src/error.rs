@@ -0,0 +1,284 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use lindera_core::error::{LinderaError, LinderaErrorKind};
+use lindera_core::LinderaResult;
+
+/// A structured build error. It is returned as the source of a `LinderaError`, from which
+/// `BuildError::downcast` recovers it, so that callers can match on the kind of failure.
+#[derive(Debug)]
+pub enum BuildError {
+    /// A file could not be read or written.
+    Io {
+        path: Option<PathBuf>,
+        source: io::Error,
+    },
+    /// A source line could not be parsed.
+    Parse {
+        file: Option<String>,
+        line: Option<usize>,
+        field: Option<String>,
+        message: String,
+    },
+    /// The sources parse but cannot be built into a dictionary.
+    Validation(String),
+    /// An artifact could not be serialized.
+    Serialize(String),
+    /// The build was cancelled before it completed.
+    Cancelled,
+    /// Another build holds the lock of the output directory.
+    Locked {
+        output_dir: PathBuf,
+        /// The process id the other build recorded in the lock file, if it could be read.
+        pid: Option<u32>,
+    },
+    /// The errors collected with `BuilderOptions::max_errors`.
+    Multiple(Vec<BuildError>),
+}
+
+impl BuildError {
+    pub(crate) fn parse_field(field: &str, message: &str) -> Self {
+        BuildError::Parse {
+            file: None,
+            line: None,
+            field: Some(field.to_string()),
+            message: message.to_string(),
+        }
+    }
+
+    /// Sets the file and line of a parse error that does not have them yet.
+    pub(crate) fn at(self, at_file: &str, at_line: Option<usize>) -> Self {
+        match self {
+            BuildError::Parse {
+                file,
+                line,
+                field,
+                message,
+            } => BuildError::Parse {
+                file: file.or_else(|| Some(at_file.to_string())),
+                line: line.or(at_line),
+                field,
+                message,
+            },
+            err => err,
+        }
+    }
+
+    pub fn kind(&self) -> LinderaErrorKind {
+        match self {
+            BuildError::Io { .. } | BuildError::Cancelled | BuildError::Locked { .. } => {
+                LinderaErrorKind::Io
+            }
+            BuildError::Parse { .. } | BuildError::Multiple(_) => LinderaErrorKind::Parse,
+            BuildError::Validation(_) => LinderaErrorKind::Content,
+            BuildError::Serialize(_) => LinderaErrorKind::Serialize,
+        }
+    }
+
+    /// The build error raised as `err` or one of its sources, if any.
+    pub fn downcast(err: &LinderaError) -> Option<&BuildError> {
+        let mut source = err.source();
+        while let Some(err) = source {
+            if let Some(build_error) = err.downcast_ref::<BuildError>() {
+                return Some(build_error);
+            }
+            source = err.source();
+        }
+        None
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::Io {
+                path: Some(path),
+                source,
+            } => write!(f, "{}: {}", path.display(), source),
+            BuildError::Io { path: None, source } => write!(f, "{}", source),
+            BuildError::Parse {
+                file,
+                line,
+                field,
+                message,
+            } => {
+                if let Some(file) = file {
+                    write!(f, "{}:", file)?;
+                    if let Some(line) = line {
+                        write!(f, "{}:", line)?;
+                    }
+                    write!(f, " ")?;
+                }
+                match field {
+                    Some(field) => write!(f, "failed to parse {}: {}", field, message),
+                    None => write!(f, "{}", message),
+                }
+            }
+            BuildError::Validation(message) => write!(f, "{}", message),
+            BuildError::Serialize(message) => write!(f, "failed to serialize: {}", message),
+            BuildError::Cancelled => write!(f, "the build was cancelled"),
+            BuildError::Locked { output_dir, pid } => {
+                write!(
+                    f,
+                    "another build is in progress in {}",
+                    output_dir.display()
+                )?;
+                if let Some(pid) = pid {
+                    write!(f, " (process {})", pid)?;
+                }
+                write!(f, "; wait for it with --wait")
+            }
+            BuildError::Multiple(errors) => {
+                write!(f, "{} errors", errors.len())?;
+                for err in errors {
+                    write!(f, "\n{}", err)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BuildError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Collects the errors of unparsable rows across all files, so that they are returned
+/// together instead of failing at the first one.
+pub(crate) struct ErrorCollector {
+    max_errors: Option<usize>,
+    errors: Vec<BuildError>,
+}
+
+impl ErrorCollector {
+    /// Without `max_errors`, the first error is returned at once.
+    pub(crate) fn new(max_errors: Option<usize>) -> Self {
+        ErrorCollector {
+            max_errors,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Records `err`, failing with the collected errors once there are `max_errors`.
+    pub(crate) fn push(&mut self, err: BuildError) -> LinderaResult<()> {
+        let max_errors = match self.max_errors {
+            Some(max_errors) => max_errors,
+            None => return Err(err.into()),
+        };
+        self.errors.push(err);
+        if self.errors.len() >= max_errors {
+            return Err(BuildError::Multiple(std::mem::take(&mut self.errors)).into());
+        }
+        Ok(())
+    }
+
+    /// Fails with the collected errors, if any.
+    pub(crate) fn finish(self) -> LinderaResult<()> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        Err(BuildError::Multiple(self.errors).into())
+    }
+}
+
+impl From<BuildError> for LinderaError {
+    fn from(err: BuildError) -> Self {
+        err.kind().with_error(anyhow::Error::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::path::PathBuf;
+
+    use lindera_core::error::{LinderaError, LinderaErrorKind};
+
+    use super::{BuildError, ErrorCollector};
+
+    #[test]
+    fn formats_parse_errors_with_their_position() {
+        let err = BuildError::parse_field("left_id", "invalid digit found in string");
+        assert_eq!(
+            err.to_string(),
+            "failed to parse left_id: invalid digit found in string"
+        );
+        let err = err.at("a.csv", Some(3)).at("b.csv", Some(4));
+        assert_eq!(
+            err.to_string(),
+            "a.csv:3: failed to parse left_id: invalid digit found in string"
+        );
+        assert_eq!(
+            BuildError::Validation("no entries".to_string())
+                .at("a.csv", None)
+                .to_string(),
+            "no entries"
+        );
+        let locked = BuildError::Locked {
+            output_dir: PathBuf::from("out"),
+            pid: Some(42),
+        };
+        assert_eq!(
+            locked.to_string(),
+            "another build is in progress in out (process 42); wait for it with --wait"
+        );
+    }
+
+    #[test]
+    fn downcasts_from_the_returned_error() {
+        let err: LinderaError = BuildError::Io {
+            path: Some(PathBuf::from("char.def")),
+            source: io::Error::new(io::ErrorKind::NotFound, "not found"),
+        }
+        .into();
+        assert!(matches!(
+            BuildError::downcast(&err),
+            Some(BuildError::Io { path: Some(path), .. }) if path == &PathBuf::from("char.def")
+        ));
+        let err: LinderaError = BuildError::Validation("no entries".to_string()).into();
+        assert!(matches!(
+            BuildError::downcast(&err),
+            Some(BuildError::Validation(_))
+        ));
+        let err = LinderaErrorKind::Io.with_error(anyhow::anyhow!("other"));
+        assert!(BuildError::downcast(&err).is_none());
+    }
+
+    #[test]
+    fn collects_errors_up_to_the_maximum() {
+        let parse_error =
+            |line| BuildError::parse_field("word_cost", "empty").at("a.csv", Some(line));
+
+        let mut errors = ErrorCollector::new(None);
+        let err = errors.push(parse_error(1)).unwrap_err();
+        assert!(matches!(
+            BuildError::downcast(&err),
+            Some(BuildError::Parse { line: Some(1), .. })
+        ));
+
+        let mut errors = ErrorCollector::new(Some(3));
+        errors.push(parse_error(1)).unwrap();
+        errors.push(parse_error(2)).unwrap();
+        let err = errors.finish().unwrap_err();
+        assert_eq!(
+            BuildError::downcast(&err).unwrap().to_string(),
+            "2 errors\na.csv:1: failed to parse word_cost: empty\na.csv:2: failed to parse word_cost: empty"
+        );
+
+        let mut errors = ErrorCollector::new(Some(2));
+        errors.push(parse_error(1)).unwrap();
+        let err = errors.push(parse_error(2)).unwrap_err();
+        assert!(matches!(
+            BuildError::downcast(&err),
+            Some(BuildError::Multiple(errors)) if errors.len() == 2
+        ));
+        assert!(ErrorCollector::new(Some(2)).finish().is_ok());
+    }
+}
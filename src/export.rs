@@ -0,0 +1,205 @@
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Int32Array, StringArray, UInt32Array};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::columnar::DETAIL_FIELDS;
+use crate::error::ErrorCollector;
+use crate::{parse_files, CsvRow, IpadicNeologdBuilder};
+
+/// Number of rows per record batch.
+const BATCH_ROWS: usize = 1 << 16;
+
+/// The file format of an exported lexicon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Arrow IPC file format.
+    Arrow,
+    Parquet,
+}
+
+fn lexicon_schema() -> SchemaRef {
+    let mut fields = vec![
+        Field::new("surface", DataType::Utf8, false),
+        Field::new("left_id", DataType::UInt32, false),
+        Field::new("right_id", DataType::UInt32, false),
+        Field::new("word_cost", DataType::Int32, false),
+    ];
+    fields.extend(
+        DETAIL_FIELDS
+            .iter()
+            .map(|name| Field::new(*name, DataType::Utf8, false)),
+    );
+    Arc::new(Schema::new(fields))
+}
+
+fn record_batch(schema: &SchemaRef, rows: &[CsvRow]) -> LinderaResult<RecordBatch> {
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|row| row.surface_form),
+        )),
+        Arc::new(UInt32Array::from_iter_values(
+            rows.iter().map(|row| row.left_id),
+        )),
+        Arc::new(UInt32Array::from_iter_values(
+            rows.iter().map(|row| row.right_id),
+        )),
+        Arc::new(Int32Array::from_iter_values(
+            rows.iter().map(|row| row.word_cost),
+        )),
+    ];
+    for field_idx in 0..DETAIL_FIELDS.len() {
+        columns.push(Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|row| row.detail_fields()[field_idx]),
+        )));
+    }
+    RecordBatch::try_new(schema.clone(), columns)
+        .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))
+}
+
+impl IpadicNeologdBuilder {
+    /// Writes the entries of the CSV files in `input_dir`, after the patch files are applied,
+    /// to `output_file` as an Arrow IPC or Parquet table with one column per CSV field.
+    pub fn export_lexicon(
+        &self,
+        input_dir: &Path,
+        output_file: &Path,
+        format: ExportFormat,
+    ) -> LinderaResult<()> {
+        let files = self.read_csv_files(input_dir)?;
+        let patches = self.load_patches()?;
+        let mut errors = ErrorCollector::new(self.options.max_errors);
+        let rows = parse_files(&files, &mut errors)?;
+        errors.finish()?;
+        let rows = self.apply_patches(&patches, rows)?;
+        let rows = self.apply_term_frequencies(rows)?;
+        let rows = self.apply_preferred_terms(rows)?;
+
+        let schema = lexicon_schema();
+        println!("creating {:?}", output_file);
+        let file = File::create(output_file)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+        match format {
+            ExportFormat::Arrow => {
+                let mut writer = FileWriter::try_new_buffered(file, &schema)
+                    .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+                for chunk in rows.chunks(BATCH_ROWS) {
+                    writer
+                        .write(&record_batch(&schema, chunk)?)
+                        .map_err(|err| {
+                            LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err))
+                        })?;
+                }
+                writer
+                    .finish()
+                    .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+            }
+            ExportFormat::Parquet => {
+                let mut writer = ArrowWriter::try_new(file, schema.clone(), None)
+                    .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+                for chunk in rows.chunks(BATCH_ROWS) {
+                    writer
+                        .write(&record_batch(&schema, chunk)?)
+                        .map_err(|err| {
+                            LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err))
+                        })?;
+                }
+                writer
+                    .close()
+                    .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+
+    use arrow::array::{Array, Int32Array, StringArray};
+    use arrow::ipc::reader::FileReader;
+    use arrow::record_batch::RecordBatch;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::ExportFormat;
+    use crate::fixtures::{write_fixture, FIXTURE_ROWS};
+    use crate::IpadicNeologdBuilder;
+
+    fn column<'a, T: 'static>(batch: &'a RecordBatch, name: &str) -> &'a T {
+        batch
+            .column_by_name(name)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<T>()
+            .unwrap()
+    }
+
+    #[test]
+    fn exports_arrow_and_parquet_tables() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-export-{}",
+            std::process::id()
+        ));
+        let input_dir = base_dir.join("input");
+        write_fixture(&input_dir).unwrap();
+        let builder = IpadicNeologdBuilder::new();
+
+        let arrow_file = base_dir.join("lexicon.arrow");
+        builder
+            .export_lexicon(&input_dir, &arrow_file, ExportFormat::Arrow)
+            .unwrap();
+        let batches: Vec<RecordBatch> = FileReader::try_new(File::open(&arrow_file).unwrap(), None)
+            .unwrap()
+            .map(|batch| batch.unwrap())
+            .collect();
+        let parquet_file = base_dir.join("lexicon.parquet");
+        builder
+            .export_lexicon(&input_dir, &parquet_file, ExportFormat::Parquet)
+            .unwrap();
+        let parquet_batches: Vec<RecordBatch> =
+            ParquetRecordBatchReaderBuilder::try_new(File::open(&parquet_file).unwrap())
+                .unwrap()
+                .build()
+                .unwrap()
+                .map(|batch| batch.unwrap())
+                .collect();
+
+        for batches in [batches, parquet_batches] {
+            assert_eq!(batches.len(), 1);
+            let batch = &batches[0];
+            assert_eq!(batch.num_rows(), FIXTURE_ROWS.len());
+            assert_eq!(batch.num_columns(), 13);
+            let surfaces = column::<StringArray>(batch, "surface");
+            let costs = column::<Int32Array>(batch, "word_cost");
+            let readings = column::<StringArray>(batch, "reading");
+            assert_eq!(surfaces.value(0), "東京");
+            assert_eq!(costs.value(0), 3000);
+            assert_eq!(readings.value(3), "イク");
+            assert_eq!(readings.null_count(), 0);
+        }
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn export_formats_are_lowercase() {
+        assert_eq!(
+            serde_json::from_str::<ExportFormat>("\"parquet\"").unwrap(),
+            ExportFormat::Parquet
+        );
+        assert!(serde_json::from_str::<ExportFormat>("\"Arrow\"").is_err());
+    }
+}
@@ -0,0 +1,713 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use yada::builder::DoubleArrayBuilder;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::word_entry::{WordEntry, WordId};
+use lindera_core::LinderaResult;
+
+use crate::audit::AuditedSurface;
+use crate::build_log::BuildEvent;
+use crate::entry_stats::count_by_provenance;
+use crate::error::ErrorCollector;
+use crate::file_util::for_each_source_line;
+use crate::frequency::{self, TermFrequencies};
+use crate::merge::{merge_rows, LayerScalings, SourceLayer};
+use crate::preferred::PreferredTerms;
+use crate::progress::ProgressStep;
+use crate::reader::DictionaryReader;
+use crate::scratch::ScratchDir;
+use crate::warning::BuildWarning;
+use crate::{da_spill, io_tuning, keyset, matrix, metrics, reading, skip, sort};
+use crate::{no_entries_error, CsvRow, IpadicNeologdBuilder};
+
+/// The purpose of the scratch directory the sorted runs are spilled to during the build.
+const RUNS_PURPOSE: &str = "runs";
+
+/// Approximate memory used per buffered entry besides its text.
+const ENTRY_OVERHEAD_BYTES: usize = 64;
+
+/// An entry line together with the layer and the file it comes from.
+struct Entry {
+    layer: u32,
+    provenance: String,
+    line: String,
+}
+
+fn surface(line: &str) -> &str {
+    line.split(',').next().unwrap_or_default()
+}
+
+fn write_string<W: Write>(wtr: &mut W, value: &str) -> io::Result<()> {
+    wtr.write_u32::<LittleEndian>(value.len() as u32)?;
+    wtr.write_all(value.as_bytes())
+}
+
+fn read_string<R: Read>(rdr: &mut R) -> LinderaResult<Option<String>> {
+    let len = match rdr.read_u32::<LittleEndian>() {
+        Ok(len) => len,
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(LinderaErrorKind::Io.with_error(anyhow::anyhow!(err))),
+    };
+    let mut buf = vec![0; len as usize];
+    rdr.read_exact(&mut buf)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    String::from_utf8(buf)
+        .map(Some)
+        .map_err(|err| LinderaErrorKind::Decode.with_error(anyhow::anyhow!(err)))
+}
+
+fn write_entry<W: Write>(wtr: &mut W, entry: &Entry) -> io::Result<()> {
+    wtr.write_u32::<LittleEndian>(entry.layer)?;
+    write_string(wtr, &entry.provenance)?;
+    write_string(wtr, &entry.line)
+}
+
+fn read_entry<R: Read>(rdr: &mut R) -> LinderaResult<Option<Entry>> {
+    let layer = match rdr.read_u32::<LittleEndian>() {
+        Ok(layer) => layer,
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(LinderaErrorKind::Io.with_error(anyhow::anyhow!(err))),
+    };
+    let truncated = || LinderaErrorKind::Io.with_error(anyhow::anyhow!("truncated sorted run"));
+    let provenance = read_string(rdr)?.ok_or_else(truncated)?;
+    let line = read_string(rdr)?.ok_or_else(truncated)?;
+    Ok(Some(Entry {
+        layer,
+        provenance,
+        line,
+    }))
+}
+
+/// Runs of entries sorted by surface, spilled to files of a scratch directory which is
+/// removed when dropped.
+struct SortedRuns {
+    scratch: ScratchDir,
+    runs: Vec<PathBuf>,
+    buffer_size: usize,
+}
+
+impl SortedRuns {
+    fn new(scratch: ScratchDir, buffer_size: usize) -> Self {
+        SortedRuns {
+            scratch,
+            runs: Vec::new(),
+            buffer_size,
+        }
+    }
+
+    /// Sorts the entries stably by surface and writes them to a new run.
+    fn spill(&mut self, entries: &mut Vec<Entry>) -> LinderaResult<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        entries.sort_by(|a, b| surface(&a.line).cmp(surface(&b.line)));
+
+        let run_path = self.scratch.join(&format!("{}.run", self.runs.len()));
+        let mut wtr = io_tuning::create_writer(&run_path, self.buffer_size, None)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        for entry in entries.drain(..) {
+            write_entry(&mut wtr, &entry)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        }
+        wtr.flush()
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+        self.runs.push(run_path);
+        Ok(())
+    }
+
+    /// Merges the runs into a single stream of entries sorted by surface. Entries with the
+    /// same surface keep the order of their runs.
+    fn merge(&self) -> LinderaResult<MergedRuns> {
+        let mut readers = Vec::with_capacity(self.runs.len());
+        for run_path in &self.runs {
+            readers.push(io::BufReader::new(File::open(run_path).map_err(|err| {
+                LinderaErrorKind::Io.with_error(anyhow::anyhow!(err))
+            })?));
+        }
+        let mut heads = Vec::with_capacity(readers.len());
+        let mut heap = BinaryHeap::with_capacity(readers.len());
+        for (run_idx, reader) in readers.iter_mut().enumerate() {
+            let head = read_entry(reader)?;
+            if let Some(entry) = &head {
+                heap.push(Reverse((surface(&entry.line).to_string(), run_idx)));
+            }
+            heads.push(head);
+        }
+        Ok(MergedRuns {
+            readers,
+            heads,
+            heap,
+        })
+    }
+}
+
+/// The k-way merge of sorted runs.
+struct MergedRuns {
+    readers: Vec<io::BufReader<File>>,
+    heads: Vec<Option<Entry>>,
+    heap: BinaryHeap<Reverse<(String, usize)>>,
+}
+
+impl Iterator for MergedRuns {
+    type Item = LinderaResult<Entry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((_, run_idx)) = self.heap.pop()?;
+        let entry = self.heads[run_idx].take();
+        match read_entry(&mut self.readers[run_idx]) {
+            Ok(head) => {
+                if let Some(entry) = &head {
+                    self.heap
+                        .push(Reverse((surface(&entry.line).to_string(), run_idx)));
+                }
+                self.heads[run_idx] = head;
+            }
+            Err(err) => return Some(Err(err)),
+        }
+        entry.map(Ok)
+    }
+}
+
+/// The entries of a stream sorted by surface, grouped by surface.
+struct SurfaceGroups<I: Iterator<Item = LinderaResult<Entry>>> {
+    entries: Peekable<I>,
+}
+
+impl<I: Iterator<Item = LinderaResult<Entry>>> Iterator for SurfaceGroups<I> {
+    type Item = LinderaResult<Vec<Entry>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut group = match self.entries.next()? {
+            Ok(entry) => vec![entry],
+            Err(err) => return Some(Err(err)),
+        };
+        while let Some(Ok(entry)) = self.entries.peek() {
+            if surface(&entry.line) != surface(&group[0].line) {
+                break;
+            }
+            if let Some(Ok(entry)) = self.entries.next() {
+                group.push(entry);
+            }
+        }
+        Some(Ok(group))
+    }
+}
+
+/// The layers of a merged build whose entries are sorted externally.
+pub(crate) struct ExternalLayers<'a> {
+    pub(crate) layers: &'a [SourceLayer],
+    /// The index in the sources of the end of the files of each layer.
+    pub(crate) layer_ends: &'a [usize],
+}
+
+/// The stages of the build that only need the entries of one surface, applied to each
+/// surface in turn, with the number of entries they changed or dropped.
+struct SurfaceStages {
+    /// The scalings of the layers of a merged build, with its number of layers.
+    layers: Option<(LayerScalings, usize)>,
+    frequencies: Option<TermFrequencies>,
+    preferred_terms: Option<PreferredTerms>,
+    reference: Option<DictionaryReader>,
+    recomputed: usize,
+    lowered: usize,
+    referenced: usize,
+    rare: usize,
+}
+
+impl IpadicNeologdBuilder {
+    /// Builds the system dictionary of the CSV files `sources` of the build of `input_dir`,
+    /// or of the `layers` of a merged build, without holding the entries in memory: they are
+    /// spilled to sorted runs fitting the memory budget, which are merged while writing the
+    /// word details and building the keyset. The stages of the build that only need the
+    /// entries of one surface, from merging the layers to sorting the homonyms, are applied
+    /// to each surface as it is merged, so that the dictionary is the one built in memory.
+    pub(crate) fn build_dict_external(
+        &self,
+        input_dir: &Path,
+        sources: &[(String, PathBuf)],
+        layers: Option<ExternalLayers>,
+        output_dir: &Path,
+    ) -> LinderaResult<()> {
+        let memory_budget = self.options.memory_budget.unwrap_or(u64::MAX);
+        let run_bytes = (memory_budget / 2).min(usize::MAX as u64) as usize;
+
+        let mut runs = SortedRuns::new(
+            self.scratch_dir(output_dir, RUNS_PURPOSE)?,
+            self.options.io_buffer_size(),
+        );
+        let mut layer_scalings = layers
+            .as_ref()
+            .map(|layers| LayerScalings::new(layers.layers, &self.options.layer_cost_scaling))
+            .transpose()?;
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut entries_bytes = 0;
+        let (forward_size, backward_size) = matrix::read_header(&input_dir.join("matrix.def"))?;
+        let mut total_lines = 0;
+        let mut errors = ErrorCollector::new(self.options.max_errors);
+        let normalizer = self.options.line_normalizer()?;
+        self.progress_start(ProgressStep::ParseFiles, sources.len() as u64);
+        for (source_idx, (name, filename)) in sources.iter().enumerate() {
+            let layer = layers.as_ref().map_or(0, |layers| {
+                layers
+                    .layer_ends
+                    .partition_point(|layer_end| *layer_end <= source_idx)
+            });
+            let mut lines = 0;
+            for_each_source_line(filename, |line_num, line| {
+                if let Some(prefix) = &self.options.comment_prefix {
+                    if line.starts_with(prefix.as_str()) {
+                        return Ok(());
+                    }
+                }
+                let mut line = self.normalize_source_line(name, line, &normalizer)?;
+                if self.options.fill_readings {
+                    let unresolved = reading::fill_readings(
+                        std::slice::from_mut(&mut line),
+                        self.reading_provider.as_deref(),
+                    );
+                    for surface in unresolved {
+                        self.report_warning(BuildWarning::MissingReading { surface })?;
+                    }
+                }
+
+                let row = match CsvRow::from_line(&line) {
+                    Ok(row) => row,
+                    Err(err) => return errors.push(err.at(name, Some(line_num))),
+                };
+                if row.left_id >= backward_size || row.right_id >= forward_size {
+                    self.report_warning(BuildWarning::UnknownContextId {
+                        file: name.clone(),
+                        surface: row.surface_form.to_string(),
+                        left_id: row.left_id,
+                        right_id: row.right_id,
+                    })?;
+                }
+                if let Some(layer_scalings) = &mut layer_scalings {
+                    layer_scalings.record_cost(layer, row.word_cost);
+                }
+                lines += 1;
+                entries_bytes += name.len() + line.len() + ENTRY_OVERHEAD_BYTES;
+                entries.push(Entry {
+                    layer: layer as u32,
+                    provenance: name.clone(),
+                    line,
+                });
+                if entries_bytes >= run_bytes {
+                    runs.spill(&mut entries)?;
+                    entries_bytes = 0;
+                }
+                Ok(())
+            })?;
+            self.log.log(&BuildEvent::FileStats {
+                file: name,
+                bytes: metrics::file_size(filename),
+                lines,
+            });
+            self.entry_stats.record(name, |stats| stats.parsed += lines);
+            total_lines += lines;
+            self.progress_advance(ProgressStep::ParseFiles, 1);
+        }
+        self.progress_finish(ProgressStep::ParseFiles);
+        errors.finish()?;
+        if total_lines == 0 {
+            return Err(no_entries_error(sources.len(), input_dir));
+        }
+        runs.spill(&mut entries)?;
+        self.print(&format!(
+            "merging {} sorted runs of entries",
+            runs.runs.len()
+        ));
+
+        let mut stages = SurfaceStages {
+            layers: layers
+                .zip(layer_scalings)
+                .map(|(layers, mut layer_scalings)| {
+                    layer_scalings.sort_costs();
+                    (layer_scalings, layers.layers.len())
+                }),
+            frequencies: self
+                .options
+                .frequency_file
+                .as_ref()
+                .map(|path| {
+                    let cost_scale = self
+                        .options
+                        .frequency_cost_scale
+                        .unwrap_or(frequency::DEFAULT_FREQUENCY_COST_SCALE);
+                    TermFrequencies::load(path, cost_scale)
+                })
+                .transpose()?,
+            preferred_terms: self.load_preferred_terms()?,
+            reference: self
+                .options
+                .reference_dict_dir
+                .as_ref()
+                .map(|reference_dir| DictionaryReader::open(reference_dir))
+                .transpose()?,
+            recomputed: 0,
+            lowered: 0,
+            referenced: 0,
+            rare: 0,
+        };
+        let groups = SurfaceGroups {
+            entries: runs.merge()?.peekable(),
+        };
+        self.write_sorted_entries(groups, &mut stages, output_dir)?;
+        self.report_surface_stages(&stages);
+        Ok(())
+    }
+
+    /// The rows of the entries of a surface, through the stages of the build that only need
+    /// the entries of the surface, in the order the build in memory writes them.
+    fn surface_rows<'a>(
+        &self,
+        group: &'a [Entry],
+        stages: &mut SurfaceStages,
+    ) -> LinderaResult<Vec<CsvRow<'a>>> {
+        let mut rows = Vec::with_capacity(group.len());
+        for entry in group {
+            let mut row =
+                CsvRow::from_line(&entry.line).map_err(|err| err.at(&entry.provenance, None))?;
+            row.provenance = Some(&entry.provenance);
+            rows.push(row);
+        }
+
+        if let Some((layer_scalings, num_layers)) = &stages.layers {
+            let parsed = count_by_provenance(&rows);
+            let mut layers_rows: Vec<Vec<CsvRow>> = (0..*num_layers).map(|_| Vec::new()).collect();
+            for (entry, mut row) in group.iter().zip(rows) {
+                let layer = entry.layer as usize;
+                row.word_cost = layer_scalings.scale(layer, row.word_cost);
+                layers_rows[layer].push(row);
+            }
+            rows = merge_rows(layers_rows, self.options.duplicate_policy);
+            let kept = count_by_provenance(&rows);
+            for (file, count) in parsed {
+                let merged = count - kept.get(file).copied().unwrap_or(0);
+                self.entry_stats
+                    .record(file, |stats| stats.merged += merged);
+            }
+        }
+        if let Some(frequencies) = &stages.frequencies {
+            stages.recomputed += frequencies.apply(&mut rows);
+        }
+        if let Some(preferred_terms) = &stages.preferred_terms {
+            for row in &mut rows {
+                if preferred_terms.apply(row) {
+                    stages.lowered += 1;
+                }
+            }
+        }
+        if let Some(reader) = &stages.reference {
+            let (kept, dropped_rows) = self.retain_new_entries(reader, rows)?;
+            rows = kept;
+            stages.referenced += dropped_rows;
+        }
+        if let Some(max_word_cost) = self.options.max_word_cost {
+            let (kept, dropped_rows) = self.retain_common_entries(max_word_cost, rows);
+            rows = kept;
+            stages.rare += dropped_rows;
+        }
+        if self.options.sort_homonyms {
+            sort::sort_homonyms(&mut rows);
+        }
+        Ok(rows)
+    }
+
+    /// Prints what the stages applied to each surface changed.
+    fn report_surface_stages(&self, stages: &SurfaceStages) {
+        if let Some(frequencies) = &stages.frequencies {
+            self.print(&format!(
+                "recomputed the cost of {} entries from {} term frequencies",
+                stages.recomputed,
+                frequencies.counts.len()
+            ));
+        }
+        if stages.preferred_terms.is_some() {
+            self.print(&format!(
+                "lowered the cost of {} entries of preferred terms",
+                stages.lowered
+            ));
+        }
+        if let Some(reference_dir) = &self.options.reference_dict_dir {
+            self.print(&format!(
+                "dropped {} entries already in {:?}",
+                stages.referenced, reference_dir
+            ));
+        }
+        if let Some(max_word_cost) = self.options.max_word_cost {
+            self.print(&format!(
+                "dropped {} entries with a word cost above {}",
+                stages.rare, max_word_cost
+            ));
+        }
+    }
+
+    /// Writes the dictionary from the entries grouped by surface, keeping only the keyset in
+    /// memory.
+    fn write_sorted_entries<I>(
+        &self,
+        groups: SurfaceGroups<I>,
+        stages: &mut SurfaceStages,
+        output_dir: &Path,
+    ) -> LinderaResult<()>
+    where
+        I: Iterator<Item = LinderaResult<Entry>>,
+    {
+        let layout = &self.options.layout;
+        let create = |name: &str| -> LinderaResult<io::BufWriter<File>> {
+            let path = layout.artifact_path(output_dir, name)?;
+            self.print(&format!("creating {:?}", path));
+            io_tuning::create_writer(&path, self.options.io_buffer_size(), None)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))
+        };
+        let mut wtr_words = create(&layout.dict_words)?;
+        let mut wtr_words_idx = create(&layout.dict_wordsidx)?;
+        let mut wtr_vals = create(&layout.dict_vals)?;
+
+        let codec = self.options.detail_codec();
+        let detail_fields = self.options.detail_field_indices()?;
+        let mut words_buffer = Vec::new();
+        let mut words_len = 0u32;
+        let mut keyset: Vec<(String, u32)> = Vec::new();
+        let mut word_entries: Vec<WordEntry> = Vec::new();
+        let mut row_id = 0u32;
+        let mut id = 0u32;
+        let skip_rules = self.options.skip_rules();
+        let mut packer = self.options.value_packing.packer();
+
+        let mut flush_surface = |surface: &str,
+                                 word_entries: &mut Vec<WordEntry>|
+         -> LinderaResult<()> {
+            if word_entries.is_empty() {
+                return Ok(());
+            }
+            let len = word_entries.len() as u32;
+            let val = packer.pack(surface, id, len)?;
+            for word_entry in word_entries.drain(..) {
+                WordEntry::serialize(&word_entry, &mut wtr_vals)
+                    .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+            }
+            keyset.push((surface.to_string(), val));
+            id += len;
+            Ok(())
+        };
+
+        for group in groups {
+            let group = group?;
+            let rows = self.surface_rows(&group, stages)?;
+            for (idx, row) in rows.iter().enumerate() {
+                wtr_words_idx
+                    .write_u32::<LittleEndian>(words_len)
+                    .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+                codec.encode(
+                    &self.word_details(row, detail_fields.as_deref()),
+                    &mut words_buffer,
+                )?;
+                words_len += words_buffer.len() as u32;
+                wtr_words
+                    .write_all(&words_buffer)
+                    .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+                words_buffer.clear();
+
+                if rows[..idx].iter().any(|other| other.same_entry(row)) {
+                    self.report_warning(BuildWarning::DuplicateEntry {
+                        file: row.provenance.unwrap_or_default().to_string(),
+                        surface: row.surface_form.to_string(),
+                    })?;
+                    self.entry_stats
+                        .record(row.provenance.unwrap_or_default(), |stats| {
+                            stats.duplicates_seen += 1
+                        });
+                } else if let Some(difference) =
+                    rows[..idx].iter().find_map(|other| row.conflict(other))
+                {
+                    self.report_warning(BuildWarning::ConflictingEntry {
+                        file: row.provenance.unwrap_or_default().to_string(),
+                        surface: row.surface_form.to_string(),
+                        difference,
+                    })?;
+                }
+
+                let word_id = row_id;
+                row_id += 1;
+                if let Some(rule_idx) = skip::find_rule(&skip_rules, row) {
+                    self.skip_row(row, &skip_rules[rule_idx]);
+                    continue;
+                }
+                word_entries.push(WordEntry {
+                    word_id: WordId(word_id, true),
+                    word_cost: self.word_cost(row)?,
+                    cost_id: row.left_id as u16,
+                });
+            }
+            flush_surface(surface(&group[0].line), &mut word_entries)?;
+        }
+
+        for wtr in [&mut wtr_words, &mut wtr_words_idx, &mut wtr_vals] {
+            wtr.flush()
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        }
+        self.write_value_table(output_dir, packer.table().as_deref())?;
+
+        self.print("building da");
+        let keyset: Vec<(&[u8], u32)> = keyset
+            .iter()
+            .map(|(key, val)| (key.as_bytes(), *val))
+            .collect();
+        if let Some(keyset_path) = &self.options.keyset_export {
+            keyset::write_keyset(keyset_path, &keyset)?;
+        }
+        self.progress_start(ProgressStep::InsertKeys, keyset.len() as u64);
+        let da_path = layout.artifact_path(output_dir, &layout.dict_da)?;
+        if self.options.da_spill {
+            self.print(&format!("creating {:?}", da_path));
+            da_spill::build_double_array(&keyset, &da_path)?;
+            self.progress_advance(ProgressStep::InsertKeys, keyset.len() as u64);
+            self.progress_finish(ProgressStep::InsertKeys);
+        } else {
+            let da_bytes = DoubleArrayBuilder::build(&keyset).ok_or_else(|| {
+                LinderaErrorKind::Io.with_error(anyhow::anyhow!("DoubleArray build error."))
+            })?;
+            self.progress_advance(ProgressStep::InsertKeys, keyset.len() as u64);
+            self.progress_finish(ProgressStep::InsertKeys);
+            let mut wtr_da = create(&layout.dict_da)?;
+            wtr_da
+                .write_all(&da_bytes[..])
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            wtr_da
+                .flush()
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        }
+
+        self.report_trie_stats(keyset.iter().map(|(key, _)| *key), &da_path)?;
+        if self.options.paranoid {
+            // The entries were streamed to dict.vals, so only their offsets are checked.
+            self.audit_lexicon(
+                output_dir,
+                keyset.iter().map(|(key, val)| AuditedSurface {
+                    surface: key,
+                    entries: packer.unpack(*val).map_or(0, |(_, entries)| entries),
+                    word_entries: None,
+                }),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use lindera_core::dictionary_builder::DictionaryBuilder;
+
+    use crate::fixtures::write_fixture;
+    use crate::merge::SourceLayer;
+    use crate::options::BuilderOptions;
+    use crate::IpadicNeologdBuilder;
+
+    /// Homonyms of the fixture surfaces, so that the surfaces span several sorted runs.
+    const EXTRA_ROWS: &str = "\
+東京,0,0,2500,名詞,固有名詞,人名,姓,*,*,東京,トウキョウ,トーキョー
+に,1,1,4500,助動詞,*,*,*,特殊・ダ,連用ニ接続,だ,ニ,ニ
+行く,2,2,9000,動詞,自立,*,*,五段・カ行促音便,連用形,行く,イキ,イキ
+";
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-external-sort-{}-{}",
+            name,
+            std::process::id()
+        ))
+    }
+
+    fn assert_same_dict(in_memory_dir: &Path, external_dir: &Path) {
+        let layout = BuilderOptions::default().layout;
+        for name in [
+            &layout.dict_da,
+            &layout.dict_vals,
+            &layout.dict_words,
+            &layout.dict_wordsidx,
+        ] {
+            assert_eq!(
+                fs::read(in_memory_dir.join(name)).unwrap(),
+                fs::read(external_dir.join(name)).unwrap(),
+                "{} differs",
+                name
+            );
+        }
+    }
+
+    fn options(memory_budget: Option<u64>) -> BuilderOptions {
+        BuilderOptions {
+            memory_budget,
+            sort_homonyms: true,
+            max_word_cost: Some(8000),
+            ..BuilderOptions::default()
+        }
+    }
+
+    #[test]
+    fn external_sort_builds_the_in_memory_dictionary() {
+        let base_dir = temp_dir("single");
+        let input_dir = base_dir.join("input");
+        write_fixture(&input_dir).unwrap();
+        fs::write(input_dir.join("extra.csv"), EXTRA_ROWS).unwrap();
+
+        let in_memory_dir = base_dir.join("in-memory");
+        IpadicNeologdBuilder::with_options(options(None))
+            .build_dictionary(&input_dir, &in_memory_dir)
+            .unwrap();
+        // A budget of one byte spills every entry to a run of its own.
+        let external_dir = base_dir.join("external");
+        IpadicNeologdBuilder::with_options(options(Some(1)))
+            .build_dictionary(&input_dir, &external_dir)
+            .unwrap();
+        assert_same_dict(&in_memory_dir, &external_dir);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn external_sort_builds_the_in_memory_merged_dictionary() {
+        let base_dir = temp_dir("merged");
+        let base_input_dir = base_dir.join("base");
+        write_fixture(&base_input_dir).unwrap();
+        let custom_input_dir = base_dir.join("custom");
+        fs::create_dir_all(&custom_input_dir).unwrap();
+        fs::write(
+            custom_input_dir.join("custom.csv"),
+            format!(
+                "{}東京,1,1,1000,名詞,固有名詞,地域,一般,*,*,東京,トウキョウ,トーキョー\n",
+                EXTRA_ROWS
+            ),
+        )
+        .unwrap();
+        let layers = [
+            SourceLayer::new("base", &base_input_dir),
+            SourceLayer::new("custom", &custom_input_dir),
+        ];
+
+        let in_memory_dir = base_dir.join("in-memory");
+        IpadicNeologdBuilder::with_options(options(None))
+            .build_merged_dictionary(&layers, &in_memory_dir)
+            .unwrap();
+        let external_dir = base_dir.join("external");
+        IpadicNeologdBuilder::with_options(options(Some(1)))
+            .build_merged_dictionary(&layers, &external_dir)
+            .unwrap();
+        assert_same_dict(&in_memory_dir, &external_dir);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
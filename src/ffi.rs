@@ -0,0 +1,163 @@
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::path::Path;
+use std::ptr;
+
+use lindera_core::dictionary_builder::DictionaryBuilder;
+
+use crate::options::BuilderOptions;
+use crate::IpadicNeologdBuilder;
+
+pub const LINDERA_NEOLOGD_OK: c_int = 0;
+pub const LINDERA_NEOLOGD_INVALID_ARGUMENT: c_int = 1;
+pub const LINDERA_NEOLOGD_INVALID_OPTIONS: c_int = 2;
+pub const LINDERA_NEOLOGD_BUILD_ERROR: c_int = 3;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).ok();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = message);
+}
+
+unsafe fn str_arg<'a>(arg: *const c_char, name: &str) -> Result<&'a str, c_int> {
+    if arg.is_null() {
+        set_last_error(format!("{} is null", name));
+        return Err(LINDERA_NEOLOGD_INVALID_ARGUMENT);
+    }
+    CStr::from_ptr(arg).to_str().map_err(|err| {
+        set_last_error(format!("{} is not valid UTF-8: {}", name, err));
+        LINDERA_NEOLOGD_INVALID_ARGUMENT
+    })
+}
+
+fn build(input_dir: &str, output_dir: &str, options_json: Option<&str>) -> c_int {
+    let options = match options_json {
+        Some(options_json) => match serde_json::from_str::<BuilderOptions>(options_json) {
+            Ok(options) => options,
+            Err(err) => {
+                set_last_error(err.to_string());
+                return LINDERA_NEOLOGD_INVALID_OPTIONS;
+            }
+        },
+        None => BuilderOptions::default(),
+    };
+
+    let builder = IpadicNeologdBuilder::with_options(options);
+    match builder.build_dictionary(Path::new(input_dir), Path::new(output_dir)) {
+        Ok(()) => LINDERA_NEOLOGD_OK,
+        Err(err) => {
+            set_last_error(err.to_string());
+            LINDERA_NEOLOGD_BUILD_ERROR
+        }
+    }
+}
+
+/// Builds the dictionary from `input_dir` into `output_dir`.
+///
+/// `options_json` is a JSON object with the fields of `BuilderOptions`, or NULL for the
+/// defaults. Returns `LINDERA_NEOLOGD_OK` on success; on failure, the error message can be
+/// retrieved with `lindera_neologd_last_error`.
+///
+/// # Safety
+///
+/// All arguments must be NULL or point to NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn lindera_neologd_build(
+    input_dir: *const c_char,
+    output_dir: *const c_char,
+    options_json: *const c_char,
+) -> c_int {
+    let input_dir = match str_arg(input_dir, "input_dir") {
+        Ok(input_dir) => input_dir,
+        Err(status) => return status,
+    };
+    let output_dir = match str_arg(output_dir, "output_dir") {
+        Ok(output_dir) => output_dir,
+        Err(status) => return status,
+    };
+    let options_json = if options_json.is_null() {
+        None
+    } else {
+        match str_arg(options_json, "options_json") {
+            Ok(options_json) => Some(options_json),
+            Err(status) => return status,
+        }
+    };
+
+    match std::panic::catch_unwind(|| build(input_dir, output_dir, options_json)) {
+        Ok(status) => status,
+        Err(_) => {
+            set_last_error("the build panicked".to_string());
+            LINDERA_NEOLOGD_BUILD_ERROR
+        }
+    }
+}
+
+/// Returns the message of the last error that occurred on the calling thread, or NULL.
+/// The returned string is owned by the library and valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn lindera_neologd_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| match &*last_error.borrow() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{CStr, CString};
+    use std::fs;
+    use std::ptr;
+
+    use super::*;
+    use crate::fixtures::write_fixture;
+
+    fn last_error() -> String {
+        let message = lindera_neologd_last_error();
+        assert!(!message.is_null());
+        unsafe { CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn reports_status_codes_and_last_errors() {
+        let base_dir =
+            std::env::temp_dir().join(format!("lindera-ipadic-neologd-ffi-{}", std::process::id()));
+        let input_dir = base_dir.join("input");
+        write_fixture(&input_dir).unwrap();
+        let input = CString::new(input_dir.to_str().unwrap()).unwrap();
+        let output = CString::new(base_dir.join("output").to_str().unwrap()).unwrap();
+        let missing = CString::new(base_dir.join("missing").to_str().unwrap()).unwrap();
+        let invalid_options = CString::new(r#"{"max_word_cost": "high"}"#).unwrap();
+        let options = CString::new(r#"{"max_word_cost": 8000}"#).unwrap();
+
+        unsafe {
+            assert_eq!(
+                lindera_neologd_build(ptr::null(), output.as_ptr(), ptr::null()),
+                LINDERA_NEOLOGD_INVALID_ARGUMENT
+            );
+            assert_eq!(last_error(), "input_dir is null");
+            assert_eq!(
+                lindera_neologd_build(input.as_ptr(), output.as_ptr(), invalid_options.as_ptr()),
+                LINDERA_NEOLOGD_INVALID_OPTIONS
+            );
+            assert!(last_error().contains("invalid type"));
+            assert_eq!(
+                lindera_neologd_build(missing.as_ptr(), output.as_ptr(), ptr::null()),
+                LINDERA_NEOLOGD_BUILD_ERROR
+            );
+            assert_eq!(
+                lindera_neologd_build(input.as_ptr(), output.as_ptr(), options.as_ptr()),
+                LINDERA_NEOLOGD_OK
+            );
+        }
+        assert!(base_dir.join("output").join("dict.da").is_file());
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
@@ -0,0 +1,214 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+
+use encoding::all::EUC_JP;
+use encoding::{DecoderTrap, Encoding};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::error::BuildError;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Reads a dictionary source file as UTF-8, stripping a leading BOM.
+pub(crate) fn read_source_file(filename: &Path) -> LinderaResult<String> {
+    let bytes = fs::read(filename).map_err(|source| BuildError::Io {
+        path: Some(filename.to_path_buf()),
+        source,
+    })?;
+    decode_source(filename, bytes)
+}
+
+/// Decodes the content of a dictionary source file as UTF-8, stripping a leading BOM.
+/// Invalid sequences are reported with the file name and the byte offset, and with a
+/// hint when the file looks like it was left in the original EUC-JP encoding.
+pub(crate) fn decode_source(filename: &Path, mut bytes: Vec<u8>) -> LinderaResult<String> {
+    let bom_len = if bytes.starts_with(UTF8_BOM) {
+        UTF8_BOM.len()
+    } else {
+        0
+    };
+    bytes.drain(..bom_len);
+
+    String::from_utf8(bytes).map_err(|err| {
+        let offset = err.utf8_error().valid_up_to();
+        let bytes = err.into_bytes();
+        let utf8_prefix = bytes[..offset].iter().any(|b| !b.is_ascii());
+        let hint = if utf8_prefix && EUC_JP.decode(&bytes[offset..], DecoderTrap::Strict).is_ok() {
+            " (the file mixes UTF-8 with EUC-JP)"
+        } else if EUC_JP.decode(&bytes, DecoderTrap::Strict).is_ok() {
+            " (the file looks like EUC-JP)"
+        } else {
+            ""
+        };
+        LinderaErrorKind::Decode.with_error(anyhow::anyhow!(
+            "{}: invalid UTF-8 sequence at byte offset {}{}",
+            filename.display(),
+            offset + bom_len,
+            hint
+        ))
+    })
+}
+
+/// Iterates over the lines of a source file together with their 1-based line numbers,
+/// accepting both LF and CRLF line endings and skipping empty or whitespace-only lines.
+pub(crate) fn source_lines(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    content
+        .split('\n')
+        .map(|line| line.trim_end_matches('\r'))
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_idx, line)| (line_idx + 1, line))
+}
+
+/// Streams the lines of a source file to `f` with the same numbering and skipping as
+/// `source_lines`, without reading the whole file into memory.
+pub(crate) fn for_each_source_line<F>(filename: &Path, f: F) -> LinderaResult<()>
+where
+    F: FnMut(usize, &str) -> LinderaResult<()>,
+{
+    let rdr = io::BufReader::new(File::open(filename).map_err(|source| BuildError::Io {
+        path: Some(filename.to_path_buf()),
+        source,
+    })?);
+    for_each_line(filename, rdr, f)
+}
+
+/// Streams the lines of `rdr` to `f` like `for_each_source_line`; `filename` is only used in
+/// error messages.
+pub(crate) fn for_each_line<R, F>(filename: &Path, mut rdr: R, mut f: F) -> LinderaResult<()>
+where
+    R: BufRead,
+    F: FnMut(usize, &str) -> LinderaResult<()>,
+{
+    let mut buf = Vec::new();
+    let mut line_num = 0;
+    loop {
+        buf.clear();
+        let len = rdr
+            .read_until(b'\n', &mut buf)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        if len == 0 {
+            break;
+        }
+        line_num += 1;
+
+        let bytes = if line_num == 1 && buf.starts_with(UTF8_BOM) {
+            &buf[UTF8_BOM.len()..]
+        } else {
+            &buf[..]
+        };
+        let line = std::str::from_utf8(bytes).map_err(|err| {
+            LinderaErrorKind::Decode.with_error(anyhow::anyhow!(
+                "{}:{}: invalid UTF-8 sequence at byte offset {} of the line",
+                filename.display(),
+                line_num,
+                err.valid_up_to()
+            ))
+        })?;
+        let line = line.trim_end_matches('\n').trim_end_matches('\r');
+        if !line.trim().is_empty() {
+            f(line_num, line)?;
+        }
+    }
+    Ok(())
+}
+
+/// Source archives usually wrap the dictionary in a top-level directory; find the directory
+/// that actually contains `matrix.def`.
+#[cfg_attr(
+    not(any(feature = "build-support", feature = "serve")),
+    allow(dead_code)
+)]
+pub(crate) fn find_input_dir(source_dir: &Path) -> LinderaResult<PathBuf> {
+    if source_dir.join("matrix.def").exists() {
+        return Ok(source_dir.to_path_buf());
+    }
+    let entries = fs::read_dir(source_dir)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?
+            .path();
+        if path.is_dir() && path.join("matrix.def").exists() {
+            return Ok(path);
+        }
+    }
+
+    Err(LinderaErrorKind::Content
+        .with_error(anyhow::anyhow!("matrix.def not found in {:?}", source_dir)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use encoding::all::EUC_JP;
+    use encoding::{EncoderTrap, Encoding};
+
+    use super::{decode_source, for_each_line, source_lines};
+
+    #[test]
+    fn strips_a_leading_bom() {
+        let mut bytes = b"\xEF\xBB\xBF".to_vec();
+        bytes.extend_from_slice("東京,1,1,3000".as_bytes());
+        assert_eq!(
+            decode_source(Path::new("a.csv"), bytes).unwrap(),
+            "東京,1,1,3000"
+        );
+    }
+
+    #[test]
+    fn reports_the_offset_of_invalid_utf8() {
+        let err = decode_source(Path::new("a.csv"), b"abc\xFFdef".to_vec()).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("a.csv"), "{}", message);
+        assert!(message.contains("byte offset 3"), "{}", message);
+
+        // The offset counts the stripped BOM.
+        let err = decode_source(Path::new("a.csv"), b"\xEF\xBB\xBFabc\xFF".to_vec()).unwrap_err();
+        assert!(err.to_string().contains("byte offset 6"));
+    }
+
+    #[test]
+    fn hints_at_euc_jp() {
+        let euc_jp = EUC_JP.encode("東京,1,1,3000", EncoderTrap::Strict).unwrap();
+        let err = decode_source(Path::new("a.csv"), euc_jp.clone()).unwrap_err();
+        assert!(err.to_string().contains("looks like EUC-JP"));
+
+        let mut mixed = "渋谷\n".as_bytes().to_vec();
+        mixed.extend_from_slice(&euc_jp);
+        let err = decode_source(Path::new("a.csv"), mixed).unwrap_err();
+        assert!(err.to_string().contains("mixes UTF-8 with EUC-JP"));
+    }
+
+    #[test]
+    fn numbers_lines_across_crlf_and_blank_lines() {
+        let content = "a,1\r\n\r\n  \nb,2\nc,3\r\n";
+        let expected = vec![(1, "a,1"), (4, "b,2"), (5, "c,3")];
+        assert_eq!(source_lines(content).collect::<Vec<_>>(), expected);
+
+        let mut streamed = Vec::new();
+        let mut bytes = b"\xEF\xBB\xBF".to_vec();
+        bytes.extend_from_slice(content.as_bytes());
+        for_each_line(Path::new("a.csv"), &bytes[..], |line_num, line| {
+            streamed.push((line_num, line.to_string()));
+            Ok(())
+        })
+        .unwrap();
+        let expected: Vec<(usize, String)> = expected
+            .into_iter()
+            .map(|(line_num, line)| (line_num, line.to_string()))
+            .collect();
+        assert_eq!(streamed, expected);
+    }
+
+    #[test]
+    fn streamed_lines_report_the_line_of_invalid_utf8() {
+        let err =
+            for_each_line(Path::new("a.csv"), &b"a,1\nb\xFF,2\n"[..], |_, _| Ok(())).unwrap_err();
+        assert!(err.to_string().contains("a.csv:2:"), "{}", err);
+    }
+}
@@ -0,0 +1,153 @@
+use std::fs;
+use std::path::Path;
+
+use lindera_core::dictionary_builder::DictionaryBuilder;
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::options::BuilderOptions;
+use crate::IpadicNeologdBuilder;
+
+pub const FIXTURE_CSV_FILENAME: &str = "fixture.csv";
+
+/// `char.def` of the fixture, with the categories used by `unk.def`.
+pub const FIXTURE_CHAR_DEF: &str = "\
+DEFAULT 0 1 0
+SPACE 0 1 0
+KANJI 0 0 2
+HIRAGANA 1 1 0
+KATAKANA 1 1 0
+ALPHA 1 1 0
+NUMERIC 1 1 0
+0x0020 SPACE
+0x0030..0x0039 NUMERIC
+0x0041..0x005A ALPHA
+0x0061..0x007A ALPHA
+0x3041..0x309F HIRAGANA
+0x30A1..0x30FF KATAKANA
+0x4E00..0x9FFF KANJI
+";
+
+/// `unk.def` of the fixture, with one entry per category.
+pub const FIXTURE_UNK_DEF: &str = "\
+DEFAULT,0,0,5000,記号,一般,*,*,*,*,*
+SPACE,0,0,5000,記号,空白,*,*,*,*,*
+KANJI,0,0,5000,名詞,一般,*,*,*,*,*
+HIRAGANA,0,0,5000,名詞,一般,*,*,*,*,*
+KATAKANA,0,0,5000,名詞,一般,*,*,*,*,*
+ALPHA,0,0,5000,名詞,一般,*,*,*,*,*
+NUMERIC,0,0,5000,名詞,数,*,*,*,*,*
+";
+
+/// `matrix.def` of the fixture, a full 3x3 matrix.
+pub const FIXTURE_MATRIX_DEF: &str = "\
+3 3
+0 0 0
+0 1 100
+0 2 100
+1 0 100
+1 1 200
+1 2 -100
+2 0 100
+2 1 -100
+2 2 200
+";
+
+/// The CSV rows of the fixture.
+pub const FIXTURE_ROWS: [&str; 5] = [
+    "東京,1,1,3000,名詞,固有名詞,地域,一般,*,*,東京,トウキョウ,トーキョー",
+    "東京スカイツリー,1,1,2000,名詞,固有名詞,一般,*,*,*,東京スカイツリー,トウキョウスカイツリー,トーキョースカイツリー",
+    "に,2,2,4000,助詞,格助詞,一般,*,*,*,に,ニ,ニ",
+    "行く,2,2,5000,動詞,自立,*,*,五段・カ行促音便,基本形,行く,イク,イク",
+    "いく,2,2,5500,動詞,自立,*,*,五段・カ行促音便,基本形,いく,イク,イク",
+];
+
+/// Writes a tiny but valid IPADIC source directory to `input_dir`.
+pub fn write_fixture(input_dir: &Path) -> LinderaResult<()> {
+    fs::create_dir_all(input_dir)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+    let mut csv = FIXTURE_ROWS.join("\n");
+    csv.push('\n');
+    for (filename, content) in &[
+        ("char.def", FIXTURE_CHAR_DEF),
+        ("unk.def", FIXTURE_UNK_DEF),
+        ("matrix.def", FIXTURE_MATRIX_DEF),
+        (FIXTURE_CSV_FILENAME, csv.as_str()),
+    ] {
+        fs::write(input_dir.join(filename), content)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    }
+
+    Ok(())
+}
+
+/// Writes the fixture source to `input_dir` and builds it into `output_dir` with `options`.
+pub fn build_fixture(
+    input_dir: &Path,
+    output_dir: &Path,
+    options: BuilderOptions,
+) -> LinderaResult<()> {
+    write_fixture(input_dir)?;
+    IpadicNeologdBuilder::with_options(options).build_dictionary(input_dir, output_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::manifest::Manifest;
+    use crate::reader::DictionaryReader;
+    use crate::sources::{parse_sources, SourceBytes};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-fixture-{}-{}",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn fixture_builds_into_a_readable_dictionary() {
+        let base_dir = temp_dir("build");
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        build_fixture(&input_dir, &output_dir, BuilderOptions::default()).unwrap();
+
+        let options = BuilderOptions::default();
+        for name in options.layout.artifact_names().iter() {
+            assert!(output_dir.join(name).is_file(), "{} is missing", name);
+        }
+        let manifest = Manifest::load(&output_dir).unwrap();
+        assert!(!manifest.artifacts.is_empty());
+
+        let reader = DictionaryReader::open(&output_dir).unwrap();
+        assert_eq!(reader.num_words(), FIXTURE_ROWS.len());
+        let entries = reader.lookup("東京");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].word_cost, 3000);
+        let details = reader.details(entries[0].word_id.0).unwrap();
+        assert!(details.iter().any(|field| field == "トウキョウ"));
+        assert_eq!(reader.prefix_search("東京スカイツリーに行く").len(), 2);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn fixture_sources_parse_in_memory() {
+        let mut csv = FIXTURE_ROWS.join("\n");
+        csv.push('\n');
+        let sources = SourceBytes {
+            char_def: FIXTURE_CHAR_DEF.as_bytes(),
+            unk_def: FIXTURE_UNK_DEF.as_bytes(),
+            matrix_def: FIXTURE_MATRIX_DEF.as_bytes(),
+            csv_files: vec![csv.as_bytes()],
+        };
+        let parsed = parse_sources(&sources, &BuilderOptions::default()).unwrap();
+        assert_eq!(parsed.rows, FIXTURE_ROWS.len());
+        assert_eq!(parsed.surfaces, FIXTURE_ROWS.len());
+        assert_eq!(&parsed.costs[..2], &[3, 3]);
+        assert_eq!(parsed.costs.len(), 2 + 3 * 3);
+    }
+}
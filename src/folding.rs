@@ -0,0 +1,93 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+
+/// How the surfaces are folded before the entries are built, to match text pipelines that
+/// normalize the text before tokenizing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SurfaceFolding {
+    /// ASCII letters are lowercased.
+    Lowercase,
+    /// Full-width digits and Latin letters are folded to their ASCII forms.
+    HalfWidth,
+    /// Full-width alphanumerics are folded to ASCII, then lowercased.
+    Both,
+}
+
+impl SurfaceFolding {
+    /// Folds `surface`, borrowing it when nothing changes.
+    pub fn fold<'a>(&self, surface: &'a str) -> Cow<'a, str> {
+        if !surface.chars().any(|c| self.fold_char(c) != c) {
+            return Cow::Borrowed(surface);
+        }
+        Cow::Owned(surface.chars().map(|c| self.fold_char(c)).collect())
+    }
+
+    fn fold_char(&self, c: char) -> char {
+        match self {
+            SurfaceFolding::Lowercase => c.to_ascii_lowercase(),
+            SurfaceFolding::HalfWidth => half_width(c),
+            SurfaceFolding::Both => half_width(c).to_ascii_lowercase(),
+        }
+    }
+}
+
+/// The ASCII form of a full-width digit or Latin letter, or `c` itself.
+fn half_width(c: char) -> char {
+    match c {
+        '０'..='９' | 'Ａ'..='Ｚ' | 'ａ'..='ｚ' => {
+            char::from_u32(c as u32 - 0xFEE0).unwrap_or(c)
+        }
+        _ => c,
+    }
+}
+
+/// Folds the surface of a CSV entry line, appending the original surface as an extra field
+/// after the entry fields, which are the first `fields_num`.
+pub(crate) fn fold_line(line: &str, folding: SurfaceFolding, fields_num: usize) -> String {
+    let fields: Vec<&str> = line.split(',').collect();
+    if fields.len() < fields_num {
+        // Left to the parser to report.
+        return line.to_string();
+    }
+    let surface = fields[0];
+    let mut folded = vec![folding.fold(surface).into_owned()];
+    folded.extend(fields[1..fields_num].iter().map(|field| field.to_string()));
+    folded.push(surface.to_string());
+    folded.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::{fold_line, SurfaceFolding};
+    use crate::CSV_FIELDS_NUM;
+
+    #[test]
+    fn folds_full_width_alphanumerics_and_case() {
+        assert_eq!(SurfaceFolding::Lowercase.fold("ＡＢＣ Abc"), "ＡＢＣ abc");
+        assert_eq!(
+            SurfaceFolding::HalfWidth.fold("ＡＢＣ１２３ａｂ"),
+            "ABC123ab"
+        );
+        assert_eq!(SurfaceFolding::Both.fold("ＡＢＣ１２３Ｘ線"), "abc123x線");
+        assert!(matches!(
+            SurfaceFolding::Both.fold("東京"),
+            Cow::Borrowed("東京")
+        ));
+    }
+
+    #[test]
+    fn keeps_the_original_surface_after_the_entry_fields() {
+        let line = "ＮＨＫ,1,1,3000,名詞,固有名詞,組織,*,*,*,ＮＨＫ,エヌエイチケー,エヌエイチケー";
+        assert_eq!(
+            fold_line(line, SurfaceFolding::Both, CSV_FIELDS_NUM),
+            "nhk,1,1,3000,名詞,固有名詞,組織,*,*,*,ＮＨＫ,エヌエイチケー,エヌエイチケー,ＮＨＫ"
+        );
+        assert_eq!(
+            fold_line("ＮＨＫ,1,1", SurfaceFolding::Both, CSV_FIELDS_NUM),
+            "ＮＨＫ,1,1"
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use lindera_core::LinderaResult;
+
+use crate::file_util::{read_source_file, source_lines};
+use crate::{normalize_line, CsvRow};
+
+/// The word cost of a term per unit of its negative log relative frequency when
+/// `BuilderOptions::frequency_cost_scale` is not set, which puts the costs of a large corpus
+/// in the range of the IPADIC costs.
+pub const DEFAULT_FREQUENCY_COST_SCALE: f64 = 700.0;
+
+/// Counts of terms in a target corpus, from which the word costs are recomputed. The file
+/// lists a surface and its count, separated by a tab, per line; blank lines and lines
+/// starting with `#` are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct TermFrequencies {
+    pub counts: HashMap<String, u64>,
+    pub total: u64,
+    pub cost_scale: f64,
+}
+
+impl TermFrequencies {
+    pub fn load(path: &Path, cost_scale: f64) -> LinderaResult<TermFrequencies> {
+        let content = read_source_file(path)?;
+        Ok(TermFrequencies::parse(&content, cost_scale))
+    }
+
+    /// Parses the lines of a frequency file. Lines without a count are ignored.
+    pub fn parse(content: &str, cost_scale: f64) -> TermFrequencies {
+        let mut counts: HashMap<String, u64> = HashMap::new();
+        for (_, line) in source_lines(content) {
+            if line.starts_with('#') {
+                continue;
+            }
+            if let Some((term, count)) = line.split_once('\t') {
+                if let Ok(count) = count.trim().parse::<u64>() {
+                    *counts.entry(normalize_line(term.trim())).or_insert(0) += count;
+                }
+            }
+        }
+        let total = counts.values().sum();
+        TermFrequencies {
+            counts,
+            total,
+            cost_scale,
+        }
+    }
+
+    /// The word cost of `surface`, `-ln(count / total) * cost_scale` clamped to the `i16`
+    /// range, if it has a count.
+    pub fn cost(&self, surface: &str) -> Option<i32> {
+        let count = *self.counts.get(surface).filter(|count| **count > 0)?;
+        let cost = -(count as f64 / self.total as f64).ln() * self.cost_scale;
+        Some(cost.round().clamp(i16::MIN as f64, i16::MAX as f64) as i32)
+    }
+
+    /// Sets the word cost of the rows of the surfaces with a count to their frequency cost,
+    /// plus the difference of their stock cost to the lowest stock cost of the surface, so
+    /// that the order of the homonyms is kept. Returns the number of rows changed.
+    pub(crate) fn apply(&self, rows: &mut [CsvRow]) -> usize {
+        let mut lowest_costs: HashMap<&str, i32> = HashMap::new();
+        for row in rows.iter() {
+            if self.counts.contains_key(row.surface_form) {
+                let lowest = lowest_costs
+                    .entry(row.surface_form)
+                    .or_insert(row.word_cost);
+                *lowest = (*lowest).min(row.word_cost);
+            }
+        }
+
+        let mut changed = 0;
+        for row in rows.iter_mut() {
+            let lowest = match lowest_costs.get(row.surface_form) {
+                Some(lowest) => *lowest,
+                None => continue,
+            };
+            if let Some(cost) = self.cost(row.surface_form) {
+                row.word_cost = cost.saturating_add(row.word_cost - lowest);
+                changed += 1;
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TermFrequencies;
+    use crate::fixtures::FIXTURE_ROWS;
+    use crate::CsvRow;
+
+    #[test]
+    fn parses_counts_per_term() {
+        let frequencies = TermFrequencies::parse(
+            "# term\tcount\n#東京\t100\n東京\t1\r\n に \t2\nに\t1\n行く\tmany\n渋谷\n",
+            700.0,
+        );
+        assert_eq!(frequencies.total, 4);
+        assert_eq!(frequencies.counts.len(), 2);
+        assert_eq!(frequencies.counts["に"], 3);
+        assert_eq!(frequencies.cost("東京"), Some(970));
+        assert_eq!(frequencies.cost("に"), Some(201));
+        assert_eq!(frequencies.cost("行く"), None);
+
+        let frequencies = TermFrequencies::parse("東京\t1\nに\t100000000\n", 10000.0);
+        assert_eq!(frequencies.cost("東京"), Some(i32::from(i16::MAX)));
+    }
+
+    #[test]
+    fn keeps_the_order_of_homonyms() {
+        let frequencies = TermFrequencies::parse("東京\t1\nに\t3\n", 700.0);
+        let lines = [
+            FIXTURE_ROWS[0],
+            "東京,1,1,3500,名詞,固有名詞,人名,姓,*,*,東京,トウキョウ,トウキョウ",
+            FIXTURE_ROWS[3],
+        ];
+        let mut rows: Vec<CsvRow> = lines
+            .iter()
+            .map(|line| CsvRow::from_line(line).unwrap())
+            .collect();
+        assert_eq!(frequencies.apply(&mut rows), 2);
+        let costs: Vec<i32> = rows.iter().map(|row| row.word_cost).collect();
+        assert_eq!(costs, vec![970, 1470, 5000]);
+    }
+}
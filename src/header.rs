@@ -0,0 +1,217 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::codec::DetailFormat;
+use crate::endian::Endianness;
+use crate::layout::DictionaryLayout;
+
+/// The first bytes of the artifacts written with `BuilderOptions::artifact_headers`.
+pub const ARTIFACT_MAGIC: &[u8; 4] = b"LNDR";
+
+/// The version of the binary format of the artifacts, bumped on incompatible changes.
+pub const ARTIFACT_FORMAT_VERSION: u16 = 1;
+
+/// The length of the header: the magic, the `u16` format version and the `u16` flags, all
+/// little-endian.
+pub const ARTIFACT_HEADER_LEN: usize = 8;
+
+/// The integers of the artifact are big-endian.
+pub const FLAG_BIG_ENDIAN: u16 = 1;
+/// The word details are encoded with `DetailFormat::Compact`.
+pub const FLAG_COMPACT_DETAILS: u16 = 1 << 1;
+/// The word details are encoded with `DetailFormat::Json`.
+pub const FLAG_JSON_DETAILS: u16 = 1 << 2;
+
+/// The header prepended to an artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArtifactHeader {
+    pub version: u16,
+    pub flags: u16,
+}
+
+impl ArtifactHeader {
+    /// The header of the current format version for artifacts of `endianness` and
+    /// `detail_format`.
+    pub fn new(endianness: Endianness, detail_format: DetailFormat) -> Self {
+        let mut flags = 0;
+        if endianness == Endianness::Big {
+            flags |= FLAG_BIG_ENDIAN;
+        }
+        match detail_format {
+            DetailFormat::Bincode => {}
+            DetailFormat::Compact => flags |= FLAG_COMPACT_DETAILS,
+            DetailFormat::Json => flags |= FLAG_JSON_DETAILS,
+        }
+        ArtifactHeader {
+            version: ARTIFACT_FORMAT_VERSION,
+            flags,
+        }
+    }
+
+    pub fn endianness(&self) -> Endianness {
+        if self.flags & FLAG_BIG_ENDIAN != 0 {
+            Endianness::Big
+        } else {
+            Endianness::Little
+        }
+    }
+
+    pub fn detail_format(&self) -> DetailFormat {
+        if self.flags & FLAG_JSON_DETAILS != 0 {
+            DetailFormat::Json
+        } else if self.flags & FLAG_COMPACT_DETAILS != 0 {
+            DetailFormat::Compact
+        } else {
+            DetailFormat::Bincode
+        }
+    }
+
+    /// Whether loaders of the current format version can read the artifact.
+    pub fn is_supported(&self) -> bool {
+        self.version == ARTIFACT_FORMAT_VERSION
+    }
+
+    pub fn to_bytes(&self) -> [u8; ARTIFACT_HEADER_LEN] {
+        let mut bytes = [0u8; ARTIFACT_HEADER_LEN];
+        bytes[..4].copy_from_slice(ARTIFACT_MAGIC);
+        LittleEndian::write_u16(&mut bytes[4..6], self.version);
+        LittleEndian::write_u16(&mut bytes[6..8], self.flags);
+        bytes
+    }
+}
+
+/// The header at the start of `data`, if it starts with `ARTIFACT_MAGIC`. Artifacts without
+/// it are in the headerless format loaded by Lindera.
+pub fn sniff(data: &[u8]) -> Option<ArtifactHeader> {
+    if data.len() < ARTIFACT_HEADER_LEN || &data[..4] != ARTIFACT_MAGIC {
+        return None;
+    }
+    Some(ArtifactHeader {
+        version: LittleEndian::read_u16(&data[4..6]),
+        flags: LittleEndian::read_u16(&data[6..8]),
+    })
+}
+
+/// The header of the artifact at `path`, read from its first bytes only.
+pub fn sniff_file(path: &Path) -> LinderaResult<Option<ArtifactHeader>> {
+    let mut data = Vec::with_capacity(ARTIFACT_HEADER_LEN);
+    File::open(path)
+        .and_then(|file| file.take(ARTIFACT_HEADER_LEN as u64).read_to_end(&mut data))
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    Ok(sniff(&data))
+}
+
+/// The content of an artifact after its header, failing if the header is of a format
+/// version this builder cannot read.
+pub fn strip_header(data: &[u8]) -> LinderaResult<&[u8]> {
+    match sniff(data) {
+        Some(header) if !header.is_supported() => Err(LinderaErrorKind::Content.with_error(
+            anyhow::anyhow!("unsupported artifact format version {}", header.version),
+        )),
+        Some(_) => Ok(&data[ARTIFACT_HEADER_LEN..]),
+        None => Ok(data),
+    }
+}
+
+/// Prepends `header` to the artifacts of `layout` in `output_dir`.
+pub(crate) fn write_headers(
+    output_dir: &Path,
+    layout: &DictionaryLayout,
+    header: ArtifactHeader,
+) -> LinderaResult<()> {
+    for name in layout.artifact_names().iter() {
+        let path = output_dir.join(name);
+        println!("adding the header to {:?}", path);
+        let data =
+            fs::read(&path).map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        let mut with_header = Vec::with_capacity(ARTIFACT_HEADER_LEN + data.len());
+        with_header.extend_from_slice(&header.to_bytes());
+        with_header.extend_from_slice(&data);
+        fs::write(&path, with_header)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{
+        sniff, sniff_file, strip_header, write_headers, ArtifactHeader, ARTIFACT_HEADER_LEN,
+        FLAG_BIG_ENDIAN, FLAG_JSON_DETAILS,
+    };
+    use crate::codec::DetailFormat;
+    use crate::endian::Endianness;
+    use crate::fixtures::build_fixture;
+    use crate::layout::DictionaryLayout;
+    use crate::options::BuilderOptions;
+
+    #[test]
+    fn headers_round_trip_through_their_bytes() {
+        for endianness in [Endianness::Little, Endianness::Big] {
+            for detail_format in [
+                DetailFormat::Bincode,
+                DetailFormat::Compact,
+                DetailFormat::Json,
+            ] {
+                let header = ArtifactHeader::new(endianness, detail_format);
+                let mut data = header.to_bytes().to_vec();
+                data.extend_from_slice(b"data");
+                let sniffed = sniff(&data).unwrap();
+                assert_eq!(sniffed, header);
+                assert_eq!(sniffed.endianness(), endianness);
+                assert_eq!(sniffed.detail_format(), detail_format);
+                assert_eq!(strip_header(&data).unwrap(), b"data");
+            }
+        }
+        let header = ArtifactHeader::new(Endianness::Big, DetailFormat::Json);
+        assert_eq!(header.flags, FLAG_BIG_ENDIAN | FLAG_JSON_DETAILS);
+        assert_eq!(&header.to_bytes(), b"LNDR\x01\x00\x05\x00");
+
+        assert_eq!(sniff(b"LNDR\x01\x00\x00"), None);
+        assert_eq!(strip_header(b"headerless").unwrap(), b"headerless");
+        let newer = ArtifactHeader {
+            version: 2,
+            flags: 0,
+        };
+        assert!(strip_header(&newer.to_bytes())
+            .unwrap_err()
+            .to_string()
+            .contains("unsupported artifact format version 2"));
+    }
+
+    #[test]
+    fn prepends_headers_to_the_artifacts() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-header-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        build_fixture(&input_dir, &output_dir, BuilderOptions::default()).unwrap();
+        let layout = DictionaryLayout::default();
+        let headerless: Vec<Vec<u8>> = layout
+            .artifact_names()
+            .iter()
+            .map(|name| fs::read(output_dir.join(name)).unwrap())
+            .collect();
+        let header = ArtifactHeader::new(Endianness::Little, DetailFormat::Compact);
+        write_headers(&output_dir, &layout, header).unwrap();
+
+        for (name, data) in layout.artifact_names().iter().zip(headerless) {
+            let path = output_dir.join(name);
+            assert_eq!(sniff_file(&path).unwrap(), Some(header));
+            let with_header = fs::read(&path).unwrap();
+            assert_eq!(with_header.len(), ARTIFACT_HEADER_LEN + data.len());
+            assert_eq!(strip_header(&with_header).unwrap(), &data[..]);
+        }
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::entry_stats::FileEntryStats;
+use crate::manifest::ArtifactEntry;
+use crate::unk::EffectiveUnkCost;
+use crate::warning::WarningKind;
+use crate::IpadicNeologdBuilder;
+
+/// What a completed build produced, passed to the post-build hooks.
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildReport {
+    pub input_dir: PathBuf,
+    pub output_dir: PathBuf,
+    /// The package written with `BuilderOptions::package`.
+    pub package: Option<PathBuf>,
+    /// The artifacts listed in `metadata.json`, with their sizes.
+    pub artifacts: Vec<ArtifactEntry>,
+    pub dictionary_version: Option<String>,
+    #[serde(serialize_with = "serialize_millis", rename = "duration_ms")]
+    pub duration: Duration,
+    /// The entry statistics of the source files, as `IpadicNeologdBuilder::entry_stats`.
+    pub entry_stats: BTreeMap<String, FileEntryStats>,
+    /// The costs of the `unk.def` entries adjusted by `BuilderOptions::unk_costs`, as
+    /// `IpadicNeologdBuilder::effective_unk_costs`.
+    pub effective_unk_costs: Vec<EffectiveUnkCost>,
+    /// The number of warnings of each kind, as `IpadicNeologdBuilder::warning_counts`.
+    pub warning_counts: BTreeMap<WarningKind, usize>,
+}
+
+fn serialize_millis<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_u128(duration.as_millis())
+}
+
+/// Runs after a dictionary is built, e.g. to upload its artifacts, warm caches or send a
+/// notification. Hooks are added with `IpadicNeologdBuilder::with_post_build_hook` and run
+/// in order while the output directory is still locked; a failing hook fails the build and
+/// the later hooks are not run.
+pub trait PostBuildHook {
+    fn run(&self, report: &BuildReport) -> LinderaResult<()>;
+}
+
+impl<F> PostBuildHook for F
+where
+    F: Fn(&BuildReport) -> LinderaResult<()>,
+{
+    fn run(&self, report: &BuildReport) -> LinderaResult<()> {
+        self(report)
+    }
+}
+
+/// A post-build hook running a shell command, as listed in
+/// `BuilderOptions::post_build_hooks`. The command receives the report as JSON on its
+/// standard input, and the output directory, the input directory and the package in
+/// `LINDERA_OUTPUT_DIR`, `LINDERA_INPUT_DIR` and `LINDERA_PACKAGE`. It fails the build if it
+/// exits with a non-zero status.
+#[derive(Debug, Clone)]
+pub struct ShellHook {
+    command: String,
+}
+
+impl ShellHook {
+    pub fn new(command: &str) -> Self {
+        ShellHook {
+            command: command.to_string(),
+        }
+    }
+
+    #[cfg(unix)]
+    fn shell(&self) -> Command {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(&self.command);
+        shell
+    }
+
+    #[cfg(not(unix))]
+    fn shell(&self) -> Command {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(&self.command);
+        shell
+    }
+}
+
+impl PostBuildHook for ShellHook {
+    fn run(&self, report: &BuildReport) -> LinderaResult<()> {
+        let json = serde_json::to_vec(report)
+            .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+        let mut command = self.shell();
+        command
+            .env("LINDERA_OUTPUT_DIR", &report.output_dir)
+            .env("LINDERA_INPUT_DIR", &report.input_dir)
+            .stdin(Stdio::piped());
+        if let Some(package) = &report.package {
+            command.env("LINDERA_PACKAGE", package);
+        }
+        let mut child = command
+            .spawn()
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // Commands not reading the report close their input early, which is not an error.
+            let _ = stdin.write_all(&json);
+        }
+        let status = child
+            .wait()
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        if !status.success() {
+            return Err(LinderaErrorKind::Io.with_error(anyhow::anyhow!(
+                "post-build hook `{}` failed with {}",
+                self.command,
+                status
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl IpadicNeologdBuilder {
+    /// Runs the shell commands of `BuilderOptions::post_build_hooks`, then the hooks added
+    /// with `with_post_build_hook`, in order.
+    pub(crate) fn run_post_build_hooks(&self, report: &BuildReport) -> LinderaResult<()> {
+        for command in &self.options.post_build_hooks {
+            self.print(&format!("running post-build hook `{}`", command));
+            ShellHook::new(command).run(report)?;
+        }
+        for hook in &self.post_build_hooks {
+            hook.run(report)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+
+    use lindera_core::dictionary_builder::DictionaryBuilder;
+    use lindera_core::error::LinderaErrorKind;
+
+    use super::BuildReport;
+    use crate::fixtures::{write_fixture, FIXTURE_CSV_FILENAME};
+    use crate::options::BuilderOptions;
+    use crate::IpadicNeologdBuilder;
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_the_hooks_with_the_build_report() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-hooks-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        write_fixture(&input_dir).unwrap();
+        let hook_dir = base_dir.join("hook");
+        let options = BuilderOptions {
+            post_build_hooks: vec![format!(
+                "cat > {0}/report.json && echo \"$LINDERA_OUTPUT_DIR\" > {0}/output_dir",
+                hook_dir.display()
+            )],
+            ..BuilderOptions::default()
+        };
+        fs::create_dir_all(&hook_dir).unwrap();
+        let reports: Arc<Mutex<Vec<BuildReport>>> = Arc::default();
+        let recorded = Arc::clone(&reports);
+        IpadicNeologdBuilder::with_options(options)
+            .with_post_build_hook(move |report: &BuildReport| {
+                // The shell command runs first.
+                assert!(report.output_dir.join("../hook/output_dir").exists());
+                recorded.lock().unwrap().push(report.clone());
+                Ok(())
+            })
+            .build_dictionary(&input_dir, &output_dir)
+            .unwrap();
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].input_dir, input_dir);
+        assert_eq!(reports[0].output_dir, output_dir);
+        assert!(reports[0].package.is_none());
+        assert!(!reports[0].artifacts.is_empty());
+        assert_eq!(reports[0].entry_stats[FIXTURE_CSV_FILENAME].parsed, 5);
+        assert_eq!(
+            fs::read_to_string(hook_dir.join("output_dir")).unwrap(),
+            format!("{}\n", output_dir.display())
+        );
+        let json: serde_json::Value =
+            serde_json::from_slice(&fs::read(hook_dir.join("report.json")).unwrap()).unwrap();
+        assert_eq!(json["output_dir"], output_dir.display().to_string());
+        assert!(json["duration_ms"].is_u64());
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fails_the_build_with_a_failing_hook() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-failing-hooks-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        write_fixture(&input_dir).unwrap();
+        let options = BuilderOptions {
+            post_build_hooks: vec!["exit 3".to_string()],
+            ..BuilderOptions::default()
+        };
+        let ran = Arc::new(Mutex::new(false));
+        let later = Arc::clone(&ran);
+        let err = IpadicNeologdBuilder::with_options(options)
+            .with_post_build_hook(move |_: &BuildReport| {
+                *later.lock().unwrap() = true;
+                Ok(())
+            })
+            .build_dictionary(&input_dir, &output_dir)
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("post-build hook `exit 3` failed"),
+            "{}",
+            err
+        );
+        assert!(!*ran.lock().unwrap());
+
+        let err = IpadicNeologdBuilder::new()
+            .with_post_build_hook(|_: &BuildReport| {
+                Err(LinderaErrorKind::Io.with_error(anyhow::anyhow!("upload failed")))
+            })
+            .build_dictionary(&input_dir, &output_dir)
+            .unwrap_err();
+        assert!(err.to_string().contains("upload failed"), "{}", err);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
@@ -0,0 +1,430 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use encoding::all::EUC_JP;
+use encoding::{DecoderTrap, Encoding};
+use serde::Serialize;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::error::BuildError;
+use crate::merge::SourceLayer;
+use crate::seed::{seed_date, SeedDates};
+use crate::{csv_filenames, IpadicNeologdBuilder};
+
+/// The number of bytes read from the start of each file to guess its encoding.
+const ENCODING_SAMPLE_LEN: u64 = 64 * 1024;
+
+/// The layout of the input directory, appended to the errors of the preflight check.
+const EXPECTED_LAYOUT_HINT: &str =
+    "the input directory is expected to be a mecab-ipadic-neologd build directory, \
+     such as build/mecab-ipadic-2.7.0-20070801-neologd-20200910 after \
+     bin/install-mecab-ipadic-neologd, holding char.def, unk.def, matrix.def and the *.csv \
+     seed files";
+
+/// The role of a source file in a build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputKind {
+    Csv,
+    CharDef,
+    CharOverrides,
+    UnkDef,
+    MatrixDef,
+}
+
+impl InputKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputKind::Csv => "csv",
+            InputKind::CharDef => "char_def",
+            InputKind::CharOverrides => "char_overrides",
+            InputKind::UnkDef => "unk_def",
+            InputKind::MatrixDef => "matrix_def",
+        }
+    }
+}
+
+/// The encoding of a source file, guessed from its first bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceEncoding {
+    Utf8,
+    /// UTF-8 with a leading byte order mark, which is stripped when reading.
+    Utf8Bom,
+    /// Left in the original EUC-JP encoding, which fails the build.
+    EucJp,
+    Unknown,
+}
+
+impl SourceEncoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SourceEncoding::Utf8 => "utf-8",
+            SourceEncoding::Utf8Bom => "utf-8-bom",
+            SourceEncoding::EucJp => "euc-jp",
+            SourceEncoding::Unknown => "unknown",
+        }
+    }
+
+    /// Whether the build can read files of this encoding.
+    pub fn is_supported(&self) -> bool {
+        matches!(self, SourceEncoding::Utf8 | SourceEncoding::Utf8Bom)
+    }
+}
+
+/// A source file found by `IpadicNeologdBuilder::discover_inputs`.
+#[derive(Debug, Clone, Serialize)]
+pub struct InputFile {
+    pub path: PathBuf,
+    pub kind: InputKind,
+    pub size: u64,
+    pub encoding: SourceEncoding,
+    /// The NEologd snapshot date in the name of a seed file, as `YYYYMMDD`.
+    pub seed_date: Option<String>,
+}
+
+/// The source files of a build, returned by `IpadicNeologdBuilder::discover_inputs`.
+#[derive(Debug, Clone, Serialize)]
+pub struct InputInventory {
+    pub files: Vec<InputFile>,
+    /// The required files that are missing.
+    pub missing: Vec<PathBuf>,
+    pub seed_dates: Option<SeedDates>,
+}
+
+impl InputInventory {
+    /// The total size of the files in bytes.
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+
+    /// The CSV files of the inventory.
+    pub fn csv_files(&self) -> impl Iterator<Item = &InputFile> {
+        self.files.iter().filter(|file| file.kind == InputKind::Csv)
+    }
+
+    /// Whether no required file is missing and every file is in an encoding the build reads.
+    pub fn is_buildable(&self) -> bool {
+        self.missing.is_empty() && self.files.iter().all(|file| file.encoding.is_supported())
+    }
+}
+
+/// Guesses the encoding of `path` from its first `ENCODING_SAMPLE_LEN` bytes, cut at the
+/// last line break so that a multi-byte character is not split.
+fn guess_encoding(path: &Path) -> LinderaResult<SourceEncoding> {
+    let mut sample = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(ENCODING_SAMPLE_LEN).read_to_end(&mut sample))
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    if sample.len() as u64 == ENCODING_SAMPLE_LEN {
+        if let Some(end) = sample.iter().rposition(|b| *b == b'\n') {
+            sample.truncate(end + 1);
+        }
+    }
+
+    let encoding = if sample.starts_with(b"\xEF\xBB\xBF") {
+        SourceEncoding::Utf8Bom
+    } else if std::str::from_utf8(&sample).is_ok() {
+        SourceEncoding::Utf8
+    } else if EUC_JP.decode(&sample, DecoderTrap::Strict).is_ok() {
+        SourceEncoding::EucJp
+    } else {
+        SourceEncoding::Unknown
+    };
+    Ok(encoding)
+}
+
+fn input_file(path: PathBuf, kind: InputKind) -> LinderaResult<InputFile> {
+    let size = path
+        .metadata()
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?
+        .len();
+    let encoding = guess_encoding(&path)?;
+    let seed_date = match kind {
+        InputKind::Csv => path
+            .file_stem()
+            .and_then(|stem| seed_date(&stem.to_string_lossy()).map(str::to_string)),
+        _ => None,
+    };
+    Ok(InputFile {
+        path,
+        kind,
+        size,
+        encoding,
+        seed_date,
+    })
+}
+
+impl IpadicNeologdBuilder {
+    /// Lists the source files a build of `input_dir` would read, with their sizes, guessed
+    /// encodings and seed snapshot dates, without parsing them, so that the inputs can be
+    /// checked before starting a long build.
+    pub fn discover_inputs(&self, input_dir: &Path) -> LinderaResult<InputInventory> {
+        let [char_def, char_overrides] = self.char_def_files(input_dir);
+        let mut files = Vec::new();
+        let mut missing = Vec::new();
+        for (path, kind, required) in [
+            (char_def, InputKind::CharDef, true),
+            (
+                char_overrides,
+                InputKind::CharOverrides,
+                self.options.char_overrides.is_some(),
+            ),
+            (input_dir.join("unk.def"), InputKind::UnkDef, true),
+            (input_dir.join("matrix.def"), InputKind::MatrixDef, true),
+        ] {
+            if path.is_file() {
+                files.push(input_file(path, kind)?);
+            } else if required {
+                missing.push(path);
+            }
+        }
+
+        let csv_files: Vec<PathBuf> = self
+            .csv_sources(input_dir)?
+            .into_iter()
+            .map(|(_, filename)| filename)
+            .collect();
+        let seed_dates = SeedDates::from_files(&csv_files);
+        for path in csv_files {
+            files.push(input_file(path, InputKind::Csv)?);
+        }
+
+        Ok(InputInventory {
+            files,
+            missing,
+            seed_dates,
+        })
+    }
+
+    /// Checks, before any heavy work, that the required files of a build of `input_dir`
+    /// exist and are readable and that there is at least one CSV file, and fails with all
+    /// the problems found at once.
+    pub fn preflight(&self, input_dir: &Path) -> LinderaResult<()> {
+        let source_dirs: Vec<&Path> = std::iter::once(input_dir)
+            .chain(self.options.extra_input_dirs.iter().map(PathBuf::as_path))
+            .collect();
+        let csv_files = self
+            .csv_sources(input_dir)?
+            .into_iter()
+            .map(|(_, filename)| filename)
+            .collect();
+        self.check_sources(input_dir, &source_dirs, csv_files)
+    }
+
+    /// Checks the sources of a merged build like `preflight`, with the required files of
+    /// the base layer and the CSV files of every layer.
+    pub fn preflight_layers(&self, layers: &[SourceLayer]) -> LinderaResult<()> {
+        let source_dirs: Vec<&Path> = layers
+            .iter()
+            .map(|layer| layer.input_dir.as_path())
+            .collect();
+        let mut csv_files = Vec::new();
+        for dir in &source_dirs {
+            csv_files.extend(csv_filenames(dir)?);
+        }
+        match source_dirs.first() {
+            Some(base_dir) => self.check_sources(base_dir, &source_dirs, csv_files),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the required files of `base_dir`, the directories of the CSV files and the
+    /// CSV files themselves.
+    fn check_sources(
+        &self,
+        base_dir: &Path,
+        source_dirs: &[&Path],
+        csv_files: Vec<PathBuf>,
+    ) -> LinderaResult<()> {
+        let [char_def, char_overrides] = self.char_def_files(base_dir);
+        let mut required = vec![
+            char_def,
+            base_dir.join("unk.def"),
+            base_dir.join("matrix.def"),
+        ];
+        if self.options.char_overrides.is_some() {
+            required.push(char_overrides);
+        }
+        let no_csv_files = csv_files.is_empty();
+        required.extend(csv_files);
+
+        let mut problems = Vec::new();
+        for dir in source_dirs {
+            if !dir.is_dir() {
+                problems.push(format!("{} is not a directory", dir.display()));
+            }
+        }
+        for path in &required {
+            if !path.is_file() {
+                problems.push(format!("{} is missing", path.display()));
+            } else if let Err(err) = File::open(path) {
+                problems.push(format!("{} is not readable: {}", path.display(), err));
+            }
+        }
+        if no_csv_files {
+            let searched: Vec<String> = source_dirs
+                .iter()
+                .map(|dir| dir.join("*.csv").display().to_string())
+                .collect();
+            problems.push(format!(
+                "no CSV files found, searched {}",
+                searched.join(", ")
+            ));
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+        Err(
+            BuildError::Validation(format!("{}\n{}", problems.join("\n"), EXPECTED_LAYOUT_HINT))
+                .into(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{InputKind, SourceEncoding};
+    use crate::fixtures::{write_fixture, FIXTURE_CSV_FILENAME};
+    use crate::merge::SourceLayer;
+    use crate::options::BuilderOptions;
+    use crate::IpadicNeologdBuilder;
+
+    #[test]
+    fn discovers_the_source_files() {
+        let input_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-inventory-{}",
+            std::process::id()
+        ));
+        write_fixture(&input_dir).unwrap();
+        fs::write(
+            input_dir.join("neologd-noun-dict-seed.20200910.csv"),
+            "\u{feff}渋谷,1,1,3000,名詞,固有名詞,地域,一般,*,*,渋谷,シブヤ,シブヤ\n",
+        )
+        .unwrap();
+        fs::write(input_dir.join("euc.csv"), b"\xC5\xEC\xB5\xFE,1,1,3000\n").unwrap();
+        fs::remove_file(input_dir.join("unk.def")).unwrap();
+
+        let inventory = IpadicNeologdBuilder::new()
+            .discover_inputs(&input_dir)
+            .unwrap();
+        let files: Vec<(String, InputKind, SourceEncoding, Option<&str>)> = inventory
+            .files
+            .iter()
+            .map(|file| {
+                (
+                    file.path.file_name().unwrap().to_string_lossy().to_string(),
+                    file.kind,
+                    file.encoding,
+                    file.seed_date.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                (
+                    "char.def".to_string(),
+                    InputKind::CharDef,
+                    SourceEncoding::Utf8,
+                    None
+                ),
+                (
+                    "matrix.def".to_string(),
+                    InputKind::MatrixDef,
+                    SourceEncoding::Utf8,
+                    None
+                ),
+                (
+                    "euc.csv".to_string(),
+                    InputKind::Csv,
+                    SourceEncoding::EucJp,
+                    None
+                ),
+                (
+                    FIXTURE_CSV_FILENAME.to_string(),
+                    InputKind::Csv,
+                    SourceEncoding::Utf8,
+                    None
+                ),
+                (
+                    "neologd-noun-dict-seed.20200910.csv".to_string(),
+                    InputKind::Csv,
+                    SourceEncoding::Utf8Bom,
+                    Some("20200910")
+                ),
+            ]
+        );
+        assert_eq!(inventory.missing, vec![input_dir.join("unk.def")]);
+        assert_eq!(inventory.csv_files().count(), 3);
+        assert_eq!(
+            inventory.total_size(),
+            inventory
+                .files
+                .iter()
+                .map(|file| fs::metadata(&file.path).unwrap().len())
+                .sum::<u64>()
+        );
+        assert_eq!(inventory.seed_dates.as_ref().unwrap().newest, "20200910");
+        assert!(!inventory.is_buildable());
+
+        fs::remove_dir_all(&input_dir).unwrap();
+    }
+
+    #[test]
+    fn reports_all_missing_files_at_once() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-preflight-{}",
+            std::process::id()
+        ));
+        let (input_dir, extra_dir) = (base_dir.join("input"), base_dir.join("extra"));
+        fs::create_dir_all(&input_dir).unwrap();
+        let options = BuilderOptions {
+            extra_input_dirs: vec![extra_dir.clone()],
+            ..BuilderOptions::default()
+        };
+        let builder = IpadicNeologdBuilder::with_options(options);
+
+        let err = builder.preflight(&input_dir).unwrap_err().to_string();
+        for problem in &[
+            format!("{} is not a directory", extra_dir.display()),
+            format!("{} is missing", input_dir.join("char.def").display()),
+            format!("{} is missing", input_dir.join("unk.def").display()),
+            format!("{} is missing", input_dir.join("matrix.def").display()),
+            format!(
+                "no CSV files found, searched {}",
+                input_dir.join("*.csv").display()
+            ),
+            "mecab-ipadic-neologd build directory".to_string(),
+        ] {
+            assert!(err.contains(problem.as_str()), "{}", err);
+        }
+
+        write_fixture(&input_dir).unwrap();
+        fs::create_dir_all(&extra_dir).unwrap();
+        builder.preflight(&input_dir).unwrap();
+        let layers = vec![
+            SourceLayer::new("base", &input_dir),
+            SourceLayer::new("extra", &extra_dir),
+        ];
+        IpadicNeologdBuilder::new()
+            .preflight_layers(&layers)
+            .unwrap();
+
+        fs::remove_file(input_dir.join("unk.def")).unwrap();
+        let err = IpadicNeologdBuilder::new()
+            .preflight_layers(&layers)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unk.def is missing"), "{}", err);
+        assert!(!err.contains("no CSV files"), "{}", err);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
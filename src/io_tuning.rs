@@ -0,0 +1,130 @@
+//! Buffer sizes and kernel hints for the large files of a build.
+//!
+//! The artifacts of a NEologd build reach hundreds of megabytes, for which the 8 KiB buffers
+//! of `std::io` mean many small writes. On Linux, large files are also preallocated with
+//! `fallocate`, which fails fast on filesystems without support instead of writing zeros,
+//! and advised as accessed sequentially with `posix_fadvise`.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// The size of the buffers of the large files when `BuilderOptions::io_buffer_size` is not
+/// set.
+pub const DEFAULT_IO_BUFFER_SIZE: usize = 1 << 20;
+
+/// Files from this size on are preallocated and advised as accessed sequentially.
+pub const IO_HINT_THRESHOLD: u64 = 64 << 20;
+
+#[cfg(target_os = "linux")]
+fn advise_sequential(file: &File) {
+    use std::os::unix::io::AsRawFd;
+
+    // The advice is only a hint, so its failure is ignored.
+    unsafe {
+        libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_SEQUENTIAL);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn advise_sequential(_file: &File) {}
+
+#[cfg(target_os = "linux")]
+fn preallocate(file: &File, len: u64) {
+    use std::os::unix::io::AsRawFd;
+
+    // Filesystems without support fail with EOPNOTSUPP, and the file then grows as written.
+    unsafe {
+        libc::fallocate(file.as_raw_fd(), 0, 0, len as libc::off_t);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn preallocate(_file: &File, _len: u64) {}
+
+/// Opens `path` for reading through a buffer of `buffer_size` bytes.
+pub(crate) fn open_reader(path: &Path, buffer_size: usize) -> io::Result<BufReader<File>> {
+    let file = File::open(path)?;
+    if file.metadata()?.len() >= IO_HINT_THRESHOLD {
+        advise_sequential(&file);
+    }
+    Ok(BufReader::with_capacity(buffer_size, file))
+}
+
+/// Creates `path` for writing through a buffer of `buffer_size` bytes. `len` is the size of
+/// the file to be written, when known in advance.
+pub(crate) fn create_writer(
+    path: &Path,
+    buffer_size: usize,
+    len: Option<u64>,
+) -> io::Result<BufWriter<File>> {
+    let file = File::create(path)?;
+    advise_sequential(&file);
+    if let Some(len) = len.filter(|len| *len >= IO_HINT_THRESHOLD) {
+        preallocate(&file, len);
+    }
+    Ok(BufWriter::with_capacity(buffer_size, file))
+}
+
+/// Writes `bytes` to `path` like `fs::write`, preallocating large files.
+pub(crate) fn write_file(path: &Path, bytes: &[u8], buffer_size: usize) -> io::Result<()> {
+    let mut wtr = create_writer(path, buffer_size, Some(bytes.len() as u64))?;
+    wtr.write_all(bytes)?;
+    wtr.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Read;
+
+    use super::{open_reader, write_file, DEFAULT_IO_BUFFER_SIZE, IO_HINT_THRESHOLD};
+    use crate::options::BuilderOptions;
+
+    #[test]
+    fn writes_and_reads_back_small_and_preallocated_files() {
+        let dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-io-tuning-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let small: Vec<u8> = (0..=255).collect();
+        write_file(&dir.join("small.bin"), &small, 1).unwrap();
+        let mut read = Vec::new();
+        open_reader(&dir.join("small.bin"), 7)
+            .unwrap()
+            .read_to_end(&mut read)
+            .unwrap();
+        assert_eq!(read, small);
+
+        let large = vec![1; IO_HINT_THRESHOLD as usize];
+        write_file(&dir.join("large.bin"), &large, DEFAULT_IO_BUFFER_SIZE).unwrap();
+        assert_eq!(
+            fs::metadata(dir.join("large.bin")).unwrap().len(),
+            IO_HINT_THRESHOLD
+        );
+        let mut read = Vec::new();
+        open_reader(&dir.join("large.bin"), DEFAULT_IO_BUFFER_SIZE)
+            .unwrap()
+            .read_to_end(&mut read)
+            .unwrap();
+        assert!(read == large);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn buffer_sizes_default_and_stay_positive() {
+        assert_eq!(
+            BuilderOptions::default().io_buffer_size(),
+            DEFAULT_IO_BUFFER_SIZE
+        );
+        let options = BuilderOptions {
+            io_buffer_size: Some(0),
+            ..BuilderOptions::default()
+        };
+        assert_eq!(options.io_buffer_size(), 1);
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+/// The word cost added to the generated kana variants by default, so that they lose to the
+/// entries of the same surface written in the source.
+pub const DEFAULT_KANA_VARIANT_PENALTY: i32 = 500;
+
+/// Which kana variants of the entries are generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KanaVariants {
+    /// Hiragana surfaces of the katakana-only entries.
+    Hiragana,
+    /// Katakana surfaces of the hiragana-only entries.
+    Katakana,
+    /// Both.
+    Both,
+}
+
+const HIRAGANA_TO_KATAKANA: u32 = 0x60;
+
+fn is_hiragana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{3096}')
+}
+
+fn is_katakana(c: char) -> bool {
+    matches!(c, '\u{30A1}'..='\u{30F6}')
+}
+
+/// Converts a surface written only in `is_script` characters and `ー` to the other script.
+fn convert(surface: &str, is_script: fn(char) -> bool, to_katakana: bool) -> Option<String> {
+    if !surface.chars().any(is_script) || !surface.chars().all(|c| is_script(c) || c == 'ー') {
+        return None;
+    }
+    surface
+        .chars()
+        .map(|c| match c {
+            'ー' => Some(c),
+            c if to_katakana => char::from_u32(c as u32 + HIRAGANA_TO_KATAKANA),
+            c => char::from_u32(c as u32 - HIRAGANA_TO_KATAKANA),
+        })
+        .collect()
+}
+
+impl KanaVariants {
+    /// The variant surface of `surface`, if it is written in a converted script.
+    pub fn variant(&self, surface: &str) -> Option<String> {
+        let to_hiragana = matches!(self, KanaVariants::Hiragana | KanaVariants::Both);
+        let to_katakana = matches!(self, KanaVariants::Katakana | KanaVariants::Both);
+        if to_hiragana {
+            if let Some(variant) = convert(surface, is_katakana, false) {
+                return Some(variant);
+            }
+        }
+        if to_katakana {
+            return convert(surface, is_hiragana, true);
+        }
+        None
+    }
+}
+
+/// Generates the variant lines of the CSV entry `lines` whose variant surface has no entry in
+/// `surfaces`, with the word cost raised by `penalty`. Returns the index of the line each
+/// variant comes from with the variant line.
+pub(crate) fn variant_lines(
+    lines: &[String],
+    variants: KanaVariants,
+    penalty: i32,
+    surfaces: &HashSet<&str>,
+) -> Vec<(usize, String)> {
+    let mut generated = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        let mut fields: Vec<String> = line.split(',').map(|field| field.to_string()).collect();
+        if fields.len() < 4 {
+            continue;
+        }
+        let variant = match variants.variant(&fields[0]) {
+            Some(variant) if !surfaces.contains(variant.as_str()) => variant,
+            _ => continue,
+        };
+        let cost = match fields[3].parse::<i32>() {
+            Ok(cost) => cost.saturating_add(penalty).min(i32::from(i16::MAX)),
+            Err(_) => continue,
+        };
+        fields[0] = variant;
+        fields[3] = cost.to_string();
+        generated.push((idx, fields.join(",")));
+    }
+    generated
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{variant_lines, KanaVariants};
+
+    #[test]
+    fn converts_kana_only_surfaces() {
+        assert_eq!(
+            KanaVariants::Hiragana.variant("スカイツリー").as_deref(),
+            Some("すかいつりー")
+        );
+        assert_eq!(KanaVariants::Hiragana.variant("いく"), None);
+        assert_eq!(
+            KanaVariants::Katakana.variant("いく").as_deref(),
+            Some("イク")
+        );
+        assert_eq!(KanaVariants::Katakana.variant("スカイツリー"), None);
+        assert_eq!(KanaVariants::Both.variant("ヴァ").as_deref(), Some("ゔぁ"));
+        assert_eq!(
+            KanaVariants::Both.variant("ぱーてぃ").as_deref(),
+            Some("パーティ")
+        );
+        assert_eq!(KanaVariants::Both.variant("東京スカイツリー"), None);
+        assert_eq!(KanaVariants::Both.variant("ーー"), None);
+    }
+
+    #[test]
+    fn generates_variants_of_surfaces_without_entries() {
+        let lines: Vec<String> = vec![
+            "いく,2,2,5500,動詞,自立,*,*,五段・カ行促音便,基本形,いく,イク,イク",
+            "イク,2,2,32500,動詞,自立,*,*,五段・カ行促音便,基本形,いく,イク,イク",
+            "に,2,2,4000,助詞,格助詞,一般,*,*,*,に,ニ,ニ",
+            "行く,2,2,5000,動詞,自立,*,*,五段・カ行促音便,基本形,行く,イク,イク",
+            "ニ,2,2,cost",
+        ]
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+        let surfaces: HashSet<&str> = vec!["いく", "イク", "に", "行く"].into_iter().collect();
+        let generated = variant_lines(&lines, KanaVariants::Both, 500, &surfaces);
+        assert_eq!(
+            generated,
+            vec![(2, "ニ,2,2,4500,助詞,格助詞,一般,*,*,*,に,ニ,ニ".to_string())]
+        );
+
+        let surfaces: HashSet<&str> = vec!["行く"].into_iter().collect();
+        let generated = variant_lines(&lines[..2], KanaVariants::Both, 500, &surfaces);
+        assert_eq!(
+            generated,
+            vec![
+                (
+                    0,
+                    "イク,2,2,6000,動詞,自立,*,*,五段・カ行促音便,基本形,いく,イク,イク"
+                        .to_string()
+                ),
+                (
+                    1,
+                    "いく,2,2,32767,動詞,自立,*,*,五段・カ行促音便,基本形,いく,イク,イク"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+fn escape(surface: &str) -> String {
+    let mut escaped = String::with_capacity(surface.len());
+    for c in surface.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(field: &str) -> Option<String> {
+    let mut surface = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            surface.push(c);
+            continue;
+        }
+        surface.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(surface)
+}
+
+/// Writes the keyset of the double array to `path`, a line per surface in the byte order the
+/// keys are inserted in, with the surface and the value the double array maps it to, as
+/// packed by the `ValuePacker` of the build, separated by a tab. Tabs, line breaks and
+/// backslashes in the surfaces are escaped with a backslash.
+pub(crate) fn write_keyset<'a, I>(path: &Path, keyset: I) -> LinderaResult<()>
+where
+    I: IntoIterator<Item = &'a (&'a [u8], u32)>,
+{
+    println!("creating {:?}", path);
+    let write = || -> io::Result<()> {
+        let mut wtr = BufWriter::new(File::create(path)?);
+        for (key, value) in keyset {
+            writeln!(wtr, "{}\t{}", escape(&String::from_utf8_lossy(key)), value)?;
+        }
+        wtr.flush()
+    };
+    write().map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))
+}
+
+/// Reads a keyset written by a build with `BuilderOptions::keyset_export`, as the surfaces
+/// and their packed values in order.
+pub fn read_keyset(path: &Path) -> LinderaResult<Vec<(String, u32)>> {
+    let file =
+        File::open(path).map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    let mut keyset = Vec::new();
+    for (idx, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        let entry = line
+            .rsplit_once('\t')
+            .and_then(|(surface, value)| Some((unescape(surface)?, value.parse::<u32>().ok()?)));
+        match entry {
+            Some(entry) => keyset.push(entry),
+            None => {
+                return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                    "{}:{}: invalid keyset line {:?}",
+                    path.display(),
+                    idx + 1,
+                    line
+                )))
+            }
+        }
+    }
+    Ok(keyset)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{read_keyset, write_keyset};
+    use crate::fixtures::build_fixture;
+    use crate::options::BuilderOptions;
+
+    #[test]
+    fn reads_back_the_written_keyset() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-keyset-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&base_dir).unwrap();
+        let keyset_file = base_dir.join("keyset.tsv");
+        let keyset: Vec<(&[u8], u32)> = vec![
+            (b"a\\b", 1),
+            (b"tab\there", 33),
+            ("改\n行\r".as_bytes(), 65),
+        ];
+        write_keyset(&keyset_file, &keyset).unwrap();
+        assert_eq!(
+            fs::read_to_string(&keyset_file).unwrap(),
+            "a\\\\b\t1\ntab\\there\t33\n改\\n行\\r\t65\n"
+        );
+        assert_eq!(
+            read_keyset(&keyset_file).unwrap(),
+            vec![
+                ("a\\b".to_string(), 1),
+                ("tab\there".to_string(), 33),
+                ("改\n行\r".to_string(), 65),
+            ]
+        );
+
+        for line in &["東京", "東京\tx", "東\\x京\t1"] {
+            fs::write(&keyset_file, line).unwrap();
+            assert!(read_keyset(&keyset_file).is_err(), "{:?}", line);
+        }
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn exports_the_keyset_of_the_build() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-keyset-export-{}",
+            std::process::id()
+        ));
+        let keyset_file = base_dir.join("keyset.tsv");
+        let options = BuilderOptions {
+            keyset_export: Some(keyset_file.clone()),
+            ..BuilderOptions::default()
+        };
+        build_fixture(&base_dir.join("input"), &base_dir.join("output"), options).unwrap();
+
+        let keyset = read_keyset(&keyset_file).unwrap();
+        let surfaces: Vec<&str> = keyset.iter().map(|(surface, _)| surface.as_str()).collect();
+        assert_eq!(
+            surfaces,
+            vec!["いく", "に", "東京", "東京スカイツリー", "行く"]
+        );
+        let values: Vec<u32> = keyset.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, vec![1, 33, 65, 97, 129]);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
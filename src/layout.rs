@@ -0,0 +1,105 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+/// The names of the files written by a dictionary build, relative to the output directory.
+/// Names may contain `/`-separated subdirectories, which are created as needed.
+///
+/// The default is the layout loaded by Lindera.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DictionaryLayout {
+    pub char_def: String,
+    pub unk: String,
+    pub dict_da: String,
+    pub dict_vals: String,
+    pub dict_words: String,
+    pub dict_wordsidx: String,
+    pub matrix: String,
+}
+
+impl Default for DictionaryLayout {
+    fn default() -> Self {
+        DictionaryLayout {
+            char_def: "char_def.bin".to_string(),
+            unk: "unk.bin".to_string(),
+            dict_da: "dict.da".to_string(),
+            dict_vals: "dict.vals".to_string(),
+            dict_words: "dict.words".to_string(),
+            dict_wordsidx: "dict.wordsidx".to_string(),
+            matrix: "matrix.mtx".to_string(),
+        }
+    }
+}
+
+impl DictionaryLayout {
+    /// The names of the files written by a build, in the order they are produced.
+    pub fn artifact_names(&self) -> [&str; 7] {
+        [
+            &self.char_def,
+            &self.unk,
+            &self.dict_da,
+            &self.dict_vals,
+            &self.dict_words,
+            &self.dict_wordsidx,
+            &self.matrix,
+        ]
+    }
+
+    /// Returns the path of the file `name` in `output_dir`, creating its parent directories.
+    pub(crate) fn artifact_path(&self, output_dir: &Path, name: &str) -> LinderaResult<PathBuf> {
+        let path = output_dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        }
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::DictionaryLayout;
+    use crate::fixtures::build_fixture;
+    use crate::manifest::Manifest;
+    use crate::options::BuilderOptions;
+
+    #[test]
+    fn builds_into_a_custom_layout() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-layout-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        let layout: DictionaryLayout =
+            serde_json::from_str(r#"{"dict_da": "dict/da.bin", "matrix": "matrix/matrix.bin"}"#)
+                .unwrap();
+        assert_eq!(layout.dict_vals, "dict.vals");
+        let options = BuilderOptions {
+            layout: layout.clone(),
+            ..BuilderOptions::default()
+        };
+        build_fixture(&input_dir, &output_dir, options).unwrap();
+
+        for name in layout.artifact_names().iter() {
+            assert!(output_dir.join(name).is_file(), "{} is missing", name);
+        }
+        assert!(!output_dir.join("dict.da").exists());
+        let manifest = Manifest::load(&output_dir).unwrap();
+        let names: Vec<&str> = manifest
+            .artifacts
+            .iter()
+            .map(|artifact| artifact.name.as_str())
+            .collect();
+        assert!(names.contains(&"dict/da.bin"));
+        assert!(names.contains(&"matrix/matrix.bin"));
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
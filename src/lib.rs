@@ -1,27 +1,204 @@
-use std::collections::BTreeMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::convert::TryFrom;
 use std::fmt::Debug;
+use std::fs;
 use std::fs::File;
 use std::io::{self, Write};
 use std::path::Path;
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::{fs, u32};
+use std::sync::Mutex;
+use std::time::Instant;
 
-use bincode;
 use byteorder::{LittleEndian, WriteBytesExt};
 use glob::glob;
 use yada::builder::DoubleArrayBuilder;
+use yada::DoubleArray;
 
-use lindera_core::character_definition::{CharacterDefinitions, CharacterDefinitionsBuilder};
+use lindera_core::character_definition::CharacterDefinitions;
 use lindera_core::dictionary_builder::DictionaryBuilder;
-use lindera_core::error::LinderaErrorKind;
-use lindera_core::file_util::read_utf8_file;
-use lindera_core::unknown_dictionary::parse_unk;
+use lindera_core::error::{LinderaError, LinderaErrorKind};
+use lindera_core::prefix_dict::PrefixDict;
 use lindera_core::user_dictionary::UserDictionary;
 use lindera_core::word_entry::{WordEntry, WordId};
 use lindera_core::LinderaResult;
 
-#[derive(Debug)]
+use crate::audit::AuditedSurface;
+use crate::build_log::BuildLog;
+use crate::columnar::DETAIL_FIELDS;
+use crate::compound::CompoundSplitter;
+use crate::entry_stats::EntryStatsCollector;
+use crate::error::ErrorCollector;
+use crate::file_util::{read_source_file, source_lines};
+use crate::manifest::ArtifactEntry;
+use crate::normalize::LineNormalizer;
+use crate::packing::CompactPacker;
+use crate::skip::SkipRuleCounter;
+use crate::warning::WarningCollector;
+
+#[cfg(feature = "async")]
+mod async_build;
+mod audit;
+pub mod base_only;
+pub mod batch;
+pub mod budget;
+pub mod build_log;
+#[cfg(feature = "build-support")]
+pub mod build_support;
+pub mod chardef;
+pub mod checksum;
+pub mod codec;
+pub mod codegen;
+pub mod columnar;
+pub mod compat;
+mod compound;
+mod da_spill;
+mod delta;
+pub mod disk_space;
+pub mod dump;
+pub mod elasticsearch;
+pub mod endian;
+pub mod entry_stats;
+pub mod env_overrides;
+pub mod error;
+#[cfg(feature = "arrow-export")]
+pub mod export;
+mod external_sort;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod file_util;
+pub mod fixtures;
+pub mod folding;
+pub mod frequency;
+pub mod header;
+pub mod hooks;
+pub mod inventory;
+pub mod io_tuning;
+pub mod kana;
+pub mod keyset;
+pub mod layout;
+pub mod lint;
+pub mod lock;
+pub mod manifest;
+mod matrix;
+pub mod merge;
+pub mod metrics;
+pub mod mmap_layout;
+#[cfg(feature = "nodejs")]
+pub mod nodejs;
+pub mod normalize;
+pub mod numeric;
+pub mod options;
+pub mod package;
+pub mod packing;
+pub mod patch;
+pub mod phases;
+pub mod phoneme;
+pub mod placeholder;
+pub mod plan;
+pub mod pos;
+pub mod preferred;
+pub mod profile;
+pub mod progress;
+#[cfg(feature = "pyo3")]
+mod python;
+#[cfg(feature = "quality-check")]
+pub mod quality;
+pub mod reader;
+pub mod reading;
+pub mod reading_index;
+pub mod scratch;
+pub mod seed;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod shard;
+#[cfg(feature = "signing")]
+pub mod signature;
+pub mod skip;
+mod sort;
+pub mod sources;
+pub mod subset;
+pub mod symbols;
+pub mod transliterate;
+pub mod trie_stats;
+mod unk;
+#[cfg(feature = "object-store")]
+pub mod upload;
+pub mod validate;
+pub mod variant_rules;
+pub mod warning;
+
+pub use batch::{build_many, BuildJob};
+pub use budget::PruningStrategy;
+pub use build_log::BuildEvent;
+pub use checksum::verify_output;
+pub use codec::{DetailCodec, DetailFormat};
+pub use compat::LinderaTarget;
+pub use disk_space::{DiskSpaceCheck, DiskUsageEstimate};
+pub use dump::{dump_chardef, dump_unk};
+pub use elasticsearch::ElasticsearchExport;
+pub use endian::Endianness;
+pub use entry_stats::FileEntryStats;
+pub use error::BuildError;
+pub use folding::SurfaceFolding;
+pub use frequency::TermFrequencies;
+pub use header::{sniff_file, ArtifactHeader};
+pub use hooks::{BuildReport, PostBuildHook, ShellHook};
+pub use inventory::{InputFile, InputInventory, InputKind, SourceEncoding};
+pub use kana::KanaVariants;
+pub use keyset::read_keyset;
+pub use layout::DictionaryLayout;
+pub use lint::{LintFinding, LintReport, LintRule};
+pub use lock::OutputLock;
+pub use manifest::Manifest;
+pub use merge::{CostScaling, DuplicatePolicy, SourceLayer};
+pub use metrics::{Metrics, PhaseMetrics, PrometheusTextfile};
+pub use normalize::NormalizationStep;
+pub use options::BuilderOptions;
+pub use packing::{ValuePacker, ValuePacking};
+pub use patch::{Patch, PatchOp};
+pub use phases::{CharDefBuilder, DictBuffers, LexiconBuilder, MatrixBuilder};
+pub use placeholder::PlaceholderHandling;
+pub use plan::{BuildPlan, PlannedArtifact};
+pub use pos::{PosMapping, PosMappingRule};
+pub use preferred::PreferredTerms;
+pub use profile::BuildProfile;
+pub use progress::{ProgressReporter, ProgressStep};
+pub use reader::DictionaryReader;
+pub use reading::{ReadingProvider, ReadingTable};
+pub use scratch::ScratchDir;
+pub use seed::SeedDates;
+pub use skip::SkipRule;
+pub use sources::{parse_sources, ParsedSources, SourceBytes};
+pub use subset::subset_dictionary;
+pub use transliterate::ReadingScript;
+pub use trie_stats::TrieStats;
+pub use unk::{EffectiveUnkCost, UnkCostAdjustment};
+pub use validate::{ValidationIssue, ValidationReport};
+pub use variant_rules::{VariantRule, VariantRules};
+pub use warning::{BuildWarning, WarningAction, WarningKind};
+
+const CSV_FIELDS_NUM: usize = 13;
+
+/// The names of the fields of a CSV entry line, in order.
+pub const CSV_FIELD_NAMES: [&str; CSV_FIELDS_NUM] = [
+    "surface",
+    "left_id",
+    "right_id",
+    "word_cost",
+    "pos_level1",
+    "pos_level2",
+    "pos_level3",
+    "pos_level4",
+    "conjugation_type",
+    "conjugate_form",
+    "base_form",
+    "reading",
+    "pronunciation",
+];
+
+#[derive(Debug, Clone)]
 pub struct CsvRow<'a> {
     surface_form: &'a str,
     left_id: u32,
@@ -39,22 +216,39 @@ pub struct CsvRow<'a> {
     pub base_form: &'a str,
     pub reading: &'a str,
     pronunciation: &'a str,
+
+    /// The source file (or layer and file) the row comes from.
+    provenance: Option<&'a str>,
+    /// The split points of the surface into shorter entries, with
+    /// `BuilderOptions::compound_splits`.
+    compound_splits: Option<String>,
+    /// The surface before folding, with `BuilderOptions::surface_folding`.
+    original_surface: Option<&'a str>,
 }
 
 impl<'a> CsvRow<'a> {
-    fn from_line(line: &'a str) -> LinderaResult<CsvRow<'a>> {
+    fn from_line(line: &'a str) -> Result<CsvRow<'a>, BuildError> {
         let fields: Vec<_> = line.split(',').collect();
+        if fields.len() < CSV_FIELDS_NUM {
+            return Err(BuildError::Parse {
+                file: None,
+                line: None,
+                field: None,
+                message: format!(
+                    "expected {} fields but found {}",
+                    CSV_FIELDS_NUM,
+                    fields.len()
+                ),
+            });
+        }
         Ok(CsvRow {
-            surface_form: &fields[0],
-            left_id: u32::from_str(fields[1]).map_err(|_err| {
-                LinderaErrorKind::Parse.with_error(anyhow::anyhow!("failed to parse left_id"))
-            })?,
-            right_id: u32::from_str(fields[2]).map_err(|_err| {
-                LinderaErrorKind::Parse.with_error(anyhow::anyhow!("failed to parse right_id"))
-            })?,
-            word_cost: i32::from_str(fields[3]).map_err(|_err| {
-                LinderaErrorKind::Parse.with_error(anyhow::anyhow!("failed to parse word_cost"))
-            })?,
+            surface_form: fields[0],
+            left_id: u32::from_str(fields[1])
+                .map_err(|err| BuildError::parse_field("left_id", &err.to_string()))?,
+            right_id: u32::from_str(fields[2])
+                .map_err(|err| BuildError::parse_field("right_id", &err.to_string()))?,
+            word_cost: i32::from_str(fields[3])
+                .map_err(|err| BuildError::parse_field("word_cost", &err.to_string()))?,
 
             pos_level1: fields[4],
             pos_level2: fields[5],
@@ -67,307 +261,1993 @@ impl<'a> CsvRow<'a> {
             base_form: fields[10],
             reading: fields[11],
             pronunciation: fields[12],
+
+            provenance: None,
+            compound_splits: None,
+            original_surface: fields.get(CSV_FIELDS_NUM).copied(),
         })
     }
+
+    /// Whether the row comes from a NEologd seed file, whose name carries a snapshot date.
+    fn is_seed_row(&self) -> bool {
+        self.provenance
+            .and_then(|name| Path::new(name).file_stem())
+            .is_some_and(|stem| seed::seed_date(&stem.to_string_lossy()).is_some())
+    }
+
+    /// Whether the rows are identical apart from their provenance.
+    fn same_entry(&self, other: &CsvRow) -> bool {
+        self.surface_form == other.surface_form
+            && self.left_id == other.left_id
+            && self.right_id == other.right_id
+            && self.word_cost == other.word_cost
+            && self.detail_fields() == other.detail_fields()
+    }
+
+    /// How the rows differ, if they are identical apart from their provenance, word cost,
+    /// reading and pronunciation but differ in the word cost or the reading.
+    fn conflict(&self, other: &CsvRow) -> Option<String> {
+        if self.surface_form != other.surface_form
+            || self.left_id != other.left_id
+            || self.right_id != other.right_id
+            || self.detail_fields()[..7] != other.detail_fields()[..7]
+        {
+            return None;
+        }
+        let mut differences = Vec::new();
+        if self.word_cost != other.word_cost {
+            differences.push(format!(
+                "word cost {} and {}",
+                other.word_cost, self.word_cost
+            ));
+        }
+        if self.reading != other.reading {
+            differences.push(format!("reading {} and {}", other.reading, self.reading));
+        }
+        if differences.is_empty() {
+            None
+        } else {
+            Some(differences.join(", "))
+        }
+    }
+
+    /// The word details, in the order they are written to `dict.words`.
+    fn detail_fields(&self) -> [&'a str; 9] {
+        [
+            self.pos_level1,
+            self.pos_level2,
+            self.pos_level3,
+            self.pos_level4,
+            self.conjugation_type,
+            self.conjugate_form,
+            self.base_form,
+            self.reading,
+            self.pronunciation,
+        ]
+    }
+}
+
+/// The index of the reading in the word details.
+const READING_DETAIL_IDX: usize = 7;
+
+const SKIP_WORDS: [&str; 2] = ["カブシキガイシャ", "タカラヅカカゲキダンキセイ"];
+
+const MAX_HOMONYMS: usize = 1 << 5;
+
+/// Approximate ratio of the memory used by the parsed entries to the size of the CSV files.
+const SOURCE_MEMORY_FACTOR: u64 = 3;
+
+/// The options whose stages need all the entries in memory, which rule out sorting them
+/// externally.
+const IN_MEMORY_STAGES: &str = "patches, the reading index, columnar details, Elasticsearch files, shards, compound splits, variants, generated entries and pruning";
+
+fn csv_filenames(input_dir: &Path) -> LinderaResult<Vec<PathBuf>> {
+    let pattern = if let Some(path) = input_dir.to_str() {
+        format!("{}/*.csv", path)
+    } else {
+        return Err(
+            LinderaErrorKind::Io.with_error(anyhow::anyhow!("Failed to convert path to &str."))
+        );
+    };
+
+    let mut filenames: Vec<PathBuf> = Vec::new();
+    for entry in
+        glob(&pattern).map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?
+    {
+        match entry {
+            Ok(path) => {
+                if let Some(filename) = path.file_name() {
+                    filenames.push(Path::new(input_dir).join(filename));
+                } else {
+                    return Err(
+                        LinderaErrorKind::Io.with_error(anyhow::anyhow!("failed to get filename"))
+                    );
+                }
+            }
+            Err(err) => return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(err))),
+        }
+    }
+
+    Ok(filenames)
+}
+
+/// The CSV files of `dir` named by their file name, prefixed with `prefix/` if given.
+fn named_csv_files(dir: &Path, prefix: Option<&str>) -> LinderaResult<Vec<(String, PathBuf)>> {
+    Ok(csv_filenames(dir)?
+        .into_iter()
+        .map(|filename| {
+            let name = filename
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let name = match prefix {
+                Some(prefix) => format!("{}/{}", prefix, name),
+                None => name,
+            };
+            (name, filename)
+        })
+        .collect())
+}
+
+/// The error of a build whose `num_files` CSV files in `input_dir` have no entry.
+fn no_entries_error(num_files: usize, input_dir: &Path) -> LinderaError {
+    BuildError::Validation(format!(
+        "the {} CSV files of {} have no entries",
+        num_files,
+        input_dir.display()
+    ))
+    .into()
+}
+
+/// Parses the entry lines of the file `name`, whose line numbers are `line_nums` if known.
+fn parse_rows<'a>(
+    name: &str,
+    lines: &'a [String],
+    line_nums: &[usize],
+) -> LinderaResult<Vec<CsvRow<'a>>> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| {
+            CsvRow::from_line(line).map_err(|err| err.at(name, line_nums.get(idx).copied()))
+        })
+        .collect::<Result<_, _>>()
+        .map_err(LinderaError::from)
+}
+
+/// The entry lines of a CSV file, normalized, with their line numbers.
+struct CsvFile {
+    name: String,
+    lines: Vec<String>,
+    line_nums: Vec<usize>,
+}
+
+/// The surfaces of the entry lines of the files.
+fn entry_surfaces(files: &[CsvFile]) -> HashSet<&str> {
+    files
+        .iter()
+        .flat_map(|file| file.lines.iter())
+        .filter_map(|line| line.split(',').next())
+        .collect()
+}
+
+/// Parses the lines of the files, recording the file name as the provenance of each row.
+/// The errors of unparsable lines are passed to `errors`, which decides whether to go on.
+fn parse_files<'a>(
+    files: &'a [CsvFile],
+    errors: &mut ErrorCollector,
+) -> LinderaResult<Vec<CsvRow<'a>>> {
+    let mut rows = Vec::new();
+    for file in files {
+        for (idx, line) in file.lines.iter().enumerate() {
+            match CsvRow::from_line(line) {
+                Ok(mut row) => {
+                    row.provenance = Some(&file.name);
+                    rows.push(row);
+                }
+                Err(err) => errors.push(err.at(&file.name, file.line_nums.get(idx).copied()))?,
+            }
+        }
+    }
+    Ok(rows)
+}
+
+/// Approximate memory used by the rows and their buffered word details.
+fn estimate_rows_memory(rows: &[CsvRow]) -> u64 {
+    rows.iter()
+        .map(|row| {
+            let fields_len: usize = row.detail_fields().iter().map(|field| field.len()).sum();
+            std::mem::size_of::<CsvRow>() + row.surface_form.len() + 2 * fields_len
+        })
+        .sum::<usize>() as u64
+}
+
+fn normalize_char(c: char) -> char {
+    match c {
+        '―' => '—',   // yeah for EUC_JP and ambiguous unicode 8012 vs 8013
+        '～' => '〜', // same bullshit as above between for 12316 vs 65374
+        _ => c,
+    }
+}
+
+fn normalize_line(line: &str) -> String {
+    line.chars().map(normalize_char).collect::<String>()
+}
+
+/// Normalizes the fields of a CSV entry line at the indices `fields`, leaving the other
+/// fields as written.
+fn normalize_fields<'a>(line: &'a str, fields: &[usize]) -> Cow<'a, str> {
+    if line.chars().all(|c| normalize_char(c) == c) {
+        return Cow::Borrowed(line);
+    }
+    Cow::Owned(
+        line.split(',')
+            .enumerate()
+            .map(|(idx, field)| {
+                if fields.contains(&idx) {
+                    normalize_line(field)
+                } else {
+                    field.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(","),
+    )
 }
 
-const SKIP_WORDS: [&'static str; 2] = ["カブシキガイシャ", "タカラヅカカゲキダンキセイ"];
+pub struct IpadicNeologdBuilder {
+    options: BuilderOptions,
+    reading_provider: Option<Box<dyn ReadingProvider + Send + Sync>>,
+    metrics: Option<Box<dyn Metrics + Send + Sync>>,
+    progress: Option<Box<dyn ProgressReporter + Send + Sync>>,
+    char_def_builder: Option<Box<dyn CharDefBuilder + Send + Sync>>,
+    lexicon_builder: Option<Box<dyn LexiconBuilder + Send + Sync>>,
+    matrix_builder: Option<Box<dyn MatrixBuilder + Send + Sync>>,
+    lint_rules: Vec<Box<dyn LintRule + Send + Sync>>,
+    post_build_hooks: Vec<Box<dyn PostBuildHook + Send + Sync>>,
+    log: BuildLog,
+    warnings: WarningCollector,
+    entry_stats: EntryStatsCollector,
+    effective_unk_costs: Mutex<Vec<EffectiveUnkCost>>,
+    skip_counts: SkipRuleCounter,
+}
 
-pub struct IpadicNeologdBuilder {}
+impl Default for IpadicNeologdBuilder {
+    fn default() -> Self {
+        IpadicNeologdBuilder::new()
+    }
+}
 
 impl IpadicNeologdBuilder {
     pub fn new() -> Self {
-        IpadicNeologdBuilder {}
+        IpadicNeologdBuilder::with_options(BuilderOptions::default())
     }
-}
 
-impl DictionaryBuilder for IpadicNeologdBuilder {
-    fn build_dictionary(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()> {
-        fs::create_dir_all(&output_dir)
-            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    /// A builder configured with the options of `profile`.
+    pub fn with_profile(profile: BuildProfile) -> Self {
+        IpadicNeologdBuilder::with_options(profile.options())
+    }
 
-        let chardef = self.build_chardef(input_dir, output_dir)?;
-        self.build_unk(input_dir, &chardef, output_dir)?;
-        self.build_dict(input_dir, output_dir)?;
-        self.build_cost_matrix(input_dir, output_dir)?;
+    pub fn with_options(options: BuilderOptions) -> Self {
+        IpadicNeologdBuilder {
+            options,
+            reading_provider: None,
+            metrics: None,
+            progress: None,
+            char_def_builder: None,
+            lexicon_builder: None,
+            matrix_builder: None,
+            lint_rules: lint::default_rules(),
+            post_build_hooks: Vec::new(),
+            log: BuildLog::default(),
+            warnings: WarningCollector::default(),
+            entry_stats: EntryStatsCollector::default(),
+            effective_unk_costs: Mutex::new(Vec::new()),
+            skip_counts: SkipRuleCounter::default(),
+        }
+    }
 
-        Ok(())
+    /// Sets the provider of readings for non-kana surfaces, used with
+    /// `BuilderOptions::fill_readings`.
+    pub fn with_reading_provider<P>(mut self, provider: P) -> Self
+    where
+        P: ReadingProvider + Send + Sync + 'static,
+    {
+        self.reading_provider = Some(Box::new(provider));
+        self
     }
 
-    fn build_chardef(
-        &self,
-        input_dir: &Path,
-        output_dir: &Path,
-    ) -> LinderaResult<CharacterDefinitions> {
-        println!("BUILD CHARDEF");
-        let char_def_path = input_dir.join("char.def");
-        let char_def = read_utf8_file(&char_def_path)?;
-        let mut char_definitions_builder = CharacterDefinitionsBuilder::default();
-        char_definitions_builder.parse(&char_def)?;
-        let char_definitions = char_definitions_builder.build();
-
-        let wtr_chardef_path = output_dir.join(Path::new("char_def.bin"));
-        let mut wtr_chardef = io::BufWriter::new(
-            File::create(wtr_chardef_path)
-                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
-        );
-        bincode::serialize_into(&mut wtr_chardef, &char_definitions)
-            .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
-        wtr_chardef
-            .flush()
-            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    /// Sets the receiver of the duration, I/O and memory measurements of each build phase.
+    pub fn with_metrics<M>(mut self, metrics: M) -> Self
+    where
+        M: Metrics + Send + Sync + 'static,
+    {
+        self.metrics = Some(Box::new(metrics));
+        self
+    }
 
-        Ok(char_definitions)
+    /// Sets the receiver of the progress of each build step and of the status messages,
+    /// which are otherwise printed to stdout.
+    pub fn with_progress<P>(mut self, progress: P) -> Self
+    where
+        P: ProgressReporter + Send + Sync + 'static,
+    {
+        self.progress = Some(Box::new(progress));
+        self
     }
 
-    fn build_unk(
+    /// Replaces the phase building `char_def.bin` and `unk.bin` of `build_dictionary`.
+    pub fn with_char_def_builder<B>(mut self, builder: B) -> Self
+    where
+        B: CharDefBuilder + Send + Sync + 'static,
+    {
+        self.char_def_builder = Some(Box::new(builder));
+        self
+    }
+
+    /// Replaces the phase building the system dictionary files of `build_dictionary`, which
+    /// then receives the content of every CSV file of the build.
+    pub fn with_lexicon_builder<B>(mut self, builder: B) -> Self
+    where
+        B: LexiconBuilder + Send + Sync + 'static,
+    {
+        self.lexicon_builder = Some(Box::new(builder));
+        self
+    }
+
+    /// Replaces the phase building `matrix.mtx` of `build_dictionary`.
+    pub fn with_matrix_builder<B>(mut self, builder: B) -> Self
+    where
+        B: MatrixBuilder + Send + Sync + 'static,
+    {
+        self.matrix_builder = Some(Box::new(builder));
+        self
+    }
+
+    /// Adds a rule to the lint rules, which are `lint::default_rules` unless replaced with
+    /// `with_lint_rules`.
+    pub fn with_lint_rule<R>(mut self, rule: R) -> Self
+    where
+        R: LintRule + Send + Sync + 'static,
+    {
+        self.lint_rules.push(Box::new(rule));
+        self
+    }
+
+    /// Replaces the lint rules.
+    pub fn with_lint_rules(mut self, rules: Vec<Box<dyn LintRule + Send + Sync>>) -> Self {
+        self.lint_rules = rules;
+        self
+    }
+
+    /// Adds a hook run with the `BuildReport` once `build_dictionary` has written the
+    /// dictionary, after the shell commands of `BuilderOptions::post_build_hooks`.
+    pub fn with_post_build_hook<H>(mut self, hook: H) -> Self
+    where
+        H: PostBuildHook + Send + Sync + 'static,
+    {
+        self.post_build_hooks.push(Box::new(hook));
+        self
+    }
+
+    pub fn options(&self) -> &BuilderOptions {
+        &self.options
+    }
+
+    /// The warnings collected by the builds of this builder, at most
+    /// `BuilderOptions::warning_limit` of each kind.
+    pub fn warnings(&self) -> Vec<BuildWarning> {
+        self.warnings.warnings()
+    }
+
+    /// The number of warnings of each kind reported by the builds of this builder, including
+    /// the ones over the limit.
+    pub fn warning_counts(&self) -> BTreeMap<WarningKind, usize> {
+        self.warnings.counts()
+    }
+
+    /// The number of entries parsed, normalized, skipped, seen twice and merged of each source
+    /// CSV file, by file name.
+    pub fn entry_stats(&self) -> BTreeMap<String, FileEntryStats> {
+        self.entry_stats.stats()
+    }
+
+    /// The effective costs of the `unk.def` entries adjusted by `BuilderOptions::unk_costs`
+    /// in the last build of `unk.bin`.
+    pub fn effective_unk_costs(&self) -> Vec<EffectiveUnkCost> {
+        self.effective_unk_costs
+            .lock()
+            .map(|costs| costs.clone())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn record_effective_unk_costs(&self, effective_costs: Vec<EffectiveUnkCost>) {
+        if let Ok(mut costs) = self.effective_unk_costs.lock() {
+            *costs = effective_costs;
+        }
+    }
+
+    /// Builds a user dictionary from a CSV file and writes it to `output_file` in the
+    /// binary format loaded by `UserDictionary::load`.
+    pub fn build_user_dictionary(
         &self,
-        input_dir: &Path,
-        chardef: &CharacterDefinitions,
-        output_dir: &Path,
+        input_file: &Path,
+        output_file: &Path,
     ) -> LinderaResult<()> {
-        println!("BUILD UNK");
-        let unk_data_path = input_dir.join("unk.def");
-        let unk_data = read_utf8_file(&unk_data_path)?;
-        let unknown_dictionary = parse_unk(chardef.categories(), &unk_data)?;
-        let wtr_unk_path = output_dir.join(Path::new("unk.bin"));
-        println!("creating {:?}", wtr_unk_path);
-        let mut wtr_unk = io::BufWriter::new(
-            File::create(wtr_unk_path)
+        let user_dict = self.build_user_dict(input_file)?;
+
+        self.print(&format!("creating {:?}", output_file));
+        let mut wtr_user_dict = io::BufWriter::new(
+            File::create(output_file)
                 .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
         );
-        bincode::serialize_into(&mut wtr_unk, &unknown_dictionary)
+        bincode::serialize_into(&mut wtr_user_dict, &user_dict)
             .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
-        wtr_unk
+        wtr_user_dict
             .flush()
             .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
 
         Ok(())
     }
 
-    fn build_dict(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()> {
-        println!("BUILD DICT");
+    /// The CSV files of `input_dir` and of the extra input directories of the options, with
+    /// the names recorded as their provenance.
+    fn csv_sources(&self, input_dir: &Path) -> LinderaResult<Vec<(String, PathBuf)>> {
+        let mut sources = named_csv_files(input_dir, None)?;
+        for extra_input_dir in &self.options.extra_input_dirs {
+            let dir_name = extra_input_dir
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            sources.extend(named_csv_files(extra_input_dir, Some(&dir_name))?);
+        }
+        Ok(sources)
+    }
 
-        let pattern = if let Some(path) = input_dir.to_str() {
-            format!("{}/*.csv", path)
-        } else {
-            return Err(
-                LinderaErrorKind::Io.with_error(anyhow::anyhow!("Failed to convert path to &str."))
-            );
-        };
+    /// Reads the entry lines of every CSV file of the build of `input_dir`, normalized.
+    fn read_csv_files(&self, input_dir: &Path) -> LinderaResult<Vec<CsvFile>> {
+        self.read_sources(&self.csv_sources(input_dir)?)
+    }
 
-        let mut filenames: Vec<PathBuf> = Vec::new();
-        for entry in
-            glob(&pattern).map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?
-        {
-            match entry {
-                Ok(path) => {
-                    if let Some(filename) = path.file_name() {
-                        filenames.push(Path::new(input_dir).join(filename));
-                    } else {
-                        return Err(LinderaErrorKind::Io
-                            .with_error(anyhow::anyhow!("failed to get filename")));
-                    }
+    /// Reads the entry lines of the named CSV files, normalized, with their variants and the
+    /// generated entries the options ask for.
+    fn read_sources(&self, sources: &[(String, PathBuf)]) -> LinderaResult<Vec<CsvFile>> {
+        let mut files = self.read_named_csv_files(sources)?;
+        self.add_symbols(&mut files);
+        self.add_numeric_entries(&mut files);
+        Ok(files)
+    }
+
+    /// The estimated memory used by the parsed entries of the named CSV files, if it exceeds
+    /// the memory budget of the options.
+    fn exceeded_memory_budget(&self, sources: &[(String, PathBuf)]) -> LinderaResult<Option<u64>> {
+        if self.options.memory_budget.is_none() {
+            return Ok(None);
+        }
+        let mut source_bytes = 0;
+        for (_, filename) in sources {
+            source_bytes += fs::metadata(filename)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?
+                .len();
+        }
+        let estimated_bytes = source_bytes * SOURCE_MEMORY_FACTOR;
+        Ok(Some(estimated_bytes).filter(|bytes| self.options.exceeds_memory_budget(*bytes)))
+    }
+
+    /// Adds the generated entries of numbers with a counter as a file of their own, if the
+    /// options ask for them. Their context ids are taken from a `名詞,数` entry of the files.
+    fn add_numeric_entries(&self, files: &mut Vec<CsvFile>) {
+        if !self.options.numeric_entries {
+            return;
+        }
+        let surfaces = entry_surfaces(files);
+        let context_ids =
+            match numeric::number_context_ids(files.iter().flat_map(|file| file.lines.iter())) {
+                Some(context_ids) => context_ids,
+                None => {
+                    self.warn(
+                        "no 名詞,数 entry to take the context ids of the numeric entries from",
+                    );
+                    return;
                 }
-                Err(err) => return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(err))),
-            }
+            };
+        let lines = numeric::numeric_lines(
+            context_ids,
+            self.options
+                .numeric_cost
+                .unwrap_or(numeric::DEFAULT_NUMERIC_COST),
+            &surfaces,
+        );
+        self.print(&format!("generated {} numeric entries", lines.len()));
+        files.push(CsvFile {
+            name: numeric::NUMERIC_FILENAME.to_string(),
+            line_nums: (1..=lines.len()).collect(),
+            lines,
+        });
+    }
+
+    /// Adds the generated emoji and kaomoji entries as a file of their own, if the options
+    /// ask for them. Symbols with an entry in the files are left out.
+    fn add_symbols(&self, files: &mut Vec<CsvFile>) {
+        if !self.options.emoji_entries && !self.options.kaomoji_entries {
+            return;
+        }
+        let surfaces = entry_surfaces(files);
+        let lines = symbols::symbol_lines(
+            self.options.emoji_entries,
+            self.options.kaomoji_entries,
+            self.options
+                .symbol_cost
+                .unwrap_or(symbols::DEFAULT_SYMBOL_COST),
+            &surfaces,
+        );
+        self.print(&format!("generated {} symbol entries", lines.len()));
+        files.push(CsvFile {
+            name: symbols::SYMBOLS_FILENAME.to_string(),
+            line_nums: (1..=lines.len()).collect(),
+            lines,
+        });
+    }
+
+    /// Reads the entry lines of the named CSV files, normalized.
+    fn read_named_csv_files(&self, sources: &[(String, PathBuf)]) -> LinderaResult<Vec<CsvFile>> {
+        self.progress_start(ProgressStep::ParseFiles, sources.len() as u64);
+        let mut files = Vec::with_capacity(sources.len());
+        for (name, filename) in sources {
+            let file_data = read_source_file(filename)?;
+            files.push(self.csv_file(name.clone(), &file_data)?);
+            self.progress_advance(ProgressStep::ParseFiles, 1);
         }
+        self.progress_finish(ProgressStep::ParseFiles);
+        self.add_variants(&mut files)?;
 
-        let files_data: Vec<String> = filenames
-            .iter()
-            .map(|filename| read_utf8_file(filename))
-            .collect::<LinderaResult<Vec<String>>>()?;
+        Ok(files)
+    }
 
-        let lines: Vec<String> = files_data
+    /// Appends the kana variants and the variants of the variant rules of the entries to
+    /// their files, if the options ask for them. Variants are only generated for surfaces
+    /// without an entry in any of the files.
+    fn add_variants(&self, files: &mut [CsvFile]) -> LinderaResult<()> {
+        let variant_rules = self
+            .options
+            .variant_rules
+            .as_ref()
+            .map(|path| VariantRules::load(path))
+            .transpose()?;
+        if self.options.kana_variants.is_none() && variant_rules.is_none() {
+            return Ok(());
+        }
+        let penalty = self
+            .options
+            .kana_variant_penalty
+            .unwrap_or(kana::DEFAULT_KANA_VARIANT_PENALTY);
+        let surfaces = entry_surfaces(files);
+        let mut kana_generated = 0;
+        let mut rules_generated = 0;
+        let files_variants: Vec<_> = files
             .iter()
-            .flat_map(|file_data: &String| file_data.lines().map(|line| line.to_string()))
-            .map(|line| {
-                line.chars()
-                    .map(|c| {
-                        match c {
-                            '―' => '—', // yeah for EUC_JP and ambiguous unicode 8012 vs 8013
-                            '～' => '〜', // same bullshit as above between for 12316 vs 65374
-                            _ => c,
-                        }
-                    })
-                    .collect::<String>()
+            .map(|file| {
+                let mut variant_lines = Vec::new();
+                if let Some(kana_variants) = self.options.kana_variants {
+                    let lines = kana::variant_lines(&file.lines, kana_variants, penalty, &surfaces);
+                    kana_generated += lines.len();
+                    variant_lines.extend(lines);
+                }
+                if let Some(variant_rules) = &variant_rules {
+                    let lines = variant_rules::variant_lines(&file.lines, variant_rules, &surfaces);
+                    rules_generated += lines.len();
+                    variant_lines.extend(lines);
+                }
+                variant_lines
             })
             .collect();
 
-        let mut rows: Vec<CsvRow> = lines
+        for (file, variant_lines) in files.iter_mut().zip(files_variants) {
+            for (idx, line) in variant_lines {
+                let line_num = file.line_nums.get(idx).copied().unwrap_or_default();
+                file.lines.push(line);
+                file.line_nums.push(line_num);
+            }
+        }
+        if self.options.kana_variants.is_some() {
+            self.print(&format!(
+                "generated {} kana variant entries",
+                kana_generated
+            ));
+        }
+        if variant_rules.is_some() {
+            self.print(&format!(
+                "generated {} entries from the variant rules",
+                rules_generated
+            ));
+        }
+        Ok(())
+    }
+
+    /// The details of a row as serialized to `dict.words`, with the POS mapping applied and,
+    /// if `selected` is given, only the detail fields of these indices.
+    fn word_details(&self, row: &CsvRow, selected: Option<&[usize]>) -> Vec<String> {
+        let mut details: Vec<String> = row
+            .detail_fields()
             .iter()
-            .map(|line| CsvRow::from_line(line))
-            .collect::<Result<_, _>>()
-            .map_err(|err| LinderaErrorKind::Parse.with_error(anyhow::anyhow!(err)))?;
-        println!("sorting entries");
-        rows.sort_by_key(|row| row.surface_form.clone());
-
-        let wtr_da_path = output_dir.join(Path::new("dict.da"));
-        println!("creating {:?}", wtr_da_path);
-        let mut wtr_da = io::BufWriter::new(
-            File::create(wtr_da_path)
-                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
-        );
+            .map(|field| field.to_string())
+            .collect();
+        if let Some(pos_mapping) = &self.options.pos_mapping {
+            pos_mapping.apply(&mut details);
+        }
+        let transliterated_reading = self
+            .options
+            .reading_script
+            .map(|script| script.transliterate(row.reading));
+        if let (Some(reading), false) =
+            (&transliterated_reading, self.options.keep_original_reading)
+        {
+            details[READING_DETAIL_IDX] = reading.clone();
+        }
+        if let Some(selected) = selected {
+            let tag = details.split_off(DETAIL_FIELDS.len());
+            details = selected
+                .iter()
+                .map(|idx| details[*idx].clone())
+                .chain(tag)
+                .collect();
+        }
+        if self.options.provenance {
+            details.push(row.provenance.unwrap_or("*").to_string());
+        }
+        if self.options.compound_splits {
+            details.push(
+                row.compound_splits
+                    .clone()
+                    .unwrap_or_else(|| "*".to_string()),
+            );
+        }
+        if self.options.surface_folding.is_some() {
+            details.push(row.original_surface.unwrap_or(row.surface_form).to_string());
+        }
+        if self.options.neologd_flag {
+            details.push(if row.is_seed_row() { "1" } else { "0" }.to_string());
+        }
+        if let (Some(reading), true) = (transliterated_reading, self.options.keep_original_reading)
+        {
+            details.push(reading);
+        }
+        self.options.placeholders.apply(&mut details);
+        details
+    }
 
-        let wtr_vals_path = output_dir.join(Path::new("dict.vals"));
-        println!("creating {:?}", wtr_vals_path);
-        let mut wtr_vals = io::BufWriter::new(
-            File::create(wtr_vals_path)
-                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
-        );
+    /// Applies the patch files of the options over the rows.
+    fn apply_patches<'a>(
+        &self,
+        patches: &'a [Patch],
+        mut rows: Vec<CsvRow<'a>>,
+    ) -> LinderaResult<Vec<CsvRow<'a>>> {
+        for patch in patches {
+            rows = patch.apply(rows, &|warning| self.report_warning(warning))?;
+        }
+        Ok(rows)
+    }
 
-        let mut word_entry_map: BTreeMap<String, Vec<WordEntry>> = BTreeMap::new();
+    /// Recomputes the word cost of the entries of the terms of the frequency file of the
+    /// options.
+    fn apply_term_frequencies<'a>(
+        &self,
+        mut rows: Vec<CsvRow<'a>>,
+    ) -> LinderaResult<Vec<CsvRow<'a>>> {
+        if let Some(path) = &self.options.frequency_file {
+            let cost_scale = self
+                .options
+                .frequency_cost_scale
+                .unwrap_or(frequency::DEFAULT_FREQUENCY_COST_SCALE);
+            let frequencies = TermFrequencies::load(path, cost_scale)?;
+            let changed = frequencies.apply(&mut rows);
+            self.print(&format!(
+                "recomputed the cost of {} entries from {} term frequencies",
+                changed,
+                frequencies.counts.len()
+            ));
+        }
+        Ok(rows)
+    }
 
-        for (row_id, row) in rows.iter().enumerate() {
-            if !SKIP_WORDS.contains(&row.surface_form) {
-                word_entry_map
-                    .entry(row.surface_form.to_string())
-                    .or_insert_with(Vec::new)
-                    .push(WordEntry {
-                        word_id: WordId(row_id as u32, true),
-                        word_cost: row.word_cost as i16,
-                        cost_id: row.left_id as u16,
-                    });
+    fn load_preferred_terms(&self) -> LinderaResult<Option<PreferredTerms>> {
+        let cost_delta = self
+            .options
+            .preferred_cost_delta
+            .unwrap_or(preferred::DEFAULT_PREFERRED_COST_DELTA);
+        self.options
+            .preferred_terms
+            .as_ref()
+            .map(|path| PreferredTerms::load(path, cost_delta))
+            .transpose()
+    }
+
+    /// Lowers the word cost of the entries of the preferred terms of the options.
+    fn apply_preferred_terms<'a>(
+        &self,
+        mut rows: Vec<CsvRow<'a>>,
+    ) -> LinderaResult<Vec<CsvRow<'a>>> {
+        if let Some(preferred_terms) = self.load_preferred_terms()? {
+            let mut found = HashSet::new();
+            let mut lowered = 0;
+            for row in &mut rows {
+                if preferred_terms.apply(row) {
+                    found.insert(row.surface_form);
+                    lowered += 1;
+                }
+            }
+            self.print(&format!(
+                "lowered the cost of {} entries of {} preferred terms",
+                lowered,
+                found.len()
+            ));
+            if found.len() < preferred_terms.surfaces.len() {
+                self.print(&format!(
+                    "{} preferred terms have no entry",
+                    preferred_terms.surfaces.len() - found.len()
+                ));
             }
         }
+        Ok(rows)
+    }
 
-        let wtr_words_path = output_dir.join(Path::new("dict.words"));
-        println!("creating {:?}", wtr_words_path);
-        let mut wtr_words = io::BufWriter::new(
-            File::create(wtr_words_path)
-                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
-        );
+    /// Records the split points of the compound surfaces of the rows, if the options ask
+    /// for them.
+    fn annotate_compounds(&self, rows: &mut [CsvRow]) {
+        if !self.options.compound_splits {
+            return;
+        }
+        let splitter = CompoundSplitter::new(rows);
+        let mut compounds = 0;
+        for row in rows.iter_mut() {
+            let points = splitter.split_points(row.surface_form);
+            if !points.is_empty() {
+                compounds += 1;
+            }
+            row.compound_splits = Some(compound::format_split_points(&points));
+        }
+        self.print(&format!(
+            "found split points of {} compound entries",
+            compounds
+        ));
+    }
 
-        let wtr_words_idx_path = output_dir.join(Path::new("dict.wordsidx"));
-        println!("creating {:?}", wtr_words_idx_path);
-        let mut wtr_words_idx = io::BufWriter::new(
-            File::create(wtr_words_idx_path)
-                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
-        );
+    /// The stages of every build of the lexicon from the parsed rows: the patches, the term
+    /// frequencies, the preferred terms, the entries of the reference dictionary and the
+    /// compound splits.
+    fn transform_rows<'a>(
+        &self,
+        patches: &'a [Patch],
+        rows: Vec<CsvRow<'a>>,
+    ) -> LinderaResult<Vec<CsvRow<'a>>> {
+        let rows = self.apply_patches(patches, rows)?;
+        let rows = self.apply_term_frequencies(rows)?;
+        let rows = self.apply_preferred_terms(rows)?;
+        let mut rows = self.drop_reference_entries(rows)?;
+        self.annotate_compounds(&mut rows);
+        Ok(rows)
+    }
 
-        let mut words_buffer = Vec::new();
-        for row in rows.iter() {
-            let word = vec![
-                row.pos_level1.to_string(),
-                row.pos_level2.to_string(),
-                row.pos_level3.to_string(),
-                row.pos_level4.to_string(),
-                row.conjugation_type.to_string(),
-                row.conjugate_form.to_string(),
-                row.base_form.to_string(),
-                row.reading.to_string(),
-                row.pronunciation.to_string(),
-            ];
-            let offset = words_buffer.len();
-            wtr_words_idx
-                .write_u32::<LittleEndian>(offset as u32)
+    /// Transforms the parsed rows, checks their context ids against `matrix_data_path`, fits
+    /// them to the size budget and writes the dictionary, as the builds of an input directory
+    /// and of merged layers do.
+    fn write_rows<'a>(
+        &self,
+        patches: &'a [Patch],
+        rows: Vec<CsvRow<'a>>,
+        matrix_data_path: &Path,
+        output_dir: &Path,
+    ) -> LinderaResult<()> {
+        let rows = self.transform_rows(patches, rows)?;
+        self.check_context_ids(&rows, matrix_data_path)?;
+        let rows = self.apply_size_budget(rows, output_dir, matrix_data_path)?;
+        self.write_dict(rows, output_dir)
+    }
+
+    fn load_patches(&self) -> LinderaResult<Vec<Patch>> {
+        self.options
+            .patch_files
+            .iter()
+            .map(|path| Patch::load(path))
+            .collect()
+    }
+
+    /// Sorts the rows and writes the double array, the word entries and the word details.
+    fn write_dict(&self, rows: Vec<CsvRow>, output_dir: &Path) -> LinderaResult<()> {
+        let rows = self.sort_rows(rows);
+
+        let layout = &self.options.layout;
+        let wtr_words_path = layout.artifact_path(output_dir, &layout.dict_words)?;
+        self.print(&format!("creating {:?}", wtr_words_path));
+        let mut wtr_words =
+            io_tuning::create_writer(&wtr_words_path, self.options.io_buffer_size(), None)
                 .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
-            bincode::serialize_into(&mut words_buffer, &word)
-                .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+
+        // The shards are cut from the buffered word details, so only unsharded builds stream.
+        let estimated_bytes = estimate_rows_memory(&rows);
+        let stream_words = self.options.shard_size.is_none()
+            && self.options.exceeds_memory_budget(estimated_bytes);
+        if stream_words {
+            self.print(&format!(
+                "estimated {} bytes exceed the memory budget, streaming word details",
+                estimated_bytes
+            ));
         }
 
+        let da_path = if self.options.da_spill {
+            Some(layout.artifact_path(output_dir, &layout.dict_da)?)
+        } else {
+            None
+        };
+        let encoded = self.encode_dict(
+            &rows,
+            if stream_words {
+                Some(&mut wtr_words)
+            } else {
+                None
+            },
+            da_path.as_deref(),
+        )?;
         wtr_words
-            .write_all(&words_buffer[..])
+            .write_all(&encoded.buffers.words[..])
             .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
         wtr_words
             .flush()
             .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
-        wtr_words_idx
-            .flush()
-            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        self.write_artifact(
+            output_dir,
+            &layout.dict_wordsidx,
+            &encoded.buffers.words_idx,
+        )?;
+        if da_path.is_none() {
+            self.write_artifact(output_dir, &layout.dict_da, &encoded.buffers.da)?;
+        }
+        self.report_trie_stats(
+            encoded.word_entry_map.keys().map(|key| key.as_bytes()),
+            &layout.artifact_path(output_dir, &layout.dict_da)?,
+        )?;
+        self.write_artifact(output_dir, &layout.dict_vals, &encoded.buffers.vals)?;
+        self.write_value_table(output_dir, encoded.buffers.value_table.as_deref())?;
+        if self.options.paranoid {
+            self.audit_lexicon(
+                output_dir,
+                encoded
+                    .word_entry_map
+                    .iter()
+                    .map(|(surface, word_entries)| AuditedSurface {
+                        surface: surface.as_bytes(),
+                        entries: word_entries.len() as u32,
+                        word_entries: Some(word_entries),
+                    }),
+            )?;
+        }
 
-        let mut id = 0u32;
+        if self.options.reading_index {
+            self.print("building reading index");
+            reading_index::write_reading_index(output_dir, &rows, &|message| self.print(message))?;
+        }
+
+        if self.options.columnar_details {
+            self.print("building columns");
+            columnar::write_columns(output_dir, &rows, &|message| self.print(message))?;
+        }
+
+        if let Some(export) = &self.options.elasticsearch_export {
+            self.print("building Elasticsearch analyzer files");
+            elasticsearch::write_elasticsearch_files(output_dir, &rows, export, &|message| {
+                self.print(message)
+            })?;
+        }
 
-        println!("building da");
+        if let Some(shard_size) = self.options.shard_size {
+            self.print("building shards");
+            shard::write_shards(
+                output_dir,
+                &self.options.layout,
+                shard_size,
+                &rows,
+                &encoded.buffers.words,
+                &encoded.words_offsets,
+                &encoded.word_entry_map,
+                &|message| self.print(message),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the manifest and checksums, then runs the optional post-build steps, returning
+    /// the artifacts of the manifest and the path of the package, if any.
+    fn finish_output(
+        &self,
+        output_dir: &Path,
+        seed_dates: Option<SeedDates>,
+    ) -> LinderaResult<(Vec<ArtifactEntry>, Option<PathBuf>)> {
+        if let Some(dictionary_version) = &self.options.dictionary_version {
+            Manifest::write_version(output_dir, dictionary_version)?;
+        }
+        if self.options.endianness == Endianness::Big {
+            endian::convert_to_big_endian(output_dir, &self.options.layout)?;
+        }
+        if self.options.artifact_headers {
+            header::write_headers(
+                output_dir,
+                &self.options.layout,
+                ArtifactHeader::new(self.options.endianness, self.options.detail_format),
+            )?;
+        }
+        if self.options.mmap_container {
+            mmap_layout::write_mmap_container(
+                output_dir,
+                &self.options.layout,
+                self.options.io_buffer_size(),
+            )?;
+        }
+        let mut manifest = Manifest::from_output_dir(output_dir, &self.options.layout)?;
+        manifest.detail_format = self.options.detail_format;
+        manifest.endianness = self.options.endianness;
+        manifest.artifact_headers = self.options.artifact_headers;
+        manifest.lindera_target = self.options.lindera_target;
+        manifest.value_packing = self.options.value_packing;
+        manifest.detail_fields = self.options.detail_fields.clone();
+        manifest.dictionary_version = self.options.dictionary_version.clone();
+        manifest.seed_dates = seed_dates;
+        if let Some(size_budget) = self.options.size_budget {
+            budget::check_size_budget(&manifest, size_budget)?;
+        }
+        manifest.write(output_dir)?;
+        checksum::write_checksums(output_dir)?;
+
+        #[cfg(feature = "signing")]
+        {
+            if let Some(signing_key) = &self.options.signing_key {
+                signature::sign_output(output_dir, &signing_key.0)?;
+            }
+        }
+
+        #[cfg(feature = "quality-check")]
+        {
+            if let Some(corpus_file) = &self.options.corpus_file {
+                let report = quality::check_quality(
+                    output_dir,
+                    corpus_file,
+                    self.options.previous_dict_dir.as_deref(),
+                )?;
+                self.print(&format!(
+                    "{} lines, {} tokens, {:.2}% unknown, {:.2} characters per token",
+                    report.lines,
+                    report.tokens,
+                    report.unknown_rate() * 100.0,
+                    report.average_token_length
+                ));
+                if self.options.previous_dict_dir.is_some() {
+                    self.print(&format!(
+                        "{} lines tokenized differently",
+                        report.changed_lines
+                    ));
+                    for diff in &report.diffs {
+                        self.print(&format!(
+                            "{}: {} -> {}",
+                            diff.line,
+                            diff.previous.join(" "),
+                            diff.current.join(" ")
+                        ));
+                    }
+                }
+            }
+        }
+
+        if let Some(codegen_dir) = &self.options.codegen_dir {
+            codegen::generate_rust_source(
+                output_dir,
+                codegen_dir,
+                self.options.codegen_compress,
+                self.options
+                    .compression_level
+                    .unwrap_or(codegen::DEFAULT_COMPRESSION_LEVEL),
+            )?;
+        }
+
+        let package = if self.options.package {
+            Some(package::package_output(
+                output_dir,
+                self.package_version(),
+                self.options
+                    .compression_level
+                    .unwrap_or(package::DEFAULT_COMPRESSION_LEVEL),
+            )?)
+        } else {
+            None
+        };
+
+        #[cfg(feature = "object-store")]
+        {
+            if let Some(sink_url) = &self.options.sink_url {
+                upload::upload_output(output_dir, sink_url, &|message| self.print(message))?;
+            }
+        }
+
+        Ok((manifest.artifacts, package))
+    }
+
+    /// Runs the finish phase of the build of `input_dir` started at `start` in each of
+    /// `output_dirs`, the first of which gets `seed_dates`, and closes the build log, then
+    /// runs the post-build hooks with the report of each output directory.
+    fn finish_build(
+        &self,
+        input_dir: &Path,
+        output_dirs: &[&Path],
+        seed_dates: Option<SeedDates>,
+        start: Instant,
+    ) -> LinderaResult<()> {
+        let mut seed_dates = seed_dates;
+        let outputs = self.measure_phase("finish", &[], output_dirs[0], || {
+            output_dirs
+                .iter()
+                .map(|output_dir| self.finish_output(output_dir, seed_dates.take()))
+                .collect::<LinderaResult<Vec<_>>>()
+        })?;
+        self.log.close()?;
+
+        for (output_dir, (artifacts, package)) in output_dirs.iter().zip(outputs) {
+            self.run_post_build_hooks(&BuildReport {
+                input_dir: input_dir.to_path_buf(),
+                output_dir: output_dir.to_path_buf(),
+                package,
+                artifacts,
+                dictionary_version: self.options.dictionary_version.clone(),
+                duration: start.elapsed(),
+                entry_stats: self.entry_stats(),
+                effective_unk_costs: self.effective_unk_costs(),
+                warning_counts: self.warning_counts(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Runs a build phase, logging its start and end and reporting its measurements to the
+    /// metrics receiver if there is one.
+    fn measure_phase<T, F>(
+        &self,
+        phase: &'static str,
+        input_files: &[PathBuf],
+        output_dir: &Path,
+        f: F,
+    ) -> LinderaResult<T>
+    where
+        F: FnOnce() -> LinderaResult<T>,
+    {
+        self.log.log(&BuildEvent::PhaseStart { phase });
+        let output_size = match &self.metrics {
+            Some(_) => metrics::dir_size(output_dir),
+            None => 0,
+        };
+        let start = Instant::now();
+        let result = f()?;
+        self.log.log(&BuildEvent::PhaseEnd {
+            phase,
+            duration_ms: start.elapsed().as_millis(),
+        });
+
+        let metrics = match &self.metrics {
+            Some(metrics) => metrics,
+            None => return Ok(result),
+        };
+        metrics.record_phase(&PhaseMetrics {
+            phase,
+            duration: start.elapsed(),
+            bytes_read: input_files
+                .iter()
+                .map(|path| metrics::file_size(path))
+                .sum(),
+            bytes_written: metrics::dir_size(output_dir).saturating_sub(output_size),
+            peak_memory: metrics::peak_memory(),
+        });
+        Ok(result)
+    }
+
+    /// Handles a warning as configured by the options, recording the printed ones in the
+    /// build log.
+    fn report_warning(&self, warning: BuildWarning) -> LinderaResult<()> {
+        let message = warning.to_string();
+        let action = self.options.warning_action(warning.kind());
+        if self
+            .warnings
+            .report(warning, action, self.options.warning_limit, &|message| {
+                self.print(message)
+            })?
+        {
+            self.log.log(&BuildEvent::Warning { message: &message });
+        }
+        Ok(())
+    }
+
+    /// Reads the NEologd snapshot dates from the names of the CSV files, reporting files of
+    /// mixed snapshots.
+    fn check_seed_dates(&self, csv_files: &[PathBuf]) -> LinderaResult<Option<SeedDates>> {
+        let seed_dates = SeedDates::from_files(csv_files);
+        if let Some(seed_dates) = &seed_dates {
+            self.print(&format!(
+                "NEologd seed dates {} to {}",
+                seed_dates.oldest, seed_dates.newest
+            ));
+            if seed_dates.is_mixed() {
+                self.report_warning(BuildWarning::MixedSnapshots {
+                    oldest: seed_dates.oldest.clone(),
+                    newest: seed_dates.newest.clone(),
+                })?;
+            }
+        }
+        Ok(seed_dates)
+    }
+
+    /// Normalizes a line of the CSV file `name`, reporting the lines changed.
+    fn normalize_source_line(
+        &self,
+        name: &str,
+        line: &str,
+        normalizer: &LineNormalizer,
+    ) -> LinderaResult<String> {
+        let normalized = normalizer.normalize(line);
+        if normalized != line {
+            self.report_warning(BuildWarning::NormalizationApplied {
+                file: name.to_string(),
+                line: line.to_string(),
+            })?;
+            self.entry_stats.record(name, |stats| stats.normalized += 1);
+        }
+        Ok(self.fold_surface(normalized.into_owned()))
+    }
+
+    /// Folds the surface of an entry line, if the options ask for it.
+    fn fold_surface(&self, line: String) -> String {
+        match self.options.surface_folding {
+            Some(folding) => folding::fold_line(&line, folding, CSV_FIELDS_NUM),
+            None => line,
+        }
+    }
+
+    /// The cost of a row clamped to the `i16` range, reporting out of range costs.
+    fn word_cost(&self, row: &CsvRow) -> LinderaResult<i16> {
+        i16::try_from(row.word_cost).or_else(|_err| {
+            self.report_warning(BuildWarning::TruncatedCost {
+                file: row.provenance.unwrap_or_default().to_string(),
+                surface: row.surface_form.to_string(),
+                word_cost: row.word_cost,
+            })?;
+            Ok(row
+                .word_cost
+                .max(i32::from(i16::MIN))
+                .min(i32::from(i16::MAX)) as i16)
+        })
+    }
+
+    /// Reports the rows whose context ids are outside the sizes of `matrix_data_path`.
+    fn check_context_ids(&self, rows: &[CsvRow], matrix_data_path: &Path) -> LinderaResult<()> {
+        let (forward_size, backward_size) = matrix::read_header(matrix_data_path)?;
+        for row in rows {
+            if row.left_id >= backward_size || row.right_id >= forward_size {
+                self.report_warning(BuildWarning::UnknownContextId {
+                    file: row.provenance.unwrap_or_default().to_string(),
+                    surface: row.surface_form.to_string(),
+                    left_id: row.left_id,
+                    right_id: row.right_id,
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints a status message of the build, through the progress reporter if there is one.
+    fn print(&self, message: &str) {
+        match &self.progress {
+            Some(progress) => progress.message(message),
+            None => println!("{}", message),
+        }
+    }
+
+    fn progress_start(&self, step: ProgressStep, total: u64) {
+        if let Some(progress) = &self.progress {
+            progress.start(step, total);
+        }
+    }
+
+    fn progress_advance(&self, step: ProgressStep, delta: u64) {
+        if let Some(progress) = &self.progress {
+            progress.advance(step, delta);
+        }
+    }
+
+    fn progress_finish(&self, step: ProgressStep) {
+        if let Some(progress) = &self.progress {
+            progress.finish(step);
+        }
+    }
+
+    /// Prints a warning and records it in the build log.
+    fn warn(&self, message: &str) {
+        self.print(message);
+        self.log.log(&BuildEvent::Warning { message });
+    }
+
+    /// Warns if `char.def` does not group digits as the numeric entries of the options
+    /// assume.
+    pub(crate) fn check_numeric_category(&self, char_def: &str) {
+        if self.options.numeric_entries {
+            if let Some(problem) = numeric::check_numeric_category(char_def) {
+                self.warn(&problem);
+            }
+        }
+    }
+
+    fn log_skipped_row(&self, row: &CsvRow, reason: &str) {
+        let file = row.provenance.unwrap_or_default();
+        self.log.log(&BuildEvent::SkippedRow {
+            file,
+            surface: row.surface_form,
+            reason,
+        });
+        self.entry_stats.record(file, |stats| stats.skipped += 1);
+    }
+
+    fn skip_row(&self, row: &CsvRow, rule: &SkipRule) {
+        self.skip_counts.record(rule);
+        self.log_skipped_row(row, &format!("skip rule {}", rule));
+    }
+
+    /// Prints the entry statistics of the source files, so that truncated or corrupted
+    /// files stand out, and the number of entries removed by each skip rule.
+    fn report_entry_stats(&self) {
+        for (file, stats) in self.entry_stats.stats() {
+            self.print(&format!(
+                "{}: {} parsed, {} normalized, {} skipped, {} duplicates, {} merged",
+                file,
+                stats.parsed,
+                stats.normalized,
+                stats.skipped,
+                stats.duplicates_seen,
+                stats.merged
+            ));
+            self.log.log(&BuildEvent::FileEntries {
+                file: &file,
+                parsed: stats.parsed,
+                normalized: stats.normalized,
+                skipped: stats.skipped,
+                duplicates_seen: stats.duplicates_seen,
+                merged: stats.merged,
+            });
+        }
+
+        let skip_counts = self.skip_counts.counts();
+        for rule in self.options.skip_rules() {
+            let rule = rule.to_string();
+            self.print(&format!(
+                "skip rule {}: {} entries removed",
+                rule,
+                skip_counts.get(&rule).copied().unwrap_or(0)
+            ));
+        }
+    }
+
+    /// Opens the build log in `output_dir` if the options ask for one.
+    fn open_build_log(&self, output_dir: &Path) -> LinderaResult<()> {
+        if self.options.build_log {
+            self.log.open(output_dir)?;
+        }
+        Ok(())
+    }
+
+    /// Creates the output directories of a build and the scratch directory, and locks the
+    /// output directories until the returned locks are dropped.
+    pub(crate) fn prepare_outputs(&self, output_dirs: &[&Path]) -> LinderaResult<Vec<OutputLock>> {
+        let mut locks = Vec::new();
+        for output_dir in output_dirs {
+            fs::create_dir_all(output_dir)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            locks.push(self.lock_output(output_dir)?);
+        }
+        if let Some(scratch_dir) = &self.options.scratch_dir {
+            fs::create_dir_all(scratch_dir)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        }
+        Ok(locks)
+    }
+
+    /// Runs `build` on the sources in `input_dir`, writing to `output_dirs`, the first of
+    /// which holds the build log, with the output directories locked, then finishes them as
+    /// `finish_build` does with the seed dates `build` returns. Every build writing an output
+    /// directory goes through it, or through `prepare_outputs` and `finish_build` for the
+    /// batch and async builds.
+    pub(crate) fn run_build<F>(
+        &self,
+        input_dir: &Path,
+        output_dirs: &[&Path],
+        build: F,
+    ) -> LinderaResult<()>
+    where
+        F: FnOnce() -> LinderaResult<Option<SeedDates>>,
+    {
+        let _locks = self.prepare_outputs(output_dirs)?;
+        let start = Instant::now();
+        self.open_build_log(output_dirs[0])?;
+        let seed_dates = build()?;
+        self.finish_build(input_dir, output_dirs, seed_dates, start)
+    }
+
+    /// The phases of `build_dictionary`, run with the output directory locked.
+    fn build_phases(
+        &self,
+        input_dir: &Path,
+        output_dir: &Path,
+    ) -> LinderaResult<Option<SeedDates>> {
+        self.preflight(input_dir)?;
+        let estimate = self.estimate_disk_usage(input_dir)?;
+        self.check_disk_space(&[self.disk_need(output_dir, estimate)])?;
+
+        let chardef = self.measure_phase(
+            "chardef",
+            &self.char_def_files(input_dir),
+            output_dir,
+            || self.build_chardef(input_dir, output_dir),
+        )?;
+        self.measure_phase("unk", &[input_dir.join("unk.def")], output_dir, || {
+            self.build_unk(input_dir, &chardef, output_dir)
+        })?;
+        let csv_files: Vec<PathBuf> = self
+            .csv_sources(input_dir)?
+            .into_iter()
+            .map(|(_, filename)| filename)
+            .collect();
+        let seed_dates = self.check_seed_dates(&csv_files)?;
+        self.measure_phase("dict", &csv_files, output_dir, || {
+            self.build_dict(input_dir, output_dir)
+        })?;
+        self.report_entry_stats();
+        self.measure_phase(
+            "matrix",
+            &[input_dir.join("matrix.def")],
+            output_dir,
+            || self.build_cost_matrix(input_dir, output_dir),
+        )?;
+        Ok(seed_dates)
+    }
+
+    /// `char.def` and the file merged over it.
+    fn char_def_files(&self, input_dir: &Path) -> [PathBuf; 2] {
+        [
+            input_dir.join("char.def"),
+            self.options
+                .char_overrides
+                .clone()
+                .unwrap_or_else(|| input_dir.join(chardef::CHAR_OVERRIDES_FILENAME)),
+        ]
+    }
+
+    /// Whether the options allow building the dictionary from externally sorted entries,
+    /// which are never all held in memory.
+    pub(crate) fn supports_external_sort(&self) -> bool {
+        self.options.patch_files.is_empty()
+            && !self.options.reading_index
+            && !self.options.columnar_details
+            && self.options.shard_size.is_none()
+            && !self.options.compound_splits
+            && self.options.kana_variants.is_none()
+            && self.options.variant_rules.is_none()
+            && !self.options.emoji_entries
+            && !self.options.kaomoji_entries
+            && !self.options.numeric_entries
+            && self.options.elasticsearch_export.is_none()
+            && (self.options.size_budget.is_none() || self.options.pruning.is_none())
+    }
+
+    /// Iterates over the entry lines of a CSV file, skipping blank and comment lines.
+    fn csv_lines<'a>(&'a self, content: &'a str) -> impl Iterator<Item = (usize, &'a str)> {
+        source_lines(content).filter(move |(_, line)| match &self.options.comment_prefix {
+            Some(prefix) => !line.starts_with(prefix.as_str()),
+            None => true,
+        })
+    }
+}
+
+impl DictionaryBuilder for IpadicNeologdBuilder {
+    fn build_dictionary(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()> {
+        self.run_build(input_dir, &[output_dir], || {
+            self.build_phases(input_dir, output_dir)
+        })
+    }
+
+    fn build_chardef(
+        &self,
+        input_dir: &Path,
+        output_dir: &Path,
+    ) -> LinderaResult<CharacterDefinitions> {
+        self.print("BUILD CHARDEF");
+        let char_def = chardef::read_char_def(input_dir, self.options.char_overrides.as_deref())?;
+        self.check_numeric_category(&char_def);
+        let (char_definitions, bytes) = match &self.char_def_builder {
+            Some(char_def_builder) => char_def_builder.build_chardef_bytes(&char_def)?,
+            None => self.build_chardef_bytes(&char_def)?,
+        };
+        self.write_artifact(output_dir, &self.options.layout.char_def, &bytes)?;
+
+        Ok(char_definitions)
+    }
+
+    fn build_unk(
+        &self,
+        input_dir: &Path,
+        chardef: &CharacterDefinitions,
+        output_dir: &Path,
+    ) -> LinderaResult<()> {
+        self.print("BUILD UNK");
+        let unk_data = read_source_file(&input_dir.join("unk.def"))?;
+        let bytes = match &self.char_def_builder {
+            Some(char_def_builder) => char_def_builder.build_unk_bytes(&unk_data, chardef)?,
+            None => self.build_unk_bytes(&unk_data, chardef)?,
+        };
+        self.write_artifact(output_dir, &self.options.layout.unk, &bytes)
+    }
+
+    fn build_dict(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()> {
+        self.print("BUILD DICT");
+        if let Some(lexicon_builder) = &self.lexicon_builder {
+            return self.build_dict_with(lexicon_builder.as_ref(), input_dir, output_dir);
+        }
+
+        let sources = self.csv_sources(input_dir)?;
+        if let Some(estimated_bytes) = self.exceeded_memory_budget(&sources)? {
+            if self.supports_external_sort() {
+                self.print(&format!(
+                    "estimated {} bytes exceed the memory budget, sorting entries externally",
+                    estimated_bytes
+                ));
+                return self.build_dict_external(input_dir, &sources, None, output_dir);
+            }
+            self.warn(&format!(
+                "estimated {} bytes exceed the memory budget, but {} need the entries in memory",
+                estimated_bytes, IN_MEMORY_STAGES
+            ));
+        }
+
+        let files = self.read_sources(&sources)?;
+        let patches = self.load_patches()?;
+        let mut errors = ErrorCollector::new(self.options.max_errors);
+        let rows = parse_files(&files, &mut errors)?;
+        errors.finish()?;
+        if rows.is_empty() {
+            return Err(no_entries_error(files.len(), input_dir));
+        }
+        self.entry_stats.record_parsed(&rows);
+        self.write_rows(&patches, rows, &input_dir.join("matrix.def"), output_dir)
+    }
+
+    fn build_cost_matrix(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()> {
+        self.print("BUILD COST MATRIX");
+        let matrix_data_path = input_dir.join("matrix.def");
+        let bytes = match &self.matrix_builder {
+            Some(matrix_builder) => {
+                let matrix_def = fs::read(&matrix_data_path).map_err(|source| BuildError::Io {
+                    path: Some(matrix_data_path.clone()),
+                    source,
+                })?;
+                matrix_builder.build_cost_matrix_bytes(&matrix_def)?
+            }
+            None => {
+                let sizes = matrix::read_header(&matrix_data_path)?;
+                let input_len = fs::metadata(&matrix_data_path)
+                    .map_err(|source| BuildError::Io {
+                        path: Some(matrix_data_path.clone()),
+                        source,
+                    })?
+                    .len();
+                let rdr = io_tuning::open_reader(&matrix_data_path, self.options.io_buffer_size())
+                    .map_err(|source| BuildError::Io {
+                        path: Some(matrix_data_path.clone()),
+                        source,
+                    })?;
+                self.cost_matrix_from(&matrix_data_path, sizes, input_len, rdr)?
+            }
+        };
+        self.write_artifact(output_dir, &self.options.layout.matrix, &bytes)
+    }
+
+    fn build_user_dict(&self, input_file: &Path) -> LinderaResult<UserDictionary> {
+        self.print("BUILD USER DICT");
+        let data = read_source_file(input_file)?;
+        let normalizer = self.options.line_normalizer()?;
+        let (line_nums, lines): (Vec<usize>, Vec<String>) = self
+            .csv_lines(&data)
+            .map(|(line_num, line)| {
+                let line = normalizer.normalize(line).into_owned();
+                (line_num, self.fold_surface(line))
+            })
+            .unzip();
+
+        let name = input_file.display().to_string();
+        let mut rows = parse_rows(&name, &lines, &line_nums)?;
+        if rows.is_empty() {
+            return Err(no_entries_error(1, input_file));
+        }
+        for row in &mut rows {
+            row.provenance = Some(&name);
+        }
+        let mut rows = self.drop_reference_entries(rows)?;
+        rows.sort_by_key(|row| row.surface_form);
+
+        let mut word_entry_map: BTreeMap<String, Vec<WordEntry>> = BTreeMap::new();
+        for (row_id, row) in rows.iter().enumerate() {
+            word_entry_map
+                .entry(row.surface_form.to_string())
+                .or_default()
+                .push(WordEntry {
+                    word_id: WordId(row_id as u32, false),
+                    word_cost: self.word_cost(row)?,
+                    cost_id: row.left_id as u16,
+                });
+        }
+
+        let codec = self.options.detail_codec();
+        let detail_fields = self.options.detail_field_indices()?;
+        let mut words_data = Vec::<u8>::new();
+        let mut words_idx_data = Vec::<u8>::new();
+        for row in rows.iter() {
+            let word = self.word_details(row, detail_fields.as_deref());
+            let offset = words_data.len();
+            words_idx_data
+                .write_u32::<LittleEndian>(offset as u32)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            codec.encode(&word, &mut words_data)?;
+        }
+
+        let mut id = 0u32;
         let mut keyset: Vec<(&[u8], u32)> = vec![];
-        let mut lastlen = 0;
+        // Lindera loads user dictionaries, so their values are always compact.
+        let mut packer = CompactPacker;
         for (key, word_entries) in &word_entry_map {
             let len = word_entries.len() as u32;
-            assert!(
-                len < (1 << 5),
-                "{} is {} length. Too long. [{}]",
-                key,
-                len,
-                (1 << 5)
-            );
-            let val = (id << 5) | len;
+            let val = packer.pack(key, id, len)?;
             keyset.push((key.as_bytes(), val));
             id += len;
-            lastlen += len;
         }
 
         let da_bytes = DoubleArrayBuilder::build(&keyset).ok_or_else(|| {
             LinderaErrorKind::Io.with_error(anyhow::anyhow!("DoubleArray build error."))
         })?;
 
-        wtr_da
-            .write_all(&da_bytes[..])
-            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
-        println!("Last len is {}", lastlen);
-
-        println!("building values");
+        let mut vals_data = Vec::<u8>::new();
         for word_entries in word_entry_map.values() {
             for word_entry in word_entries {
                 word_entry
-                    .serialize(&mut wtr_vals)
+                    .serialize(&mut vals_data)
                     .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
             }
         }
-        wtr_vals
-            .flush()
-            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
 
-        Ok(())
+        let dict = PrefixDict {
+            da: DoubleArray::new(da_bytes),
+            vals_data,
+            is_system: false,
+        };
+
+        Ok(UserDictionary {
+            dict,
+            words_idx_data,
+            words_data,
+        })
     }
+}
 
-    fn build_cost_matrix(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()> {
-        println!("BUILD COST MATRIX");
-        let matrix_data_path = input_dir.join("matrix.def");
-        let matrix_data = read_utf8_file(&matrix_data_path)?;
-        let mut lines = Vec::new();
-        for line in matrix_data.lines() {
-            let fields: Vec<i32> = line
-                .split_whitespace()
-                .map(i32::from_str)
-                .collect::<Result<_, _>>()
-                .map_err(|err| LinderaErrorKind::Parse.with_error(anyhow::anyhow!(err)))?;
-            lines.push(fields);
-        }
-        let mut lines_it = lines.into_iter();
-        let header = lines_it.next().ok_or_else(|| {
-            LinderaErrorKind::Content.with_error(anyhow::anyhow!("unknown error"))
-        })?;
-        let forward_size = header[0] as u32;
-        let backward_size = header[1] as u32;
-        let len = 2 + (forward_size * backward_size) as usize;
-        let mut costs = vec![i16::max_value(); len];
-        costs[0] = forward_size as i16;
-        costs[1] = backward_size as i16;
-        for fields in lines_it {
-            let forward_id = fields[0] as u32;
-            let backward_id = fields[1] as u32;
-            let cost = fields[2] as u16;
-            costs[2 + (backward_id + forward_id * backward_size) as usize] = cost as i16;
-        }
-
-        let wtr_matrix_mtx_path = output_dir.join(Path::new("matrix.mtx"));
-        let mut wtr_matrix_mtx = io::BufWriter::new(
-            File::create(wtr_matrix_mtx_path)
-                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use lindera_core::dictionary_builder::DictionaryBuilder;
+
+    use crate::fixtures::{
+        build_fixture, write_fixture, FIXTURE_CSV_FILENAME, FIXTURE_MATRIX_DEF, FIXTURE_ROWS,
+    };
+    use crate::manifest::{Manifest, VERSION_FILENAME};
+    use crate::options::BuilderOptions;
+    use crate::reader::DictionaryReader;
+    use crate::warning::BuildWarning;
+    use crate::{normalize_fields, CsvRow, IpadicNeologdBuilder, ReadingScript};
+
+    #[test]
+    fn skips_comment_lines_with_a_comment_prefix() {
+        let options = BuilderOptions {
+            comment_prefix: Some("#".to_string()),
+            ..BuilderOptions::default()
+        };
+        let builder = IpadicNeologdBuilder::with_options(options.clone());
+        let content = "# surface,left_id,right_id,cost\n東京,1,1\n #indented\n";
+        assert_eq!(
+            builder.csv_lines(content).collect::<Vec<_>>(),
+            vec![(2, "東京,1,1"), (3, " #indented")]
+        );
+        assert_eq!(
+            IpadicNeologdBuilder::new()
+                .csv_lines(content)
+                .map(|(line_num, _)| line_num)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3]
         );
-        for cost in costs {
-            wtr_matrix_mtx
-                .write_i16::<LittleEndian>(cost)
-                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
-        }
-        wtr_matrix_mtx
-            .flush()
-            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
 
-        Ok(())
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-comments-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        write_fixture(&input_dir).unwrap();
+        let mut csv = format!("# generated\n{}\n", FIXTURE_ROWS.join("\n"));
+        csv.push_str("#渋谷,1,1,3000,名詞,固有名詞,地域,一般,*,*,渋谷,シブヤ,シブヤ\n");
+        fs::write(input_dir.join(FIXTURE_CSV_FILENAME), csv).unwrap();
+        IpadicNeologdBuilder::with_options(options)
+            .build_dictionary(&input_dir, &output_dir)
+            .unwrap();
+        let reader = DictionaryReader::open(&output_dir).unwrap();
+        assert_eq!(reader.num_words(), FIXTURE_ROWS.len());
+        assert!(reader.lookup("#渋谷").is_empty());
+
+        fs::remove_dir_all(&base_dir).unwrap();
     }
 
-    fn build_user_dict(&self, input_file: &Path) -> LinderaResult<UserDictionary> {
-        unimplemented!();
+    #[test]
+    fn streams_matrix_def_into_the_cost_matrix() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-cost-matrix-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        write_fixture(&input_dir).unwrap();
+        fs::create_dir_all(&output_dir).unwrap();
+        let matrix_def = format!("\u{feff}{}\n", FIXTURE_MATRIX_DEF.replace('\n', "\r\n\n"));
+        fs::write(input_dir.join("matrix.def"), matrix_def).unwrap();
+
+        IpadicNeologdBuilder::new()
+            .build_cost_matrix(&input_dir, &output_dir)
+            .unwrap();
+        let costs: Vec<i16> = fs::read(output_dir.join("matrix.mtx"))
+            .unwrap()
+            .chunks(2)
+            .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]))
+            .collect();
+        assert_eq!(
+            costs,
+            vec![3, 3, 0, 100, 100, 100, 200, -100, 100, -100, 200]
+        );
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn records_the_source_file_in_the_word_details() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-provenance-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        write_fixture(&input_dir).unwrap();
+        fs::write(
+            input_dir.join("extra.csv"),
+            "渋谷,1,1,3000,名詞,固有名詞,地域,一般,*,*,渋谷,シブヤ,シブヤ\n",
+        )
+        .unwrap();
+        let options = BuilderOptions {
+            provenance: true,
+            ..BuilderOptions::default()
+        };
+        IpadicNeologdBuilder::with_options(options)
+            .build_dictionary(&input_dir, &output_dir)
+            .unwrap();
+
+        let reader = DictionaryReader::open(&output_dir).unwrap();
+        let provenance = |surface: &str| {
+            let details = reader.details(reader.lookup(surface)[0].word_id.0).unwrap();
+            assert_eq!(details.len(), 10);
+            details[9].clone()
+        };
+        assert_eq!(provenance("渋谷"), "extra.csv");
+        assert_eq!(provenance("東京"), FIXTURE_CSV_FILENAME);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn builds_the_csv_files_of_extra_input_directories() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-extra-input-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        let extra_input_dir = base_dir.join("user");
+        write_fixture(&input_dir).unwrap();
+        fs::create_dir_all(&extra_input_dir).unwrap();
+        fs::write(
+            extra_input_dir.join(FIXTURE_CSV_FILENAME),
+            "渋谷,1,1,3000,名詞,固有名詞,地域,一般,*,*,渋谷,シブヤ,シブヤ\n",
+        )
+        .unwrap();
+        let options = BuilderOptions {
+            extra_input_dirs: vec![extra_input_dir],
+            provenance: true,
+            ..BuilderOptions::default()
+        };
+        IpadicNeologdBuilder::with_options(options)
+            .build_dictionary(&input_dir, &output_dir)
+            .unwrap();
+
+        let reader = DictionaryReader::open(&output_dir).unwrap();
+        assert_eq!(reader.num_words(), FIXTURE_ROWS.len() + 1);
+        let provenance = |surface: &str| {
+            let details = reader.details(reader.lookup(surface)[0].word_id.0).unwrap();
+            details[9].clone()
+        };
+        assert_eq!(provenance("渋谷"), format!("user/{}", FIXTURE_CSV_FILENAME));
+        assert_eq!(provenance("東京"), FIXTURE_CSV_FILENAME);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn records_the_dictionary_version() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-dictionary-version-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        let options = BuilderOptions {
+            dictionary_version: Some("v0.0.7-20200910".to_string()),
+            ..BuilderOptions::default()
+        };
+        build_fixture(&input_dir, &output_dir, options).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(output_dir.join(VERSION_FILENAME)).unwrap(),
+            "v0.0.7-20200910\n"
+        );
+        let manifest = Manifest::load(&output_dir).unwrap();
+        assert_eq!(
+            manifest.dictionary_version.as_deref(),
+            Some("v0.0.7-20200910")
+        );
+        assert!(manifest
+            .artifacts
+            .iter()
+            .any(|artifact| artifact.name == VERSION_FILENAME));
+
+        let unversioned_dir = base_dir.join("unversioned");
+        build_fixture(&input_dir, &unversioned_dir, BuilderOptions::default()).unwrap();
+        assert!(!unversioned_dir.join(VERSION_FILENAME).exists());
+        assert!(Manifest::load(&unversioned_dir)
+            .unwrap()
+            .dictionary_version
+            .is_none());
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn normalizes_only_the_configured_fields() {
+        let line = "ド～ン,1,1,3000,感動詞,*,*,*,*,*,ド～ン,ド―ン,ド―ン";
+        assert_eq!(
+            normalize_fields(line, &[0]),
+            "ド〜ン,1,1,3000,感動詞,*,*,*,*,*,ド～ン,ド―ン,ド―ン"
+        );
+        assert_eq!(
+            normalize_fields(line, &[10, 11]),
+            "ド～ン,1,1,3000,感動詞,*,*,*,*,*,ド〜ン,ド—ン,ド―ン"
+        );
+        assert_eq!(normalize_fields(FIXTURE_ROWS[0], &[0]), FIXTURE_ROWS[0]);
+
+        let options = BuilderOptions {
+            normalized_fields: Some(vec!["surface".to_string(), "reading".to_string()]),
+            ..BuilderOptions::default()
+        };
+        assert_eq!(options.normalized_field_indices().unwrap(), vec![0, 11]);
+        assert_eq!(
+            BuilderOptions::default()
+                .normalized_field_indices()
+                .unwrap(),
+            vec![0]
+        );
+        let options = BuilderOptions {
+            normalized_fields: Some(vec!["yomi".to_string()]),
+            ..BuilderOptions::default()
+        };
+        assert!(options.normalized_field_indices().is_err());
+
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-normalized-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        write_fixture(&input_dir).unwrap();
+        fs::write(input_dir.join("extra.csv"), format!("{}\n", line)).unwrap();
+        let builder = IpadicNeologdBuilder::new();
+        builder.build_dictionary(&input_dir, &output_dir).unwrap();
+        assert_eq!(builder.entry_stats()["extra.csv"].normalized, 1);
+        assert_eq!(builder.entry_stats()[FIXTURE_CSV_FILENAME].normalized, 0);
+        let reader = DictionaryReader::open(&output_dir).unwrap();
+        assert_eq!(reader.lookup("ド〜ン").len(), 1);
+        assert!(reader.lookup("ド～ン").is_empty());
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn reports_entries_differing_in_cost_or_reading() {
+        let row = |line| CsvRow::from_line(line).unwrap();
+        let tokyo = row(FIXTURE_ROWS[0]);
+        let cheaper = row("東京,1,1,2500,名詞,固有名詞,地域,一般,*,*,東京,トーキョー,トーキョー");
+        assert!(tokyo.same_entry(&row(FIXTURE_ROWS[0])));
+        assert!(!tokyo.same_entry(&cheaper));
+        assert_eq!(
+            cheaper.conflict(&tokyo).as_deref(),
+            Some("word cost 3000 and 2500, reading トウキョウ and トーキョー")
+        );
+        let other_pos = row("東京,1,1,2500,名詞,固有名詞,一般,*,*,*,東京,トウキョウ,トーキョー");
+        assert_eq!(other_pos.conflict(&tokyo), None);
+        assert_eq!(tokyo.conflict(&row(FIXTURE_ROWS[0])), None);
+
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-conflicts-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        write_fixture(&input_dir).unwrap();
+        fs::write(
+            input_dir.join("user.csv"),
+            format!(
+                "{}\n東京,1,1,2500,名詞,固有名詞,地域,一般,*,*,東京,トウキョウ,トーキョー\n",
+                FIXTURE_ROWS[2]
+            ),
+        )
+        .unwrap();
+        let builder = IpadicNeologdBuilder::new();
+        builder.build_dictionary(&input_dir, &output_dir).unwrap();
+        let warnings = builder.warnings();
+        assert!(warnings.iter().any(|warning| matches!(
+            warning,
+            BuildWarning::DuplicateEntry { surface, .. } if surface == "に"
+        )));
+        assert!(warnings.iter().any(|warning| matches!(
+            warning,
+            BuildWarning::ConflictingEntry { surface, difference, .. }
+                if surface == "東京" && difference == "word cost 3000 and 2500"
+        )));
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn flags_the_entries_of_seed_files() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-seed-flag-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        write_fixture(&input_dir).unwrap();
+        fs::write(
+            input_dir.join("neologd-noun-dict-seed.20200910.csv"),
+            "渋谷,1,1,3000,名詞,固有名詞,地域,一般,*,*,渋谷,シブヤ,シブヤ\n",
+        )
+        .unwrap();
+        let options = BuilderOptions {
+            neologd_flag: true,
+            ..BuilderOptions::default()
+        };
+        IpadicNeologdBuilder::with_options(options)
+            .build_dictionary(&input_dir, &output_dir)
+            .unwrap();
+
+        let reader = DictionaryReader::open(&output_dir).unwrap();
+        let flag = |surface: &str| {
+            let details = reader.details(reader.lookup(surface)[0].word_id.0).unwrap();
+            assert_eq!(details.len(), 10);
+            details[9].clone()
+        };
+        assert_eq!(flag("渋谷"), "1");
+        assert_eq!(flag("東京"), "0");
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn writes_the_selected_detail_fields() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-detail-fields-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        let detail_fields = vec!["reading".to_string(), "pos_level1".to_string()];
+        let options = BuilderOptions {
+            detail_fields: Some(detail_fields.clone()),
+            provenance: true,
+            ..BuilderOptions::default()
+        };
+        assert_eq!(options.detail_field_indices().unwrap(), Some(vec![7, 0]));
+        build_fixture(&input_dir, &output_dir, options).unwrap();
+
+        let reader = DictionaryReader::open(&output_dir).unwrap();
+        let details = reader.details(reader.lookup("東京")[0].word_id.0).unwrap();
+        assert_eq!(details, vec!["トウキョウ", "名詞", FIXTURE_CSV_FILENAME]);
+        assert_eq!(
+            Manifest::load(&output_dir).unwrap().detail_fields,
+            Some(detail_fields)
+        );
+
+        let options = BuilderOptions {
+            detail_fields: Some(vec!["surface".to_string()]),
+            ..BuilderOptions::default()
+        };
+        assert!(options.detail_field_indices().is_err());
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn transliterates_the_readings() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-reading-script-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        let options = BuilderOptions {
+            reading_script: Some(ReadingScript::Romaji),
+            ..BuilderOptions::default()
+        };
+        build_fixture(&input_dir, &output_dir, options).unwrap();
+        let reader = DictionaryReader::open(&output_dir).unwrap();
+        let details = reader.details(reader.lookup("東京")[0].word_id.0).unwrap();
+        assert_eq!(details[7], "toukyou");
+
+        fs::remove_dir_all(&output_dir).unwrap();
+        let options = BuilderOptions {
+            reading_script: Some(ReadingScript::Hiragana),
+            keep_original_reading: true,
+            ..BuilderOptions::default()
+        };
+        build_fixture(&input_dir, &output_dir, options).unwrap();
+        let reader = DictionaryReader::open(&output_dir).unwrap();
+        let details = reader.details(reader.lookup("東京")[0].word_id.0).unwrap();
+        assert_eq!(details[7], "トウキョウ");
+        assert_eq!(details.last().map(String::as_str), Some("とうきょう"));
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn fails_on_csv_files_without_entries() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-no-entries-{}",
+            std::process::id()
+        ));
+        let input_dir = base_dir.join("input");
+        write_fixture(&input_dir).unwrap();
+        let csv_file = input_dir.join(FIXTURE_CSV_FILENAME);
+        fs::write(&csv_file, "\n\n").unwrap();
+
+        for memory_budget in [None, Some(1)] {
+            let options = BuilderOptions {
+                memory_budget,
+                ..BuilderOptions::default()
+            };
+            let err = IpadicNeologdBuilder::with_options(options)
+                .build_dictionary(&input_dir, &base_dir.join("output"))
+                .unwrap_err();
+            assert!(
+                err.to_string().contains("CSV files of")
+                    && err.to_string().contains("have no entries"),
+                "{}",
+                err
+            );
+        }
+        let err = IpadicNeologdBuilder::new()
+            .build_user_dictionary(&csv_file, &base_dir.join("userdic.bin"))
+            .unwrap_err();
+        assert!(err.to_string().contains("have no entries"), "{}", err);
+        assert!(!base_dir.join("userdic.bin").exists());
+
+        fs::remove_dir_all(&base_dir).unwrap();
     }
 }
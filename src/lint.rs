@@ -0,0 +1,408 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::file_util::decode_source;
+use crate::{CsvRow, IpadicNeologdBuilder, CSV_FIELD_NAMES};
+
+/// The lowest word cost `AbsurdCost` accepts by default.
+pub const DEFAULT_MIN_LINT_COST: i32 = -20000;
+
+/// The highest word cost `AbsurdCost` accepts by default.
+pub const DEFAULT_MAX_LINT_COST: i32 = 20000;
+
+/// A check of the CSV entries. Rules only flag suspicious entries; the entries are still
+/// built.
+pub trait LintRule {
+    /// The name of the rule, reported with its findings.
+    fn name(&self) -> &str;
+
+    /// Checks the fields of a CSV entry line, in the order of `CSV_FIELD_NAMES`, returning
+    /// the problem found.
+    fn check(&self, fields: &[&str]) -> Option<String>;
+}
+
+/// Readings, other than `*`, with characters other than katakana.
+#[derive(Debug, Clone, Default)]
+pub struct ReadingNotKatakana;
+
+fn is_katakana(c: char) -> bool {
+    matches!(c, '\u{30A1}'..='\u{30FA}' | 'ー' | 'ヽ' | 'ヾ' | '・')
+}
+
+impl LintRule for ReadingNotKatakana {
+    fn name(&self) -> &str {
+        "reading-not-katakana"
+    }
+
+    fn check(&self, fields: &[&str]) -> Option<String> {
+        let reading = fields[11];
+        if reading == "*" {
+            return None;
+        }
+        reading
+            .chars()
+            .find(|c| !is_katakana(*c))
+            .map(|c| format!("reading {} has the non-katakana character {:?}", reading, c))
+    }
+}
+
+/// Conjugation types missing from verbs and adjectives, set on words that do not
+/// conjugate, or of adjectives that are not an adjective conjugation.
+#[derive(Debug, Clone, Default)]
+pub struct ConjugationMismatch;
+
+impl LintRule for ConjugationMismatch {
+    fn name(&self) -> &str {
+        "conjugation-mismatch"
+    }
+
+    fn check(&self, fields: &[&str]) -> Option<String> {
+        let (pos, conjugation_type) = (fields[4], fields[8]);
+        match (pos, conjugation_type) {
+            ("動詞" | "形容詞", "*") => Some(format!("{} without a conjugation type", pos)),
+            ("形容詞", _) if !conjugation_type.starts_with("形容詞") => Some(format!(
+                "{} with the conjugation type {}",
+                pos, conjugation_type
+            )),
+            ("動詞" | "形容詞" | "助動詞", _) | (_, "*") => None,
+            _ => Some(format!(
+                "{} with the conjugation type {}",
+                pos, conjugation_type
+            )),
+        }
+    }
+}
+
+/// Word costs outside `min..=max`.
+#[derive(Debug, Clone)]
+pub struct AbsurdCost {
+    pub min: i32,
+    pub max: i32,
+}
+
+impl Default for AbsurdCost {
+    fn default() -> Self {
+        AbsurdCost {
+            min: DEFAULT_MIN_LINT_COST,
+            max: DEFAULT_MAX_LINT_COST,
+        }
+    }
+}
+
+impl LintRule for AbsurdCost {
+    fn name(&self) -> &str {
+        "absurd-cost"
+    }
+
+    fn check(&self, fields: &[&str]) -> Option<String> {
+        let cost: i32 = fields[3].parse().ok()?;
+        if (self.min..=self.max).contains(&cost) {
+            return None;
+        }
+        Some(format!(
+            "word cost {} is out of the range {}..={}",
+            cost, self.min, self.max
+        ))
+    }
+}
+
+/// Fields with leading or trailing whitespace, including ideographic spaces, or with
+/// tabs.
+#[derive(Debug, Clone, Default)]
+pub struct SuspiciousWhitespace;
+
+impl LintRule for SuspiciousWhitespace {
+    fn name(&self) -> &str {
+        "suspicious-whitespace"
+    }
+
+    fn check(&self, fields: &[&str]) -> Option<String> {
+        fields.iter().enumerate().find_map(|(idx, field)| {
+            if field.trim() != *field || field.contains('\t') {
+                Some(format!(
+                    "{} {:?} has suspicious whitespace",
+                    CSV_FIELD_NAMES[idx], field
+                ))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Surfaces with control characters or invisible characters such as zero-width spaces.
+/// The zero-width joiner of emoji sequences is allowed.
+#[derive(Debug, Clone, Default)]
+pub struct ControlCharacters;
+
+fn is_invisible(c: char) -> bool {
+    c.is_control() || matches!(c, '\u{200B}' | '\u{200C}' | '\u{2060}' | '\u{FEFF}')
+}
+
+impl LintRule for ControlCharacters {
+    fn name(&self) -> &str {
+        "control-characters"
+    }
+
+    fn check(&self, fields: &[&str]) -> Option<String> {
+        fields[0].chars().find(|c| is_invisible(*c)).map(|c| {
+            format!(
+                "surface {:?} has the character U+{:04X}",
+                fields[0], c as u32
+            )
+        })
+    }
+}
+
+/// The rules a builder lints with unless given others.
+pub fn default_rules() -> Vec<Box<dyn LintRule + Send + Sync>> {
+    vec![
+        Box::new(ReadingNotKatakana),
+        Box::new(ConjugationMismatch),
+        Box::new(AbsurdCost::default()),
+        Box::new(SuspiciousWhitespace),
+        Box::new(ControlCharacters),
+    ]
+}
+
+/// An entry flagged by a lint rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintFinding {
+    pub file: PathBuf,
+    /// 1-based line number.
+    pub line: usize,
+    pub rule: String,
+    pub message: String,
+}
+
+/// The result of `IpadicNeologdBuilder::lint`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LintReport {
+    pub csv_files: usize,
+    pub rows: usize,
+    pub findings: Vec<LintFinding>,
+}
+
+impl LintReport {
+    pub fn is_clean(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// The number of findings of each rule.
+    pub fn counts(&self) -> BTreeMap<&str, usize> {
+        let mut counts = BTreeMap::new();
+        for finding in &self.findings {
+            *counts.entry(finding.rule.as_str()).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+impl IpadicNeologdBuilder {
+    /// Checks the CSV entries of the source files in `input_dir` with the lint rules of the
+    /// builder, without building anything. Unparsable lines are left to `validate`.
+    pub fn lint(&self, input_dir: &Path) -> LinderaResult<LintReport> {
+        let mut report = LintReport::default();
+        let normalizer = self.options.line_normalizer()?;
+        for (_, filename) in self.csv_sources(input_dir)? {
+            report.csv_files += 1;
+            let bytes = fs::read(&filename)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            let file_data = decode_source(&filename, bytes)?;
+            for (line_num, line) in self.csv_lines(&file_data) {
+                let line = normalizer.normalize(line);
+                if CsvRow::from_line(&line).is_ok() {
+                    report.rows += 1;
+                    self.lint_line(&filename, line_num, &line, &mut report);
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Adds the findings of the lint rules for the CSV entry `line` to `report`.
+    pub(crate) fn lint_line(
+        &self,
+        file: &Path,
+        line_num: usize,
+        line: &str,
+        report: &mut LintReport,
+    ) {
+        let fields: Vec<&str> = line.split(',').collect();
+        for rule in &self.lint_rules {
+            if let Some(message) = rule.check(&fields) {
+                report.findings.push(LintFinding {
+                    file: file.to_path_buf(),
+                    line: line_num,
+                    rule: rule.name().to_string(),
+                    message,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{
+        AbsurdCost, ConjugationMismatch, ControlCharacters, LintRule, ReadingNotKatakana,
+        SuspiciousWhitespace,
+    };
+    use crate::fixtures::write_fixture;
+    use crate::IpadicNeologdBuilder;
+
+    fn fields(line: &str) -> Vec<&str> {
+        line.split(',').collect()
+    }
+
+    /// Flags every entry of the surface `東京`.
+    struct NoTokyo;
+
+    impl LintRule for NoTokyo {
+        fn name(&self) -> &str {
+            "no-tokyo"
+        }
+
+        fn check(&self, fields: &[&str]) -> Option<String> {
+            if fields[0] == "東京" {
+                Some("東京".to_string())
+            } else {
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn flags_suspicious_entries() {
+        let check = |rule: &dyn LintRule, line: &str| rule.check(&fields(line));
+        assert_eq!(
+            check(&ReadingNotKatakana, "a,1,1,0,名詞,*,*,*,*,*,a,とうきょう,*").as_deref(),
+            Some("reading とうきょう has the non-katakana character 'と'")
+        );
+        assert_eq!(
+            check(&ReadingNotKatakana, "a,1,1,0,名詞,*,*,*,*,*,a,ヴァー・ヽ,*"),
+            None
+        );
+        assert_eq!(
+            check(&ConjugationMismatch, "a,1,1,0,動詞,自立,*,*,*,*,a,ア,ア").as_deref(),
+            Some("動詞 without a conjugation type")
+        );
+        assert_eq!(
+            check(
+                &ConjugationMismatch,
+                "a,1,1,0,形容詞,自立,*,*,五段・カ行促音便,*,a,ア,ア"
+            )
+            .as_deref(),
+            Some("形容詞 with the conjugation type 五段・カ行促音便")
+        );
+        assert_eq!(
+            check(
+                &ConjugationMismatch,
+                "a,1,1,0,名詞,一般,*,*,サ変・スル,*,a,ア,ア"
+            )
+            .as_deref(),
+            Some("名詞 with the conjugation type サ変・スル")
+        );
+        assert_eq!(
+            check(
+                &ConjugationMismatch,
+                "a,1,1,0,助動詞,*,*,*,特殊・デス,*,a,ア,ア"
+            ),
+            None
+        );
+        assert_eq!(
+            check(
+                &AbsurdCost::default(),
+                "a,1,1,-20001,名詞,*,*,*,*,*,a,ア,ア"
+            )
+            .as_deref(),
+            Some("word cost -20001 is out of the range -20000..=20000")
+        );
+        assert_eq!(
+            check(&AbsurdCost::default(), "a,1,1,20000,名詞,*,*,*,*,*,a,ア,ア"),
+            None
+        );
+        assert_eq!(
+            check(
+                &SuspiciousWhitespace,
+                "a,1,1,0,名詞,*,*,*,*,*,a\u{3000},ア,ア"
+            )
+            .as_deref(),
+            Some("base_form \"a\\u{3000}\" has suspicious whitespace")
+        );
+        assert_eq!(
+            check(
+                &ControlCharacters,
+                "a\u{200B}b,1,1,0,名詞,*,*,*,*,*,a,ア,ア"
+            )
+            .as_deref(),
+            Some("surface \"a\\u{200b}b\" has the character U+200B")
+        );
+        assert_eq!(
+            check(
+                &ControlCharacters,
+                "👨\u{200D}💻,1,1,0,記号,*,*,*,*,*,a,*,*"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn lints_the_source_files() {
+        let input_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-lint-{}",
+            std::process::id()
+        ));
+        write_fixture(&input_dir).unwrap();
+        let report = IpadicNeologdBuilder::new().lint(&input_dir).unwrap();
+        assert!(report.is_clean(), "{:?}", report.findings);
+        assert_eq!((report.csv_files, report.rows), (1, 5));
+
+        fs::write(
+            input_dir.join("extra.csv"),
+            "渋谷,1,1,30000,名詞,固有名詞,地域,一般,*,*,渋谷,しぶや,シブヤ\nbroken\n東京,1,1,3000,名詞,固有名詞,地域,一般,*,*,東京,トウキョウ,トーキョー\n",
+        )
+        .unwrap();
+        let report = IpadicNeologdBuilder::new()
+            .with_lint_rule(NoTokyo)
+            .lint(&input_dir)
+            .unwrap();
+        assert_eq!((report.csv_files, report.rows), (2, 7));
+        let findings: Vec<(String, usize, &str)> = report
+            .findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding
+                        .file
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string(),
+                    finding.line,
+                    finding.rule.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                ("extra.csv".to_string(), 1, "reading-not-katakana"),
+                ("extra.csv".to_string(), 1, "absurd-cost"),
+                ("extra.csv".to_string(), 3, "no-tokyo"),
+                ("fixture.csv".to_string(), 1, "no-tokyo"),
+            ]
+        );
+        assert_eq!(report.counts()["no-tokyo"], 2);
+
+        fs::remove_dir_all(&input_dir).unwrap();
+    }
+}
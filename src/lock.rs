@@ -0,0 +1,154 @@
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::Path;
+
+use lindera_core::LinderaResult;
+
+use crate::error::BuildError;
+use crate::IpadicNeologdBuilder;
+
+/// The lock file of the output directory, held for the duration of a build.
+pub const LOCK_FILENAME: &str = ".lindera-build.lock";
+
+/// The advisory lock of an output directory, released when dropped, also if the build fails
+/// or its process dies.
+#[derive(Debug)]
+pub struct OutputLock {
+    file: File,
+}
+
+impl OutputLock {
+    /// Locks `output_dir`, recording the process id in the lock file. If another build holds
+    /// the lock, waits for it to be released when `wait` is set, and fails with
+    /// `BuildError::Locked` otherwise.
+    pub fn acquire(output_dir: &Path, wait: bool) -> LinderaResult<OutputLock> {
+        let path = output_dir.join(LOCK_FILENAME);
+        let io_error = |source| -> BuildError {
+            BuildError::Io {
+                path: Some(path.clone()),
+                source,
+            }
+        };
+        // Not truncated before the lock is taken, so that the process id of the holder is
+        // kept.
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(io_error)?;
+        match file.try_lock() {
+            Ok(()) => (),
+            Err(TryLockError::WouldBlock) => {
+                let pid = fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|pid| pid.trim().parse().ok());
+                if !wait {
+                    return Err(BuildError::Locked {
+                        output_dir: output_dir.to_path_buf(),
+                        pid,
+                    }
+                    .into());
+                }
+                println!(
+                    "waiting for the build in progress in {} to finish",
+                    output_dir.display()
+                );
+                file.lock().map_err(io_error)?;
+            }
+            Err(TryLockError::Error(err)) => return Err(io_error(err).into()),
+        }
+        file.set_len(0)
+            .and_then(|_| writeln!(file, "{}", std::process::id()))
+            .map_err(io_error)?;
+        Ok(OutputLock { file })
+    }
+}
+
+impl Drop for OutputLock {
+    fn drop(&mut self) {
+        // The lock file is left in place: removing it would let a waiting build lock the
+        // removed file while a new one locks a new file.
+        let _ = self.file.set_len(0);
+        let _ = self.file.unlock();
+    }
+}
+
+impl IpadicNeologdBuilder {
+    /// Locks `output_dir` for a build, waiting for another build holding it if
+    /// `BuilderOptions::wait_for_lock` is set.
+    pub(crate) fn lock_output(&self, output_dir: &Path) -> LinderaResult<OutputLock> {
+        OutputLock::acquire(output_dir, self.options.wait_for_lock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use lindera_core::dictionary_builder::DictionaryBuilder;
+
+    use super::{OutputLock, LOCK_FILENAME};
+    use crate::error::BuildError;
+    use crate::fixtures::write_fixture;
+    use crate::IpadicNeologdBuilder;
+
+    #[test]
+    fn locks_the_output_directory() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-lock-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        write_fixture(&input_dir).unwrap();
+        fs::create_dir_all(&output_dir).unwrap();
+
+        let lock = OutputLock::acquire(&output_dir, false).unwrap();
+        assert_eq!(
+            fs::read_to_string(output_dir.join(LOCK_FILENAME)).unwrap(),
+            format!("{}\n", std::process::id())
+        );
+        let err = OutputLock::acquire(&output_dir, false).unwrap_err();
+        match BuildError::downcast(&err) {
+            Some(BuildError::Locked {
+                output_dir: dir,
+                pid,
+            }) => {
+                assert_eq!(dir, &output_dir);
+                assert_eq!(*pid, Some(std::process::id()));
+            }
+            _ => panic!("{}", err),
+        }
+        let err = IpadicNeologdBuilder::new()
+            .build_dictionary(&input_dir, &output_dir)
+            .unwrap_err();
+        assert!(
+            matches!(BuildError::downcast(&err), Some(BuildError::Locked { .. })),
+            "{}",
+            err
+        );
+
+        // A waiting build takes the lock once it is released.
+        let (sender, receiver) = mpsc::channel();
+        let waiting_dir = output_dir.clone();
+        let waiting = thread::spawn(move || {
+            let lock = OutputLock::acquire(&waiting_dir, true);
+            sender.send(()).unwrap();
+            lock.map(drop)
+        });
+        assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+        drop(lock);
+        waiting.join().unwrap().unwrap();
+        assert_eq!(
+            fs::read_to_string(output_dir.join(LOCK_FILENAME)).unwrap(),
+            ""
+        );
+        OutputLock::acquire(&output_dir, false).unwrap();
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
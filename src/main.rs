@@ -1,13 +1,521 @@
-use std::path::Path;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use clap::{crate_authors, crate_description, crate_name, crate_version, App, AppSettings, Arg};
+use clap::{
+    crate_authors, crate_description, crate_name, crate_version, App, AppSettings, Arg, ArgMatches,
+    SubCommand,
+};
+
+use serde::Serialize;
 
 use lindera_core::dictionary_builder::DictionaryBuilder;
-use lindera_ipadic_neologd_builder::IpadicNeologdBuilder;
+use lindera_core::LinderaResult;
+use lindera_ipadic_neologd_builder::columnar::DETAIL_FIELDS;
+use lindera_ipadic_neologd_builder::env_overrides::{
+    CONFIG_ENV, INPUT_DIR_ENV, MAX_COMPRESSION_LEVEL, OUTPUT_DIR_ENV,
+};
+#[cfg(feature = "signing")]
+use lindera_ipadic_neologd_builder::signature::SigningKey;
+use lindera_ipadic_neologd_builder::subset::load_terms;
+use lindera_ipadic_neologd_builder::{
+    dump_chardef, dump_unk, subset_dictionary, BuildProfile, BuilderOptions, DetailFormat,
+    DictionaryReader, DiskSpaceCheck, DuplicatePolicy, Endianness, IpadicNeologdBuilder,
+    KanaVariants, LinderaTarget, LintReport, NormalizationStep, PlaceholderHandling, PosMapping,
+    PrometheusTextfile, PruningStrategy, ReadingScript, ReadingTable, SourceLayer, SurfaceFolding,
+    ValuePacking, WarningKind, CSV_FIELD_NAMES,
+};
 
-fn main() {
+fn parse_category_value<T: FromStr>(value: &str) -> (String, T) {
+    let parsed = value
+        .split_once('=')
+        .and_then(|(category, value)| Some((category.to_string(), value.parse().ok()?)));
+    match parsed {
+        Some(parsed) => parsed,
+        None => {
+            println!("invalid value {}, expected CATEGORY=VALUE", value);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parses a chain of normalization steps joined with `+`, such as `nfkc+width-folding`.
+fn parse_normalization_chain(value: &str) -> Option<Vec<NormalizationStep>> {
+    value
+        .split('+')
+        .map(|step| match step {
+            "nfkc" => Some(NormalizationStep::Nfkc),
+            "width-folding" => Some(NormalizationStep::WidthFolding),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Parses a size in bytes with an optional `K`, `M` or `G` suffix.
+fn parse_size(value: &str) -> Option<u64> {
+    let (digits, unit) = match value.char_indices().last()? {
+        (idx, 'K') | (idx, 'k') => (&value[..idx], 1 << 10),
+        (idx, 'M') | (idx, 'm') => (&value[..idx], 1 << 20),
+        (idx, 'G') | (idx, 'g') => (&value[..idx], 1 << 30),
+        _ => (value, 1),
+    };
+    digits.parse::<u64>().ok()?.checked_mul(unit)
+}
+
+fn print_dump<T, E>(matches: &ArgMatches, dump: Result<T, E>)
+where
+    T: Display + Serialize,
+    E: Display,
+{
+    let dump = match dump {
+        Ok(dump) => dump,
+        Err(msg) => {
+            println!("{}", msg);
+            std::process::exit(1);
+        }
+    };
+    if matches.is_present("JSON") {
+        match serde_json::to_string_pretty(&dump) {
+            Ok(json) => println!("{}", json),
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print!("{}", dump);
+    }
+}
+
+fn print_lookup(dict_dir: &Path, surface: &str) -> LinderaResult<()> {
+    let reader = DictionaryReader::open(dict_dir)?;
+    for word_entry in reader.lookup(surface) {
+        let details = reader.details(word_entry.word_id.0)?;
+        println!(
+            "{}\t{},{},{}\t{}",
+            surface,
+            word_entry.left_id(),
+            word_entry.right_id(),
+            word_entry.word_cost,
+            details.join(",")
+        );
+    }
+    Ok(())
+}
+
+fn print_lint_report(report: &LintReport) {
+    for finding in &report.findings {
+        println!(
+            "{}:{}: [{}] {}",
+            finding.file.display(),
+            finding.line,
+            finding.rule,
+            finding.message
+        );
+    }
+    for (rule, count) in report.counts() {
+        println!("{}\t{}", rule, count);
+    }
+    println!(
+        "{} files, {} rows, {} findings",
+        report.csv_files,
+        report.rows,
+        report.findings.len()
+    );
+}
+
+/// Overrides the options loaded from the config file or profile with the flags present on
+/// the command line.
+fn apply_matches(options: &mut BuilderOptions, matches: &ArgMatches) {
+    if let Some(prefix) = matches.value_of("COMMENT_PREFIX") {
+        options.comment_prefix = Some(prefix.to_string());
+    }
+    if let Some(dirs) = matches.values_of("INPUT") {
+        options.extra_input_dirs = dirs.map(PathBuf::from).collect();
+    }
+    if let Some(char_overrides) = matches.value_of("CHAR_OVERRIDES") {
+        options.char_overrides = Some(PathBuf::from(char_overrides));
+    }
+    for value in matches.values_of("UNK_COST").into_iter().flatten() {
+        let (category, cost) = parse_category_value(value);
+        options.unk_costs.entry(category).or_default().cost = Some(cost);
+    }
+    for value in matches.values_of("UNK_COST_SCALE").into_iter().flatten() {
+        let (category, scale) = parse_category_value(value);
+        options.unk_costs.entry(category).or_default().scale = Some(scale);
+    }
+    match matches.value_of("DUPLICATE_POLICY") {
+        Some("override") => options.duplicate_policy = DuplicatePolicy::Override,
+        Some("keep-lowest-cost") => options.duplicate_policy = DuplicatePolicy::KeepLowestCost,
+        Some("keep-all") => options.duplicate_policy = DuplicatePolicy::KeepAll,
+        _ => (),
+    }
+    for value in matches
+        .values_of("LAYER_COST_SCALING")
+        .into_iter()
+        .flatten()
+    {
+        let (layer, scaling) = parse_category_value(value);
+        options.layer_cost_scaling.insert(layer, scaling);
+    }
+    if let Some(fields) = matches.values_of("NORMALIZE_FIELD") {
+        options.normalized_fields = Some(fields.map(String::from).collect());
+    }
+    for value in matches.values_of("FIELD_NORMALIZATION").unwrap_or_default() {
+        let parsed = value.split_once('=').and_then(|(field, chain)| {
+            Some((field.to_string(), parse_normalization_chain(chain)?))
+        });
+        match parsed {
+            Some((field, chain)) => {
+                options.field_normalization.insert(field, chain);
+            }
+            None => {
+                println!("invalid value {}, expected FIELD=STEPS", value);
+                std::process::exit(1);
+            }
+        }
+    }
+    options.fill_readings |= matches.is_present("FILL_READINGS");
+    if let Some(files) = matches.values_of("PATCH") {
+        options.patch_files = files.map(PathBuf::from).collect();
+    }
+    options.reading_index |= matches.is_present("READING_INDEX");
+    options.columnar_details |= matches.is_present("COLUMNAR_DETAILS");
+    options.mmap_container |= matches.is_present("MMAP_CONTAINER");
+    if matches.is_present("ELASTICSEARCH") {
+        let mut export = options.elasticsearch_export.take().unwrap_or_default();
+        if let Some(pos) = matches.value_of("ELASTICSEARCH_POS") {
+            export.pos_prefix = pos.split(',').map(String::from).collect();
+        }
+        export.neologd_only |= matches.is_present("ELASTICSEARCH_NEOLOGD_ONLY");
+        options.elasticsearch_export = Some(export);
+    }
+    if let Some(preferred_terms) = matches.value_of("PREFERRED_TERMS") {
+        options.preferred_terms = Some(PathBuf::from(preferred_terms));
+    }
+    if let Some(cost_delta) = matches.value_of("PREFERRED_COST_DELTA") {
+        match cost_delta.parse() {
+            Ok(cost_delta) => options.preferred_cost_delta = Some(cost_delta),
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(frequency_file) = matches.value_of("FREQUENCY_FILE") {
+        options.frequency_file = Some(PathBuf::from(frequency_file));
+    }
+    if let Some(cost_scale) = matches.value_of("FREQUENCY_COST_SCALE") {
+        match cost_scale.parse() {
+            Ok(cost_scale) => options.frequency_cost_scale = Some(cost_scale),
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(reference_dict_dir) = matches.value_of("REFERENCE_DICT") {
+        options.reference_dict_dir = Some(PathBuf::from(reference_dict_dir));
+    }
+    options.emoji_entries |= matches.is_present("EMOJI");
+    options.kaomoji_entries |= matches.is_present("KAOMOJI");
+    if let Some(symbol_cost) = matches.value_of("SYMBOL_COST") {
+        match symbol_cost.parse() {
+            Ok(symbol_cost) => options.symbol_cost = Some(symbol_cost),
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    options.numeric_entries |= matches.is_present("NUMERIC");
+    if let Some(numeric_cost) = matches.value_of("NUMERIC_COST") {
+        match numeric_cost.parse() {
+            Ok(numeric_cost) => options.numeric_cost = Some(numeric_cost),
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(variant_rules) = matches.value_of("VARIANT_RULES") {
+        options.variant_rules = Some(PathBuf::from(variant_rules));
+    }
+    match matches.value_of("KANA_VARIANTS") {
+        Some("hiragana") => options.kana_variants = Some(KanaVariants::Hiragana),
+        Some("katakana") => options.kana_variants = Some(KanaVariants::Katakana),
+        Some("both") => options.kana_variants = Some(KanaVariants::Both),
+        _ => (),
+    }
+    if let Some(penalty) = matches.value_of("KANA_VARIANT_PENALTY") {
+        match penalty.parse() {
+            Ok(penalty) => options.kana_variant_penalty = Some(penalty),
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(shard_size) = matches.value_of("SHARD_SIZE") {
+        match shard_size.parse() {
+            Ok(shard_size) => options.shard_size = Some(shard_size),
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(pos_mapping) = matches.value_of("POS_MAPPING") {
+        match PosMapping::load(Path::new(pos_mapping)) {
+            Ok(pos_mapping) => options.pos_mapping = Some(pos_mapping),
+            Err(msg) => {
+                println!("{}", msg);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(skip_rules_file) = matches.value_of("SKIP_RULES") {
+        let skip_rules = std::fs::read(skip_rules_file)
+            .map_err(|err| err.to_string())
+            .and_then(|data| serde_json::from_slice(&data).map_err(|err| err.to_string()));
+        match skip_rules {
+            Ok(skip_rules) => options.skip_rules = Some(skip_rules),
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    options.provenance |= matches.is_present("PROVENANCE");
+    match matches.value_of("SURFACE_FOLDING") {
+        Some("lowercase") => options.surface_folding = Some(SurfaceFolding::Lowercase),
+        Some("half-width") => options.surface_folding = Some(SurfaceFolding::HalfWidth),
+        Some("both") => options.surface_folding = Some(SurfaceFolding::Both),
+        _ => (),
+    }
+    options.neologd_flag |= matches.is_present("NEOLOGD_FLAG");
+    match matches.value_of("READING_SCRIPT") {
+        Some("hiragana") => options.reading_script = Some(ReadingScript::Hiragana),
+        Some("katakana") => options.reading_script = Some(ReadingScript::Katakana),
+        Some("romaji") => options.reading_script = Some(ReadingScript::Romaji),
+        _ => (),
+    }
+    options.keep_original_reading |= matches.is_present("KEEP_ORIGINAL_READING");
+    if let Some(fields) = matches.values_of("DETAIL_FIELD") {
+        options.detail_fields = Some(fields.map(String::from).collect());
+    }
+    match matches.value_of("PLACEHOLDERS") {
+        Some("keep") => options.placeholders = PlaceholderHandling::Keep,
+        Some("empty") => options.placeholders = PlaceholderHandling::Empty,
+        Some("drop-trailing") => options.placeholders = PlaceholderHandling::DropTrailing,
+        _ => (),
+    }
+    options.compound_splits |= matches.is_present("COMPOUND_SPLITS");
+    match matches.value_of("DETAIL_FORMAT") {
+        Some("bincode") => options.detail_format = DetailFormat::Bincode,
+        Some("compact") => options.detail_format = DetailFormat::Compact,
+        Some("json") => options.detail_format = DetailFormat::Json,
+        _ => (),
+    }
+    match matches.value_of("ENDIANNESS") {
+        Some("little") => options.endianness = Endianness::Little,
+        Some("big") => options.endianness = Endianness::Big,
+        _ => (),
+    }
+    options.artifact_headers |= matches.is_present("ARTIFACT_HEADERS");
+    match matches.value_of("LINDERA_TARGET") {
+        Some("0.8") => options.lindera_target = LinderaTarget::V0_8,
+        Some("0.3") => options.lindera_target = LinderaTarget::V0_3,
+        _ => (),
+    }
+    match matches.value_of("VALUE_PACKING") {
+        Some("compact") => options.value_packing = ValuePacking::Compact,
+        Some("indirect") => options.value_packing = ValuePacking::Indirect,
+        _ => (),
+    }
+    if let Some(dictionary_version) = matches.value_of("DICTIONARY_VERSION") {
+        options.dictionary_version = Some(dictionary_version.to_string());
+    }
+    if let Some(layout_file) = matches.value_of("LAYOUT") {
+        let layout = std::fs::read(layout_file)
+            .map_err(|err| err.to_string())
+            .and_then(|data| serde_json::from_slice(&data).map_err(|err| err.to_string()));
+        match layout {
+            Ok(layout) => options.layout = layout,
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(threads) = matches.value_of("THREADS") {
+        match threads.parse() {
+            Ok(threads) => options.threads = Some(threads),
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(memory_budget) = matches.value_of("MEMORY_BUDGET") {
+        match parse_size(memory_budget) {
+            Some(memory_budget) => options.memory_budget = Some(memory_budget),
+            None => {
+                println!("invalid size {}", memory_budget);
+                std::process::exit(1);
+            }
+        }
+    }
+    match matches.value_of("DISK_SPACE_CHECK") {
+        Some("fail") => options.disk_space_check = DiskSpaceCheck::Fail,
+        Some("warn") => options.disk_space_check = DiskSpaceCheck::Warn,
+        Some("skip") => options.disk_space_check = DiskSpaceCheck::Skip,
+        _ => (),
+    }
+    if let Some(scratch_dir) = matches.value_of("SCRATCH_DIR") {
+        options.scratch_dir = Some(PathBuf::from(scratch_dir));
+    }
+    options.wait_for_lock |= matches.is_present("WAIT");
+    if let Some(commands) = matches.values_of("POST_BUILD_HOOK") {
+        options
+            .post_build_hooks
+            .extend(commands.map(|command| command.to_string()));
+    }
+    if let Some(io_buffer_size) = matches.value_of("IO_BUFFER_SIZE") {
+        match parse_size(io_buffer_size) {
+            Some(io_buffer_size) => options.io_buffer_size = Some(io_buffer_size as usize),
+            None => {
+                println!("invalid size {}", io_buffer_size);
+                std::process::exit(1);
+            }
+        }
+    }
+    options.da_spill |= matches.is_present("DA_SPILL");
+    options.trie_stats |= matches.is_present("TRIE_STATS");
+    options.sort_homonyms |= matches.is_present("SORT_HOMONYMS");
+    options.paranoid |= matches.is_present("PARANOID");
+    if let Some(keyset_export) = matches.value_of("KEYSET_EXPORT") {
+        options.keyset_export = Some(PathBuf::from(keyset_export));
+    }
+    for value in matches.values_of("WARNING").into_iter().flatten() {
+        let (kind, action) = parse_category_value(value);
+        match WarningKind::from_str(&kind) {
+            Ok(kind) => {
+                options.warnings.insert(kind, action);
+            }
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(warning_limit) = matches.value_of("WARNING_LIMIT") {
+        match warning_limit.parse() {
+            Ok(warning_limit) => options.warning_limit = Some(warning_limit),
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(max_errors) = matches.value_of("MAX_ERRORS") {
+        match max_errors.parse() {
+            Ok(max_errors) => options.max_errors = Some(max_errors),
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    options.lint |= matches.is_present("LINT");
+    options.build_log |= matches.is_present("BUILD_LOG");
+    if let Some(size_budget) = matches.value_of("SIZE_BUDGET") {
+        match parse_size(size_budget) {
+            Some(size_budget) => options.size_budget = Some(size_budget),
+            None => {
+                println!("invalid size {}", size_budget);
+                std::process::exit(1);
+            }
+        }
+    }
+    match matches.value_of("PRUNING") {
+        Some("highest-cost") => options.pruning = Some(PruningStrategy::HighestCost),
+        Some("longest-surface") => options.pruning = Some(PruningStrategy::LongestSurface),
+        _ => (),
+    }
+    if let Some(max_word_cost) = matches.value_of("MAX_WORD_COST") {
+        match max_word_cost.parse() {
+            Ok(max_word_cost) => options.max_word_cost = Some(max_word_cost),
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(codegen_dir) = matches.value_of("CODEGEN_DIR") {
+        options.codegen_dir = Some(PathBuf::from(codegen_dir));
+    }
+    options.codegen_compress |= matches.is_present("CODEGEN_COMPRESS");
+    if let Some(level) = matches.value_of("COMPRESSION_LEVEL") {
+        match level.parse() {
+            Ok(level) if level <= MAX_COMPRESSION_LEVEL => options.compression_level = Some(level),
+            Ok(level) => {
+                println!(
+                    "compression level {} is above {}",
+                    level, MAX_COMPRESSION_LEVEL
+                );
+                std::process::exit(1);
+            }
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    options.package |= matches.is_present("PACKAGE");
+
+    #[cfg(feature = "signing")]
+    {
+        if let Some(key_file) = matches.value_of("SIGNING_KEY") {
+            let key_hex = match std::fs::read_to_string(key_file) {
+                Ok(key_hex) => key_hex,
+                Err(err) => {
+                    println!("{}", err);
+                    std::process::exit(1);
+                }
+            };
+            match hex::decode(key_hex.trim()) {
+                Ok(key) => options.signing_key = Some(SigningKey(key)),
+                Err(err) => {
+                    println!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "quality-check")]
+    {
+        if let Some(corpus_file) = matches.value_of("CORPUS") {
+            options.corpus_file = Some(PathBuf::from(corpus_file));
+        }
+        if let Some(previous_dict_dir) = matches.value_of("PREVIOUS_DICT") {
+            options.previous_dict_dir = Some(PathBuf::from(previous_dict_dir));
+        }
+    }
+
+    #[cfg(feature = "object-store")]
+    {
+        if let Some(sink_url) = matches.value_of("SINK_URL") {
+            options.sink_url = Some(sink_url.to_string());
+        }
+    }
+}
+
+fn app() -> App<'static, 'static> {
     let app = App::new(crate_name!())
         .setting(AppSettings::DeriveDisplayOrder)
+        .setting(AppSettings::SubcommandsNegateReqs)
         .version(crate_version!())
         .author(crate_authors!())
         .about(crate_description!())
@@ -18,6 +526,7 @@ fn main() {
             Arg::with_name("INPUT_DIR")
                 .help("The directory where the IPADIC source containing.")
                 .value_name("INPUT_DIR")
+                .env(INPUT_DIR_ENV)
                 .required(true)
                 .takes_value(true),
         )
@@ -25,19 +534,1094 @@ fn main() {
             Arg::with_name("OUTPUT_DIR")
                 .help("The directory where the IPADIC binary for Lindera is output.")
                 .value_name("OUTPUT_DIR")
-                .required(true)
+                .env(OUTPUT_DIR_ENV)
+                .required_unless_one(&["VALIDATE", "LINT", "DRY_RUN", "INVENTORY"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("BASE_OUTPUT_DIR")
+                .help("Additionally writes the dictionary without the NEologd seed files to this directory.")
+                .long("base-output")
+                .value_name("DIR")
                 .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("REFERENCE_DICT")
+                .help("Leaves out the entries whose surface and POS are in the dictionary built in this directory, building only the delta to it.")
+                .long("reference-dict")
+                .value_name("DIR")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("CONFIG")
+                .help("A TOML or YAML file of builder options the options start from, overridden by the other options.")
+                .long("config")
+                .value_name("FILE")
+                .env(CONFIG_ENV)
+                .takes_value(true)
+                .conflicts_with("PROFILE"),
+        )
+        .arg(
+            Arg::with_name("PROFILE")
+                .help("The preset the options start from, overridden by the other options.")
+                .long("profile")
+                .value_name("PROFILE")
+                .possible_values(&["search", "nlp", "embedded"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("COMMENT_PREFIX")
+                .help("Skips lines of CSV files starting with this prefix.")
+                .long("comment-prefix")
+                .value_name("PREFIX")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("CHAR_OVERRIDES")
+                .help("A file merged over char.def. Defaults to char_overrides.def in INPUT_DIR if it exists.")
+                .long("char-overrides")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("UNK_COST")
+                .help("Overrides the unknown word costs of a character category, e.g. KATAKANA=8000. Can be repeated.")
+                .long("unk-cost")
+                .value_name("CATEGORY=COST")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("UNK_COST_SCALE")
+                .help("Scales the unknown word costs of a character category, e.g. KATAKANA=1.5. Can be repeated.")
+                .long("unk-cost-scale")
+                .value_name("CATEGORY=FACTOR")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("INPUT")
+                .help("An additional directory whose CSV files are built together with those of INPUT_DIR. Can be repeated.")
+                .long("input")
+                .value_name("DIR")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("LAYER")
+                .help("An additional source directory merged over INPUT_DIR. Can be repeated; later layers take precedence.")
+                .long("layer")
+                .value_name("DIR")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("DUPLICATE_POLICY")
+                .help("How entries with the same surface and POS in several layers are resolved. Defaults to override.")
+                .long("duplicate-policy")
+                .value_name("POLICY")
+                .possible_values(&["override", "keep-lowest-cost", "keep-all"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("LAYER_COST_SCALING")
+                .help("Rescales the word costs of a layer to the scale of INPUT_DIR before merging, with percentile or affine:SCALE[:OFFSET], e.g. ./company-terms=percentile. Can be repeated.")
+                .long("layer-cost-scaling")
+                .value_name("LAYER=SCALING")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("NORMALIZE_FIELD")
+                .help("A CSV field in which ― and ～ are normalized, such as surface or reading. Defaults to the surface.")
+                .long("normalize-field")
+                .value_name("FIELD")
+                .possible_values(&CSV_FIELD_NAMES)
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("FIELD_NORMALIZATION")
+                .help("The normalization steps of a CSV field, nfkc and width-folding joined with +, e.g. surface=nfkc.")
+                .long("field-normalization")
+                .value_name("FIELD=STEPS")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("FILL_READINGS")
+                .help("Fills readings left as * from kana-only surfaces and the reading table.")
+                .long("fill-readings"),
+        )
+        .arg(
+            Arg::with_name("READING_TABLE")
+                .help("A file of tab-separated surfaces and readings used by --fill-readings.")
+                .long("reading-table")
+                .value_name("FILE")
+                .requires("FILL_READINGS")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("PATCH")
+                .help("A patch file of +row, -surface[,pos] and ~surface[,pos] cost lines applied before building. Can be repeated.")
+                .long("patch")
+                .value_name("FILE")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("PREFERRED_TERMS")
+                .help("A file of preferred surfaces, one per line, whose entries get their word cost lowered.")
+                .long("preferred-terms")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("PREFERRED_COST_DELTA")
+                .help("The word cost subtracted from the entries of preferred terms. Defaults to 1000.")
+                .long("preferred-cost-delta")
+                .value_name("NUM")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("FREQUENCY_FILE")
+                .help("A term-frequency file of a target corpus, term<TAB>count per line, from which the word costs of its terms are recomputed.")
+                .long("frequency-file")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("FREQUENCY_COST_SCALE")
+                .help("The word cost per unit of negative log relative frequency. Defaults to 700.")
+                .long("frequency-cost-scale")
+                .value_name("NUM")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("KANA_VARIANTS")
+                .help("Generates entries of kana-only surfaces written in the other kana script.")
+                .long("kana-variants")
+                .value_name("SCRIPT")
+                .possible_values(&["hiragana", "katakana", "both"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("KANA_VARIANT_PENALTY")
+                .help("The word cost added to the generated kana variants. Defaults to 500.")
+                .long("kana-variant-penalty")
+                .value_name("NUM")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("VARIANT_RULES")
+                .help("A file of tab-separated rules expanding the entries into spelling variations of their surface.")
+                .long("variant-rules")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("EMOJI")
+                .help("Adds entries for emoji and emoji sequences.")
+                .long("emoji"),
+        )
+        .arg(
+            Arg::with_name("KAOMOJI")
+                .help("Adds entries for common kaomoji.")
+                .long("kaomoji"),
+        )
+        .arg(
+            Arg::with_name("SYMBOL_COST")
+                .help("The word cost of the emoji and kaomoji entries. Defaults to 2000.")
+                .long("symbol-cost")
+                .value_name("NUM")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("NUMERIC")
+                .help("Adds entries for numbers with common counters and for years.")
+                .long("numeric"),
+        )
+        .arg(
+            Arg::with_name("NUMERIC_COST")
+                .help("The word cost of the numeric entries. Defaults to 3000.")
+                .long("numeric-cost")
+                .value_name("NUM")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("READING_INDEX")
+                .help("Additionally writes an index from readings to surfaces.")
+                .long("reading-index"),
+        )
+        .arg(
+            Arg::with_name("COLUMNAR_DETAILS")
+                .help("Additionally writes the word details as one column per field.")
+                .long("columnar-details"),
+        )
+        .arg(
+            Arg::with_name("ELASTICSEARCH")
+                .help("Additionally writes the entries as a kuromoji user dictionary and a synonym file for Elasticsearch.")
+                .long("elasticsearch"),
+        )
+        .arg(
+            Arg::with_name("ELASTICSEARCH_POS")
+                .help("Exports only the entries whose POS levels start with these, joined with commas, e.g. 名詞,固有名詞.")
+                .long("elasticsearch-pos")
+                .value_name("POS")
+                .requires("ELASTICSEARCH")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ELASTICSEARCH_NEOLOGD_ONLY")
+                .help("Exports only the entries from NEologd seed files.")
+                .long("elasticsearch-neologd-only")
+                .requires("ELASTICSEARCH"),
+        )
+        .arg(
+            Arg::with_name("MMAP_CONTAINER")
+                .help("Additionally writes the artifacts into dict.mmap, page-aligned for mapping them without a load step.")
+                .long("mmap-container"),
+        )
+        .arg(
+            Arg::with_name("SHARD_SIZE")
+                .help("Additionally splits the word details and entries into shards of this many words.")
+                .long("shard-size")
+                .value_name("WORDS")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("POS_MAPPING")
+                .help("A JSON file of POS mapping rules applied to the word details.")
+                .long("pos-mapping")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("SKIP_RULES")
+                .help("A JSON file of rules leaving entries out of the dictionary by surface, POS and reading.")
+                .long("skip-rules")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("PROVENANCE")
+                .help("Appends the source file of each entry to its word details.")
+                .long("provenance"),
+        )
+        .arg(
+            Arg::with_name("SURFACE_FOLDING")
+                .help("Folds the surfaces, keeping the original surface in the word details.")
+                .long("surface-folding")
+                .value_name("FOLDING")
+                .possible_values(&["lowercase", "half-width", "both"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("NEOLOGD_FLAG")
+                .help("Appends 1 to the word details of the entries from NEologd seed files and 0 to the others.")
+                .long("neologd-flag"),
+        )
+        .arg(
+            Arg::with_name("READING_SCRIPT")
+                .help("Transliterates the readings to this script during the build.")
+                .long("reading-script")
+                .value_name("SCRIPT")
+                .possible_values(&["hiragana", "katakana", "romaji"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("KEEP_ORIGINAL_READING")
+                .help("Keeps the original reading and appends the transliterated one to the word details.")
+                .long("keep-original-reading"),
+        )
+        .arg(
+            Arg::with_name("DETAIL_FIELD")
+                .help("A detail field written to the word details, such as pos_level1 or reading. Defaults to all of them.")
+                .long("detail-field")
+                .value_name("FIELD")
+                .possible_values(&DETAIL_FIELDS)
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("PLACEHOLDERS")
+                .help("How the * placeholders of empty fields are written to the word details. Lindera expects keep, the default.")
+                .long("placeholders")
+                .value_name("HANDLING")
+                .possible_values(&["keep", "empty", "drop-trailing"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("COMPOUND_SPLITS")
+                .help("Appends the split points of compound surfaces into shorter entries to the word details.")
+                .long("compound-splits"),
+        )
+        .arg(
+            Arg::with_name("DETAIL_FORMAT")
+                .help("The encoding of the word details. Lindera loads bincode, the default.")
+                .long("detail-format")
+                .value_name("FORMAT")
+                .possible_values(&["bincode", "compact", "json"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ENDIANNESS")
+                .help("The byte order of the integers of the binary artifacts, recorded in the manifest. Lindera loads little, the default.")
+                .long("endianness")
+                .value_name("ORDER")
+                .possible_values(&["little", "big"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ARTIFACT_HEADERS")
+                .help("Prepends a header with a magic, the format version and flags to each artifact. Lindera cannot load such artifacts.")
+                .long("artifact-headers"),
+        )
+        .arg(
+            Arg::with_name("LINDERA_TARGET")
+                .help("The lindera version the artifacts are written for, recorded in the manifest. 0.3 writes the word details as a struct.")
+                .long("lindera-target")
+                .value_name("VERSION")
+                .possible_values(&["0.8", "0.3"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("VALUE_PACKING")
+                .help("How the entries of the surfaces are packed into the values of the double array. indirect allows any number of entries with a value table lindera does not load.")
+                .long("value-packing")
+                .value_name("PACKING")
+                .possible_values(&["compact", "indirect"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("DICTIONARY_VERSION")
+                .help("The version of the lexicon, e.g. the NEologd seed tag, recorded in the manifest and version.txt, and naming the package.")
+                .long("dictionary-version")
+                .value_name("VERSION")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("LAYOUT")
+                .help("A JSON file with the names of the output files, e.g. {\"dict_da\": \"dict/da.bin\"}.")
+                .long("layout")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("THREADS")
+                .help("The number of threads used for parsing. Defaults to the available parallelism.")
+                .long("threads")
+                .value_name("NUM")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("MEMORY_BUDGET")
+                .help("The approximate memory budget of the build in bytes, with an optional K, M or G suffix.")
+                .long("memory-budget")
+                .value_name("SIZE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("DISK_SPACE_CHECK")
+                .help("What to do when the output filesystem lacks the estimated disk space: fail before building, warn, or skip the estimate.")
+                .long("disk-space-check")
+                .value_name("ACTION")
+                .possible_values(&["fail", "warn", "skip"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("SCRATCH_DIR")
+                .help("The directory the temporary files of the build are created in. Defaults to the output directory.")
+                .long("scratch-dir")
+                .value_name("DIR")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("WAIT")
+                .help("Waits for another build into the output directory to finish instead of failing.")
+                .long("wait"),
+        )
+        .arg(
+            Arg::with_name("POST_BUILD_HOOK")
+                .help("A shell command run once the dictionary is built, receiving the build report as JSON on its standard input. Can be repeated.")
+                .long("post-build-hook")
+                .value_name("COMMAND")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("IO_BUFFER_SIZE")
+                .help("The size of the read and write buffers of the large files in bytes, with an optional K, M or G suffix.")
+                .long("io-buffer-size")
+                .value_name("SIZE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("DA_SPILL")
+                .help("Builds the double array in the memory-mapped dict.da with bounded memory.")
+                .long("da-spill"),
+        )
+        .arg(
+            Arg::with_name("TRIE_STATS")
+                .help("Prints the statistics of the trie and the fill rate of the double array.")
+                .long("trie-stats"),
+        )
+        .arg(
+            Arg::with_name("SORT_HOMONYMS")
+                .help("Orders the entries of each surface by word cost, POS and reading instead of their input order.")
+                .long("sort-homonyms"),
+        )
+        .arg(
+            Arg::with_name("PARANOID")
+                .help("Reads dict.da and dict.vals back after writing them and checks them against the keyset.")
+                .long("paranoid"),
+        )
+        .arg(
+            Arg::with_name("KEYSET_EXPORT")
+                .help("The file the sorted keyset of the double array is written to.")
+                .long("keyset-export")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("SIZE_BUDGET")
+                .help("The maximum total size of the dictionary files in bytes, with an optional K, M or G suffix.")
+                .long("size-budget")
+                .value_name("SIZE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("MAX_WORD_COST")
+                .help("Drops the entries with a word cost above this.")
+                .long("max-word-cost")
+                .value_name("COST")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("PRUNING")
+                .help("Drops entries to fit the size budget: highest-cost or longest-surface first.")
+                .long("pruning")
+                .value_name("STRATEGY")
+                .takes_value(true)
+                .possible_values(&["highest-cost", "longest-surface"]),
+        )
+        .arg(
+            Arg::with_name("METRICS_TEXTFILE")
+                .help("A file the duration, I/O and memory of each build phase are written to in the Prometheus text format.")
+                .long("metrics-textfile")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("WARNING")
+                .help("How warnings of a kind are handled, one of ignore, warn or error, e.g. truncated_cost=error. Can be repeated.")
+                .long("warning")
+                .value_name("KIND=ACTION")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("WARNING_LIMIT")
+                .help("The maximum number of warnings of each kind printed.")
+                .long("warning-limit")
+                .value_name("NUM")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("MAX_ERRORS")
+                .help("Keeps parsing past unparsable lines and reports up to NUM errors together.")
+                .long("max-errors")
+                .value_name("NUM")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("BUILD_LOG")
+                .help("Writes a JSONL log of the build events to build.jsonl in the output directory.")
+                .long("build-log"),
+        )
+        .arg(
+            Arg::with_name("CODEGEN_DIR")
+                .help("The directory where dictionary.rs embedding the dictionary is generated.")
+                .long("codegen")
+                .value_name("DIR")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("CODEGEN_COMPRESS")
+                .help("Compresses the dictionary embedded by dictionary.rs.")
+                .long("codegen-compress")
+                .requires("CODEGEN_DIR"),
+        )
+        .arg(
+            Arg::with_name("COMPRESSION_LEVEL")
+                .help("The gzip level, 0 to 9, of the package and the dictionary embedded by dictionary.rs.")
+                .long("compression-level")
+                .value_name("LEVEL")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("PACKAGE")
+                .help("Packages the dictionary into a .tar.gz archive next to OUTPUT_DIR.")
+                .long("package"),
+        )
+        .arg(
+            Arg::with_name("VALIDATE")
+                .help("Validates the IPADIC source without building the dictionary.")
+                .long("validate"),
+        )
+        .arg(
+            Arg::with_name("LINT")
+                .help("Lints the CSV entries without building the dictionary, or as part of --validate.")
+                .long("lint"),
+        )
+        .arg(
+            Arg::with_name("DRY_RUN")
+                .help("Prints the files a build would write with their sizes, without writing anything.")
+                .long("dry-run"),
+        )
+        .arg(
+            Arg::with_name("INVENTORY")
+                .help("Lists the source files with their sizes, encodings and seed snapshot dates, without parsing them.")
+                .long("inventory"),
+        )
+        .subcommand(
+            SubCommand::with_name("dump-chardef")
+                .about("Prints the categories and code point ranges of char_def.bin.")
+                .arg(
+                    Arg::with_name("CHAR_DEF_FILE")
+                        .help("The char_def.bin file.")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("JSON")
+                        .help("Prints JSON instead of the char.def format.")
+                        .long("json"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("dump-unk")
+                .about("Prints the unknown word entries of unk.bin by character category.")
+                .arg(
+                    Arg::with_name("UNK_FILE")
+                        .help("The unk.bin file.")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("CHAR_DEF_FILE")
+                        .help("The char_def.bin file built together with UNK_FILE.")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("JSON")
+                        .help("Prints JSON instead of the unk.def format.")
+                        .long("json"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export-phonemes")
+                .about("Writes a pronunciation lexicon of the surfaces and the phonemes of their pronunciations.")
+                .arg(
+                    Arg::with_name("INPUT_DIR")
+                        .help("The directory where the IPADIC source containing.")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("OUTPUT_FILE")
+                        .help("The lexicon file to write.")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("lookup")
+                .about("Prints the entries of a surface in a built dictionary.")
+                .arg(
+                    Arg::with_name("DICT_DIR")
+                        .help("The directory of the built dictionary.")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("SURFACE")
+                        .help("The surface to look up.")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("subset")
+                .about("Writes the sub-dictionary of a built dictionary with only the entries of a list of surfaces and of their prefixes.")
+                .arg(
+                    Arg::with_name("DICT_DIR")
+                        .help("The directory of the built dictionary.")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("TERMS_FILE")
+                        .help("The file of the surfaces to keep, one per line.")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("OUTPUT_DIR")
+                        .help("The directory where the sub-dictionary is written.")
+                        .required(true),
+                ),
         );
 
-    let matches = app.get_matches();
+    #[cfg(feature = "signing")]
+    let app = app.arg(
+        Arg::with_name("SIGNING_KEY")
+            .help("The file containing a hex-encoded Ed25519 secret key to sign the dictionary.")
+            .long("signing-key")
+            .value_name("KEY_FILE")
+            .takes_value(true),
+    );
+
+    #[cfg(feature = "quality-check")]
+    let app = app
+        .arg(
+            Arg::with_name("CORPUS")
+                .help("A plain-text corpus tokenized with the built dictionary to report its quality.")
+                .long("corpus")
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("PREVIOUS_DICT")
+                .help("A previous build the tokenization of the corpus is compared against.")
+                .long("previous-dict")
+                .value_name("DIR")
+                .requires("CORPUS")
+                .takes_value(true),
+        );
+
+    #[cfg(feature = "object-store")]
+    let app = app.arg(
+        Arg::with_name("SINK_URL")
+            .help("The object storage URL (e.g. s3://bucket/prefix) to upload the dictionary to.")
+            .long("sink-url")
+            .value_name("URL")
+            .takes_value(true),
+    );
+
+    #[cfg(feature = "progress")]
+    let app = app.arg(
+        Arg::with_name("PROGRESS")
+            .help("Shows progress bars of the build steps.")
+            .long("progress"),
+    );
+
+    #[cfg(feature = "arrow-export")]
+    let app = app.subcommand(
+        SubCommand::with_name("export")
+            .about("Exports the entries of the IPADIC source as an Arrow or Parquet table.")
+            .arg(
+                Arg::with_name("INPUT_DIR")
+                    .help("The directory where the IPADIC source containing.")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("OUTPUT_FILE")
+                    .help("The table file to write.")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("FORMAT")
+                    .help("The table format.")
+                    .long("format")
+                    .value_name("FORMAT")
+                    .possible_values(&["arrow", "parquet"])
+                    .default_value("parquet")
+                    .takes_value(true),
+            ),
+    );
+    app
+}
+
+fn main() {
+    let matches = app().get_matches();
+
+    match matches.subcommand() {
+        ("dump-chardef", Some(sub_matches)) => {
+            let char_def_file = Path::new(sub_matches.value_of("CHAR_DEF_FILE").unwrap());
+            print_dump(sub_matches, dump_chardef(char_def_file));
+            return;
+        }
+        ("dump-unk", Some(sub_matches)) => {
+            let unk_file = Path::new(sub_matches.value_of("UNK_FILE").unwrap());
+            let char_def_file = Path::new(sub_matches.value_of("CHAR_DEF_FILE").unwrap());
+            print_dump(sub_matches, dump_unk(unk_file, char_def_file));
+            return;
+        }
+        #[cfg(feature = "arrow-export")]
+        ("export", Some(sub_matches)) => {
+            use lindera_ipadic_neologd_builder::export::ExportFormat;
+
+            let input_dir = Path::new(sub_matches.value_of("INPUT_DIR").unwrap());
+            let output_file = Path::new(sub_matches.value_of("OUTPUT_FILE").unwrap());
+            let format = match sub_matches.value_of("FORMAT") {
+                Some("arrow") => ExportFormat::Arrow,
+                _ => ExportFormat::Parquet,
+            };
+            if let Err(msg) =
+                IpadicNeologdBuilder::new().export_lexicon(input_dir, output_file, format)
+            {
+                println!("{}", msg);
+                std::process::exit(1);
+            }
+            return;
+        }
+        ("export-phonemes", Some(sub_matches)) => {
+            let input_dir = Path::new(sub_matches.value_of("INPUT_DIR").unwrap());
+            let output_file = Path::new(sub_matches.value_of("OUTPUT_FILE").unwrap());
+            if let Err(msg) = IpadicNeologdBuilder::new().export_phonemes(input_dir, output_file) {
+                println!("{}", msg);
+                std::process::exit(1);
+            }
+            return;
+        }
+        ("lookup", Some(sub_matches)) => {
+            let dict_dir = Path::new(sub_matches.value_of("DICT_DIR").unwrap());
+            let surface = sub_matches.value_of("SURFACE").unwrap();
+            if let Err(msg) = print_lookup(dict_dir, surface) {
+                println!("{}", msg);
+                std::process::exit(1);
+            }
+            return;
+        }
+        ("subset", Some(sub_matches)) => {
+            let dict_dir = Path::new(sub_matches.value_of("DICT_DIR").unwrap());
+            let terms_file = Path::new(sub_matches.value_of("TERMS_FILE").unwrap());
+            let output_dir = Path::new(sub_matches.value_of("OUTPUT_DIR").unwrap());
+            if let Err(msg) = load_terms(terms_file)
+                .and_then(|terms| subset_dictionary(dict_dir, &terms, output_dir))
+            {
+                println!("{}", msg);
+                std::process::exit(1);
+            }
+            return;
+        }
+        _ => {}
+    }
 
     let input_dir = Path::new(matches.value_of("INPUT_DIR").unwrap()).to_path_buf();
-    let output_dir = Path::new(matches.value_of("OUTPUT_DIR").unwrap()).to_path_buf();
 
-    let builder = IpadicNeologdBuilder::new();
+    let mut options = match (matches.value_of("CONFIG"), matches.value_of("PROFILE")) {
+        (Some(config), _) => match BuilderOptions::load(Path::new(config)) {
+            Ok(options) => options,
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        },
+        (None, Some(profile)) => match BuildProfile::from_str(profile) {
+            Ok(profile) => profile.options(),
+            Err(err) => {
+                println!("{}", err);
+                std::process::exit(1);
+            }
+        },
+        (None, None) => BuilderOptions::default(),
+    };
+    if let Err(err) = options.apply_env() {
+        println!("{}", err);
+        std::process::exit(1);
+    }
+    apply_matches(&mut options, &matches);
+
+    let mut builder = IpadicNeologdBuilder::with_options(options);
+    if let Some(reading_table) = matches.value_of("READING_TABLE") {
+        match ReadingTable::load(Path::new(reading_table)) {
+            Ok(reading_table) => builder = builder.with_reading_provider(reading_table),
+            Err(msg) => {
+                println!("{}", msg);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(metrics_textfile) = matches.value_of("METRICS_TEXTFILE") {
+        builder = builder.with_metrics(PrometheusTextfile::new(Path::new(metrics_textfile)));
+    }
+    #[cfg(feature = "progress")]
+    {
+        if matches.is_present("PROGRESS") {
+            builder = builder
+                .with_progress(lindera_ipadic_neologd_builder::progress::IndicatifProgress::new());
+        }
+    }
+
+    if matches.is_present("VALIDATE") {
+        match builder.validate(&input_dir) {
+            Ok(report) => {
+                for issue in &report.issues {
+                    match issue.line {
+                        Some(line) => {
+                            println!("{}:{}: {}", issue.file.display(), line, issue.message)
+                        }
+                        None => println!("{}: {}", issue.file.display(), issue.message),
+                    }
+                }
+                if let Some(lint_report) = &report.lint {
+                    print_lint_report(lint_report);
+                }
+                println!(
+                    "{} files, {} rows, {} surfaces, {} duplicate rows, max {} homonyms",
+                    report.csv_files,
+                    report.rows,
+                    report.surfaces,
+                    report.duplicate_rows,
+                    report.max_homonyms
+                );
+                if !report.is_valid() {
+                    std::process::exit(1);
+                }
+            }
+            Err(msg) => {
+                println!("{}", msg);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if matches.is_present("LINT") {
+        match builder.lint(&input_dir) {
+            Ok(report) => {
+                print_lint_report(&report);
+                if !report.is_clean() {
+                    std::process::exit(1);
+                }
+            }
+            Err(msg) => {
+                println!("{}", msg);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if matches.is_present("INVENTORY") {
+        match builder.discover_inputs(&input_dir) {
+            Ok(inventory) => {
+                for path in &inventory.missing {
+                    println!("{}: missing", path.display());
+                }
+                for file in &inventory.files {
+                    println!(
+                        "{}\t{}\t{}\t{}\t{}",
+                        file.kind.name(),
+                        file.size,
+                        file.encoding.name(),
+                        file.seed_date.as_deref().unwrap_or("-"),
+                        file.path.display()
+                    );
+                }
+                println!(
+                    "{} CSV files, {} bytes",
+                    inventory.csv_files().count(),
+                    inventory.total_size()
+                );
+                if !inventory.is_buildable() {
+                    std::process::exit(1);
+                }
+            }
+            Err(msg) => {
+                println!("{}", msg);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if matches.is_present("DRY_RUN") {
+        match builder.plan(&input_dir) {
+            Ok(plan) => {
+                for issue in &plan.validation.issues {
+                    println!("{}: {}", issue.file.display(), issue.message);
+                }
+                for artifact in &plan.artifacts {
+                    match artifact.estimated_size {
+                        Some(size) => println!("{}\t{}", artifact.name, size),
+                        None => println!("{}\t-", artifact.name),
+                    }
+                }
+                println!(
+                    "{} rows, {} surfaces, {} bytes estimated",
+                    plan.rows,
+                    plan.surfaces,
+                    plan.estimated_size()
+                );
+                if !plan.validation.is_valid() {
+                    std::process::exit(1);
+                }
+            }
+            Err(msg) => {
+                println!("{}", msg);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let output_dir = Path::new(matches.value_of("OUTPUT_DIR").unwrap()).to_path_buf();
 
-    match builder.build_dictionary(&input_dir, &output_dir) {
-        Ok(()) => println!("{}", "done"),
+    let result = match matches.values_of("LAYER") {
+        Some(layer_dirs) => {
+            let mut layers = vec![SourceLayer::new("base", &input_dir)];
+            layers.extend(layer_dirs.map(|dir| SourceLayer::new(dir, Path::new(dir))));
+            builder.build_merged_dictionary(&layers, &output_dir)
+        }
+        None => match matches.value_of("BASE_OUTPUT_DIR") {
+            Some(base_output_dir) => builder.build_with_base_dictionary(
+                &input_dir,
+                &output_dir,
+                Path::new(base_output_dir),
+            ),
+            None => builder.build_dictionary(&input_dir, &output_dir),
+        },
+    };
+    match result {
+        Ok(()) => println!("done"),
         Err(msg) => println!("{}", msg),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+extra_input_dirs = ["extra"]
+char_overrides = "char_overrides.def"
+fill_readings = true
+patch_files = ["fixes.patch"]
+reading_index = true
+columnar_details = true
+preferred_terms = "preferred.txt"
+emoji_entries = true
+kaomoji_entries = true
+numeric_entries = true
+variant_rules = "variants.toml"
+dictionary_version = "v0.0.7-20200910"
+scratch_dir = "/var/tmp/scratch"
+keyset_export = "keyset.tsv"
+build_log = true
+codegen_dir = "generated"
+package = true
+"#;
+
+    fn load_config(name: &str, args: &[&str]) -> BuilderOptions {
+        let config_path = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-{}-{}.toml",
+            name,
+            std::process::id()
+        ));
+        std::fs::write(&config_path, CONFIG).unwrap();
+        let mut argv = vec![
+            "lindera-ipadic-neologd",
+            "--config",
+            config_path.to_str().unwrap(),
+        ];
+        argv.extend_from_slice(args);
+        argv.extend_from_slice(&["input", "output"]);
+        let matches = app().get_matches_from(argv);
+        let mut options = BuilderOptions::load(&config_path).unwrap();
+        std::fs::remove_file(&config_path).unwrap();
+        apply_matches(&mut options, &matches);
+        options
+    }
+
+    #[test]
+    fn config_values_survive_absent_flags() {
+        let options = load_config("absent-flags", &[]);
+        assert_eq!(options.extra_input_dirs, vec![PathBuf::from("extra")]);
+        assert_eq!(
+            options.char_overrides,
+            Some(PathBuf::from("char_overrides.def"))
+        );
+        assert!(options.fill_readings);
+        assert_eq!(options.patch_files, vec![PathBuf::from("fixes.patch")]);
+        assert!(options.reading_index);
+        assert!(options.columnar_details);
+        assert_eq!(
+            options.preferred_terms,
+            Some(PathBuf::from("preferred.txt"))
+        );
+        assert!(options.emoji_entries);
+        assert!(options.kaomoji_entries);
+        assert!(options.numeric_entries);
+        assert_eq!(options.variant_rules, Some(PathBuf::from("variants.toml")));
+        assert_eq!(
+            options.dictionary_version.as_deref(),
+            Some("v0.0.7-20200910")
+        );
+        assert_eq!(options.scratch_dir, Some(PathBuf::from("/var/tmp/scratch")));
+        assert_eq!(options.keyset_export, Some(PathBuf::from("keyset.tsv")));
+        assert!(options.build_log);
+        assert_eq!(options.codegen_dir, Some(PathBuf::from("generated")));
+        assert!(options.package);
+    }
+
+    #[test]
+    fn present_flags_override_config_values() {
+        let options = load_config(
+            "present-flags",
+            &[
+                "--dictionary-version",
+                "v0.0.7-20201001",
+                "--input",
+                "other",
+                "--scratch-dir",
+                "/tmp/scratch",
+            ],
+        );
+        assert_eq!(
+            options.dictionary_version.as_deref(),
+            Some("v0.0.7-20201001")
+        );
+        assert_eq!(options.extra_input_dirs, vec![PathBuf::from("other")]);
+        assert_eq!(options.scratch_dir, Some(PathBuf::from("/tmp/scratch")));
+        assert_eq!(
+            options.preferred_terms,
+            Some(PathBuf::from("preferred.txt"))
+        );
+    }
+
+    #[test]
+    fn parses_sizes_with_unit_suffixes() {
+        assert_eq!(parse_size("4096"), Some(4096));
+        assert_eq!(parse_size("512k"), Some(512 << 10));
+        assert_eq!(parse_size("64M"), Some(64 << 20));
+        assert_eq!(parse_size("2G"), Some(2 << 30));
+        assert_eq!(parse_size(""), None);
+        assert_eq!(parse_size("G"), None);
+        assert_eq!(parse_size("1.5G"), None);
+        assert_eq!(parse_size("-1"), None);
+        assert_eq!(parse_size("17179869184G"), None);
+    }
+
+    #[test]
+    fn parses_normalization_chains() {
+        assert_eq!(
+            parse_normalization_chain("nfkc+width-folding"),
+            Some(vec![
+                NormalizationStep::Nfkc,
+                NormalizationStep::WidthFolding
+            ])
+        );
+        assert_eq!(
+            parse_normalization_chain("nfkc"),
+            Some(vec![NormalizationStep::Nfkc])
+        );
+        assert_eq!(parse_normalization_chain("nfkc+nfd"), None);
+        assert_eq!(parse_normalization_chain(""), None);
+    }
+}
@@ -0,0 +1,221 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::codec::DetailFormat;
+use crate::columnar::{column_filenames, COLUMNS_DIRNAME};
+use crate::compat::LinderaTarget;
+use crate::elasticsearch::{elasticsearch_filenames, ELASTICSEARCH_DIRNAME};
+use crate::endian::Endianness;
+use crate::layout::DictionaryLayout;
+use crate::mmap_layout::MMAP_FILENAME;
+use crate::packing::{ValuePacking, VALUE_TABLE_FILENAME};
+use crate::reading_index::{READING_DA_FILENAME, READING_VALS_FILENAME};
+use crate::seed::SeedDates;
+use crate::shard::{ShardIndex, SHARD_INDEX_FILENAME};
+
+pub const MANIFEST_FILENAME: &str = "metadata.json";
+/// Holds the dictionary version on a single line, for systems loading the artifacts directly.
+pub const VERSION_FILENAME: &str = "version.txt";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactEntry {
+    pub name: String,
+    pub size: u64,
+}
+
+/// Metadata describing a built dictionary, written to `metadata.json` in the output directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub builder: String,
+    pub builder_version: String,
+    /// Version of the lexicon the dictionary was built from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dictionary_version: Option<String>,
+    /// Snapshot dates of the NEologd seed files the dictionary was built from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed_dates: Option<SeedDates>,
+    /// Encoding of the word details in `dict.words`.
+    #[serde(default)]
+    pub detail_format: DetailFormat,
+    /// Byte order of the integers of the binary artifacts.
+    #[serde(default)]
+    pub endianness: Endianness,
+    /// Whether the artifacts of the layout start with an `ArtifactHeader`.
+    #[serde(default)]
+    pub artifact_headers: bool,
+    /// The lindera version the artifacts are written for.
+    #[serde(default)]
+    pub lindera_target: LinderaTarget,
+    /// How the entries of the surfaces are packed into the values of the double array.
+    #[serde(default)]
+    pub value_packing: ValuePacking,
+    /// The detail fields of the word details, if not all of them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail_fields: Option<Vec<String>>,
+    pub artifacts: Vec<ArtifactEntry>,
+}
+
+impl Manifest {
+    /// Creates a manifest describing the artifacts of `layout` present in `output_dir`,
+    /// including the optional value table, shards, reading index, columnar word details and
+    /// Elasticsearch files.
+    pub fn from_output_dir(
+        output_dir: &Path,
+        layout: &DictionaryLayout,
+    ) -> LinderaResult<Manifest> {
+        let mut names: Vec<String> = layout
+            .artifact_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        if output_dir.join(VALUE_TABLE_FILENAME).exists() {
+            names.push(VALUE_TABLE_FILENAME.to_string());
+        }
+        if output_dir.join(SHARD_INDEX_FILENAME).exists() {
+            names.extend(ShardIndex::load(output_dir)?.filenames());
+        }
+        if output_dir.join(READING_DA_FILENAME).exists() {
+            names.push(READING_DA_FILENAME.to_string());
+            names.push(READING_VALS_FILENAME.to_string());
+        }
+        if output_dir.join(COLUMNS_DIRNAME).is_dir() {
+            names.extend(column_filenames());
+        }
+        if output_dir.join(ELASTICSEARCH_DIRNAME).is_dir() {
+            names.extend(elasticsearch_filenames());
+        }
+        if output_dir.join(MMAP_FILENAME).exists() {
+            names.push(MMAP_FILENAME.to_string());
+        }
+        if output_dir.join(VERSION_FILENAME).exists() {
+            names.push(VERSION_FILENAME.to_string());
+        }
+
+        let mut artifacts = Vec::new();
+        for name in names {
+            let metadata = fs::metadata(output_dir.join(&name))
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            artifacts.push(ArtifactEntry {
+                name,
+                size: metadata.len(),
+            });
+        }
+
+        Ok(Manifest {
+            builder: env!("CARGO_PKG_NAME").to_string(),
+            builder_version: env!("CARGO_PKG_VERSION").to_string(),
+            dictionary_version: None,
+            seed_dates: None,
+            detail_format: DetailFormat::default(),
+            endianness: Endianness::default(),
+            artifact_headers: false,
+            lindera_target: LinderaTarget::default(),
+            value_packing: ValuePacking::default(),
+            detail_fields: None,
+            artifacts,
+        })
+    }
+
+    /// Writes `version.txt` with the dictionary version.
+    pub fn write_version(output_dir: &Path, version: &str) -> LinderaResult<()> {
+        let wtr_version_path = output_dir.join(VERSION_FILENAME);
+        println!("creating {:?}", wtr_version_path);
+        fs::write(wtr_version_path, format!("{}\n", version))
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))
+    }
+
+    pub fn load(output_dir: &Path) -> LinderaResult<Manifest> {
+        let manifest_data = fs::read(output_dir.join(MANIFEST_FILENAME))
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        serde_json::from_slice(&manifest_data)
+            .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))
+    }
+
+    pub fn write(&self, output_dir: &Path) -> LinderaResult<()> {
+        let wtr_manifest_path = output_dir.join(Path::new(MANIFEST_FILENAME));
+        println!("creating {:?}", wtr_manifest_path);
+        let mut wtr_manifest = io::BufWriter::new(
+            File::create(wtr_manifest_path)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
+        );
+        serde_json::to_writer_pretty(&mut wtr_manifest, self)
+            .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+        wtr_manifest
+            .flush()
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{Manifest, VERSION_FILENAME};
+    use crate::layout::DictionaryLayout;
+
+    #[test]
+    fn manifests_round_trip_through_metadata_json() {
+        let output_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-manifest-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&output_dir);
+        fs::create_dir_all(&output_dir).unwrap();
+        let layout = DictionaryLayout::default();
+        for (size, name) in layout.artifact_names().iter().enumerate() {
+            fs::write(output_dir.join(name), vec![0; size]).unwrap();
+        }
+        Manifest::write_version(&output_dir, "2020.01.01").unwrap();
+
+        let mut manifest = Manifest::from_output_dir(&output_dir, &layout).unwrap();
+        let mut names: Vec<String> = layout
+            .artifact_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        names.push(VERSION_FILENAME.to_string());
+        assert_eq!(
+            manifest
+                .artifacts
+                .iter()
+                .map(|artifact| artifact.name.clone())
+                .collect::<Vec<_>>(),
+            names
+        );
+        assert_eq!(manifest.artifacts[2].size, 2);
+        assert_eq!(manifest.builder_version, env!("CARGO_PKG_VERSION"));
+
+        manifest.dictionary_version = Some("2020.01.01".to_string());
+        manifest.detail_fields = Some(vec!["reading".to_string()]);
+        manifest.write(&output_dir).unwrap();
+        let loaded = Manifest::load(&output_dir).unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&manifest).unwrap()
+        );
+
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    fn missing_fields_take_their_defaults() {
+        let manifest: Manifest = serde_json::from_str(
+            r#"{"builder": "b", "builder_version": "0.1.0", "artifacts": []}"#,
+        )
+        .unwrap();
+        assert!(manifest.dictionary_version.is_none());
+        assert!(!manifest.artifact_headers);
+        assert!(manifest.detail_fields.is_none());
+        let json = serde_json::to_value(&manifest).unwrap();
+        assert!(json.get("dictionary_version").is_none());
+        assert!(json.get("seed_dates").is_none());
+    }
+}
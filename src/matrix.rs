@@ -0,0 +1,457 @@
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::str::FromStr;
+use std::thread;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::file_util::for_each_line;
+
+/// Number of matrix.def lines parsed together by the worker threads.
+pub(crate) const BATCH_LINES: usize = 1 << 20;
+
+/// Approximate memory used per buffered line: the text, its offsets and the parsed cell.
+const BATCH_LINE_BYTES: u64 = 64;
+
+/// The number of lines per batch fitting in `memory_budget`, at most `BATCH_LINES`.
+pub(crate) fn batch_lines(memory_budget: Option<u64>) -> usize {
+    match memory_budget {
+        Some(budget) => ((budget / BATCH_LINE_BYTES) as usize).clamp(1024, BATCH_LINES),
+        None => BATCH_LINES,
+    }
+}
+
+/// Parses up to 3 whitespace-separated integers of a matrix.def line, returning the number
+/// of fields found.
+fn parse_fields(line: &str) -> LinderaResult<([i32; 3], usize)> {
+    let mut fields = [0i32; 3];
+    let mut fields_num = 0;
+    for (field, value) in fields.iter_mut().zip(line.split_whitespace()) {
+        *field = i32::from_str(value)
+            .map_err(|err| LinderaErrorKind::Parse.with_error(anyhow::anyhow!(err)))?;
+        fields_num += 1;
+    }
+    Ok((fields, fields_num))
+}
+
+/// Lines of matrix.def buffered for parallel parsing.
+#[derive(Default)]
+struct Batch {
+    data: String,
+    lines: Vec<(usize, usize, usize)>,
+}
+
+impl Batch {
+    fn push(&mut self, line_num: usize, line: &str) {
+        let start = self.data.len();
+        self.data.push_str(line);
+        self.lines.push((line_num, start, self.data.len()));
+    }
+
+    fn clear(&mut self) {
+        self.data.clear();
+        self.lines.clear();
+    }
+}
+
+struct CostMatrix {
+    costs: Vec<i16>,
+    forward_size: u32,
+    backward_size: u32,
+    written: Vec<bool>,
+    duplicates: usize,
+}
+
+impl CostMatrix {
+    /// Allocates the matrix of the header sizes, which are stored as `i16` in `matrix.mtx`.
+    /// The header is checked before anything is allocated: the sizes must be positive and
+    /// the matrix no larger than `max_cells`.
+    fn new(forward_size: i16, backward_size: i16, max_cells: usize) -> Result<Self, String> {
+        if forward_size <= 0 || backward_size <= 0 {
+            return Err(format!(
+                "matrix sizes {}x{} are not positive",
+                forward_size, backward_size
+            ));
+        }
+        let cells = forward_size as usize * backward_size as usize;
+        if cells > max_cells {
+            return Err(format!(
+                "matrix sizes {}x{} make {} cells, more than the {} the input can list",
+                forward_size, backward_size, cells, max_cells
+            ));
+        }
+        let mut costs = vec![i16::MAX; 2 + cells];
+        costs[0] = forward_size;
+        costs[1] = backward_size;
+        Ok(CostMatrix {
+            costs,
+            forward_size: forward_size as u32,
+            backward_size: backward_size as u32,
+            written: vec![false; cells],
+            duplicates: 0,
+        })
+    }
+
+    /// Parses the lines of `batch` on `threads` threads and stores the costs.
+    fn apply_batch(
+        &mut self,
+        matrix_data_path: &Path,
+        batch: &Batch,
+        threads: usize,
+    ) -> LinderaResult<()> {
+        let chunk_size = (batch.lines.len() + threads - 1) / threads.max(1);
+        if chunk_size == 0 {
+            return Ok(());
+        }
+        let forward_size = self.forward_size;
+        let backward_size = self.backward_size;
+
+        let results: Vec<LinderaResult<Vec<(usize, i16)>>> = thread::scope(|scope| {
+            let handles: Vec<_> = batch
+                .lines
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        let mut cells = Vec::with_capacity(chunk.len());
+                        for (line_num, start, end) in chunk {
+                            let line_error = |message: String| {
+                                LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                                    "{}:{}: {}",
+                                    matrix_data_path.display(),
+                                    line_num,
+                                    message
+                                ))
+                            };
+                            let (fields, fields_num) = parse_fields(&batch.data[*start..*end])
+                                .map_err(|err| line_error(err.to_string()))?;
+                            if fields_num < 3 {
+                                return Err(line_error(format!(
+                                    "expected 3 fields but found {}",
+                                    fields_num
+                                )));
+                            }
+                            let (forward_id, backward_id) = (fields[0], fields[1]);
+                            if forward_id < 0 || forward_id as u32 >= forward_size {
+                                return Err(line_error(format!(
+                                    "forward id {} is out of the range 0..{} of the header",
+                                    forward_id, forward_size
+                                )));
+                            }
+                            if backward_id < 0 || backward_id as u32 >= backward_size {
+                                return Err(line_error(format!(
+                                    "backward id {} is out of the range 0..{} of the header",
+                                    backward_id, backward_size
+                                )));
+                            }
+                            let cost = i16::try_from(fields[2]).map_err(|_err| {
+                                line_error(format!(
+                                    "cost {} is out of the range {}..={}",
+                                    fields[2],
+                                    i16::MIN,
+                                    i16::MAX
+                                ))
+                            })?;
+                            cells.push((
+                                2 + (backward_id as u32 + forward_id as u32 * backward_size)
+                                    as usize,
+                                cost,
+                            ));
+                        }
+                        Ok(cells)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle.join().unwrap_or_else(|_err| {
+                        Err(LinderaErrorKind::Content
+                            .with_error(anyhow::anyhow!("matrix.def parser thread panicked")))
+                    })
+                })
+                .collect()
+        });
+
+        for cells in results {
+            for (idx, cost) in cells? {
+                self.costs[idx] = cost;
+                if std::mem::replace(&mut self.written[idx - 2], true) {
+                    self.duplicates += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the (forward, backward) sizes from the header of matrix.def.
+pub(crate) fn read_header(matrix_data_path: &Path) -> LinderaResult<(u32, u32)> {
+    let rdr = io::BufReader::new(
+        File::open(matrix_data_path)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
+    );
+    read_header_from(matrix_data_path, rdr)
+}
+
+/// Reads the header sizes from matrix.def content read from `rdr` like `read_header`;
+/// `matrix_data_path` is only used in error messages.
+pub(crate) fn read_header_from<R: BufRead>(
+    matrix_data_path: &Path,
+    rdr: R,
+) -> LinderaResult<(u32, u32)> {
+    for (line_idx, line) in rdr.lines().enumerate() {
+        let line = line.map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        let line = line.trim_start_matches('\u{feff}');
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (fields, fields_num) = parse_fields(line)?;
+        if fields_num < 2 || fields[0] <= 0 || fields[1] <= 0 {
+            return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                "{}:{}: invalid header {:?}",
+                matrix_data_path.display(),
+                line_idx + 1,
+                line
+            )));
+        }
+        return Ok((fields[0] as u32, fields[1] as u32));
+    }
+    Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!("matrix.def is empty")))
+}
+
+/// The number of cells a matrix.def of `input_len` bytes can declare. Every cell takes a
+/// line of several bytes, so a header declaring more cells than the input has bytes is
+/// corrupted, and is rejected before its cells are allocated.
+pub(crate) fn max_cells(input_len: u64) -> usize {
+    usize::try_from(input_len).unwrap_or(usize::MAX)
+}
+
+/// Parses matrix.def content read from `rdr` into the cost array written to `matrix.mtx`:
+/// the forward and backward sizes followed by the costs. The lines are read in batches of
+/// `batch_lines` lines parsed by `threads` threads, and `progress` is called with the number
+/// of lines of each batch. A header of more than `max_cells` cells is an error, and missing
+/// or repeated cells are reported to `warn`. `matrix_data_path` is only used in messages.
+pub(crate) fn parse_matrix_from<R: BufRead>(
+    matrix_data_path: &Path,
+    rdr: R,
+    max_cells: usize,
+    threads: usize,
+    batch_lines: usize,
+    progress: &dyn Fn(usize),
+    warn: &dyn Fn(&str),
+) -> LinderaResult<Vec<i16>> {
+    let threads = threads.max(1);
+    let mut matrix: Option<CostMatrix> = None;
+    let mut batch = Batch::default();
+    for_each_line(matrix_data_path, rdr, |line_num, line| match &mut matrix {
+        None => {
+            let header_error = |message: String| {
+                LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                    "{}:{}: invalid header {:?}: {}",
+                    matrix_data_path.display(),
+                    line_num,
+                    line,
+                    message
+                ))
+            };
+            let (fields, fields_num) = parse_fields(line)?;
+            if fields_num < 2 {
+                return Err(header_error(format!(
+                    "expected 2 fields but found {}",
+                    fields_num
+                )));
+            }
+            let sizes = (i16::try_from(fields[0]), i16::try_from(fields[1]));
+            match sizes {
+                (Ok(forward_size), Ok(backward_size)) => {
+                    matrix = Some(
+                        CostMatrix::new(forward_size, backward_size, max_cells)
+                            .map_err(header_error)?,
+                    );
+                    Ok(())
+                }
+                _ => Err(header_error(format!(
+                    "matrix sizes are out of the range 1..={}",
+                    i16::MAX
+                ))),
+            }
+        }
+        Some(matrix) => {
+            batch.push(line_num, line);
+            if batch.lines.len() >= batch_lines {
+                matrix.apply_batch(matrix_data_path, &batch, threads)?;
+                progress(batch.lines.len());
+                batch.clear();
+            }
+            Ok(())
+        }
+    })?;
+
+    let mut matrix = matrix.ok_or_else(|| {
+        LinderaErrorKind::Content.with_error(anyhow::anyhow!("matrix.def is empty"))
+    })?;
+    matrix.apply_batch(matrix_data_path, &batch, threads)?;
+    progress(batch.lines.len());
+
+    // Every cell is expected exactly once.
+    let missing = matrix.written.iter().filter(|written| !**written).count();
+    if missing > 0 || matrix.duplicates > 0 {
+        warn(&format!(
+            "{}: {} of {} cells missing, {} cells repeated",
+            matrix_data_path.display(),
+            missing,
+            matrix.written.len(),
+            matrix.duplicates
+        ));
+    }
+
+    Ok(matrix.costs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::path::Path;
+
+    use super::*;
+    use crate::fixtures::FIXTURE_MATRIX_DEF;
+
+    fn parse(content: &str, threads: usize, batch_lines: usize) -> LinderaResult<Vec<i16>> {
+        parse_matrix_from(
+            Path::new("matrix.def"),
+            content.as_bytes(),
+            max_cells(content.len() as u64),
+            threads,
+            batch_lines,
+            &|_| {},
+            &|_| {},
+        )
+    }
+
+    #[test]
+    fn reads_the_header_past_a_bom_and_blank_lines() {
+        let content = format!("\u{feff}\n{}", FIXTURE_MATRIX_DEF);
+        assert_eq!(
+            read_header_from(Path::new("matrix.def"), content.as_bytes()).unwrap(),
+            (3, 3)
+        );
+        let err = read_header_from(Path::new("matrix.def"), &b"3\n"[..]).unwrap_err();
+        assert!(err.to_string().contains("matrix.def:1: invalid header"));
+        let err = read_header_from(Path::new("matrix.def"), &b"\n\n"[..]).unwrap_err();
+        assert!(err.to_string().contains("matrix.def is empty"));
+    }
+
+    #[test]
+    fn streams_the_cells_in_row_major_order() {
+        let costs = parse(&FIXTURE_MATRIX_DEF.replace('\n', "\r\n"), 1, BATCH_LINES).unwrap();
+        assert_eq!(
+            costs,
+            vec![3, 3, 0, 100, 100, 100, 200, -100, 100, -100, 200]
+        );
+    }
+
+    #[test]
+    fn warns_of_missing_and_repeated_cells() {
+        let warnings = RefCell::new(Vec::new());
+        let content = "2 2\n0 0 1\n0 0 2\n1 1 3\n";
+        let costs = parse_matrix_from(
+            Path::new("matrix.def"),
+            content.as_bytes(),
+            max_cells(content.len() as u64),
+            1,
+            BATCH_LINES,
+            &|_| {},
+            &|message| warnings.borrow_mut().push(message.to_string()),
+        )
+        .unwrap();
+        assert_eq!(costs, vec![2, 2, 2, i16::MAX, i16::MAX, 3]);
+        assert_eq!(
+            warnings.into_inner(),
+            vec!["matrix.def: 2 of 4 cells missing, 1 cells repeated".to_string()]
+        );
+    }
+
+    #[test]
+    fn rejects_headers_before_allocating() {
+        let error = |content: &str| parse(content, 1, BATCH_LINES).unwrap_err().to_string();
+        assert!(error("").contains("matrix.def is empty"));
+        assert!(error("3\n").contains("expected 2 fields but found 1"));
+        assert!(error("0 3\n").contains("are not positive"));
+        assert!(error("40000 1\n").contains("out of the range 1..=32767"));
+        assert!(error("1000 1000\n0 0 0\n").contains("more than the"));
+    }
+
+    #[test]
+    fn parses_in_parallel_batches_like_a_single_thread() {
+        let mut content = String::from("7 5\n");
+        for forward_id in 0..7 {
+            for backward_id in 0..5 {
+                content.push_str(&format!(
+                    "{} {} {}\n",
+                    forward_id,
+                    backward_id,
+                    forward_id * 100 - backward_id * 7
+                ));
+            }
+        }
+        let expected = parse(&content, 1, BATCH_LINES).unwrap();
+        assert_eq!(expected.len(), 2 + 7 * 5);
+        assert_eq!(expected[2 + 6 * 5 + 4], 572);
+        for (threads, batch_lines) in [(4, BATCH_LINES), (3, 4), (8, 1)] {
+            assert_eq!(parse(&content, threads, batch_lines).unwrap(), expected);
+        }
+
+        let batches = RefCell::new(Vec::new());
+        parse_matrix_from(
+            Path::new("matrix.def"),
+            content.as_bytes(),
+            max_cells(content.len() as u64),
+            2,
+            16,
+            &|lines| batches.borrow_mut().push(lines),
+            &|_| {},
+        )
+        .unwrap();
+        assert_eq!(batches.into_inner(), vec![16, 16, 3]);
+    }
+
+    #[test]
+    fn sizes_batches_to_the_memory_budget() {
+        assert_eq!(batch_lines(None), BATCH_LINES);
+        assert_eq!(batch_lines(Some(1)), 1024);
+        assert_eq!(batch_lines(Some(64 * 4096)), 4096);
+        assert_eq!(batch_lines(Some(u64::MAX)), BATCH_LINES);
+    }
+
+    #[test]
+    fn rejects_ids_outside_the_header_with_their_line() {
+        let error = |content: &str| parse(content, 2, 2).unwrap_err().to_string();
+        assert!(error("2 2\n0 0 1\n0 1 1\n\n2 0 1\n")
+            .contains("matrix.def:5: forward id 2 is out of the range 0..2 of the header"));
+        assert!(error("2 2\n0 0 1\n0 -1 1\n")
+            .contains("matrix.def:3: backward id -1 is out of the range 0..2 of the header"));
+        assert!(error("2 2\n0 0\n").contains("matrix.def:2: expected 3 fields but found 2"));
+        assert!(error("2 2\n0 0 x\n").contains("matrix.def:2: "));
+    }
+
+    #[test]
+    fn rejects_costs_outside_i16_instead_of_wrapping() {
+        let costs = parse("1 2\n0 0 -32768\n0 1 32767\n", 1, BATCH_LINES).unwrap();
+        assert_eq!(costs, vec![1, 2, i16::MIN, i16::MAX]);
+        for cost in ["-32769", "40000"] {
+            let content = format!("1 2\n0 0 {}\n0 1 {}\n", cost, cost);
+            let message = parse(&content, 1, BATCH_LINES).unwrap_err().to_string();
+            assert!(
+                message.contains(&format!(
+                    "matrix.def:2: cost {} is out of the range -32768..=32767",
+                    cost
+                )),
+                "{}",
+                message
+            );
+        }
+    }
+}
@@ -0,0 +1,540 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use lindera_core::dictionary_builder::DictionaryBuilder;
+use lindera_core::error::{LinderaError, LinderaErrorKind};
+use lindera_core::LinderaResult;
+
+use crate::error::{BuildError, ErrorCollector};
+use crate::external_sort::ExternalLayers;
+use crate::{
+    named_csv_files, parse_files, CsvRow, IpadicNeologdBuilder, SeedDates, IN_MEMORY_STAGES,
+};
+
+/// A source dictionary directory taking part in a merged build.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceLayer {
+    pub name: String,
+    pub input_dir: PathBuf,
+}
+
+impl SourceLayer {
+    pub fn new(name: &str, input_dir: &Path) -> Self {
+        SourceLayer {
+            name: name.to_string(),
+            input_dir: input_dir.to_path_buf(),
+        }
+    }
+}
+
+/// How rows sharing the same surface and POS across layers are resolved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DuplicatePolicy {
+    /// Rows of a later layer replace the rows of earlier layers.
+    #[default]
+    Override,
+    /// Only the row with the lowest word cost is kept.
+    KeepLowestCost,
+    /// All rows are kept.
+    KeepAll,
+}
+
+/// How the word costs of a source layer are brought to the scale of the first layer before
+/// merging, for layers whose costs were trained separately.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostScaling {
+    /// Each cost becomes `cost * scale + offset`.
+    Affine { scale: f64, offset: f64 },
+    /// Each cost becomes the cost at the same percentile of the costs of the first layer.
+    Percentile,
+}
+
+impl CostScaling {
+    /// The rescaled `cost`, given the sorted word costs of its layer and of the first layer.
+    fn scale(&self, cost: i32, layer_costs: &[i32], reference_costs: &[i32]) -> i32 {
+        match self {
+            CostScaling::Affine { scale, offset } => {
+                let cost = (f64::from(cost) * scale + offset).round();
+                cost.clamp(f64::from(i16::MIN), f64::from(i16::MAX)) as i32
+            }
+            CostScaling::Percentile => {
+                if reference_costs.is_empty() {
+                    return cost;
+                }
+                let last = layer_costs.len().saturating_sub(1).max(1) as f64;
+                let reference_last = (reference_costs.len() - 1) as f64;
+                let rank = layer_costs.partition_point(|other| *other < cost) as f64;
+                let idx = (rank / last * reference_last).round() as usize;
+                reference_costs[idx.min(reference_costs.len() - 1)]
+            }
+        }
+    }
+}
+
+impl FromStr for CostScaling {
+    type Err = LinderaError;
+
+    /// Parses `percentile`, `affine:SCALE` or `affine:SCALE:OFFSET`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || LinderaErrorKind::Args.with_error(anyhow::anyhow!("invalid cost scaling {}", value));
+        let mut parts = value.split(':');
+        match parts.next() {
+            Some("percentile") if parts.next().is_none() => Ok(CostScaling::Percentile),
+            Some("affine") => {
+                let scale = parts
+                    .next()
+                    .and_then(|scale| scale.parse().ok())
+                    .ok_or_else(invalid)?;
+                let offset = match parts.next() {
+                    Some(offset) => offset.parse().map_err(|_| invalid())?,
+                    None => 0.0,
+                };
+                if parts.next().is_some() {
+                    return Err(invalid());
+                }
+                Ok(CostScaling::Affine { scale, offset })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// The cost scalings of the layers of a merged build, by layer index, with the word costs
+/// of the layers the percentile scaling needs. Layers past the given ones, such as the
+/// generated entries, are not rescaled.
+pub(crate) struct LayerScalings {
+    scalings: Vec<Option<CostScaling>>,
+    /// The word costs of each layer, recorded for the first layer and the layers with a
+    /// percentile scaling only.
+    layer_costs: Vec<Vec<i32>>,
+}
+
+impl LayerScalings {
+    /// The scalings of `layers` in `scalings`, by layer name, which must all name a layer.
+    pub(crate) fn new(
+        layers: &[SourceLayer],
+        scalings: &BTreeMap<String, CostScaling>,
+    ) -> LinderaResult<LayerScalings> {
+        for name in scalings.keys() {
+            if !layers.iter().any(|layer| &layer.name == name) {
+                return Err(LinderaErrorKind::Args
+                    .with_error(anyhow::anyhow!("no source layer named {}", name)));
+            }
+        }
+        Ok(LayerScalings {
+            scalings: layers
+                .iter()
+                .map(|layer| scalings.get(&layer.name).copied())
+                .collect(),
+            layer_costs: vec![Vec::new(); layers.len()],
+        })
+    }
+
+    fn needs_costs(&self, layer: usize) -> bool {
+        let percentile = |scaling: &Option<CostScaling>| *scaling == Some(CostScaling::Percentile);
+        match self.scalings.get(layer) {
+            Some(scaling) if percentile(scaling) => true,
+            Some(_) => layer == 0 && self.scalings.iter().any(percentile),
+            None => false,
+        }
+    }
+
+    /// Records the word cost of an entry of `layer`, before any is rescaled.
+    pub(crate) fn record_cost(&mut self, layer: usize, cost: i32) {
+        if self.needs_costs(layer) {
+            self.layer_costs[layer].push(cost);
+        }
+    }
+
+    /// Sorts the recorded costs, once every entry is recorded.
+    pub(crate) fn sort_costs(&mut self) {
+        for costs in &mut self.layer_costs {
+            costs.sort_unstable();
+        }
+    }
+
+    /// The word cost of an entry of `layer` on the scale of the first layer.
+    pub(crate) fn scale(&self, layer: usize, cost: i32) -> i32 {
+        match self.scalings.get(layer) {
+            Some(Some(scaling)) => {
+                scaling.scale(cost, &self.layer_costs[layer], &self.layer_costs[0])
+            }
+            _ => cost,
+        }
+    }
+}
+
+/// Rescales the word costs of the layers with an entry in `scalings`, by layer name.
+fn rescale_layers(
+    layers: &[SourceLayer],
+    layers_rows: &mut [Vec<CsvRow>],
+    scalings: &BTreeMap<String, CostScaling>,
+) -> LinderaResult<()> {
+    let mut layer_scalings = LayerScalings::new(layers, scalings)?;
+    for (layer, rows) in layers_rows.iter().enumerate() {
+        for row in rows {
+            layer_scalings.record_cost(layer, row.word_cost);
+        }
+    }
+    layer_scalings.sort_costs();
+    for (layer, rows) in layers_rows.iter_mut().enumerate() {
+        for row in rows.iter_mut() {
+            row.word_cost = layer_scalings.scale(layer, row.word_cost);
+        }
+    }
+    Ok(())
+}
+
+type RowKey<'a> = (&'a str, &'a str, &'a str, &'a str, &'a str);
+
+fn row_key<'a>(row: &CsvRow<'a>) -> RowKey<'a> {
+    (
+        row.surface_form,
+        row.pos_level1,
+        row.pos_level2,
+        row.pos_level3,
+        row.pos_level4,
+    )
+}
+
+/// Resolves duplicate rows of the layers, given in increasing order of precedence.
+pub(crate) fn merge_rows<'a>(
+    layers_rows: Vec<Vec<CsvRow<'a>>>,
+    policy: DuplicatePolicy,
+) -> Vec<CsvRow<'a>> {
+    if policy == DuplicatePolicy::KeepAll {
+        return layers_rows.into_iter().flatten().collect();
+    }
+
+    // Keyed rows, remembering the layer they come from.
+    let mut merged: HashMap<RowKey<'a>, (usize, Vec<CsvRow<'a>>)> = HashMap::new();
+    let mut order: Vec<RowKey<'a>> = Vec::new();
+    for (layer_idx, rows) in layers_rows.into_iter().enumerate() {
+        for row in rows {
+            let key = row_key(&row);
+            match merged.get_mut(&key) {
+                None => {
+                    order.push(key);
+                    merged.insert(key, (layer_idx, vec![row]));
+                }
+                Some((entry_layer_idx, entry_rows)) => match policy {
+                    DuplicatePolicy::Override if *entry_layer_idx == layer_idx => {
+                        entry_rows.push(row)
+                    }
+                    DuplicatePolicy::Override => {
+                        *entry_layer_idx = layer_idx;
+                        *entry_rows = vec![row];
+                    }
+                    DuplicatePolicy::KeepLowestCost => {
+                        if row.word_cost < entry_rows[0].word_cost {
+                            *entry_layer_idx = layer_idx;
+                            *entry_rows = vec![row];
+                        }
+                    }
+                    DuplicatePolicy::KeepAll => entry_rows.push(row),
+                },
+            }
+        }
+    }
+
+    order
+        .into_iter()
+        .flat_map(|key| {
+            merged
+                .remove(&key)
+                .map(|(_, rows)| rows)
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+impl IpadicNeologdBuilder {
+    /// Builds one dictionary from several source layers, given in increasing order of
+    /// precedence (e.g. IPADIC, NEologd seed, custom terms). `char.def`, `unk.def` and
+    /// `matrix.def` are taken from the first layer, and the CSVs of all layers are merged
+    /// according to `BuilderOptions::duplicate_policy`, after their word costs are rescaled
+    /// with `BuilderOptions::layer_cost_scaling`. The merged entries then go through the
+    /// same stages as the entries of a single input directory, from the patches to the size
+    /// budget. Extra input directories and a replaced lexicon phase are rejected, as the
+    /// sources of a merged build are its layers. Over the memory budget, the entries of the
+    /// layers are sorted externally and merged surface by surface, unless an option needs
+    /// them all in memory, in which case the build fails.
+    pub fn build_merged_dictionary(
+        &self,
+        layers: &[SourceLayer],
+        output_dir: &Path,
+    ) -> LinderaResult<()> {
+        let base_layer = layers.first().ok_or_else(|| {
+            LinderaErrorKind::Args.with_error(anyhow::anyhow!("no source layers given"))
+        })?;
+        if !self.options.extra_input_dirs.is_empty() {
+            return Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!(
+                "merged builds take their sources from the layers; add the extra input directories as layers"
+            )));
+        }
+        if self.lexicon_builder.is_some() {
+            return Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!(
+                "merged builds merge the parsed entries of the layers, which a replaced lexicon phase does not produce"
+            )));
+        }
+
+        self.run_build(&base_layer.input_dir, &[output_dir], || {
+            self.build_merged_phases(layers, &base_layer.input_dir, output_dir)
+        })
+    }
+
+    /// The phases of `build_merged_dictionary`, run with the output directory locked.
+    fn build_merged_phases(
+        &self,
+        layers: &[SourceLayer],
+        base_dir: &Path,
+        output_dir: &Path,
+    ) -> LinderaResult<Option<SeedDates>> {
+        self.preflight_layers(layers)?;
+        let estimate = self.estimate_layers_disk_usage(layers)?;
+        self.check_disk_space(&[self.disk_need(output_dir, estimate)])?;
+        let chardef =
+            self.measure_phase("chardef", &[base_dir.join("char.def")], output_dir, || {
+                self.build_chardef(base_dir, output_dir)
+            })?;
+        self.measure_phase("unk", &[base_dir.join("unk.def")], output_dir, || {
+            self.build_unk(base_dir, &chardef, output_dir)
+        })?;
+
+        // The files of each layer, named after the layer, end at the index in `layer_ends`.
+        let mut sources = Vec::new();
+        let mut layer_ends = Vec::with_capacity(layers.len());
+        for layer in layers {
+            sources.extend(
+                named_csv_files(&layer.input_dir, None)?
+                    .into_iter()
+                    .map(|(name, filename)| (format!("{}:{}", layer.name, name), filename)),
+            );
+            layer_ends.push(sources.len());
+        }
+        let csv_files: Vec<PathBuf> = sources
+            .iter()
+            .map(|(_, filename)| filename.clone())
+            .collect();
+        let seed_dates = self.check_seed_dates(&csv_files)?;
+        self.measure_phase("dict", &csv_files, output_dir, || {
+            self.print("BUILD DICT");
+            if let Some(estimated_bytes) = self.exceeded_memory_budget(&sources)? {
+                if !self.supports_external_sort() {
+                    return Err(BuildError::Validation(format!(
+                        "estimated {} bytes exceed the memory budget, but {} need the entries in memory",
+                        estimated_bytes, IN_MEMORY_STAGES
+                    ))
+                    .into());
+                }
+                self.print(&format!(
+                    "estimated {} bytes exceed the memory budget, sorting entries externally",
+                    estimated_bytes
+                ));
+                let layers = ExternalLayers {
+                    layers,
+                    layer_ends: &layer_ends,
+                };
+                return self.build_dict_external(base_dir, &sources, Some(layers), output_dir);
+            }
+            let files = self.read_sources(&sources)?;
+            let patches = self.load_patches()?;
+            let mut errors = ErrorCollector::new(self.options.max_errors);
+            // The generated entries, added after the files of the last layer, are merged as
+            // a layer of their own, which only has surfaces without an entry in the layers.
+            let mut layers_rows = Vec::with_capacity(layers.len() + 1);
+            let mut layer_start = 0;
+            for layer_end in layer_ends.iter().copied().chain(Some(files.len())) {
+                layers_rows.push(parse_files(&files[layer_start..layer_end], &mut errors)?);
+                layer_start = layer_end;
+            }
+            errors.finish()?;
+            for rows in &layers_rows {
+                self.entry_stats.record_parsed(rows);
+            }
+            rescale_layers(layers, &mut layers_rows, &self.options.layer_cost_scaling)?;
+            let rows = merge_rows(layers_rows, self.options.duplicate_policy);
+            self.entry_stats.record_merged(&rows);
+            self.write_rows(&patches, rows, &base_dir.join("matrix.def"), output_dir)
+        })?;
+        self.report_entry_stats();
+
+        self.measure_phase("matrix", &[base_dir.join("matrix.def")], output_dir, || {
+            self.build_cost_matrix(base_dir, output_dir)
+        })?;
+
+        Ok(seed_dates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use super::{CostScaling, LayerScalings, SourceLayer};
+    use crate::fixtures::write_fixture;
+    use crate::options::BuilderOptions;
+    use crate::reader::DictionaryReader;
+    use crate::IpadicNeologdBuilder;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-merge-{}-{}",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn parses_cost_scalings() {
+        assert_eq!(
+            "percentile".parse::<CostScaling>().unwrap(),
+            CostScaling::Percentile
+        );
+        assert_eq!(
+            "affine:0.5".parse::<CostScaling>().unwrap(),
+            CostScaling::Affine {
+                scale: 0.5,
+                offset: 0.0
+            }
+        );
+        assert_eq!(
+            "affine:2:-100".parse::<CostScaling>().unwrap(),
+            CostScaling::Affine {
+                scale: 2.0,
+                offset: -100.0
+            }
+        );
+        for invalid in [
+            "",
+            "affine",
+            "affine:x",
+            "affine:1:2:3",
+            "percentile:1",
+            "log",
+        ] {
+            assert!(invalid.parse::<CostScaling>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn rescales_costs_of_layers() {
+        let affine = CostScaling::Affine {
+            scale: 2.0,
+            offset: -100.0,
+        };
+        assert_eq!(affine.scale(1000, &[], &[]), 1900);
+        assert_eq!(affine.scale(20000, &[], &[]), i32::from(i16::MAX));
+
+        let layers = vec![
+            SourceLayer::new("base", Path::new("base")),
+            SourceLayer::new("custom", Path::new("custom")),
+            SourceLayer::new("user", Path::new("user")),
+        ];
+        let mut scalings = BTreeMap::new();
+        scalings.insert("custom".to_string(), CostScaling::Percentile);
+        let mut layer_scalings = LayerScalings::new(&layers, &scalings).unwrap();
+        for cost in [5000, 1000, 3000, 2000, 4000] {
+            layer_scalings.record_cost(0, cost);
+        }
+        for cost in [30, 10, 20] {
+            layer_scalings.record_cost(1, cost);
+            layer_scalings.record_cost(2, cost);
+        }
+        layer_scalings.sort_costs();
+        let scaled: Vec<i32> = [10, 20, 25, 30]
+            .iter()
+            .map(|cost| layer_scalings.scale(1, *cost))
+            .collect();
+        assert_eq!(scaled, vec![1000, 3000, 5000, 5000]);
+        assert_eq!(layer_scalings.scale(0, 1234), 1234);
+        assert_eq!(layer_scalings.scale(2, 20), 20);
+        assert_eq!(layer_scalings.scale(3, 20), 20);
+
+        scalings.insert("system".to_string(), CostScaling::Percentile);
+        assert!(LayerScalings::new(&layers, &scalings).is_err());
+    }
+
+    /// Writes the fixture as the base layer and `custom_csv` as the custom layer.
+    fn write_layers(base_dir: &Path, custom_csv: &str) -> Vec<SourceLayer> {
+        let base_input_dir = base_dir.join("base");
+        write_fixture(&base_input_dir).unwrap();
+        let custom_input_dir = base_dir.join("custom");
+        fs::create_dir_all(&custom_input_dir).unwrap();
+        fs::write(custom_input_dir.join("custom.csv"), custom_csv).unwrap();
+        vec![
+            SourceLayer::new("base", &base_input_dir),
+            SourceLayer::new("custom", &custom_input_dir),
+        ]
+    }
+
+    #[test]
+    fn merged_builds_add_the_symbol_entries() {
+        let base_dir = temp_dir("symbols");
+        let layers = write_layers(&base_dir, "(^_^),1,1,1000,名詞,一般,*,*,*,*,(^_^),*,*\n");
+        let output_dir = base_dir.join("output");
+        let options = BuilderOptions {
+            emoji_entries: true,
+            kaomoji_entries: true,
+            ..BuilderOptions::default()
+        };
+        IpadicNeologdBuilder::with_options(options)
+            .build_merged_dictionary(&layers, &output_dir)
+            .unwrap();
+
+        let reader = DictionaryReader::open(&output_dir).unwrap();
+        for symbol in ["(^o^)", "m(_ _)m", "😀"] {
+            let entries = reader.lookup(symbol);
+            assert_eq!(entries.len(), 1, "{} is missing", symbol);
+            let details = reader.details(entries[0].word_id.0).unwrap();
+            assert_eq!(&details[..2], &["記号", "一般"]);
+        }
+        // A symbol with an entry in a layer keeps it instead of the generated one.
+        let entries = reader.lookup("(^_^)");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].word_cost, 1000);
+        assert_eq!(reader.lookup("東京").len(), 1);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn merged_builds_add_the_numeric_entries() {
+        let base_dir = temp_dir("numeric");
+        // The context ids of the numeric entries come from the 名詞,数 entry of a layer.
+        let layers = write_layers(
+            &base_dir,
+            "一,2,2,3500,名詞,数,*,*,*,*,一,イチ,イチ\n3個,1,1,1000,名詞,一般,*,*,*,*,3個,サンコ,サンコ\n",
+        );
+        let output_dir = base_dir.join("output");
+        let options = BuilderOptions {
+            numeric_entries: true,
+            ..BuilderOptions::default()
+        };
+        IpadicNeologdBuilder::with_options(options)
+            .build_merged_dictionary(&layers, &output_dir)
+            .unwrap();
+
+        let reader = DictionaryReader::open(&output_dir).unwrap();
+        for surface in ["2個", "１００人", "2024年"] {
+            let entries = reader.lookup(surface);
+            assert_eq!(entries.len(), 1, "{} is missing", surface);
+            assert_eq!(entries[0].cost_id, 2);
+            let details = reader.details(entries[0].word_id.0).unwrap();
+            assert_eq!(&details[..2], &["名詞", "数"]);
+        }
+        // A number with an entry in a layer keeps it instead of the generated one.
+        let entries = reader.lookup("3個");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].word_cost, 1000);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
@@ -0,0 +1,211 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+/// Measurements of one phase of a build.
+#[derive(Debug, Clone)]
+pub struct PhaseMetrics {
+    /// `chardef`, `unk`, `dict`, `matrix` or `finish`.
+    pub phase: &'static str,
+    pub duration: Duration,
+    /// Size of the source files read by the phase.
+    pub bytes_read: u64,
+    /// Growth of the output directory during the phase.
+    pub bytes_written: u64,
+    /// Peak resident memory of the process at the end of the phase, where available.
+    pub peak_memory: Option<u64>,
+}
+
+/// Receives the measurements of each build phase, set with
+/// `IpadicNeologdBuilder::with_metrics`.
+pub trait Metrics {
+    fn record_phase(&self, metrics: &PhaseMetrics);
+}
+
+/// Writes the phase measurements to a file in the Prometheus text format, for the textfile
+/// collector of the node exporter. The file is rewritten after each phase.
+pub struct PrometheusTextfile {
+    path: PathBuf,
+    phases: Mutex<Vec<PhaseMetrics>>,
+}
+
+impl PrometheusTextfile {
+    pub fn new(path: &Path) -> Self {
+        PrometheusTextfile {
+            path: path.to_path_buf(),
+            phases: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn render(phases: &[PhaseMetrics]) -> String {
+        let mut text = String::new();
+        let mut metric = |name: &str, help: &str, value: &dyn Fn(&PhaseMetrics) -> Option<f64>| {
+            text.push_str(&format!(
+                "# HELP {} {}\n# TYPE {} gauge\n",
+                name, help, name
+            ));
+            for phase in phases {
+                if let Some(value) = value(phase) {
+                    text.push_str(&format!(
+                        "{}{{phase=\"{}\"}} {}\n",
+                        name, phase.phase, value
+                    ));
+                }
+            }
+        };
+        metric(
+            "lindera_neologd_build_phase_duration_seconds",
+            "Duration of the build phase.",
+            &|phase| Some(phase.duration.as_secs_f64()),
+        );
+        metric(
+            "lindera_neologd_build_phase_read_bytes",
+            "Size of the source files read by the build phase.",
+            &|phase| Some(phase.bytes_read as f64),
+        );
+        metric(
+            "lindera_neologd_build_phase_written_bytes",
+            "Bytes written to the output directory by the build phase.",
+            &|phase| Some(phase.bytes_written as f64),
+        );
+        metric(
+            "lindera_neologd_build_phase_peak_memory_bytes",
+            "Peak resident memory of the builder at the end of the build phase.",
+            &|phase| phase.peak_memory.map(|bytes| bytes as f64),
+        );
+        text
+    }
+
+    fn write(&self, text: &str) -> LinderaResult<()> {
+        // Written aside and renamed, so that the collector never reads a partial file.
+        let tmp_path = self.path.with_extension("prom.tmp");
+        let mut file = fs::File::create(&tmp_path)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        file.write_all(text.as_bytes())
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        fs::rename(&tmp_path, &self.path)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))
+    }
+}
+
+impl Metrics for PrometheusTextfile {
+    fn record_phase(&self, metrics: &PhaseMetrics) {
+        let text = match self.phases.lock() {
+            Ok(mut phases) => {
+                phases.push(metrics.clone());
+                PrometheusTextfile::render(&phases)
+            }
+            Err(_err) => return,
+        };
+        if let Err(err) = self.write(&text) {
+            println!("failed to write {:?}: {}", self.path, err);
+        }
+    }
+}
+
+/// The size of a file, or 0 if it does not exist.
+pub(crate) fn file_size(path: &Path) -> u64 {
+    fs::metadata(path)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0)
+}
+
+/// The total size of the files under `dir`.
+pub(crate) fn dir_size(dir: &Path) -> u64 {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_err) => return 0,
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let path = entry.path();
+            if path.is_dir() {
+                dir_size(&path)
+            } else {
+                file_size(&path)
+            }
+        })
+        .sum()
+}
+
+/// The peak resident memory of the process, from `/proc/self/status` on Linux.
+pub(crate) fn peak_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes: u64 = line
+        .trim_start_matches("VmHWM:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+
+    use super::{dir_size, Metrics, PhaseMetrics, PrometheusTextfile};
+
+    fn phase(phase: &'static str, seconds: u64, peak_memory: Option<u64>) -> PhaseMetrics {
+        PhaseMetrics {
+            phase,
+            duration: Duration::from_millis(seconds * 1000 + 500),
+            bytes_read: 1024,
+            bytes_written: 2048,
+            peak_memory,
+        }
+    }
+
+    #[test]
+    fn renders_the_prometheus_text_format() {
+        let text = PrometheusTextfile::render(&[
+            phase("chardef", 0, None),
+            phase("dict", 2, Some(1 << 20)),
+        ]);
+        assert!(text.starts_with(
+            "# HELP lindera_neologd_build_phase_duration_seconds Duration of the build phase.\n\
+             # TYPE lindera_neologd_build_phase_duration_seconds gauge\n\
+             lindera_neologd_build_phase_duration_seconds{phase=\"chardef\"} 0.5\n\
+             lindera_neologd_build_phase_duration_seconds{phase=\"dict\"} 2.5\n"
+        ));
+        assert!(text.contains("lindera_neologd_build_phase_written_bytes{phase=\"dict\"} 2048\n"));
+        assert!(text
+            .contains("lindera_neologd_build_phase_peak_memory_bytes{phase=\"dict\"} 1048576\n"));
+        assert!(!text.contains("lindera_neologd_build_phase_peak_memory_bytes{phase=\"chardef\"}"));
+    }
+
+    #[test]
+    fn rewrites_the_textfile_after_each_phase() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-metrics-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&base_dir);
+        fs::create_dir_all(base_dir.join("sub")).unwrap();
+        let path = base_dir.join("build.prom");
+        let textfile = PrometheusTextfile::new(&path);
+
+        textfile.record_phase(&phase("chardef", 0, None));
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("{phase=\"chardef\"}"));
+        textfile.record_phase(&phase("unk", 0, None));
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("{phase=\"chardef\"}") && text.contains("{phase=\"unk\"}"));
+        assert!(!base_dir.join("build.prom.tmp").exists());
+
+        fs::write(base_dir.join("sub").join("data"), vec![0; 100]).unwrap();
+        assert_eq!(dir_size(&base_dir), 100 + text.len() as u64);
+        assert_eq!(dir_size(&base_dir.join("missing")), 0);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
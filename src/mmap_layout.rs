@@ -0,0 +1,227 @@
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::io_tuning;
+use crate::layout::DictionaryLayout;
+
+/// The file holding all the artifacts, aligned for mapping them in place.
+pub const MMAP_FILENAME: &str = "dict.mmap";
+
+/// The first bytes of `dict.mmap`.
+pub const MMAP_MAGIC: &[u8; 8] = b"LNDRMMAP";
+
+/// The version of the layout of `dict.mmap`, bumped on incompatible changes.
+pub const MMAP_FORMAT_VERSION: u32 = 1;
+
+/// The alignment of the sections, the page size, so that each section can also be mapped
+/// on its own and its `u32` and `i16` values are on their natural boundaries.
+pub const MMAP_ALIGNMENT: u64 = 4096;
+
+/// The width of the zero-padded section names of the section table.
+const SECTION_NAME_LEN: usize = 16;
+
+/// The width of the header before the section table: magic, version and section count.
+const HEADER_LEN: usize = 16;
+
+/// The width of an entry of the section table: name, offset and length.
+const SECTION_ENTRY_LEN: usize = SECTION_NAME_LEN + 16;
+
+/// A section of `dict.mmap`, at `offset` bytes from the start of the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MmapSection {
+    pub name: String,
+    pub offset: u64,
+    pub len: u64,
+}
+
+fn align(offset: u64) -> u64 {
+    offset.div_ceil(MMAP_ALIGNMENT) * MMAP_ALIGNMENT
+}
+
+/// The sections of the artifacts of `layout`, named as the fields of `DictionaryLayout`.
+fn section_artifacts(layout: &DictionaryLayout) -> [(&'static str, &str); 7] {
+    [
+        ("char_def", &layout.char_def),
+        ("unk", &layout.unk),
+        ("dict_da", &layout.dict_da),
+        ("dict_vals", &layout.dict_vals),
+        ("dict_words", &layout.dict_words),
+        ("dict_wordsidx", &layout.dict_wordsidx),
+        ("matrix", &layout.matrix),
+    ]
+}
+
+/// Writes the artifacts of `layout` in `output_dir` into `dict.mmap`, so that mobile apps
+/// can map the dictionary directly instead of parsing and copying it.
+///
+/// The file starts with a fixed-width header, all little-endian: the magic `LNDRMMAP`, the
+/// `u32` format version and the `u32` number of sections, followed by the section table of
+/// 16-byte zero-padded names and `u64` offsets and lengths. Each section starts at a
+/// multiple of `MMAP_ALIGNMENT` and is padded with zeros up to the next one.
+pub fn write_mmap_container(
+    output_dir: &Path,
+    layout: &DictionaryLayout,
+    buffer_size: usize,
+) -> LinderaResult<()> {
+    let artifacts = section_artifacts(layout);
+    let mut sections = Vec::with_capacity(artifacts.len());
+    let mut offset = align((HEADER_LEN + SECTION_ENTRY_LEN * artifacts.len()) as u64);
+    for (name, artifact) in &artifacts {
+        let len = output_dir
+            .join(artifact)
+            .metadata()
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?
+            .len();
+        sections.push(MmapSection {
+            name: name.to_string(),
+            offset,
+            len,
+        });
+        offset = align(offset + len);
+    }
+
+    let mmap_path = output_dir.join(MMAP_FILENAME);
+    println!("creating {:?}", mmap_path);
+    let write = || -> io::Result<()> {
+        let mut wtr = io_tuning::create_writer(&mmap_path, buffer_size, Some(offset))?;
+        wtr.write_all(MMAP_MAGIC)?;
+        wtr.write_u32::<LittleEndian>(MMAP_FORMAT_VERSION)?;
+        wtr.write_u32::<LittleEndian>(sections.len() as u32)?;
+        for section in &sections {
+            let mut name = [0u8; SECTION_NAME_LEN];
+            name[..section.name.len()].copy_from_slice(section.name.as_bytes());
+            wtr.write_all(&name)?;
+            wtr.write_u64::<LittleEndian>(section.offset)?;
+            wtr.write_u64::<LittleEndian>(section.len)?;
+        }
+        let mut written = (HEADER_LEN + SECTION_ENTRY_LEN * sections.len()) as u64;
+        for (section, (_, artifact)) in sections.iter().zip(&artifacts) {
+            io::copy(&mut io::repeat(0).take(section.offset - written), &mut wtr)?;
+            let mut rdr = io_tuning::open_reader(&output_dir.join(artifact), buffer_size)?;
+            written = section.offset + io::copy(&mut rdr, &mut wtr)?;
+        }
+        io::copy(&mut io::repeat(0).take(offset - written), &mut wtr)?;
+        wtr.flush()
+    };
+    write().map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))
+}
+
+/// Reads the section table of a `dict.mmap` file mapped or read into `data`.
+pub fn read_mmap_sections(data: &[u8]) -> LinderaResult<Vec<MmapSection>> {
+    let invalid = |message: &str| {
+        LinderaErrorKind::Content.with_error(anyhow::anyhow!("{}: {}", MMAP_FILENAME, message))
+    };
+    if data.len() < HEADER_LEN || &data[..MMAP_MAGIC.len()] != MMAP_MAGIC {
+        return Err(invalid("not a memory-mappable dictionary"));
+    }
+    let version = LittleEndian::read_u32(&data[8..12]);
+    if version != MMAP_FORMAT_VERSION {
+        return Err(invalid(&format!("unsupported format version {}", version)));
+    }
+    let count = LittleEndian::read_u32(&data[12..16]) as usize;
+    let table = data
+        .get(HEADER_LEN..HEADER_LEN + SECTION_ENTRY_LEN * count)
+        .ok_or_else(|| invalid("truncated section table"))?;
+
+    let mut sections = Vec::with_capacity(count);
+    for entry in table.chunks(SECTION_ENTRY_LEN) {
+        let name = &entry[..SECTION_NAME_LEN];
+        let name_len = name
+            .iter()
+            .position(|b| *b == 0)
+            .unwrap_or(SECTION_NAME_LEN);
+        let section = MmapSection {
+            name: String::from_utf8_lossy(&name[..name_len]).to_string(),
+            offset: LittleEndian::read_u64(&entry[SECTION_NAME_LEN..SECTION_NAME_LEN + 8]),
+            len: LittleEndian::read_u64(&entry[SECTION_NAME_LEN + 8..]),
+        };
+        if !section.offset.is_multiple_of(MMAP_ALIGNMENT)
+            || section.offset + section.len > data.len() as u64
+        {
+            return Err(invalid(&format!(
+                "section {} is out of bounds",
+                section.name
+            )));
+        }
+        sections.push(section);
+    }
+    Ok(sections)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{read_mmap_sections, write_mmap_container, MMAP_ALIGNMENT, MMAP_FILENAME};
+    use crate::fixtures::build_fixture;
+    use crate::layout::DictionaryLayout;
+    use crate::options::BuilderOptions;
+
+    #[test]
+    fn sections_hold_the_artifacts_aligned() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-mmap-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        build_fixture(&input_dir, &output_dir, BuilderOptions::default()).unwrap();
+        let layout = DictionaryLayout::default();
+        write_mmap_container(&output_dir, &layout, 64).unwrap();
+
+        let data = fs::read(output_dir.join(MMAP_FILENAME)).unwrap();
+        assert_eq!(data.len() as u64 % MMAP_ALIGNMENT, 0);
+        let sections = read_mmap_sections(&data).unwrap();
+        let names: Vec<&str> = sections
+            .iter()
+            .map(|section| section.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "char_def",
+                "unk",
+                "dict_da",
+                "dict_vals",
+                "dict_words",
+                "dict_wordsidx",
+                "matrix"
+            ]
+        );
+        for (section, artifact) in sections.iter().zip([
+            &layout.char_def,
+            &layout.unk,
+            &layout.dict_da,
+            &layout.dict_vals,
+            &layout.dict_words,
+            &layout.dict_wordsidx,
+            &layout.matrix,
+        ]) {
+            assert_eq!(section.offset % MMAP_ALIGNMENT, 0);
+            let (start, end) = (
+                section.offset as usize,
+                (section.offset + section.len) as usize,
+            );
+            assert_eq!(
+                data[start..end],
+                fs::read(output_dir.join(artifact)).unwrap()[..]
+            );
+        }
+
+        let error = |data: &[u8]| read_mmap_sections(data).unwrap_err().to_string();
+        assert!(error(b"LNDRMMAX\x01\0\0\0\0\0\0\0").contains("not a memory-mappable dictionary"));
+        let mut other_version = data.clone();
+        other_version[8] = 2;
+        assert!(error(&other_version).contains("unsupported format version 2"));
+        assert!(error(&data[..100]).contains("truncated section table"));
+        let matrix = sections.last().unwrap();
+        let truncated = &data[..(matrix.offset + matrix.len - 1) as usize];
+        assert!(error(truncated).contains("section matrix is out of bounds"));
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
@@ -0,0 +1,96 @@
+use std::path::PathBuf;
+
+use napi::bindgen_prelude::AsyncTask;
+use napi::{Env, Error, Result, Task};
+use napi_derive::napi;
+
+use lindera_core::dictionary_builder::DictionaryBuilder;
+
+use crate::options::BuilderOptions;
+use crate::IpadicNeologdBuilder;
+
+#[napi(object, js_name = "BuilderOptions")]
+pub struct JsBuilderOptions {
+    pub comment_prefix: Option<String>,
+    pub package: Option<bool>,
+}
+
+fn builder(options: Option<JsBuilderOptions>) -> IpadicNeologdBuilder {
+    let mut builder_options = BuilderOptions::default();
+    if let Some(options) = options {
+        builder_options.comment_prefix = options.comment_prefix;
+        builder_options.package = options.package.unwrap_or(false);
+    }
+    IpadicNeologdBuilder::with_options(builder_options)
+}
+
+pub struct BuildDictionaryTask {
+    builder: IpadicNeologdBuilder,
+    input_dir: PathBuf,
+    output_dir: PathBuf,
+}
+
+impl Task for BuildDictionaryTask {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> Result<()> {
+        self.builder
+            .build_dictionary(&self.input_dir, &self.output_dir)
+            .map_err(|err| Error::from_reason(err.to_string()))
+    }
+
+    fn resolve(&mut self, _env: Env, _output: ()) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub struct BuildUserDictionaryTask {
+    builder: IpadicNeologdBuilder,
+    input_file: PathBuf,
+    output_file: PathBuf,
+}
+
+impl Task for BuildUserDictionaryTask {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> Result<()> {
+        self.builder
+            .build_user_dictionary(&self.input_file, &self.output_file)
+            .map_err(|err| Error::from_reason(err.to_string()))
+    }
+
+    fn resolve(&mut self, _env: Env, _output: ()) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Builds the dictionary from `inputDir` into `outputDir` on the libuv thread pool.
+#[napi]
+pub fn build_dictionary(
+    input_dir: String,
+    output_dir: String,
+    options: Option<JsBuilderOptions>,
+) -> AsyncTask<BuildDictionaryTask> {
+    AsyncTask::new(BuildDictionaryTask {
+        builder: builder(options),
+        input_dir: PathBuf::from(input_dir),
+        output_dir: PathBuf::from(output_dir),
+    })
+}
+
+/// Builds a user dictionary from the CSV `inputFile` into the binary `outputFile` on the
+/// libuv thread pool.
+#[napi]
+pub fn build_user_dict(
+    input_file: String,
+    output_file: String,
+    options: Option<JsBuilderOptions>,
+) -> AsyncTask<BuildUserDictionaryTask> {
+    AsyncTask::new(BuildUserDictionaryTask {
+        builder: builder(options),
+        input_file: PathBuf::from(input_file),
+        output_file: PathBuf::from(output_file),
+    })
+}
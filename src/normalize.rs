@@ -0,0 +1,121 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::normalize_fields;
+
+/// A step of the normalization chain of a CSV field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NormalizationStep {
+    /// Unicode NFKC, which also folds full-width alphanumerics and half-width katakana.
+    Nfkc,
+    /// Replaces every occurrence of each key with its value.
+    CharMap(BTreeMap<String, String>),
+    /// Folds full-width ASCII characters to ASCII.
+    WidthFolding,
+}
+
+impl NormalizationStep {
+    pub fn apply(&self, field: &str) -> String {
+        match self {
+            NormalizationStep::Nfkc => field.nfkc().collect(),
+            NormalizationStep::CharMap(map) => {
+                map.iter().fold(field.to_string(), |field, (from, to)| {
+                    field.replace(from, to)
+                })
+            }
+            NormalizationStep::WidthFolding => field
+                .chars()
+                .map(|c| match c {
+                    '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+                    _ => c,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Normalizes the fields of CSV entry lines: `―` and `～` in the fields at `euc_jp_fields`,
+/// then the chains of steps of the fields.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LineNormalizer {
+    pub(crate) euc_jp_fields: Vec<usize>,
+    pub(crate) chains: Vec<(usize, Vec<NormalizationStep>)>,
+}
+
+impl LineNormalizer {
+    pub(crate) fn normalize<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let line = normalize_fields(line, &self.euc_jp_fields);
+        if self.chains.is_empty() {
+            return line;
+        }
+
+        let mut fields: Vec<String> = line.split(',').map(|field| field.to_string()).collect();
+        let mut changed = false;
+        for (idx, chain) in &self.chains {
+            if let Some(field) = fields.get_mut(*idx) {
+                let normalized = chain
+                    .iter()
+                    .fold(field.clone(), |field, step| step.apply(&field));
+                // A comma, e.g. from NFKC of `，`, would split the field.
+                if normalized != *field && !normalized.contains(',') {
+                    *field = normalized;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return line;
+        }
+        Cow::Owned(fields.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::collections::BTreeMap;
+
+    use super::{LineNormalizer, NormalizationStep};
+
+    #[test]
+    fn applies_each_step() {
+        assert_eq!(NormalizationStep::Nfkc.apply("ｶﾀｶﾅＡ１"), "カタカナA1");
+        assert_eq!(
+            NormalizationStep::WidthFolding.apply("ｶﾀｶﾅＡ１！"),
+            "ｶﾀｶﾅA1!"
+        );
+        let mut map = BTreeMap::new();
+        map.insert("ヴァ".to_string(), "バ".to_string());
+        map.insert("ヶ".to_string(), "ケ".to_string());
+        assert_eq!(
+            NormalizationStep::CharMap(map).apply("ヴァイオリン三ヶ月"),
+            "バイオリン三ケ月"
+        );
+    }
+
+    #[test]
+    fn normalizes_the_fields_of_lines() {
+        let normalizer = LineNormalizer {
+            euc_jp_fields: vec![0],
+            chains: vec![
+                (0, vec![NormalizationStep::WidthFolding]),
+                (11, vec![NormalizationStep::Nfkc]),
+            ],
+        };
+        assert_eq!(
+            normalizer.normalize("ＮＨＫ～,1,1,3000,名詞,*,*,*,*,*,ＮＨＫ,ｴﾇｴｲﾁｹｰ,*"),
+            "NHK〜,1,1,3000,名詞,*,*,*,*,*,ＮＨＫ,エヌエイチケー,*"
+        );
+        assert_eq!(
+            normalizer.normalize("Ａ，Ｂ,1,1,3000,名詞,*,*,*,*,*,*,*,*"),
+            "Ａ，Ｂ,1,1,3000,名詞,*,*,*,*,*,*,*,*"
+        );
+        assert!(matches!(
+            normalizer.normalize("東京,1,1,3000,名詞,*,*,*,*,*,東京,トウキョウ,*"),
+            Cow::Borrowed(_)
+        ));
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+
+/// The name of the generated numeric entries, recorded as their provenance.
+pub const NUMERIC_FILENAME: &str = "numeric.csv";
+
+/// The word cost of the generated numeric entries when `BuilderOptions::numeric_cost` is not
+/// set.
+pub const DEFAULT_NUMERIC_COST: i32 = 3000;
+
+/// Counters combined with the numbers from 0 to `MAX_COUNTED`.
+const COUNTERS: [&str; 20] = [
+    "個", "人", "回", "円", "歳", "本", "枚", "件", "台", "冊", "匹", "杯", "階", "番", "月", "日",
+    "時", "分", "秒", "点",
+];
+
+const MAX_COUNTED: u32 = 100;
+
+/// The years combined with `年`.
+const YEARS: (u32, u32) = (1900, 2100);
+
+/// The surfaces of the numbers with a counter, in ASCII and full-width digits.
+pub fn numeric_surfaces() -> Vec<String> {
+    let mut surfaces = Vec::new();
+    for counter in COUNTERS {
+        for number in 0..=MAX_COUNTED {
+            surfaces.push(format!("{}{}", number, counter));
+        }
+    }
+    for year in YEARS.0..=YEARS.1 {
+        surfaces.push(format!("{}年", year));
+    }
+    let full_width: Vec<String> = surfaces.iter().map(|surface| full_width(surface)).collect();
+    surfaces.extend(full_width);
+    surfaces
+}
+
+fn full_width(surface: &str) -> String {
+    surface
+        .chars()
+        .map(|c| match c {
+            '0'..='9' => char::from_u32(c as u32 + 0xFEE0).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// The context ids of the `名詞,数` entries of the CSV entry `lines`, if there is one.
+pub(crate) fn number_context_ids<'a, I>(lines: I) -> Option<(&'a str, &'a str)>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    lines.into_iter().find_map(|line| {
+        let fields: Vec<&str> = line.split(',').collect();
+        match fields.as_slice() {
+            [_, left_id, right_id, _, "名詞", "数", ..] => Some((*left_id, *right_id)),
+            _ => None,
+        }
+    })
+}
+
+/// The CSV entry lines of the numeric surfaces, as `名詞,数` entries of the context ids
+/// `context_ids` and the word cost `cost`. Surfaces with an entry in `surfaces` are left
+/// out.
+pub(crate) fn numeric_lines(
+    context_ids: (&str, &str),
+    cost: i32,
+    surfaces: &HashSet<&str>,
+) -> Vec<String> {
+    numeric_surfaces()
+        .into_iter()
+        .filter(|surface| !surfaces.contains(surface.as_str()))
+        .map(|surface| {
+            format!(
+                "{},{},{},{},名詞,数,*,*,*,*,{},*,*",
+                surface, context_ids.0, context_ids.1, cost, surface
+            )
+        })
+        .collect()
+}
+
+/// Checks that `char.def` groups runs of digits into one unknown word, as the numeric
+/// entries assume, returning the problem if it does not.
+pub(crate) fn check_numeric_category(char_def: &str) -> Option<String> {
+    let fields = char_def
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|fields| fields.first() == Some(&"NUMERIC"));
+    match fields.as_deref() {
+        None => Some("char.def has no NUMERIC category".to_string()),
+        Some([_, _, "1", ..]) => None,
+        Some(_) => Some(
+            "the NUMERIC category of char.def does not group digits, so numbers outside the numeric entries are split into single digits"
+                .to_string(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{check_numeric_category, number_context_ids, numeric_lines, numeric_surfaces};
+    use crate::fixtures::FIXTURE_CHAR_DEF;
+
+    #[test]
+    fn generates_numbers_with_counters_and_years() {
+        let surfaces = numeric_surfaces();
+        for surface in ["0個", "100点", "1900年", "2100年", "１２人", "２０２０年"] {
+            assert!(surfaces.iter().any(|s| s == surface), "{}", surface);
+        }
+        assert!(!surfaces.iter().any(|s| s == "101個"));
+
+        let lines = vec![
+            "東京,1,1,3000,名詞,固有名詞,地域,一般,*,*,東京,トウキョウ,トーキョー".to_string(),
+            "一,1285,1285,3000,名詞,数,*,*,*,*,一,イチ,イチ".to_string(),
+        ];
+        let context_ids = number_context_ids(&lines).unwrap();
+        assert_eq!(context_ids, ("1285", "1285"));
+        assert_eq!(number_context_ids(&lines[..1]), None);
+
+        let existing: HashSet<&str> = vec!["0個"].into_iter().collect();
+        let lines = numeric_lines(context_ids, 3000, &existing);
+        assert_eq!(lines.len(), surfaces.len() - 1);
+        assert_eq!(lines[0], "1個,1285,1285,3000,名詞,数,*,*,*,*,1個,*,*");
+    }
+
+    #[test]
+    fn checks_that_numeric_groups_digits() {
+        assert_eq!(
+            check_numeric_category("DEFAULT 0 1 0\nNUMERIC 1 1 0 # digits\n"),
+            None
+        );
+        assert!(check_numeric_category("NUMERIC 1 0 0\n")
+            .unwrap()
+            .contains("does not group digits"));
+        assert_eq!(
+            check_numeric_category("DEFAULT 0 1 0\n# NUMERIC 1 1 0\n").as_deref(),
+            Some("char.def has no NUMERIC category")
+        );
+        assert_eq!(check_numeric_category(FIXTURE_CHAR_DEF), None);
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::budget::PruningStrategy;
+use crate::codec::{DetailCodec, DetailFormat};
+use crate::columnar::DETAIL_FIELDS;
+use crate::compat::LinderaTarget;
+use crate::disk_space::DiskSpaceCheck;
+use crate::elasticsearch::ElasticsearchExport;
+use crate::endian::Endianness;
+use crate::folding::SurfaceFolding;
+use crate::io_tuning;
+use crate::kana::KanaVariants;
+use crate::layout::DictionaryLayout;
+use crate::merge::{CostScaling, DuplicatePolicy};
+use crate::normalize::{LineNormalizer, NormalizationStep};
+use crate::packing::ValuePacking;
+use crate::placeholder::PlaceholderHandling;
+use crate::pos::PosMapping;
+#[cfg(feature = "signing")]
+use crate::signature::SigningKey;
+use crate::skip::{self, SkipRule};
+use crate::transliterate::ReadingScript;
+use crate::unk::UnkCostAdjustment;
+use crate::warning::{WarningAction, WarningKind};
+use crate::CSV_FIELD_NAMES;
+
+/// Options controlling how `IpadicNeologdBuilder` reads the source files and builds
+/// the dictionary.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BuilderOptions {
+    /// Lines of CSV files starting with this prefix are treated as comments and skipped.
+    /// `None` disables comment support, since some surfaces may start with `#`.
+    pub comment_prefix: Option<String>,
+    /// Directories whose CSV files are built together with the CSV files of the input
+    /// directory, which provides `char.def`, `unk.def` and `matrix.def`.
+    pub extra_input_dirs: Vec<PathBuf>,
+    /// Version of the lexicon, such as the NEologd seed tag `v0.0.7-20200910`, recorded in the
+    /// manifest and in `version.txt`, and naming the package instead of the builder version.
+    pub dictionary_version: Option<String>,
+    /// File merged over `char.def` before parsing. Defaults to `char_overrides.def` in the
+    /// input directory when it exists.
+    pub char_overrides: Option<PathBuf>,
+    /// Cost adjustments of the `unk.def` entries, by character category.
+    pub unk_costs: BTreeMap<String, UnkCostAdjustment>,
+    /// How rows with the same surface and POS are resolved when merging source layers.
+    pub duplicate_policy: DuplicatePolicy,
+    /// Rescaling of the word costs of the source layers of merged builds, by layer name,
+    /// applied before merging so that layers whose costs were trained separately compete
+    /// on the scale of the first layer.
+    pub layer_cost_scaling: BTreeMap<String, CostScaling>,
+    /// The fields of the CSV entries in which `―` and `～` are normalized to `—` and `〜`, by
+    /// their names in `CSV_FIELD_NAMES`. Defaults to the surface only, since the other fields
+    /// may rightly use them.
+    pub normalized_fields: Option<Vec<String>>,
+    /// Chains of normalization steps applied, in order, to the CSV fields of the names in
+    /// `CSV_FIELD_NAMES`, after the normalization of `―` and `～`. Readings can so stay
+    /// katakana while the surfaces are folded.
+    pub field_normalization: BTreeMap<String, Vec<NormalizationStep>>,
+    /// Fills readings and pronunciations left as `*` from kana-only surfaces or the reading
+    /// provider of the builder, reporting the entries left unresolved.
+    pub fill_readings: bool,
+    /// Patch files applied, in order, over the source entries before building.
+    pub patch_files: Vec<PathBuf>,
+    /// File of preferred surfaces, one per line, whose entries get their word cost lowered.
+    pub preferred_terms: Option<PathBuf>,
+    /// The word cost subtracted from the entries of preferred terms. Defaults to
+    /// `DEFAULT_PREFERRED_COST_DELTA`.
+    pub preferred_cost_delta: Option<i32>,
+    /// Term-frequency file of a target corpus, with a surface and its count separated by a
+    /// tab per line, from which the word costs of the entries of its surfaces are
+    /// recomputed.
+    pub frequency_file: Option<PathBuf>,
+    /// The word cost per unit of negative log relative frequency. Defaults to
+    /// `DEFAULT_FREQUENCY_COST_SCALE`.
+    pub frequency_cost_scale: Option<f64>,
+    /// Directory of a built dictionary whose entries, by surface and POS, are left out of
+    /// the build, so that it yields the delta lexicon, e.g. a small user dictionary of the
+    /// new terms of a large term dump.
+    pub reference_dict_dir: Option<PathBuf>,
+    /// Folds the surfaces to match text normalized before tokenizing. The original surface is
+    /// appended to the word details, after the compound splits.
+    pub surface_folding: Option<SurfaceFolding>,
+    /// Appends `1` to the word details of the entries from NEologd seed files and `0` to the
+    /// others, after the original surface, so that applications can tell them apart.
+    pub neologd_flag: bool,
+    /// Transliterates the readings to this script during the build, in place of the original
+    /// unless `keep_original_reading` is set.
+    pub reading_script: Option<ReadingScript>,
+    /// Keeps the original reading and appends the transliterated one to the word details,
+    /// after the NEologd flag.
+    pub keep_original_reading: bool,
+    /// How the `*` placeholders of empty fields are written to the word details, after the
+    /// appended columns. Lindera expects `Keep`, the default.
+    pub placeholders: PlaceholderHandling,
+    /// The detail fields written to the word details, in this order, by their names in
+    /// `columnar::DETAIL_FIELDS`, recorded in the manifest. Defaults to all of them. The tag
+    /// of the POS mapping and the appended columns are kept.
+    pub detail_fields: Option<Vec<String>>,
+    /// Generates entries of the kana-only surfaces written in the other kana script, for
+    /// informal text writing NEologd terms in hiragana or katakana.
+    pub kana_variants: Option<KanaVariants>,
+    /// The word cost added to the generated kana variants. Defaults to
+    /// `DEFAULT_KANA_VARIANT_PENALTY`.
+    pub kana_variant_penalty: Option<i32>,
+    /// File of variant rules expanding the entries into the spelling variations of their
+    /// surface.
+    pub variant_rules: Option<PathBuf>,
+    /// Adds entries for emoji, emoji with skin tones, keycaps and common flags, which the
+    /// unknown word processing splits apart.
+    pub emoji_entries: bool,
+    /// Adds entries for common kaomoji.
+    pub kaomoji_entries: bool,
+    /// The word cost of the emoji and kaomoji entries. Defaults to `DEFAULT_SYMBOL_COST`.
+    pub symbol_cost: Option<i32>,
+    /// Adds entries for the numbers up to 100 with common counters, such as `1個`, and for
+    /// the years 1900 to 2100, such as `2023年`, in ASCII and full-width digits.
+    pub numeric_entries: bool,
+    /// The word cost of the numeric entries. Defaults to `DEFAULT_NUMERIC_COST`.
+    pub numeric_cost: Option<i32>,
+    /// Appends the split points of compound surfaces into shorter entries to the word
+    /// details, after the provenance.
+    pub compound_splits: bool,
+    /// Additionally writes the index from readings to surfaces, `reading.da`/`reading.vals`.
+    pub reading_index: bool,
+    /// Additionally writes the word details as one column per field to `columns/`.
+    pub columnar_details: bool,
+    /// Additionally writes the entries as a kuromoji user dictionary and a synonym file for
+    /// Elasticsearch to `elasticsearch/`.
+    pub elasticsearch_export: Option<ElasticsearchExport>,
+    /// Additionally splits `dict.words`, `dict.wordsidx` and `dict.vals` into shards of this
+    /// many word_ids, indexed by `dict.shards.json`, for consumers loading them lazily.
+    pub shard_size: Option<usize>,
+    /// Mapping of the POS levels applied to the word details before serialization.
+    pub pos_mapping: Option<PosMapping>,
+    /// Keeps parsing past unparsable CSV lines, collecting up to this many errors across all
+    /// files, which are returned together as `BuildError::Multiple`. By default the build
+    /// fails at the first unparsable line.
+    pub max_errors: Option<usize>,
+    /// Also checks the CSV entries with the lint rules of the builder when validating,
+    /// reporting the findings in `ValidationReport::lint`.
+    pub lint: bool,
+    /// Rules leaving entries out of the dictionary. Defaults to every entry of the surfaces
+    /// of `SKIP_WORDS`.
+    pub skip_rules: Option<Vec<SkipRule>>,
+    /// Appends the source file of each entry, prefixed with the layer name in merged builds,
+    /// to its word details.
+    pub provenance: bool,
+    /// Encoding of the word details. Lindera loads `Bincode`, the default.
+    pub detail_format: DetailFormat,
+    /// Byte order of the integers of the binary artifacts, recorded in the manifest. Lindera
+    /// loads `Little`, the default.
+    pub endianness: Endianness,
+    /// Prepends a header with a magic, the format version and flags to each artifact, so that
+    /// loaders can detect incompatible artifacts. Lindera cannot load artifacts with headers.
+    pub artifact_headers: bool,
+    /// The lindera version the artifacts are written for, recorded in the manifest.
+    pub lindera_target: LinderaTarget,
+    /// How the entries of the surfaces are packed into the values of the double array.
+    /// `Indirect` lifts the limits of 31 entries per surface and 2^26 entries, but writes
+    /// `dict.valtable`, which Lindera does not load.
+    pub value_packing: ValuePacking,
+    /// Names of the output files.
+    pub layout: DictionaryLayout,
+    /// Additionally writes the artifacts into `dict.mmap`, with a fixed-width header and
+    /// page-aligned sections, for apps mapping the dictionary without a load step.
+    pub mmap_container: bool,
+    /// Maximum number of worker threads used for parsing. Defaults to the available
+    /// parallelism.
+    pub threads: Option<usize>,
+    /// Size in bytes of the read and write buffers of the large files. Defaults to
+    /// `DEFAULT_IO_BUFFER_SIZE`.
+    pub io_buffer_size: Option<usize>,
+    /// Approximate memory budget of the build in bytes. matrix.def is parsed in batches
+    /// fitting the budget, and the word details of builds estimated to exceed it are
+    /// streamed to disk instead of being buffered.
+    pub memory_budget: Option<u64>,
+    /// What the build does when the filesystem of the output directory lacks the disk space
+    /// it is estimated to need. Builds fail before any work by default.
+    pub disk_space_check: DiskSpaceCheck,
+    /// Directory the scratch directories of the build are created in: the sorted runs of the
+    /// external sort, the extracted sources of the server and the artifacts before they are
+    /// moved into place. Defaults to the output directory. The scratch directories are
+    /// removed when the build succeeds, fails or is cancelled.
+    pub scratch_dir: Option<PathBuf>,
+    /// Waits for another build holding the lock of the output directory to finish, instead
+    /// of failing with `BuildError::Locked`.
+    pub wait_for_lock: bool,
+    /// Shell commands run in order once the dictionary is built, receiving the
+    /// `BuildReport` as JSON on their standard input. See `hooks::ShellHook`.
+    pub post_build_hooks: Vec<String>,
+    /// Builds the double array in the memory-mapped `dict.da` with the construction state
+    /// bounded to its last blocks, instead of in memory, so that the last phase of huge
+    /// keysets fits machines with little memory. The written trie is checked against the
+    /// keyset.
+    pub da_spill: bool,
+    /// Prints the statistics of the trie after the double array is built: its nodes, the
+    /// fill rate and size of the double array, the longest surface and the prefixes with
+    /// the most branches.
+    pub trie_stats: bool,
+    /// Reads `dict.da` and `dict.vals` back once written and checks that every surface maps
+    /// to the offset and number of its entries re-derived from the keyset, and to the
+    /// entries written for it, failing the build otherwise.
+    pub paranoid: bool,
+    /// Orders the entries of each surface by word cost, then POS, then reading, instead of
+    /// keeping their input order, so that the word_ids of homonyms are the same whatever the
+    /// order of the input files.
+    pub sort_homonyms: bool,
+    /// File the sorted keyset of the double array, the surfaces and the values they map to,
+    /// is written to, to experiment with other index structures without parsing the sources.
+    pub keyset_export: Option<PathBuf>,
+    /// Maximum total size in bytes of the artifacts. Builds over the budget fail with the
+    /// largest files listed, unless a pruning strategy drops entries to fit it.
+    pub size_budget: Option<u64>,
+    /// Which entries are dropped to fit the size budget.
+    pub pruning: Option<PruningStrategy>,
+    /// Drops the entries whose word cost is above this, the rarest ones, before the size
+    /// budget is applied.
+    pub max_word_cost: Option<i32>,
+    /// How the warnings of each kind are handled. Kinds not listed are printed.
+    pub warnings: BTreeMap<WarningKind, WarningAction>,
+    /// Maximum number of warnings of each kind printed and collected.
+    pub warning_limit: Option<usize>,
+    /// Writes the JSONL event log `build.jsonl` to the output directory.
+    pub build_log: bool,
+    /// Directory to write `dictionary.rs` embedding the built dictionary to.
+    pub codegen_dir: Option<PathBuf>,
+    /// Compresses the artifacts embedded by `dictionary.rs`.
+    pub codegen_compress: bool,
+    /// Packages the output directory into a `.tar.gz` archive after the build.
+    pub package: bool,
+    /// The gzip level, 0 to 9, of the package and the compressed artifacts of
+    /// `dictionary.rs`. Defaults to 6 for the package and 9 for `dictionary.rs`.
+    pub compression_level: Option<u32>,
+    /// Plain-text corpus tokenized with the built dictionary to report its quality.
+    #[cfg(feature = "quality-check")]
+    pub corpus_file: Option<PathBuf>,
+    /// Previous build the corpus tokenization is compared against.
+    #[cfg(feature = "quality-check")]
+    pub previous_dict_dir: Option<PathBuf>,
+    /// 32-byte Ed25519 secret key used to sign the manifest and the artifacts. It is never
+    /// serialized, so it stays out of the written options.
+    #[cfg(feature = "signing")]
+    #[serde(skip_serializing)]
+    pub signing_key: Option<SigningKey>,
+    /// Object storage URL (e.g. `s3://bucket/prefix`) the built files are uploaded to.
+    #[cfg(feature = "object-store")]
+    pub sink_url: Option<String>,
+}
+
+fn csv_field_index(name: &str) -> LinderaResult<usize> {
+    CSV_FIELD_NAMES
+        .iter()
+        .position(|field| *field == name)
+        .ok_or_else(|| {
+            LinderaErrorKind::Args.with_error(anyhow::anyhow!("unknown CSV field {}", name))
+        })
+}
+
+fn detail_field_index(name: &str) -> LinderaResult<usize> {
+    DETAIL_FIELDS
+        .iter()
+        .position(|field| *field == name)
+        .ok_or_else(|| {
+            LinderaErrorKind::Args.with_error(anyhow::anyhow!("unknown detail field {}", name))
+        })
+}
+
+impl BuilderOptions {
+    /// Loads the options from a TOML file, or a YAML file when its extension is `.yaml` or
+    /// `.yml`, with the fields of `BuilderOptions` as keys. Fields left out keep their
+    /// default.
+    pub fn load(path: &Path) -> LinderaResult<BuilderOptions> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&content).map_err(|err| {
+                LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(
+                    "{}: {}",
+                    path.display(),
+                    err
+                ))
+            }),
+            _ => toml::from_str(&content).map_err(|err| {
+                LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(
+                    "{}: {}",
+                    path.display(),
+                    err
+                ))
+            }),
+        }
+    }
+
+    /// The number of threads to use, at least 1.
+    pub fn threads(&self) -> usize {
+        self.threads
+            .unwrap_or_else(|| {
+                std::thread::available_parallelism()
+                    .map(|threads| threads.get())
+                    .unwrap_or(1)
+            })
+            .max(1)
+    }
+
+    /// How the warnings of `kind` are handled.
+    pub fn warning_action(&self, kind: WarningKind) -> WarningAction {
+        self.warnings.get(&kind).copied().unwrap_or_default()
+    }
+
+    /// The skip rules of the options, or the default ones.
+    pub fn skip_rules(&self) -> Vec<SkipRule> {
+        self.skip_rules
+            .clone()
+            .unwrap_or_else(skip::default_skip_rules)
+    }
+
+    /// The indices of the fields of the CSV entries in which `―` and `～` are normalized.
+    pub fn normalized_field_indices(&self) -> LinderaResult<Vec<usize>> {
+        match &self.normalized_fields {
+            Some(names) => names.iter().map(|name| csv_field_index(name)).collect(),
+            None => Ok(vec![0]),
+        }
+    }
+
+    /// The indices in `columnar::DETAIL_FIELDS` of the detail fields written to the word
+    /// details, or `None` for all of them.
+    pub fn detail_field_indices(&self) -> LinderaResult<Option<Vec<usize>>> {
+        self.detail_fields
+            .as_ref()
+            .map(|names| names.iter().map(|name| detail_field_index(name)).collect())
+            .transpose()
+    }
+
+    pub(crate) fn line_normalizer(&self) -> LinderaResult<LineNormalizer> {
+        Ok(LineNormalizer {
+            euc_jp_fields: self.normalized_field_indices()?,
+            chains: self
+                .field_normalization
+                .iter()
+                .map(|(name, chain)| Ok((csv_field_index(name)?, chain.clone())))
+                .collect::<LinderaResult<_>>()?,
+        })
+    }
+
+    /// The codec of the word details, as the lindera target and the detail format select it.
+    pub fn detail_codec(&self) -> Box<dyn DetailCodec + Send + Sync> {
+        self.lindera_target.detail_codec(self.detail_format)
+    }
+
+    /// The size of the read and write buffers of the large files.
+    pub fn io_buffer_size(&self) -> usize {
+        self.io_buffer_size
+            .unwrap_or(io_tuning::DEFAULT_IO_BUFFER_SIZE)
+            .max(1)
+    }
+
+    /// Whether a working set of `bytes` exceeds the memory budget, if there is one.
+    pub fn exceeds_memory_budget(&self, bytes: u64) -> bool {
+        self.memory_budget.is_some_and(|budget| bytes > budget)
+    }
+}
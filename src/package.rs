@@ -0,0 +1,128 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::lock::LOCK_FILENAME;
+use crate::IpadicNeologdBuilder;
+
+/// The gzip level of the archive when `BuilderOptions::compression_level` is not set.
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 6;
+
+/// Packages the output directory into `lindera-ipadic-neologd-<version>.tar.gz`, placed next
+/// to the output directory, compressed with the gzip `level`, and returns the path of the
+/// archive.
+pub fn package_output(output_dir: &Path, version: &str, level: u32) -> LinderaResult<PathBuf> {
+    let package_name = format!("lindera-ipadic-neologd-{}", version);
+    let parent_dir = output_dir.parent().unwrap_or_else(|| Path::new("."));
+    let wtr_package_path = parent_dir.join(format!("{}.tar.gz", package_name));
+    println!("creating {:?}", wtr_package_path);
+
+    let wtr_package = File::create(&wtr_package_path)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    let mut tar_builder = tar::Builder::new(GzEncoder::new(wtr_package, Compression::new(level)));
+    // The lock file of the build in progress is left out.
+    let append = |tar_builder: &mut tar::Builder<_>| -> io::Result<()> {
+        tar_builder.append_dir(&package_name, output_dir)?;
+        for entry in fs::read_dir(output_dir)? {
+            let entry = entry?;
+            if entry.file_name() == LOCK_FILENAME {
+                continue;
+            }
+            let name = Path::new(&package_name).join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                tar_builder.append_dir_all(name, entry.path())?;
+            } else {
+                tar_builder.append_path_with_name(entry.path(), name)?;
+            }
+        }
+        Ok(())
+    };
+    append(&mut tar_builder)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    let mut encoder = tar_builder
+        .into_inner()
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    encoder
+        .flush()
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    encoder
+        .finish()
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+    Ok(wtr_package_path)
+}
+
+impl IpadicNeologdBuilder {
+    /// The version naming the package: `BuilderOptions::dictionary_version` if it is set,
+    /// or the version of the builder.
+    pub(crate) fn package_version(&self) -> &str {
+        self.options
+            .dictionary_version
+            .as_deref()
+            .unwrap_or(env!("CARGO_PKG_VERSION"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+
+    use flate2::read::GzDecoder;
+
+    use super::{package_output, DEFAULT_COMPRESSION_LEVEL};
+    use crate::lock::LOCK_FILENAME;
+
+    #[test]
+    fn packages_the_output_without_its_lock_file() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-package-{}",
+            std::process::id()
+        ));
+        let output_dir = base_dir.join("output");
+        let _ = fs::remove_dir_all(&base_dir);
+        fs::create_dir_all(output_dir.join("columns")).unwrap();
+        fs::write(output_dir.join("dict.da"), b"da").unwrap();
+        fs::write(output_dir.join("columns").join("pos.bin"), b"pos").unwrap();
+        fs::write(output_dir.join(LOCK_FILENAME), b"1").unwrap();
+
+        let package_path =
+            package_output(&output_dir, "2020.01.01", DEFAULT_COMPRESSION_LEVEL).unwrap();
+        assert_eq!(
+            package_path,
+            base_dir.join("lindera-ipadic-neologd-2020.01.01.tar.gz")
+        );
+
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(&package_path).unwrap()));
+        let mut names: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                entry
+                    .unwrap()
+                    .path()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .map(|name| name.trim_end_matches('/').to_string())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "lindera-ipadic-neologd-2020.01.01",
+                "lindera-ipadic-neologd-2020.01.01/columns",
+                "lindera-ipadic-neologd-2020.01.01/columns/pos.bin",
+                "lindera-ipadic-neologd-2020.01.01/dict.da",
+            ]
+        );
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
@@ -0,0 +1,237 @@
+use std::str::FromStr;
+
+use byteorder::{ByteOrder, LittleEndian};
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::error::BuildError;
+use crate::MAX_HOMONYMS;
+
+/// The table of the entries of the surfaces of a dictionary built with
+/// `ValuePacking::Indirect`, in the output directory.
+pub const VALUE_TABLE_FILENAME: &str = "dict.valtable";
+
+/// The bits of a compact value holding the number of entries of the surface.
+const ENTRY_COUNT_BITS: u32 = 5;
+
+/// The largest value the double array holds, its leaf units keeping 31 bits.
+const MAX_VALUE: u32 = (1 << 31) - 1;
+
+/// The largest id of the first entry of a surface a compact value holds.
+const MAX_COMPACT_ID: u32 = MAX_VALUE >> ENTRY_COUNT_BITS;
+
+/// How the entries of a surface in `dict.vals` are packed into the value the double array
+/// maps the surface to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValuePacking {
+    /// The id of the first entry shifted left by 5 bits, ORed with the number of entries, as
+    /// read by lindera. Surfaces have fewer than 32 entries, and the ids of the first entries
+    /// fit in the remaining 26 bits of the 31-bit values.
+    #[default]
+    Compact,
+    /// The index of the surface in `dict.valtable`, which holds the id of its first entry
+    /// and its number of entries as two little-endian `u32`s. Lindera cannot load these
+    /// dictionaries, but surfaces can have any number of entries.
+    Indirect,
+}
+
+impl ValuePacking {
+    /// A packer for a new keyset.
+    pub fn packer(&self) -> Box<dyn ValuePacker + Send + Sync> {
+        match self {
+            ValuePacking::Compact => Box::new(CompactPacker),
+            ValuePacking::Indirect => Box::new(IndirectPacker::default()),
+        }
+    }
+}
+
+impl FromStr for ValuePacking {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "compact" => Ok(ValuePacking::Compact),
+            "indirect" => Ok(ValuePacking::Indirect),
+            _ => Err(format!("unknown value packing: {}", s)),
+        }
+    }
+}
+
+/// Packs the entries of the surfaces of a keyset into the values of the double array, and
+/// unpacks them back.
+pub trait ValuePacker {
+    /// The value of `surface`, whose `entries` entries start at `first_id` in `dict.vals`.
+    /// Surfaces are packed in the order of the keyset.
+    fn pack(&mut self, surface: &str, first_id: u32, entries: u32) -> LinderaResult<u32>;
+
+    /// The id of the first entry and the number of entries of a value, if it is valid.
+    fn unpack(&self, value: u32) -> Option<(u32, u32)>;
+
+    /// The contents of `VALUE_TABLE_FILENAME`, if the packing needs it.
+    fn table(&self) -> Option<Vec<u8>>;
+}
+
+/// The packing of `ValuePacking::Compact`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompactPacker;
+
+impl ValuePacker for CompactPacker {
+    fn pack(&mut self, surface: &str, first_id: u32, entries: u32) -> LinderaResult<u32> {
+        if entries as usize >= MAX_HOMONYMS {
+            return Err(BuildError::Validation(format!(
+                "{} is {} length. Too long. [{}]",
+                surface, entries, MAX_HOMONYMS
+            ))
+            .into());
+        }
+        // Shifting a larger id would drop its high bits, mapping the surface to the entries
+        // of another one.
+        if first_id > MAX_COMPACT_ID {
+            return Err(BuildError::Validation(format!(
+                "the entries of {} start at id {}, over the {} ids compact values can address. \
+                 Build with the indirect value packing (--value-packing indirect) instead",
+                surface,
+                first_id,
+                MAX_COMPACT_ID as u64 + 1
+            ))
+            .into());
+        }
+        Ok((first_id << ENTRY_COUNT_BITS) | entries)
+    }
+
+    fn unpack(&self, value: u32) -> Option<(u32, u32)> {
+        Some((
+            value >> ENTRY_COUNT_BITS,
+            value & ((1 << ENTRY_COUNT_BITS) - 1),
+        ))
+    }
+
+    fn table(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+/// The packing of `ValuePacking::Indirect`.
+#[derive(Debug, Clone, Default)]
+pub struct IndirectPacker {
+    table: Vec<(u32, u32)>,
+}
+
+impl IndirectPacker {
+    /// Reads the packer of a dictionary from the contents of its `VALUE_TABLE_FILENAME`.
+    pub fn from_table(data: &[u8]) -> LinderaResult<IndirectPacker> {
+        if !data.len().is_multiple_of(8) {
+            return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                "{} has {} bytes, not a multiple of 8",
+                VALUE_TABLE_FILENAME,
+                data.len()
+            )));
+        }
+        Ok(IndirectPacker {
+            table: data
+                .chunks_exact(8)
+                .map(|chunk| {
+                    (
+                        LittleEndian::read_u32(&chunk[..4]),
+                        LittleEndian::read_u32(&chunk[4..]),
+                    )
+                })
+                .collect(),
+        })
+    }
+}
+
+impl ValuePacker for IndirectPacker {
+    fn pack(&mut self, surface: &str, first_id: u32, entries: u32) -> LinderaResult<u32> {
+        if self.table.len() > MAX_VALUE as usize {
+            return Err(BuildError::Validation(format!(
+                "{} is surface {} of the keyset, over the {} values of the double array",
+                surface,
+                self.table.len(),
+                MAX_VALUE as u64 + 1
+            ))
+            .into());
+        }
+        self.table.push((first_id, entries));
+        Ok(self.table.len() as u32 - 1)
+    }
+
+    fn unpack(&self, value: u32) -> Option<(u32, u32)> {
+        self.table.get(value as usize).copied()
+    }
+
+    fn table(&self) -> Option<Vec<u8>> {
+        let mut data = Vec::with_capacity(self.table.len() * 8);
+        for (first_id, entries) in &self.table {
+            data.extend_from_slice(&first_id.to_le_bytes());
+            data.extend_from_slice(&entries.to_le_bytes());
+        }
+        Some(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompactPacker, IndirectPacker, ValuePacker, ValuePacking, MAX_COMPACT_ID};
+
+    #[test]
+    fn packs_compact_values() {
+        let mut packer = ValuePacking::Compact.packer();
+        assert_eq!(packer.pack("東京", 2, 1).unwrap(), 65);
+        assert_eq!(packer.unpack(65), Some((2, 1)));
+        assert_eq!(packer.pack("東京", 0, 31).unwrap(), 31);
+        assert!(packer.pack("東京", 0, 32).is_err());
+        assert!(packer.table().is_none());
+        assert_eq!(CompactPacker.unpack(0), Some((0, 0)));
+    }
+
+    #[test]
+    fn rejects_ids_over_the_compact_values() {
+        let mut packer = CompactPacker;
+        assert_eq!(
+            packer.pack("東京", MAX_COMPACT_ID, 1).unwrap(),
+            (MAX_COMPACT_ID << 5) | 1
+        );
+        let err = packer.pack("東京", MAX_COMPACT_ID + 1, 1).unwrap_err();
+        assert!(err.to_string().contains("start at id 67108864"), "{}", err);
+        assert!(
+            err.to_string().contains("--value-packing indirect"),
+            "{}",
+            err
+        );
+        assert!(IndirectPacker::default()
+            .pack("東京", MAX_COMPACT_ID + 1, 1)
+            .is_ok());
+    }
+
+    #[test]
+    fn packs_indirect_values_into_a_table() {
+        let mut packer = ValuePacking::Indirect.packer();
+        assert_eq!(packer.pack("いく", 0, 1).unwrap(), 0);
+        assert_eq!(packer.pack("東京", 1, 40).unwrap(), 1);
+        assert_eq!(packer.pack("行く", 1 << 30, 1).unwrap(), 2);
+        assert_eq!(packer.unpack(1), Some((1, 40)));
+        assert_eq!(packer.unpack(3), None);
+
+        let table = packer.table().unwrap();
+        assert_eq!(table.len(), 24);
+        assert_eq!(&table[8..16], &[1, 0, 0, 0, 40, 0, 0, 0]);
+        let packer = IndirectPacker::from_table(&table).unwrap();
+        assert_eq!(packer.unpack(2), Some((1 << 30, 1)));
+        assert!(IndirectPacker::from_table(&table[..20]).is_err());
+    }
+
+    #[test]
+    fn parses_value_packings() {
+        assert_eq!("compact".parse(), Ok(ValuePacking::Compact));
+        assert_eq!("indirect".parse(), Ok(ValuePacking::Indirect));
+        assert!("wide".parse::<ValuePacking>().is_err());
+        assert_eq!(
+            serde_json::to_string(&ValuePacking::Indirect).unwrap(),
+            "\"indirect\""
+        );
+    }
+}
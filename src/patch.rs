@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::file_util::{read_source_file, source_lines};
+use crate::warning::BuildWarning;
+use crate::{normalize_fields, CsvRow};
+
+/// A single operation of a patch file.
+///
+/// - `+<csv row>` adds an entry.
+/// - `-<surface>[,<pos>...]` removes the entries with the surface, optionally restricted to
+///   the given leading POS levels.
+/// - `~<surface>[,<pos>...] <word_cost>` sets the word cost of the matching entries.
+///
+/// Blank lines and lines starting with `#` are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchOp {
+    Add(String),
+    Remove {
+        surface: String,
+        pos: Vec<String>,
+    },
+    SetCost {
+        surface: String,
+        pos: Vec<String>,
+        word_cost: i32,
+    },
+}
+
+impl PatchOp {
+    fn matches(&self, row: &CsvRow) -> bool {
+        let (surface, pos) = match self {
+            PatchOp::Add(_) => return false,
+            PatchOp::Remove { surface, pos } => (surface, pos),
+            PatchOp::SetCost { surface, pos, .. } => (surface, pos),
+        };
+        let row_pos = [
+            row.pos_level1,
+            row.pos_level2,
+            row.pos_level3,
+            row.pos_level4,
+        ];
+        surface == row.surface_form
+            && pos.len() <= row_pos.len()
+            && pos.iter().zip(row_pos.iter()).all(|(a, b)| a == b)
+    }
+}
+
+fn parse_key(key: &str) -> (String, Vec<String>) {
+    let mut fields = key.split(',').map(|field| field.to_string());
+    let surface = fields.next().unwrap_or_default();
+    (surface, fields.collect())
+}
+
+/// A list of add/remove/modify operations applied over the source entries before building.
+#[derive(Debug, Clone, Default)]
+pub struct Patch {
+    /// The name recorded as the provenance of the added entries.
+    pub name: String,
+    pub ops: Vec<PatchOp>,
+}
+
+impl Patch {
+    pub fn load(path: &Path) -> LinderaResult<Patch> {
+        let content = read_source_file(path)?;
+        let mut patch = Patch::parse(&content).map_err(|err| {
+            LinderaErrorKind::Parse.with_error(anyhow::anyhow!("{}:{}", path.display(), err))
+        })?;
+        if let Some(name) = path.file_name() {
+            patch.name = name.to_string_lossy().to_string();
+        }
+        Ok(patch)
+    }
+
+    pub fn parse(content: &str) -> LinderaResult<Patch> {
+        let mut ops = Vec::new();
+        for (line_num, line) in source_lines(content) {
+            // The surfaces of the rows and keys, after the prefix.
+            let line = normalize_fields(line, &[0]);
+            let op = if let Some(row) = line.strip_prefix('+') {
+                CsvRow::from_line(row).map_err(|err| {
+                    LinderaErrorKind::Parse.with_error(anyhow::anyhow!("{}: {}", line_num, err))
+                })?;
+                PatchOp::Add(row.to_string())
+            } else if let Some(key) = line.strip_prefix('-') {
+                let (surface, pos) = parse_key(key);
+                PatchOp::Remove { surface, pos }
+            } else if let Some(rest) = line.strip_prefix('~') {
+                let (key, cost) = rest.rsplit_once(char::is_whitespace).ok_or_else(|| {
+                    LinderaErrorKind::Parse.with_error(anyhow::anyhow!(
+                        "{}: expected `~surface word_cost`",
+                        line_num
+                    ))
+                })?;
+                let word_cost = i32::from_str(cost).map_err(|_err| {
+                    LinderaErrorKind::Parse
+                        .with_error(anyhow::anyhow!("{}: failed to parse word_cost", line_num))
+                })?;
+                let (surface, pos) = parse_key(key.trim_end());
+                PatchOp::SetCost {
+                    surface,
+                    pos,
+                    word_cost,
+                }
+            } else if line.starts_with('#') {
+                continue;
+            } else {
+                return Err(LinderaErrorKind::Parse
+                    .with_error(anyhow::anyhow!("{}: unknown patch operation", line_num)));
+            };
+            ops.push(op);
+        }
+        Ok(Patch {
+            name: "patch".to_string(),
+            ops,
+        })
+    }
+
+    /// Applies the operations in order. Removals and cost changes matching no entry are
+    /// reported with `report`, since they usually mean the patch is stale.
+    pub(crate) fn apply<'a>(
+        &'a self,
+        mut rows: Vec<CsvRow<'a>>,
+        report: &dyn Fn(BuildWarning) -> LinderaResult<()>,
+    ) -> LinderaResult<Vec<CsvRow<'a>>> {
+        // The indices of the rows of each surface, so that each operation only looks at the
+        // rows of its surface.
+        let mut surface_rows: HashMap<&'a str, Vec<usize>> = HashMap::new();
+        for (idx, row) in rows.iter().enumerate() {
+            surface_rows.entry(row.surface_form).or_default().push(idx);
+        }
+        let mut removed = vec![false; rows.len()];
+
+        for op in &self.ops {
+            let surface = match op {
+                PatchOp::Add(line) => {
+                    let mut row = CsvRow::from_line(line)?;
+                    row.provenance = Some(&self.name);
+                    surface_rows
+                        .entry(row.surface_form)
+                        .or_default()
+                        .push(rows.len());
+                    rows.push(row);
+                    removed.push(false);
+                    continue;
+                }
+                PatchOp::Remove { surface, .. } | PatchOp::SetCost { surface, .. } => surface,
+            };
+            let mut matched = false;
+            for idx in surface_rows.get(surface.as_str()).into_iter().flatten() {
+                if removed[*idx] || !op.matches(&rows[*idx]) {
+                    continue;
+                }
+                matched = true;
+                match op {
+                    PatchOp::SetCost { word_cost, .. } => rows[*idx].word_cost = *word_cost,
+                    _ => removed[*idx] = true,
+                }
+            }
+            if !matched {
+                report(BuildWarning::StalePatchOp {
+                    patch: self.name.clone(),
+                    surface: surface.clone(),
+                })?;
+            }
+        }
+
+        let mut removed = removed.into_iter();
+        rows.retain(|_| !removed.next().unwrap_or(false));
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::{Patch, PatchOp};
+    use crate::fixtures::FIXTURE_ROWS;
+    use crate::warning::BuildWarning;
+    use crate::CsvRow;
+
+    #[test]
+    fn parses_each_operation() {
+        let patch = Patch::parse(
+            "# a comment\n\
+             +トートバッグ,1,1,5000,名詞,固有名詞,一般,*,*,*,トートバッグ,トートバッグ,トートバッグ\n\
+             \n\
+             -東京\n\
+             -行く,動詞,自立\n\
+             ~東京スカイツリー,名詞,固有名詞 1500\n",
+        )
+        .unwrap();
+        assert_eq!(
+            patch.ops,
+            vec![
+                PatchOp::Add(
+                    "トートバッグ,1,1,5000,名詞,固有名詞,一般,*,*,*,トートバッグ,トートバッグ,トートバッグ"
+                        .to_string()
+                ),
+                PatchOp::Remove {
+                    surface: "東京".to_string(),
+                    pos: Vec::new(),
+                },
+                PatchOp::Remove {
+                    surface: "行く".to_string(),
+                    pos: vec!["動詞".to_string(), "自立".to_string()],
+                },
+                PatchOp::SetCost {
+                    surface: "東京スカイツリー".to_string(),
+                    pos: vec!["名詞".to_string(), "固有名詞".to_string()],
+                    word_cost: 1500,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_cost_changes_with_extra_whitespace() {
+        let patch = Patch::parse("~東京,名詞 \t  -300\n").unwrap();
+        assert_eq!(
+            patch.ops,
+            vec![PatchOp::SetCost {
+                surface: "東京".to_string(),
+                pos: vec!["名詞".to_string()],
+                word_cost: -300,
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_operations_and_bad_costs() {
+        assert!(Patch::parse("*東京\n").is_err());
+        assert!(Patch::parse("~東京 cheap\n").is_err());
+        assert!(Patch::parse("~東京\n").is_err());
+        assert!(Patch::parse("+東京,1,1\n").is_err());
+    }
+
+    #[test]
+    fn applies_the_operations_in_order() {
+        let patch = Patch::parse(
+            "+東京,1,1,2500,名詞,固有名詞,人名,姓,*,*,東京,トウキョウ,トーキョー\n\
+             -東京,名詞,固有名詞,地域\n\
+             ~行く,動詞 4500\n\
+             ~いく,名詞 1000\n\
+             -大阪\n",
+        )
+        .unwrap();
+        let rows: Vec<CsvRow> = FIXTURE_ROWS
+            .iter()
+            .map(|line| CsvRow::from_line(line).unwrap())
+            .collect();
+        let warnings = RefCell::new(Vec::new());
+        let rows = patch
+            .apply(rows, &|warning| {
+                warnings.borrow_mut().push(warning);
+                Ok(())
+            })
+            .unwrap();
+
+        let surfaces: Vec<(&str, i32)> = rows
+            .iter()
+            .map(|row| (row.surface_form, row.word_cost))
+            .collect();
+        assert_eq!(
+            surfaces,
+            vec![
+                ("東京スカイツリー", 2000),
+                ("に", 4000),
+                ("行く", 4500),
+                ("いく", 5500),
+                ("東京", 2500),
+            ]
+        );
+        assert_eq!(rows[4].provenance, Some("patch"));
+        assert_eq!(
+            warnings.into_inner(),
+            vec![
+                BuildWarning::StalePatchOp {
+                    patch: "patch".to_string(),
+                    surface: "いく".to_string(),
+                },
+                BuildWarning::StalePatchOp {
+                    patch: "patch".to_string(),
+                    surface: "大阪".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,522 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use yada::builder::DoubleArrayBuilder;
+
+use lindera_core::character_definition::{CharacterDefinitions, CharacterDefinitionsBuilder};
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::unknown_dictionary::parse_unk;
+use lindera_core::word_entry::{WordEntry, WordId};
+use lindera_core::LinderaResult;
+
+use crate::build_log::BuildEvent;
+use crate::error::{BuildError, ErrorCollector};
+use crate::file_util::{read_source_file, source_lines};
+use crate::packing::VALUE_TABLE_FILENAME;
+use crate::progress::ProgressStep;
+use crate::warning::BuildWarning;
+use crate::{chardef, da_spill, io_tuning, keyset, matrix, parse_files, reading, skip, sort, unk};
+use crate::{CsvFile, CsvRow, IpadicNeologdBuilder};
+
+/// The purpose of the scratch directories the artifacts are written to before being moved
+/// into the output directory.
+const ARTIFACT_PURPOSE: &str = "artifact";
+
+/// The serialized system dictionary files built from the CSV entries.
+#[derive(Debug, Clone, Default)]
+pub struct DictBuffers {
+    /// The double array of the surfaces, `dict.da`.
+    pub da: Vec<u8>,
+    /// The word entries of the surfaces, `dict.vals`.
+    pub vals: Vec<u8>,
+    /// The word details, `dict.words`.
+    pub words: Vec<u8>,
+    /// The offsets of the word details, `dict.wordsidx`.
+    pub words_idx: Vec<u8>,
+    /// The entries of the surfaces with `ValuePacking::Indirect`, `dict.valtable`.
+    pub value_table: Option<Vec<u8>>,
+}
+
+/// The dictionary encoded from the sorted rows, with what the optional artifacts are cut
+/// from.
+pub(crate) struct EncodedDict {
+    pub(crate) buffers: DictBuffers,
+    pub(crate) word_entry_map: BTreeMap<String, Vec<WordEntry>>,
+    pub(crate) words_offsets: Vec<u32>,
+}
+
+/// Builds the character definitions and the unknown word dictionary from the content of
+/// `char.def` and `unk.def`.
+pub trait CharDefBuilder {
+    /// Returns the character definitions with their serialization, `char_def.bin`.
+    fn build_chardef_bytes(&self, char_def: &str)
+        -> LinderaResult<(CharacterDefinitions, Vec<u8>)>;
+
+    /// Returns the serialized unknown word dictionary, `unk.bin`.
+    fn build_unk_bytes(
+        &self,
+        unk_def: &str,
+        chardef: &CharacterDefinitions,
+    ) -> LinderaResult<Vec<u8>>;
+}
+
+/// Builds the system dictionary files from the named contents of CSV files.
+pub trait LexiconBuilder {
+    fn build_dict_bytes(&self, csv_files: &[(&str, &str)]) -> LinderaResult<DictBuffers>;
+}
+
+/// Builds the connection cost matrix, `matrix.mtx`, from the content of `matrix.def`.
+pub trait MatrixBuilder {
+    fn build_cost_matrix_bytes(&self, matrix_def: &[u8]) -> LinderaResult<Vec<u8>>;
+}
+
+impl CharDefBuilder for IpadicNeologdBuilder {
+    /// Checks every line of `char_def` before it is parsed.
+    fn build_chardef_bytes(
+        &self,
+        char_def: &str,
+    ) -> LinderaResult<(CharacterDefinitions, Vec<u8>)> {
+        chardef::check_char_def(char_def)?;
+        let mut char_definitions_builder = CharacterDefinitionsBuilder::default();
+        char_definitions_builder.parse(char_def)?;
+        let char_definitions = char_definitions_builder.build();
+        let bytes = bincode::serialize(&char_definitions)
+            .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+        Ok((char_definitions, bytes))
+    }
+
+    /// Applies the cost adjustments of the options.
+    fn build_unk_bytes(
+        &self,
+        unk_def: &str,
+        chardef: &CharacterDefinitions,
+    ) -> LinderaResult<Vec<u8>> {
+        let unk_lines = source_lines(unk_def)
+            .map(|(_, line)| line)
+            .collect::<Vec<_>>();
+        unk::check_unk_lines(&unk_lines, chardef.categories())?;
+        let (unk_lines, effective_costs) =
+            unk::adjust_unk_costs(&unk_lines, &self.options.unk_costs)?;
+        for unk_cost in &effective_costs {
+            self.print(&format!(
+                "unk {} {},{}: cost {} -> {}",
+                unk_cost.category,
+                unk_cost.left_id,
+                unk_cost.right_id,
+                unk_cost.cost,
+                unk_cost.effective_cost
+            ));
+        }
+        self.record_effective_unk_costs(effective_costs);
+        let unk_data = unk_lines.join("\n");
+        let unknown_dictionary = parse_unk(chardef.categories(), &unk_data)?;
+        bincode::serialize(&unknown_dictionary)
+            .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))
+    }
+}
+
+impl LexiconBuilder for IpadicNeologdBuilder {
+    /// The patch and preferred term files of the options are still read, but the sources
+    /// are not.
+    fn build_dict_bytes(&self, csv_files: &[(&str, &str)]) -> LinderaResult<DictBuffers> {
+        let mut files = csv_files
+            .iter()
+            .map(|(name, content)| self.csv_file(name.to_string(), content))
+            .collect::<LinderaResult<Vec<_>>>()?;
+        self.add_variants(&mut files)?;
+        self.add_symbols(&mut files);
+        self.add_numeric_entries(&mut files);
+        let patches = self.load_patches()?;
+        let mut errors = ErrorCollector::new(self.options.max_errors);
+        let rows = parse_files(&files, &mut errors)?;
+        errors.finish()?;
+        self.entry_stats.record_parsed(&rows);
+        let rows = self.transform_rows(&patches, rows)?;
+        let rows = self.sort_rows(rows);
+        Ok(self.encode_dict(&rows, None, None)?.buffers)
+    }
+}
+
+impl MatrixBuilder for IpadicNeologdBuilder {
+    fn build_cost_matrix_bytes(&self, matrix_def: &[u8]) -> LinderaResult<Vec<u8>> {
+        let matrix_data_path = Path::new("matrix.def");
+        let sizes = matrix::read_header_from(matrix_data_path, matrix_def)?;
+        self.cost_matrix_from(matrix_data_path, sizes, matrix_def.len() as u64, matrix_def)
+    }
+}
+
+impl IpadicNeologdBuilder {
+    /// Builds the system dictionary files of the CSV files of the build with a replaced
+    /// lexicon phase.
+    pub(crate) fn build_dict_with(
+        &self,
+        lexicon_builder: &dyn LexiconBuilder,
+        input_dir: &Path,
+        output_dir: &Path,
+    ) -> LinderaResult<()> {
+        let mut contents = Vec::new();
+        for (name, filename) in self.csv_sources(input_dir)? {
+            contents.push((name, read_source_file(&filename)?));
+        }
+        let csv_files: Vec<(&str, &str)> = contents
+            .iter()
+            .map(|(name, content)| (name.as_str(), content.as_str()))
+            .collect();
+        let buffers = lexicon_builder.build_dict_bytes(&csv_files)?;
+
+        let layout = &self.options.layout;
+        self.write_artifact(output_dir, &layout.dict_da, &buffers.da)?;
+        self.write_artifact(output_dir, &layout.dict_vals, &buffers.vals)?;
+        self.write_artifact(output_dir, &layout.dict_words, &buffers.words)?;
+        self.write_artifact(output_dir, &layout.dict_wordsidx, &buffers.words_idx)?;
+        self.write_value_table(output_dir, buffers.value_table.as_deref())
+    }
+
+    /// Parses matrix.def read from `rdr`, of the header `sizes` and `input_len` bytes, into
+    /// the content of `matrix.mtx`.
+    pub(crate) fn cost_matrix_from<R: BufRead>(
+        &self,
+        matrix_data_path: &Path,
+        (forward_size, backward_size): (u32, u32),
+        input_len: u64,
+        rdr: R,
+    ) -> LinderaResult<Vec<u8>> {
+        self.progress_start(
+            ProgressStep::MatrixRows,
+            u64::from(forward_size) * u64::from(backward_size),
+        );
+        let costs = matrix::parse_matrix_from(
+            matrix_data_path,
+            rdr,
+            matrix::max_cells(input_len),
+            self.options.threads(),
+            matrix::batch_lines(self.options.memory_budget),
+            &|lines| self.progress_advance(ProgressStep::MatrixRows, lines as u64),
+            &|message| self.warn(message),
+        )?;
+        self.progress_finish(ProgressStep::MatrixRows);
+
+        let mut bytes = Vec::with_capacity(costs.len() * 2);
+        for cost in costs {
+            bytes
+                .write_i16::<LittleEndian>(cost)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        }
+        Ok(bytes)
+    }
+
+    /// Reads the entry lines of a CSV file from its content, normalized.
+    pub(crate) fn csv_file(&self, name: String, content: &str) -> LinderaResult<CsvFile> {
+        let mut lines = Vec::new();
+        let mut line_nums = Vec::new();
+        let normalizer = self.options.line_normalizer()?;
+        for (line_num, line) in self.csv_lines(content) {
+            lines.push(self.normalize_source_line(&name, line, &normalizer)?);
+            line_nums.push(line_num);
+        }
+
+        self.log.log(&BuildEvent::FileStats {
+            file: &name,
+            bytes: content.len() as u64,
+            lines: lines.len(),
+        });
+
+        if self.options.fill_readings {
+            let unresolved = reading::fill_readings(&mut lines, self.reading_provider.as_deref());
+            for surface in unresolved {
+                self.report_warning(BuildWarning::MissingReading { surface })?;
+            }
+        }
+
+        Ok(CsvFile {
+            name,
+            lines,
+            line_nums,
+        })
+    }
+
+    /// Sorts the rows by surface, as the word_ids are assigned in that order.
+    pub(crate) fn sort_rows<'a>(&self, rows: Vec<CsvRow<'a>>) -> Vec<CsvRow<'a>> {
+        self.progress_start(ProgressStep::SortRows, rows.len() as u64);
+        let mut rows = sort::sort_rows(rows, &|message| self.print(message));
+        if self.options.sort_homonyms {
+            sort::sort_homonyms(&mut rows);
+        }
+        self.progress_advance(ProgressStep::SortRows, rows.len() as u64);
+        self.progress_finish(ProgressStep::SortRows);
+        rows
+    }
+
+    /// Encodes the sorted rows into the double array, the word entries and the word details.
+    /// With `wtr_words`, the word details are written to it as they are encoded instead of
+    /// being kept in the buffers. With `da_path`, the double array is built into that file
+    /// instead of the buffers.
+    pub(crate) fn encode_dict(
+        &self,
+        rows: &[CsvRow],
+        mut wtr_words: Option<&mut dyn Write>,
+        da_path: Option<&Path>,
+    ) -> LinderaResult<EncodedDict> {
+        let skip_rules = self.options.skip_rules();
+        let mut word_entry_map: BTreeMap<String, Vec<WordEntry>> = BTreeMap::new();
+
+        let mut group_start = 0;
+        for (row_id, row) in rows.iter().enumerate() {
+            if row.surface_form != rows[group_start].surface_form {
+                group_start = row_id;
+            }
+            if rows[group_start..row_id]
+                .iter()
+                .any(|other| other.same_entry(row))
+            {
+                self.report_warning(BuildWarning::DuplicateEntry {
+                    file: row.provenance.unwrap_or_default().to_string(),
+                    surface: row.surface_form.to_string(),
+                })?;
+                self.entry_stats
+                    .record(row.provenance.unwrap_or_default(), |stats| {
+                        stats.duplicates_seen += 1
+                    });
+            } else if let Some(difference) = rows[group_start..row_id]
+                .iter()
+                .find_map(|other| row.conflict(other))
+            {
+                self.report_warning(BuildWarning::ConflictingEntry {
+                    file: row.provenance.unwrap_or_default().to_string(),
+                    surface: row.surface_form.to_string(),
+                    difference,
+                })?;
+            }
+
+            if let Some(rule_idx) = skip::find_rule(&skip_rules, row) {
+                self.skip_row(row, &skip_rules[rule_idx]);
+            } else {
+                word_entry_map
+                    .entry(row.surface_form.to_string())
+                    .or_default()
+                    .push(WordEntry {
+                        word_id: WordId(row_id as u32, true),
+                        word_cost: self.word_cost(row)?,
+                        cost_id: row.left_id as u16,
+                    });
+            }
+        }
+
+        let codec = self.options.detail_codec();
+        let detail_fields = self.options.detail_field_indices()?;
+        let mut words = Vec::new();
+        let mut words_idx = Vec::with_capacity(rows.len() * 4);
+        let mut words_offsets = Vec::with_capacity(rows.len());
+        let mut words_len = 0;
+        for row in rows.iter() {
+            let word = self.word_details(row, detail_fields.as_deref());
+            let offset = words_len;
+            words_offsets.push(offset as u32);
+            words_idx
+                .write_u32::<LittleEndian>(offset as u32)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            codec.encode(&word, &mut words)?;
+            match wtr_words.as_deref_mut() {
+                Some(wtr_words) => {
+                    words_len += words.len();
+                    wtr_words
+                        .write_all(&words[..])
+                        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+                    words.clear();
+                }
+                None => words_len = words.len(),
+            }
+        }
+
+        let mut id = 0u32;
+
+        self.print("building da");
+        self.progress_start(ProgressStep::InsertKeys, word_entry_map.len() as u64);
+        let mut keyset: Vec<(&[u8], u32)> = vec![];
+        let mut packer = self.options.value_packing.packer();
+        let mut lastlen = 0;
+        for (key, word_entries) in &word_entry_map {
+            let len = word_entries.len() as u32;
+            let val = packer.pack(key, id, len)?;
+            keyset.push((key.as_bytes(), val));
+            id += len;
+            lastlen += len;
+            self.progress_advance(ProgressStep::InsertKeys, 1);
+        }
+        if let Some(keyset_path) = &self.options.keyset_export {
+            keyset::write_keyset(keyset_path, &keyset)?;
+        }
+
+        let da = match da_path {
+            Some(da_path) => {
+                da_spill::build_double_array(&keyset, da_path)?;
+                Vec::new()
+            }
+            None => DoubleArrayBuilder::build(&keyset).ok_or_else(|| {
+                LinderaErrorKind::Io.with_error(anyhow::anyhow!("DoubleArray build error."))
+            })?,
+        };
+        self.progress_finish(ProgressStep::InsertKeys);
+        self.print(&format!("Last len is {}", lastlen));
+
+        self.print("building values");
+        let mut vals = Vec::new();
+        for word_entries in word_entry_map.values() {
+            for word_entry in word_entries {
+                word_entry
+                    .serialize(&mut vals)
+                    .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+            }
+        }
+
+        Ok(EncodedDict {
+            buffers: DictBuffers {
+                da,
+                vals,
+                words,
+                words_idx,
+                value_table: packer.table(),
+            },
+            word_entry_map,
+            words_offsets,
+        })
+    }
+
+    /// Writes `dict.valtable` to `output_dir` if the value packing has a table.
+    pub(crate) fn write_value_table(
+        &self,
+        output_dir: &Path,
+        value_table: Option<&[u8]>,
+    ) -> LinderaResult<()> {
+        match value_table {
+            Some(value_table) => self.write_artifact(output_dir, VALUE_TABLE_FILENAME, value_table),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes an artifact of the layout to `output_dir` atomically, through a scratch
+    /// directory, so that an interrupted build never leaves it partially written.
+    pub(crate) fn write_artifact(
+        &self,
+        output_dir: &Path,
+        name: &str,
+        bytes: &[u8],
+    ) -> LinderaResult<()> {
+        let path = self.options.layout.artifact_path(output_dir, name)?;
+        self.print(&format!("creating {:?}", path));
+        let scratch = self.scratch_dir(output_dir, ARTIFACT_PURPOSE)?;
+        scratch
+            .write_atomic(&path, |tmp_path| {
+                io_tuning::write_file(tmp_path, bytes, self.options.io_buffer_size())
+            })
+            .map_err(|source| {
+                BuildError::Io {
+                    path: Some(path.clone()),
+                    source,
+                }
+                .into()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+
+    use lindera_core::dictionary_builder::DictionaryBuilder;
+    use lindera_core::LinderaResult;
+
+    use super::{CharDefBuilder, DictBuffers, LexiconBuilder, MatrixBuilder};
+    use crate::fixtures::{
+        build_fixture, write_fixture, FIXTURE_CHAR_DEF, FIXTURE_CSV_FILENAME, FIXTURE_MATRIX_DEF,
+        FIXTURE_ROWS, FIXTURE_UNK_DEF,
+    };
+    use crate::options::BuilderOptions;
+    use crate::IpadicNeologdBuilder;
+
+    /// Builds the lexicon like the builder, recording the names of the CSV files.
+    struct RecordingLexiconBuilder(Arc<Mutex<Vec<String>>>);
+
+    impl LexiconBuilder for RecordingLexiconBuilder {
+        fn build_dict_bytes(&self, csv_files: &[(&str, &str)]) -> LinderaResult<DictBuffers> {
+            let mut names = self.0.lock().unwrap();
+            names.extend(csv_files.iter().map(|(name, _)| name.to_string()));
+            IpadicNeologdBuilder::new().build_dict_bytes(csv_files)
+        }
+    }
+
+    /// A 1x1 cost matrix whatever `matrix.def` holds.
+    struct ConstantMatrixBuilder;
+
+    impl MatrixBuilder for ConstantMatrixBuilder {
+        fn build_cost_matrix_bytes(&self, _matrix_def: &[u8]) -> LinderaResult<Vec<u8>> {
+            Ok(vec![1, 0, 1, 0, 7, 0])
+        }
+    }
+
+    #[test]
+    fn in_memory_phases_match_the_built_files() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-phases-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        build_fixture(&input_dir, &output_dir, BuilderOptions::default()).unwrap();
+        let read = |name: &str| fs::read(output_dir.join(name)).unwrap();
+
+        let builder = IpadicNeologdBuilder::new();
+        let (chardef, char_def_bytes) = builder.build_chardef_bytes(FIXTURE_CHAR_DEF).unwrap();
+        assert_eq!(char_def_bytes, read("char_def.bin"));
+        assert_eq!(
+            builder.build_unk_bytes(FIXTURE_UNK_DEF, &chardef).unwrap(),
+            read("unk.bin")
+        );
+
+        let csv = format!("{}\n", FIXTURE_ROWS.join("\n"));
+        let buffers = builder
+            .build_dict_bytes(&[(FIXTURE_CSV_FILENAME, &csv)])
+            .unwrap();
+        assert_eq!(buffers.da, read("dict.da"));
+        assert_eq!(buffers.vals, read("dict.vals"));
+        assert_eq!(buffers.words, read("dict.words"));
+        assert_eq!(buffers.words_idx, read("dict.wordsidx"));
+        assert!(buffers.value_table.is_none());
+
+        assert_eq!(
+            builder
+                .build_cost_matrix_bytes(FIXTURE_MATRIX_DEF.as_bytes())
+                .unwrap(),
+            read("matrix.mtx")
+        );
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn replaces_phases_with_custom_builders() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-custom-phases-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        write_fixture(&input_dir).unwrap();
+        let names = Arc::new(Mutex::new(Vec::new()));
+        IpadicNeologdBuilder::new()
+            .with_lexicon_builder(RecordingLexiconBuilder(Arc::clone(&names)))
+            .with_matrix_builder(ConstantMatrixBuilder)
+            .build_dictionary(&input_dir, &output_dir)
+            .unwrap();
+
+        assert_eq!(*names.lock().unwrap(), vec![FIXTURE_CSV_FILENAME]);
+        assert_eq!(
+            fs::read(output_dir.join("matrix.mtx")).unwrap(),
+            vec![1, 0, 1, 0, 7, 0]
+        );
+        assert!(fs::metadata(output_dir.join("dict.da")).unwrap().len() > 0);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
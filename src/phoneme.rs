@@ -0,0 +1,142 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::error::ErrorCollector;
+use crate::transliterate::{is_vowel, syllables, Syllable};
+use crate::{parse_files, IpadicNeologdBuilder};
+
+/// The phonemes of the kana `pronunciation`, in the phoneme set of the Julius Japanese
+/// acoustic models: the vowels `a i u e o`, long vowels such as `a:`, the consonants of the
+/// Hepburn romanization (`k`, `sh`, `ch`, `ts`, `ky`, ...), `N` for `ン` and `q` for `ッ`.
+/// Returns `None` if the pronunciation has other characters than kana.
+pub fn pronunciation_phonemes(pronunciation: &str) -> Option<Vec<String>> {
+    let mut phonemes: Vec<String> = Vec::new();
+    for syllable in syllables(pronunciation) {
+        match syllable {
+            Syllable::Kana(kana) if kana == "n" => phonemes.push("N".to_string()),
+            Syllable::Kana(kana) => {
+                let (onset, vowel) = kana.split_at(kana.len() - 1);
+                if !onset.is_empty() {
+                    phonemes.push(onset.to_string());
+                }
+                phonemes.push(vowel.to_string());
+            }
+            Syllable::Sokuon => phonemes.push("q".to_string()),
+            Syllable::LongVowel => {
+                if let Some(last) = phonemes
+                    .last_mut()
+                    .filter(|last| last.len() == 1 && last.starts_with(is_vowel))
+                {
+                    last.push(':');
+                }
+            }
+            Syllable::Other(_) => return None,
+        }
+    }
+    if phonemes.is_empty() {
+        None
+    } else {
+        Some(phonemes)
+    }
+}
+
+impl IpadicNeologdBuilder {
+    /// Writes the pronunciation lexicon of the CSV files in `input_dir`, after the patch
+    /// files are applied, to `output_file` for speech recognition and synthesis: one line per
+    /// distinct surface and pronunciation, with the surface and its phonemes separated by a
+    /// tab and the phonemes by spaces, as read by aligners such as the Montreal Forced
+    /// Aligner. Entries without a kana pronunciation are left out.
+    pub fn export_phonemes(&self, input_dir: &Path, output_file: &Path) -> LinderaResult<()> {
+        let files = self.read_csv_files(input_dir)?;
+        let patches = self.load_patches()?;
+        let mut errors = ErrorCollector::new(self.options.max_errors);
+        let rows = parse_files(&files, &mut errors)?;
+        errors.finish()?;
+        let rows = self.apply_patches(&patches, rows)?;
+
+        let mut lexicon = BTreeSet::new();
+        let mut skipped = 0;
+        for row in &rows {
+            match pronunciation_phonemes(row.pronunciation) {
+                Some(phonemes) => {
+                    lexicon.insert((row.surface_form, phonemes.join(" ")));
+                }
+                None => skipped += 1,
+            }
+        }
+
+        println!("creating {:?}", output_file);
+        let write = || -> io::Result<()> {
+            let mut wtr = BufWriter::new(File::create(output_file)?);
+            for (surface, phonemes) in &lexicon {
+                writeln!(wtr, "{}\t{}", surface, phonemes)?;
+            }
+            wtr.flush()
+        };
+        write().map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        println!(
+            "{} pronunciations written, {} entries without a kana pronunciation left out",
+            lexicon.len(),
+            skipped
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::pronunciation_phonemes;
+    use crate::fixtures::write_fixture;
+    use crate::IpadicNeologdBuilder;
+
+    fn phonemes(pronunciation: &str) -> Option<String> {
+        pronunciation_phonemes(pronunciation).map(|phonemes| phonemes.join(" "))
+    }
+
+    #[test]
+    fn splits_pronunciations_into_phonemes() {
+        assert_eq!(phonemes("トーキョー").as_deref(), Some("t o: ky o:"));
+        assert_eq!(phonemes("シンジュク").as_deref(), Some("sh i N j u k u"));
+        assert_eq!(phonemes("ガッコー").as_deref(), Some("g a q k o:"));
+        assert_eq!(phonemes("イク").as_deref(), Some("i k u"));
+        assert_eq!(phonemes("*"), None);
+        assert_eq!(phonemes(""), None);
+    }
+
+    #[test]
+    fn exports_the_pronunciation_lexicon() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-phoneme-{}",
+            std::process::id()
+        ));
+        let input_dir = base_dir.join("input");
+        write_fixture(&input_dir).unwrap();
+        fs::write(
+            input_dir.join("symbols.csv"),
+            "ＡＢＣ,1,1,3000,名詞,固有名詞,一般,*,*,*,ＡＢＣ,*,*\n",
+        )
+        .unwrap();
+
+        let output_file = base_dir.join("lexicon.txt");
+        IpadicNeologdBuilder::new()
+            .export_phonemes(&input_dir, &output_file)
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&output_file).unwrap(),
+            "いく\ti k u\n\
+             に\tn i\n\
+             東京\tt o: ky o:\n\
+             東京スカイツリー\tt o: ky o: s u k a i ts u r i:\n\
+             行く\ti k u\n"
+        );
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+/// The value IPADIC uses for empty fields.
+pub const PLACEHOLDER: &str = "*";
+
+/// How the `*` placeholders of empty fields are written to the word details.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlaceholderHandling {
+    /// Kept as `*`, as Lindera expects.
+    #[default]
+    Keep,
+    /// Replaced with empty strings.
+    Empty,
+    /// The placeholders after the last non-empty field are dropped, so that the fields
+    /// before them keep their positions.
+    DropTrailing,
+}
+
+impl PlaceholderHandling {
+    /// Applies the handling to the placeholders of `details`.
+    pub fn apply(&self, details: &mut Vec<String>) {
+        match self {
+            PlaceholderHandling::Keep => {}
+            PlaceholderHandling::Empty => {
+                for field in details.iter_mut().filter(|field| *field == PLACEHOLDER) {
+                    field.clear();
+                }
+            }
+            PlaceholderHandling::DropTrailing => {
+                while details.last().is_some_and(|field| field == PLACEHOLDER) {
+                    details.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PlaceholderHandling;
+
+    fn details(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|field| field.to_string()).collect()
+    }
+
+    #[test]
+    fn writes_placeholders_as_asked() {
+        let fields = ["名詞", "*", "*", "*", "*", "*", "東京", "*", "*"];
+        let apply = |handling: PlaceholderHandling| {
+            let mut handled = details(&fields);
+            handling.apply(&mut handled);
+            handled
+        };
+        assert_eq!(apply(PlaceholderHandling::Keep), details(&fields));
+        assert_eq!(
+            apply(PlaceholderHandling::Empty),
+            details(&["名詞", "", "", "", "", "", "東京", "", ""])
+        );
+        assert_eq!(
+            apply(PlaceholderHandling::DropTrailing),
+            details(&["名詞", "*", "*", "*", "*", "*", "東京"])
+        );
+
+        let mut all_placeholders = details(&["*", "*"]);
+        PlaceholderHandling::DropTrailing.apply(&mut all_placeholders);
+        assert!(all_placeholders.is_empty());
+    }
+}
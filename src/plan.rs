@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Serialize;
+use yada::builder::DoubleArrayBuilder;
+
+use lindera_core::character_definition::CharacterDefinitionsBuilder;
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::unknown_dictionary::parse_unk;
+use lindera_core::word_entry::WordEntry;
+use lindera_core::LinderaResult;
+
+use crate::error::ErrorCollector;
+use crate::file_util::{read_source_file, source_lines};
+use crate::seed::SeedDates;
+use crate::validate::ValidationReport;
+use crate::{chardef, matrix, parse_files, skip, unk, IpadicNeologdBuilder};
+
+/// An output file a build would write.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedArtifact {
+    pub name: String,
+    /// The size of the file in bytes, if it can be computed without building it.
+    pub estimated_size: Option<u64>,
+}
+
+/// What a build of the input directory would write, returned by
+/// `IpadicNeologdBuilder::plan`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildPlan {
+    pub rows: usize,
+    pub surfaces: usize,
+    pub artifacts: Vec<PlannedArtifact>,
+    pub seed_dates: Option<SeedDates>,
+    #[serde(skip)]
+    pub validation: ValidationReport,
+}
+
+impl BuildPlan {
+    /// The total of the estimated artifact sizes.
+    pub fn estimated_size(&self) -> u64 {
+        self.artifacts
+            .iter()
+            .filter_map(|artifact| artifact.estimated_size)
+            .sum()
+    }
+}
+
+impl IpadicNeologdBuilder {
+    /// Parses and validates the sources of `input_dir` and computes the artifacts a build
+    /// would write with their sizes, without writing anything.
+    pub fn plan(&self, input_dir: &Path) -> LinderaResult<BuildPlan> {
+        let validation = self.validate(input_dir)?;
+        let layout = &self.options.layout;
+        let mut artifacts = Vec::new();
+        let mut planned = |name: &str, estimated_size: Option<u64>| {
+            artifacts.push(PlannedArtifact {
+                name: name.to_string(),
+                estimated_size,
+            })
+        };
+
+        let char_def = chardef::read_char_def(input_dir, self.options.char_overrides.as_deref())?;
+        chardef::check_char_def(&char_def)?;
+        let mut char_definitions_builder = CharacterDefinitionsBuilder::default();
+        char_definitions_builder.parse(&char_def)?;
+        let char_definitions = char_definitions_builder.build();
+        planned(&layout.char_def, Some(serialized_size(&char_definitions)?));
+
+        let unk_data = read_source_file(&input_dir.join("unk.def"))?;
+        let unk_lines = source_lines(&unk_data)
+            .map(|(_, line)| line)
+            .collect::<Vec<_>>();
+        unk::check_unk_lines(&unk_lines, char_definitions.categories())?;
+        let (unk_lines, _) = unk::adjust_unk_costs(&unk_lines, &self.options.unk_costs)?;
+        let unk_data = unk_lines.join("\n");
+        let unknown_dictionary = parse_unk(char_definitions.categories(), &unk_data)?;
+        planned(&layout.unk, Some(serialized_size(&unknown_dictionary)?));
+
+        let csv_files: Vec<_> = self
+            .csv_sources(input_dir)?
+            .into_iter()
+            .map(|(_, filename)| filename)
+            .collect();
+        let seed_dates = self.check_seed_dates(&csv_files)?;
+        let files = self.read_csv_files(input_dir)?;
+        let patches = self.load_patches()?;
+        let mut errors = ErrorCollector::new(self.options.max_errors);
+        let rows = parse_files(&files, &mut errors)?;
+        errors.finish()?;
+        let mut rows = self.apply_patches(&patches, rows)?;
+        self.annotate_compounds(&mut rows);
+
+        let codec = self.options.detail_codec();
+        let detail_fields = self.options.detail_field_indices()?;
+        let mut words_size = 0;
+        let mut words_buffer = Vec::new();
+        let skip_rules = self.options.skip_rules();
+        let mut homonyms: BTreeMap<&str, u32> = BTreeMap::new();
+        for row in &rows {
+            codec.encode(
+                &self.word_details(row, detail_fields.as_deref()),
+                &mut words_buffer,
+            )?;
+            words_size += words_buffer.len() as u64;
+            words_buffer.clear();
+            if skip::find_rule(&skip_rules, row).is_none() {
+                *homonyms.entry(row.surface_form).or_default() += 1;
+            }
+        }
+
+        let mut id = 0u32;
+        let mut keyset: Vec<(&[u8], u32)> = Vec::with_capacity(homonyms.len());
+        let mut packer = self.options.value_packing.packer();
+        for (key, len) in &homonyms {
+            keyset.push((key.as_bytes(), packer.pack(key, id, *len)?));
+            id += len;
+        }
+        let da_bytes = DoubleArrayBuilder::build(&keyset).ok_or_else(|| {
+            LinderaErrorKind::Io.with_error(anyhow::anyhow!("DoubleArray build error."))
+        })?;
+        planned(&layout.dict_da, Some(da_bytes.len() as u64));
+        planned(
+            &layout.dict_vals,
+            Some(u64::from(id) * WordEntry::SERIALIZED_LEN as u64),
+        );
+        planned(&layout.dict_words, Some(words_size));
+        planned(&layout.dict_wordsidx, Some(rows.len() as u64 * 4));
+        if let Some(value_table) = packer.table() {
+            planned(
+                crate::packing::VALUE_TABLE_FILENAME,
+                Some(value_table.len() as u64),
+            );
+        }
+
+        let (forward_size, backward_size) = matrix::read_header(&input_dir.join("matrix.def"))?;
+        planned(
+            &layout.matrix,
+            Some((2 + u64::from(forward_size) * u64::from(backward_size)) * 2),
+        );
+
+        if self.options.reading_index {
+            planned(crate::reading_index::READING_DA_FILENAME, None);
+            planned(crate::reading_index::READING_VALS_FILENAME, None);
+        }
+        if self.options.columnar_details {
+            for name in crate::columnar::column_filenames() {
+                planned(&name, None);
+            }
+        }
+        if self.options.shard_size.is_some() {
+            planned(crate::shard::SHARD_INDEX_FILENAME, None);
+        }
+        planned(crate::manifest::MANIFEST_FILENAME, None);
+        planned(crate::checksum::CHECKSUMS_FILENAME, None);
+
+        Ok(BuildPlan {
+            rows: rows.len(),
+            surfaces: homonyms.len(),
+            artifacts,
+            seed_dates,
+            validation,
+        })
+    }
+}
+
+fn serialized_size<T: Serialize>(value: &T) -> LinderaResult<u64> {
+    bincode::serialized_size(value)
+        .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use lindera_core::dictionary_builder::DictionaryBuilder;
+
+    use crate::fixtures::{write_fixture, FIXTURE_ROWS};
+    use crate::IpadicNeologdBuilder;
+
+    #[test]
+    fn estimates_the_sizes_of_the_built_artifacts() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-plan-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        write_fixture(&input_dir).unwrap();
+        let builder = IpadicNeologdBuilder::new();
+
+        let plan = builder.plan(&input_dir).unwrap();
+        assert!(!output_dir.exists());
+        assert!(plan.validation.is_valid());
+        assert_eq!(plan.rows, FIXTURE_ROWS.len());
+        assert_eq!(plan.surfaces, FIXTURE_ROWS.len());
+        assert!(plan.seed_dates.is_none());
+
+        builder.build_dictionary(&input_dir, &output_dir).unwrap();
+        for artifact in &plan.artifacts {
+            let size = fs::metadata(output_dir.join(&artifact.name))
+                .unwrap_or_else(|_| panic!("{} was not built", artifact.name))
+                .len();
+            if let Some(estimated_size) = artifact.estimated_size {
+                assert_eq!(estimated_size, size, "{}", artifact.name);
+            }
+        }
+        assert_eq!(plan.artifacts.len(), 9);
+        assert!(plan.estimated_size() > 0);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
@@ -0,0 +1,134 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+/// A rule of a `PosMapping`, matching the leading POS levels of an entry.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PosMappingRule {
+    /// Leading POS levels to match, where `*` matches any value.
+    pub pos: Vec<String>,
+    /// POS levels replacing the leading levels of the matching entries.
+    pub replace: Option<Vec<String>>,
+    /// Tag stored in the extra detail column of the matching entries, e.g. a UD POS tag.
+    pub tag: Option<String>,
+}
+
+impl PosMappingRule {
+    fn matches(&self, details: &[String]) -> bool {
+        self.pos.len() <= 4
+            && self
+                .pos
+                .iter()
+                .zip(details)
+                .all(|(pattern, level)| pattern == "*" || pattern == level)
+    }
+}
+
+/// Rewrites the POS levels of word details and optionally appends a tag column, applied
+/// before the details are serialized. The first matching rule wins.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PosMapping {
+    pub rules: Vec<PosMappingRule>,
+    /// Tag of the entries no rule with a tag matches. Without tags in any rule, no tag
+    /// column is appended.
+    pub default_tag: Option<String>,
+}
+
+impl PosMapping {
+    /// Loads a mapping from a JSON file.
+    pub fn load(path: &Path) -> LinderaResult<PosMapping> {
+        let data =
+            fs::read(path).map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        serde_json::from_slice(&data)
+            .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))
+    }
+
+    fn has_tags(&self) -> bool {
+        self.default_tag.is_some() || self.rules.iter().any(|rule| rule.tag.is_some())
+    }
+
+    /// Applies the mapping to word details starting with the 4 POS levels.
+    pub fn apply(&self, details: &mut Vec<String>) {
+        let rule = self.rules.iter().find(|rule| rule.matches(details));
+
+        if let Some(replace) = rule.and_then(|rule| rule.replace.as_ref()) {
+            for (level, value) in details.iter_mut().zip(replace.iter().take(4)) {
+                *level = value.clone();
+            }
+        }
+
+        if self.has_tags() {
+            let tag = rule
+                .and_then(|rule| rule.tag.as_ref())
+                .or(self.default_tag.as_ref())
+                .map(|tag| tag.as_str())
+                .unwrap_or("*");
+            details.push(tag.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PosMapping;
+
+    fn details(pos: &str) -> Vec<String> {
+        pos.split(',')
+            .chain(vec!["*", "*", "東京", "トウキョウ", "トーキョー"])
+            .map(|field| field.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn rewrites_levels_and_tags_with_the_first_matching_rule() {
+        let mapping: PosMapping = serde_json::from_str(
+            r#"{
+                "rules": [
+                    {"pos": ["名詞", "固有名詞"], "replace": ["名詞", "固有"], "tag": "PROPN"},
+                    {"pos": ["名詞", "*", "地域"], "tag": "NOUN-LOC"},
+                    {"pos": ["名詞"], "tag": "NOUN"}
+                ],
+                "default_tag": "X"
+            }"#,
+        )
+        .unwrap();
+
+        let mut proper_noun = details("名詞,固有名詞,地域,一般");
+        mapping.apply(&mut proper_noun);
+        assert_eq!(&proper_noun[..4], &["名詞", "固有", "地域", "一般"]);
+        assert_eq!(proper_noun.len(), 10);
+        assert_eq!(proper_noun[9], "PROPN");
+
+        let mut place = details("名詞,一般,地域,*");
+        mapping.apply(&mut place);
+        assert_eq!(&place[..2], &["名詞", "一般"]);
+        assert_eq!(place[9], "NOUN-LOC");
+
+        let mut particle = details("助詞,格助詞,一般,*");
+        mapping.apply(&mut particle);
+        assert_eq!(particle[9], "X");
+    }
+
+    #[test]
+    fn appends_no_tag_column_without_tags() {
+        let mapping: PosMapping =
+            serde_json::from_str(r#"{"rules": [{"pos": ["名詞"], "replace": ["名詞", "普通"]}]}"#)
+                .unwrap();
+        let mut noun = details("名詞,一般,*,*");
+        mapping.apply(&mut noun);
+        assert_eq!(noun.len(), 9);
+        assert_eq!(noun[1], "普通");
+
+        let mapping: PosMapping =
+            serde_json::from_str(r#"{"rules": [{"pos": ["名詞"], "tag": "NOUN"}]}"#).unwrap();
+        let mut verb = details("動詞,自立,*,*");
+        mapping.apply(&mut verb);
+        assert_eq!(verb[9], "*");
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use lindera_core::LinderaResult;
+
+use crate::file_util::{read_source_file, source_lines};
+use crate::{normalize_line, CsvRow};
+
+/// The word cost subtracted from the entries of preferred terms when
+/// `BuilderOptions::preferred_cost_delta` is not set.
+pub const DEFAULT_PREFERRED_COST_DELTA: i32 = 1000;
+
+/// Surfaces whose entries get their word cost lowered, so that the tokenizer keeps them
+/// whole. The file lists one surface per line; blank lines and lines starting with `#` are
+/// ignored.
+#[derive(Debug, Clone, Default)]
+pub struct PreferredTerms {
+    pub surfaces: HashSet<String>,
+    pub cost_delta: i32,
+}
+
+impl PreferredTerms {
+    pub fn load(path: &Path, cost_delta: i32) -> LinderaResult<PreferredTerms> {
+        let content = read_source_file(path)?;
+        Ok(PreferredTerms::parse(&content, cost_delta))
+    }
+
+    pub fn parse(content: &str, cost_delta: i32) -> PreferredTerms {
+        PreferredTerms {
+            surfaces: source_lines(content)
+                .map(|(_, line)| line.trim())
+                .filter(|line| !line.starts_with('#'))
+                .map(normalize_line)
+                .collect(),
+            cost_delta,
+        }
+    }
+
+    /// Lowers the word cost of `row` if its surface is preferred, returning whether it did.
+    pub(crate) fn apply(&self, row: &mut CsvRow) -> bool {
+        if !self.surfaces.contains(row.surface_form) {
+            return false;
+        }
+        row.word_cost = row.word_cost.saturating_sub(self.cost_delta);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PreferredTerms;
+    use crate::fixtures::FIXTURE_ROWS;
+    use crate::CsvRow;
+
+    #[test]
+    fn parses_one_surface_per_line() {
+        let preferred =
+            PreferredTerms::parse("# terms\r\n東京\r\n\n  東京スカイツリー \n～\n", 1000);
+        let mut surfaces: Vec<&str> = preferred.surfaces.iter().map(String::as_str).collect();
+        surfaces.sort_unstable();
+        assert_eq!(surfaces, vec!["〜", "東京", "東京スカイツリー"]);
+    }
+
+    #[test]
+    fn lowers_the_cost_of_preferred_entries() {
+        let preferred = PreferredTerms::parse("東京\n", 1000);
+        let mut row = CsvRow::from_line(FIXTURE_ROWS[0]).unwrap();
+        assert!(preferred.apply(&mut row));
+        assert_eq!(row.word_cost, 2000);
+        let mut row = CsvRow::from_line(FIXTURE_ROWS[1]).unwrap();
+        assert!(!preferred.apply(&mut row));
+        assert_eq!(row.word_cost, 2000);
+
+        let preferred = PreferredTerms::parse("東京\n", i32::MAX);
+        let mut row = CsvRow::from_line(FIXTURE_ROWS[0]).unwrap();
+        row.word_cost = -2;
+        assert!(preferred.apply(&mut row));
+        assert_eq!(row.word_cost, i32::MIN);
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::{LinderaError, LinderaErrorKind};
+
+use crate::budget::PruningStrategy;
+use crate::codec::DetailFormat;
+use crate::env_overrides::MAX_COMPRESSION_LEVEL;
+use crate::folding::SurfaceFolding;
+use crate::kana::KanaVariants;
+use crate::merge::DuplicatePolicy;
+use crate::normalize::NormalizationStep;
+use crate::options::BuilderOptions;
+
+/// The word cost above which the `Search` profile drops entries as rare.
+pub const SEARCH_MAX_WORD_COST: i32 = 12000;
+
+/// The size budget in bytes of the `Embedded` profile.
+pub const EMBEDDED_SIZE_BUDGET: u64 = 64 * 1024 * 1024;
+
+/// Named presets of `BuilderOptions` configuring the whole pipeline for a use case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildProfile {
+    /// For search indexes: NFKC and folded surfaces, kana variants, and the entries above
+    /// `SEARCH_MAX_WORD_COST` dropped.
+    Search,
+    /// For linguistic analysis: every entry and every field kept as written, with the
+    /// provenance and the compound splits in the word details.
+    Nlp,
+    /// For embedding in applications: compact word details, the highest compression level,
+    /// and the highest cost entries dropped to fit `EMBEDDED_SIZE_BUDGET`. The profile does
+    /// not generate code, since only the application knows where to write it; set
+    /// `BuilderOptions::codegen_dir` and `codegen_compress` to embed the dictionary that way.
+    Embedded,
+}
+
+impl BuildProfile {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BuildProfile::Search => "search",
+            BuildProfile::Nlp => "nlp",
+            BuildProfile::Embedded => "embedded",
+        }
+    }
+
+    /// The options of the profile.
+    pub fn options(&self) -> BuilderOptions {
+        match self {
+            BuildProfile::Search => BuilderOptions {
+                field_normalization: BTreeMap::from([
+                    ("surface".to_string(), vec![NormalizationStep::Nfkc]),
+                    ("base_form".to_string(), vec![NormalizationStep::Nfkc]),
+                ]),
+                surface_folding: Some(SurfaceFolding::Both),
+                kana_variants: Some(KanaVariants::Both),
+                max_word_cost: Some(SEARCH_MAX_WORD_COST),
+                ..BuilderOptions::default()
+            },
+            BuildProfile::Nlp => BuilderOptions {
+                normalized_fields: Some(Vec::new()),
+                skip_rules: Some(Vec::new()),
+                duplicate_policy: DuplicatePolicy::KeepAll,
+                provenance: true,
+                compound_splits: true,
+                ..BuilderOptions::default()
+            },
+            BuildProfile::Embedded => BuilderOptions {
+                detail_format: DetailFormat::Compact,
+                size_budget: Some(EMBEDDED_SIZE_BUDGET),
+                pruning: Some(PruningStrategy::HighestCost),
+                compression_level: Some(MAX_COMPRESSION_LEVEL),
+                ..BuilderOptions::default()
+            },
+        }
+    }
+}
+
+impl FromStr for BuildProfile {
+    type Err = LinderaError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "search" => Ok(BuildProfile::Search),
+            "nlp" => Ok(BuildProfile::Nlp),
+            "embedded" => Ok(BuildProfile::Embedded),
+            _ => Err(LinderaErrorKind::Args
+                .with_error(anyhow::anyhow!("unknown build profile {}", value))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::BuildProfile;
+    use crate::fixtures::build_fixture;
+    use crate::reader::DictionaryReader;
+
+    const PROFILES: [BuildProfile; 3] = [
+        BuildProfile::Search,
+        BuildProfile::Nlp,
+        BuildProfile::Embedded,
+    ];
+
+    #[test]
+    fn parses_profile_names() {
+        for profile in PROFILES {
+            assert_eq!(profile.name().parse::<BuildProfile>().unwrap(), profile);
+            assert_eq!(
+                serde_json::to_string(&profile).unwrap(),
+                format!("\"{}\"", profile.name())
+            );
+            assert!(profile.options().line_normalizer().is_ok());
+        }
+        assert!("Search".parse::<BuildProfile>().is_err());
+    }
+
+    #[test]
+    fn builds_the_fixture_with_the_search_profile() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-profile-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        build_fixture(&input_dir, &output_dir, BuildProfile::Search.options()).unwrap();
+
+        let reader = DictionaryReader::open(&output_dir).unwrap();
+        assert_eq!(reader.lookup("イク").len(), 1);
+        assert_eq!(reader.lookup("東京").len(), 1);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
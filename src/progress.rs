@@ -0,0 +1,99 @@
+/// A step of a build whose progress is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ProgressStep {
+    /// Source CSV files parsed.
+    ParseFiles,
+    /// Entries sorted by surface.
+    SortRows,
+    /// Surfaces inserted into the double array.
+    InsertKeys,
+    /// Lines of `matrix.def` processed.
+    MatrixRows,
+}
+
+impl ProgressStep {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProgressStep::ParseFiles => "files parsed",
+            ProgressStep::SortRows => "rows sorted",
+            ProgressStep::InsertKeys => "keys inserted",
+            ProgressStep::MatrixRows => "matrix rows",
+        }
+    }
+}
+
+/// Receives the progress of a build, set with `IpadicNeologdBuilder::with_progress`.
+pub trait ProgressReporter {
+    /// A step of `total` units starts.
+    fn start(&self, step: ProgressStep, total: u64);
+    /// `delta` more units of the step are done.
+    fn advance(&self, step: ProgressStep, delta: u64);
+    fn finish(&self, step: ProgressStep);
+    /// A status message of the build, printed to stdout by default.
+    fn message(&self, message: &str) {
+        println!("{}", message);
+    }
+}
+
+/// Shows a progress bar with an ETA for each step, with the `progress` feature.
+#[cfg(feature = "progress")]
+pub struct IndicatifProgress {
+    multi: indicatif::MultiProgress,
+    bars: std::sync::Mutex<std::collections::BTreeMap<ProgressStep, indicatif::ProgressBar>>,
+}
+
+#[cfg(feature = "progress")]
+impl IndicatifProgress {
+    pub fn new() -> Self {
+        IndicatifProgress {
+            multi: indicatif::MultiProgress::new(),
+            bars: std::sync::Mutex::new(std::collections::BTreeMap::new()),
+        }
+    }
+
+    fn bar(&self, step: ProgressStep) -> Option<indicatif::ProgressBar> {
+        self.bars.lock().ok()?.get(&step).cloned()
+    }
+}
+
+#[cfg(feature = "progress")]
+impl Default for IndicatifProgress {
+    fn default() -> Self {
+        IndicatifProgress::new()
+    }
+}
+
+#[cfg(feature = "progress")]
+impl ProgressReporter for IndicatifProgress {
+    fn start(&self, step: ProgressStep, total: u64) {
+        let bar = self.multi.add(indicatif::ProgressBar::new(total));
+        if let Ok(style) = indicatif::ProgressStyle::with_template(
+            "{msg:>14} [{bar:40}] {human_pos}/{human_len} ({eta})",
+        ) {
+            bar.set_style(style.progress_chars("=> "));
+        }
+        bar.set_message(step.name());
+        if let Ok(mut bars) = self.bars.lock() {
+            bars.insert(step, bar);
+        }
+    }
+
+    fn advance(&self, step: ProgressStep, delta: u64) {
+        if let Some(bar) = self.bar(step) {
+            bar.inc(delta);
+        }
+    }
+
+    fn finish(&self, step: ProgressStep) {
+        if let Some(bar) = self.bar(step) {
+            bar.finish();
+        }
+    }
+
+    fn message(&self, message: &str) {
+        // Printed above the bars, which would otherwise be drawn over.
+        if self.multi.println(message).is_err() {
+            println!("{}", message);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// `#[pyfunction]` expands to conversions of `PyResult` that clippy flags.
+#![allow(clippy::useless_conversion)]
+
+use std::path::PathBuf;
+
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+
+use lindera_core::dictionary_builder::DictionaryBuilder;
+use lindera_core::error::LinderaError;
+
+use crate::options::BuilderOptions;
+use crate::IpadicNeologdBuilder;
+
+fn to_py_err(err: LinderaError) -> PyErr {
+    PyRuntimeError::new_err(err.to_string())
+}
+
+fn builder(comment_prefix: Option<String>) -> IpadicNeologdBuilder {
+    IpadicNeologdBuilder::with_options(BuilderOptions {
+        comment_prefix,
+        ..BuilderOptions::default()
+    })
+}
+
+/// Builds the dictionary from the IPADIC NEologd source in `input_dir` into `output_dir`.
+#[pyfunction]
+#[pyo3(signature = (input_dir, output_dir, comment_prefix = None))]
+fn build_dictionary(
+    py: Python<'_>,
+    input_dir: PathBuf,
+    output_dir: PathBuf,
+    comment_prefix: Option<String>,
+) -> PyResult<()> {
+    let builder = builder(comment_prefix);
+    py.allow_threads(|| builder.build_dictionary(&input_dir, &output_dir))
+        .map_err(to_py_err)
+}
+
+/// Builds a user dictionary from the CSV `input_file` into the binary `output_file`.
+#[pyfunction]
+#[pyo3(signature = (input_file, output_file, comment_prefix = None))]
+fn build_user_dict(
+    py: Python<'_>,
+    input_file: PathBuf,
+    output_file: PathBuf,
+    comment_prefix: Option<String>,
+) -> PyResult<()> {
+    let builder = builder(comment_prefix);
+    py.allow_threads(|| builder.build_user_dictionary(&input_file, &output_file))
+        .map_err(to_py_err)
+}
+
+/// Validates the source in `input_dir` and returns the report as a dict.
+#[pyfunction]
+#[pyo3(signature = (input_dir, comment_prefix = None))]
+fn validate(
+    py: Python<'_>,
+    input_dir: PathBuf,
+    comment_prefix: Option<String>,
+) -> PyResult<PyObject> {
+    let builder = builder(comment_prefix);
+    let report = py
+        .allow_threads(|| builder.validate(&input_dir))
+        .map_err(to_py_err)?;
+
+    let issues = PyList::empty_bound(py);
+    for issue in &report.issues {
+        let py_issue = PyDict::new_bound(py);
+        py_issue.set_item("file", issue.file.to_string_lossy().to_string())?;
+        py_issue.set_item("line", issue.line)?;
+        py_issue.set_item("message", &issue.message)?;
+        issues.append(py_issue)?;
+    }
+
+    let py_report = PyDict::new_bound(py);
+    py_report.set_item("valid", report.is_valid())?;
+    py_report.set_item("csv_files", report.csv_files)?;
+    py_report.set_item("rows", report.rows)?;
+    py_report.set_item("surfaces", report.surfaces)?;
+    py_report.set_item("duplicate_rows", report.duplicate_rows)?;
+    py_report.set_item("max_homonyms", report.max_homonyms)?;
+    py_report.set_item("issues", issues)?;
+
+    Ok(py_report.into())
+}
+
+#[pymodule]
+fn lindera_ipadic_neologd_builder(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(build_dictionary, m)?)?;
+    m.add_function(wrap_pyfunction!(build_user_dict, m)?)?;
+    m.add_function(wrap_pyfunction!(validate, m)?)?;
+    Ok(())
+}
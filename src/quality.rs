@@ -0,0 +1,189 @@
+use std::path::Path;
+
+use lindera::tokenizer::{Tokenizer, TokenizerConfig};
+use serde::{Deserialize, Serialize};
+
+use lindera_core::LinderaResult;
+
+use crate::file_util::{read_source_file, source_lines};
+
+/// Maximum number of tokenization diffs kept in a `QualityReport`.
+const MAX_DIFFS: usize = 100;
+
+/// A corpus line tokenized differently by the previous build.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenizationDiff {
+    pub line: usize,
+    pub previous: Vec<String>,
+    pub current: Vec<String>,
+}
+
+/// Statistics of a sample corpus tokenized with a built dictionary.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QualityReport {
+    pub lines: usize,
+    pub tokens: usize,
+    pub unknown_tokens: usize,
+    /// Average token length in characters.
+    pub average_token_length: f64,
+    /// Number of lines tokenized differently by the previous build, if one was given.
+    pub changed_lines: usize,
+    /// The first of the changed lines.
+    pub diffs: Vec<TokenizationDiff>,
+}
+
+impl QualityReport {
+    pub fn unknown_rate(&self) -> f64 {
+        if self.tokens == 0 {
+            0.0
+        } else {
+            self.unknown_tokens as f64 / self.tokens as f64
+        }
+    }
+}
+
+/// Accumulates the `QualityReport` of the tokenized lines of a corpus.
+#[derive(Default)]
+struct QualityTally {
+    report: QualityReport,
+    token_chars: usize,
+}
+
+impl QualityTally {
+    /// Records the tokens of a line, with whether they are unknown words, and the tokens of
+    /// the previous build, if one was given.
+    fn record_line(
+        &mut self,
+        line_num: usize,
+        tokens: Vec<(String, bool)>,
+        previous: Option<Vec<String>>,
+    ) {
+        self.report.lines += 1;
+        self.report.tokens += tokens.len();
+        for (text, unknown) in &tokens {
+            self.token_chars += text.chars().count();
+            if *unknown {
+                self.report.unknown_tokens += 1;
+            }
+        }
+
+        if let Some(previous) = previous {
+            let current: Vec<String> = tokens.into_iter().map(|(text, _)| text).collect();
+            if previous != current {
+                self.report.changed_lines += 1;
+                if self.report.diffs.len() < MAX_DIFFS {
+                    self.report.diffs.push(TokenizationDiff {
+                        line: line_num,
+                        previous,
+                        current,
+                    });
+                }
+            }
+        }
+    }
+
+    fn finish(mut self) -> QualityReport {
+        if self.report.tokens > 0 {
+            self.report.average_token_length = self.token_chars as f64 / self.report.tokens as f64;
+        }
+        self.report
+    }
+}
+
+fn tokenizer(dict_dir: &Path) -> LinderaResult<Tokenizer> {
+    Tokenizer::with_config(TokenizerConfig {
+        dict_path: Some(dict_dir.to_path_buf()),
+        ..TokenizerConfig::default()
+    })
+}
+
+/// Tokenizes every line of the plain-text `corpus_file` with the dictionary built in
+/// `dict_dir`, and with the one in `previous_dict_dir` to report the lines tokenized
+/// differently.
+pub fn check_quality(
+    dict_dir: &Path,
+    corpus_file: &Path,
+    previous_dict_dir: Option<&Path>,
+) -> LinderaResult<QualityReport> {
+    let corpus = read_source_file(corpus_file)?;
+    let mut tokenizer = tokenizer(dict_dir)?;
+    let mut previous_tokenizer = match previous_dict_dir {
+        Some(previous_dict_dir) => Some(self::tokenizer(previous_dict_dir)?),
+        None => None,
+    };
+
+    let mut tally = QualityTally::default();
+    for (line_num, line) in source_lines(&corpus) {
+        let tokens = tokenizer.tokenize(line)?;
+        let current: Vec<(String, bool)> = tokens
+            .iter()
+            .map(|token| {
+                let unknown = token.detail.first().map(|pos| pos.as_str()) == Some("UNK");
+                (token.text.to_string(), unknown)
+            })
+            .collect();
+        let previous = match &mut previous_tokenizer {
+            Some(previous_tokenizer) => Some(
+                previous_tokenizer
+                    .tokenize(line)?
+                    .iter()
+                    .map(|token| token.text.to_string())
+                    .collect(),
+            ),
+            None => None,
+        };
+        tally.record_line(line_num, current, previous);
+    }
+
+    Ok(tally.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QualityReport, QualityTally, MAX_DIFFS};
+
+    fn tokens(texts: &[&str]) -> Vec<(String, bool)> {
+        texts
+            .iter()
+            .map(|text| (text.to_string(), text.starts_with('?')))
+            .collect()
+    }
+
+    #[test]
+    fn tallies_tokens_and_unknown_words() {
+        let mut tally = QualityTally::default();
+        tally.record_line(1, tokens(&["東京", "に", "行く"]), None);
+        tally.record_line(3, tokens(&["?ほげ", "行く"]), None);
+        let report = tally.finish();
+        assert_eq!(report.lines, 2);
+        assert_eq!(report.tokens, 5);
+        assert_eq!(report.unknown_tokens, 1);
+        assert!((report.unknown_rate() - 0.2).abs() < 1e-9);
+        assert!((report.average_token_length - 2.0).abs() < 1e-9);
+        assert_eq!(report.changed_lines, 0);
+
+        assert_eq!(QualityReport::default().unknown_rate(), 0.0);
+        assert_eq!(QualityTally::default().finish().average_token_length, 0.0);
+    }
+
+    #[test]
+    fn reports_lines_tokenized_differently() {
+        let mut tally = QualityTally::default();
+        let previous = |texts: &[&str]| Some(texts.iter().map(|text| text.to_string()).collect());
+        tally.record_line(1, tokens(&["東京", "に"]), previous(&["東京", "に"]));
+        tally.record_line(
+            2,
+            tokens(&["東京スカイツリー"]),
+            previous(&["東京", "スカイツリー"]),
+        );
+        for line_num in 3..3 + MAX_DIFFS {
+            tally.record_line(line_num, tokens(&["a"]), previous(&["b"]));
+        }
+        let report = tally.finish();
+        assert_eq!(report.changed_lines, MAX_DIFFS + 1);
+        assert_eq!(report.diffs.len(), MAX_DIFFS);
+        assert_eq!(report.diffs[0].line, 2);
+        assert_eq!(report.diffs[0].previous, vec!["東京", "スカイツリー"]);
+        assert_eq!(report.diffs[0].current, vec!["東京スカイツリー"]);
+    }
+}
@@ -0,0 +1,248 @@
+use std::fs;
+use std::path::Path;
+
+use byteorder::{ByteOrder, LittleEndian};
+use yada::DoubleArray;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::word_entry::WordEntry;
+use lindera_core::LinderaResult;
+
+use crate::codec::{DetailCodec, DetailFormat};
+use crate::endian::{swap_byte_order, Endianness, U32_RECORD, WORD_ENTRY_RECORD};
+use crate::header::strip_header;
+use crate::layout::DictionaryLayout;
+use crate::manifest::{Manifest, MANIFEST_FILENAME};
+use crate::packing::{
+    CompactPacker, IndirectPacker, ValuePacker, ValuePacking, VALUE_TABLE_FILENAME,
+};
+use crate::reading_index::{lookup_reading, READING_DA_FILENAME, READING_VALS_FILENAME};
+
+fn read_artifact(output_dir: &Path, name: &str) -> LinderaResult<Vec<u8>> {
+    fs::read(output_dir.join(name))
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))
+}
+
+/// Read access to the lexicon of a built dictionary, for checking and auditing builds.
+pub struct DictionaryReader {
+    da: DoubleArray<Vec<u8>>,
+    vals: Vec<u8>,
+    words_idx: Vec<u8>,
+    words: Vec<u8>,
+    reading_index: Option<(DoubleArray<Vec<u8>>, Vec<u8>)>,
+    codec: Box<dyn DetailCodec + Send + Sync>,
+    packer: Box<dyn ValuePacker + Send + Sync>,
+}
+
+impl DictionaryReader {
+    /// Opens the dictionary built in `output_dir` with the default layout.
+    pub fn open(output_dir: &Path) -> LinderaResult<DictionaryReader> {
+        DictionaryReader::open_with_layout(output_dir, &DictionaryLayout::default())
+    }
+
+    /// Opens the dictionary built in `output_dir` with `layout`. The detail format, the
+    /// lindera target, the value packing, the endianness and whether the artifacts have
+    /// headers are taken from the manifest, if there is one.
+    pub fn open_with_layout(
+        output_dir: &Path,
+        layout: &DictionaryLayout,
+    ) -> LinderaResult<DictionaryReader> {
+        let (codec, value_packing, endianness, artifact_headers) =
+            if output_dir.join(MANIFEST_FILENAME).exists() {
+                let manifest = Manifest::load(output_dir)?;
+                (
+                    manifest.lindera_target.detail_codec(manifest.detail_format),
+                    manifest.value_packing,
+                    manifest.endianness,
+                    manifest.artifact_headers,
+                )
+            } else {
+                (
+                    DetailFormat::default().codec(),
+                    ValuePacking::default(),
+                    Endianness::default(),
+                    false,
+                )
+            };
+        let read_layout_artifact = |name: &str| -> LinderaResult<Vec<u8>> {
+            let data = read_artifact(output_dir, name)?;
+            if artifact_headers {
+                Ok(strip_header(&data)?.to_vec())
+            } else {
+                Ok(data)
+            }
+        };
+        // Big-endian artifacts are swapped back to the byte order yada and lindera read.
+        let read_integers = |name: &str, record: &[usize]| -> LinderaResult<Vec<u8>> {
+            let mut data = read_layout_artifact(name)?;
+            if endianness == Endianness::Big {
+                swap_byte_order(&mut data, record);
+            }
+            Ok(data)
+        };
+
+        let reading_index = if output_dir.join(READING_DA_FILENAME).exists() {
+            Some((
+                DoubleArray::new(read_integers(READING_DA_FILENAME, U32_RECORD)?),
+                read_integers(READING_VALS_FILENAME, U32_RECORD)?,
+            ))
+        } else {
+            None
+        };
+
+        let packer: Box<dyn ValuePacker + Send + Sync> = match value_packing {
+            ValuePacking::Compact => Box::new(CompactPacker),
+            ValuePacking::Indirect => Box::new(IndirectPacker::from_table(&read_integers(
+                VALUE_TABLE_FILENAME,
+                U32_RECORD,
+            )?)?),
+        };
+
+        Ok(DictionaryReader {
+            da: DoubleArray::new(read_integers(&layout.dict_da, U32_RECORD)?),
+            vals: read_integers(&layout.dict_vals, WORD_ENTRY_RECORD)?,
+            words_idx: read_integers(&layout.dict_wordsidx, U32_RECORD)?,
+            words: read_layout_artifact(&layout.dict_words)?,
+            reading_index,
+            codec,
+            packer,
+        })
+    }
+
+    /// The number of words, i.e. of word details.
+    pub fn num_words(&self) -> usize {
+        self.words_idx.len() / 4
+    }
+
+    fn word_entries(&self, val: u32) -> Vec<WordEntry> {
+        let (offset, len) = self.packer.unpack(val).unwrap_or_default();
+        let (offset, len) = (offset as usize, len as usize);
+        (offset..offset + len)
+            .filter_map(|idx| {
+                let start = idx * WordEntry::SERIALIZED_LEN;
+                self.vals
+                    .get(start..start + WordEntry::SERIALIZED_LEN)
+                    .map(|data| WordEntry::deserialize(data, true))
+            })
+            .collect()
+    }
+
+    /// Returns the entries of the words whose surface is exactly `surface`.
+    pub fn lookup(&self, surface: &str) -> Vec<WordEntry> {
+        self.da
+            .exact_match_search(surface)
+            .map(|val| self.word_entries(val))
+            .unwrap_or_default()
+    }
+
+    /// Returns the entries of the words whose surface is a prefix of `text`, with the byte
+    /// length of the surface, as looked up by the tokenizer.
+    pub fn prefix_search(&self, text: &str) -> Vec<(usize, WordEntry)> {
+        self.da
+            .common_prefix_search(text)
+            .flat_map(|(val, len)| {
+                self.word_entries(val)
+                    .into_iter()
+                    .map(move |word_entry| (len, word_entry))
+            })
+            .collect()
+    }
+
+    /// Returns the word_ids of the words with the reading, if the dictionary was built with
+    /// the reading index.
+    pub fn lookup_reading(&self, reading: &str) -> Option<Vec<u32>> {
+        self.reading_index
+            .as_ref()
+            .map(|(da, vals)| lookup_reading(da, vals, reading))
+    }
+
+    /// Returns the details (POS, conjugation, base form, reading, pronunciation) of a word.
+    pub fn details(&self, word_id: u32) -> LinderaResult<Vec<String>> {
+        let idx = word_id as usize * 4;
+        let offset = self
+            .words_idx
+            .get(idx..idx + 4)
+            .map(LittleEndian::read_u32)
+            .ok_or_else(|| {
+                LinderaErrorKind::Args
+                    .with_error(anyhow::anyhow!("word_id {} is out of range", word_id))
+            })?;
+        let data = self.words.get(offset as usize..).ok_or_else(|| {
+            LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                "offset {} of word_id {} is out of range",
+                offset,
+                word_id
+            ))
+        })?;
+        self.codec.decode(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::DictionaryReader;
+    use crate::endian::Endianness;
+    use crate::fixtures::build_fixture;
+    use crate::manifest::MANIFEST_FILENAME;
+    use crate::options::BuilderOptions;
+    use crate::packing::ValuePacking;
+
+    #[test]
+    fn reads_every_artifact_format_alike() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-reader-{}",
+            std::process::id()
+        ));
+        let variants = vec![
+            BuilderOptions::default(),
+            BuilderOptions {
+                endianness: Endianness::Big,
+                artifact_headers: true,
+                ..BuilderOptions::default()
+            },
+            BuilderOptions {
+                value_packing: ValuePacking::Indirect,
+                ..BuilderOptions::default()
+            },
+        ];
+        for (idx, options) in variants.into_iter().enumerate() {
+            let input_dir = base_dir.join("input");
+            let output_dir = base_dir.join(format!("output-{}", idx));
+            build_fixture(&input_dir, &output_dir, options).unwrap();
+
+            let reader = DictionaryReader::open(&output_dir).unwrap();
+            assert_eq!(reader.num_words(), 5);
+            let entries = reader.lookup("東京スカイツリー");
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].word_cost, 2000);
+            assert_eq!(
+                reader.details(entries[0].word_id.0).unwrap()[7],
+                "トウキョウスカイツリー"
+            );
+            let prefixes: Vec<(usize, i16)> = reader
+                .prefix_search("東京スカイツリーに")
+                .into_iter()
+                .map(|(len, entry)| (len, entry.word_cost))
+                .collect();
+            assert_eq!(prefixes, vec![(6, 3000), (24, 2000)]);
+            assert!(reader.lookup("東").is_empty());
+            assert!(reader.lookup_reading("トウキョウ").is_none());
+            assert!(reader.details(5).is_err());
+        }
+
+        // Without a manifest, the defaults are assumed.
+        let output_dir = base_dir.join("output-0");
+        fs::remove_file(output_dir.join(MANIFEST_FILENAME)).unwrap();
+        assert_eq!(
+            DictionaryReader::open(&output_dir)
+                .unwrap()
+                .lookup("東京")
+                .len(),
+            1
+        );
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use lindera_core::LinderaResult;
+
+use crate::file_util::{read_source_file, source_lines};
+
+/// Provides the reading, in katakana, of surfaces that are not kana-only.
+pub trait ReadingProvider {
+    fn reading(&self, surface: &str) -> Option<String>;
+}
+
+/// A `ReadingProvider` backed by a table of surfaces and readings.
+#[derive(Debug, Clone, Default)]
+pub struct ReadingTable {
+    readings: HashMap<String, String>,
+}
+
+impl ReadingTable {
+    /// Loads a table of tab-separated surface and reading lines.
+    pub fn load(path: &Path) -> LinderaResult<ReadingTable> {
+        let content = read_source_file(path)?;
+        let mut readings = HashMap::new();
+        for (_, line) in source_lines(&content) {
+            if let Some((surface, reading)) = line.split_once('\t') {
+                readings.insert(surface.to_string(), reading.trim().to_string());
+            }
+        }
+        Ok(ReadingTable { readings })
+    }
+
+    pub fn insert(&mut self, surface: &str, reading: &str) {
+        self.readings
+            .insert(surface.to_string(), reading.to_string());
+    }
+}
+
+impl ReadingProvider for ReadingTable {
+    fn reading(&self, surface: &str) -> Option<String> {
+        self.readings.get(surface).cloned()
+    }
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{3096}' | '\u{30A1}'..='\u{30FA}' | 'ー' | 'ヽ' | 'ヾ' | 'ゝ' | 'ゞ')
+}
+
+/// Converts a kana-only surface to katakana, or returns `None` if it has other characters.
+pub fn kana_reading(surface: &str) -> Option<String> {
+    if surface.is_empty() || !surface.chars().all(is_kana) {
+        return None;
+    }
+    Some(
+        surface
+            .chars()
+            .map(|c| match c {
+                '\u{3041}'..='\u{3096}' | 'ゝ' | 'ゞ' => {
+                    std::char::from_u32(c as u32 + 0x60).unwrap_or(c)
+                }
+                _ => c,
+            })
+            .collect(),
+    )
+}
+
+/// Fills the reading and pronunciation fields left as `*` in the CSV `lines`, using the kana
+/// of kana-only surfaces or `provider`. Returns the surfaces whose reading is still missing.
+pub(crate) fn fill_readings(
+    lines: &mut [String],
+    provider: Option<&(dyn ReadingProvider + Send + Sync)>,
+) -> Vec<String> {
+    let mut unresolved = Vec::new();
+    for line in lines.iter_mut() {
+        let mut fields: Vec<&str> = line.split(',').collect();
+        if fields.len() < 13 || (fields[11] != "*" && fields[12] != "*") {
+            continue;
+        }
+        let reading = kana_reading(fields[0])
+            .or_else(|| provider.and_then(|provider| provider.reading(fields[0])));
+        let reading = match reading {
+            Some(reading) => reading,
+            None => {
+                unresolved.push(fields[0].to_string());
+                continue;
+            }
+        };
+        if fields[11] == "*" {
+            fields[11] = &reading;
+        }
+        if fields[12] == "*" {
+            fields[12] = &reading;
+        }
+        *line = fields.join(",");
+    }
+    unresolved
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{fill_readings, kana_reading, ReadingTable};
+
+    #[test]
+    fn reads_kana_only_surfaces_as_katakana() {
+        assert_eq!(kana_reading("いくゝ").as_deref(), Some("イクヽ"));
+        assert_eq!(
+            kana_reading("スカイツリー").as_deref(),
+            Some("スカイツリー")
+        );
+        assert_eq!(kana_reading("東京"), None);
+        assert_eq!(kana_reading("いくA"), None);
+        assert_eq!(kana_reading(""), None);
+    }
+
+    #[test]
+    fn fills_missing_readings_and_reports_the_unresolved() {
+        let table_file = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-readings-{}.tsv",
+            std::process::id()
+        ));
+        fs::write(&table_file, "渋谷\tシブヤ \n\nno-tab\n").unwrap();
+        let table = ReadingTable::load(&table_file).unwrap();
+
+        let mut lines: Vec<String> = [
+            "さくら,1,1,100,名詞,一般,*,*,*,*,さくら,*,*",
+            "渋谷,1,1,100,名詞,固有名詞,地域,一般,*,*,渋谷,*,シブヤ",
+            "新宿,1,1,100,名詞,固有名詞,地域,一般,*,*,新宿,*,*",
+            "東京,1,1,100,名詞,固有名詞,地域,一般,*,*,東京,トウキョウ,トーキョー",
+            "短い,1,1,100",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        let unresolved = fill_readings(&mut lines, Some(&table));
+        assert_eq!(unresolved, vec!["新宿".to_string()]);
+        assert_eq!(
+            lines[0],
+            "さくら,1,1,100,名詞,一般,*,*,*,*,さくら,サクラ,サクラ"
+        );
+        assert_eq!(
+            lines[1],
+            "渋谷,1,1,100,名詞,固有名詞,地域,一般,*,*,渋谷,シブヤ,シブヤ"
+        );
+        assert!(lines[2].ends_with(",新宿,*,*"));
+        assert!(lines[3].ends_with(",トウキョウ,トーキョー"));
+
+        let mut lines = vec!["渋谷,1,1,100,名詞,固有名詞,地域,一般,*,*,渋谷,*,*".to_string()];
+        assert_eq!(fill_readings(&mut lines, None), vec!["渋谷".to_string()]);
+
+        fs::remove_file(&table_file).unwrap();
+    }
+}
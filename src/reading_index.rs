@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+use yada::builder::DoubleArrayBuilder;
+use yada::DoubleArray;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::CsvRow;
+
+pub const READING_DA_FILENAME: &str = "reading.da";
+pub const READING_VALS_FILENAME: &str = "reading.vals";
+
+/// Writes the index from readings to the word_ids of their surfaces.
+///
+/// `reading.da` maps each reading to the position, in `u32`s, of its list in `reading.vals`,
+/// where a list is the number of word_ids followed by the `u32` word_ids. The files created
+/// are reported with `print`.
+pub(crate) fn write_reading_index(
+    output_dir: &Path,
+    rows: &[CsvRow],
+    print: &dyn Fn(&str),
+) -> LinderaResult<()> {
+    let mut word_ids: BTreeMap<&str, Vec<u32>> = BTreeMap::new();
+    for (word_id, row) in rows.iter().enumerate() {
+        if row.reading != "*" && !row.reading.is_empty() {
+            word_ids
+                .entry(row.reading)
+                .or_default()
+                .push(word_id as u32);
+        }
+    }
+
+    let mut vals_data = Vec::new();
+    let mut keyset: Vec<(&[u8], u32)> = Vec::with_capacity(word_ids.len());
+    for (reading, ids) in &word_ids {
+        keyset.push((reading.as_bytes(), (vals_data.len() / 4) as u32));
+        vals_data
+            .write_u32::<LittleEndian>(ids.len() as u32)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        for id in ids {
+            vals_data
+                .write_u32::<LittleEndian>(*id)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        }
+    }
+
+    let da_bytes = DoubleArrayBuilder::build(&keyset).ok_or_else(|| {
+        LinderaErrorKind::Io.with_error(anyhow::anyhow!("DoubleArray build error."))
+    })?;
+
+    for (filename, data) in &[
+        (READING_DA_FILENAME, &da_bytes),
+        (READING_VALS_FILENAME, &vals_data),
+    ] {
+        let path = output_dir.join(filename);
+        print(&format!("creating {:?}", path));
+        fs::write(path, data)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    }
+
+    Ok(())
+}
+
+/// Looks up the word_ids of a reading in the index loaded from `reading.da`/`reading.vals`.
+pub(crate) fn lookup_reading(da: &DoubleArray<Vec<u8>>, vals: &[u8], reading: &str) -> Vec<u32> {
+    let pos = match da.exact_match_search(reading) {
+        Some(pos) => pos as usize * 4,
+        None => return Vec::new(),
+    };
+    let len = match vals.get(pos..pos + 4) {
+        Some(data) => LittleEndian::read_u32(data) as usize,
+        None => return Vec::new(),
+    };
+    vals.get(pos + 4..pos + 4 + len * 4)
+        .map(|data| data.chunks(4).map(LittleEndian::read_u32).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::fixtures::build_fixture;
+    use crate::options::BuilderOptions;
+    use crate::reader::DictionaryReader;
+
+    #[test]
+    fn maps_readings_to_the_word_ids_of_their_surfaces() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-reading-index-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        let options = BuilderOptions {
+            reading_index: true,
+            ..BuilderOptions::default()
+        };
+        build_fixture(&input_dir, &output_dir, options).unwrap();
+
+        let reader = DictionaryReader::open(&output_dir).unwrap();
+        // いく and 行く share their reading.
+        let word_ids = reader.lookup_reading("イク").unwrap();
+        assert_eq!(word_ids, vec![0, 4]);
+        for word_id in word_ids {
+            assert_eq!(reader.details(word_id).unwrap()[7], "イク");
+        }
+        assert_eq!(reader.lookup_reading("トウキョウ").unwrap(), vec![2]);
+        assert_eq!(reader.lookup_reading("トウ").unwrap(), Vec::<u32>::new());
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
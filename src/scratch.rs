@@ -0,0 +1,179 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::IpadicNeologdBuilder;
+
+/// The prefix of the names of the scratch directories.
+pub const SCRATCH_DIR_PREFIX: &str = ".lindera-scratch";
+
+/// Tells apart the scratch directories of a process.
+static NEXT_SCRATCH_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A directory of temporary files, removed with its contents when dropped, whether the
+/// work using it succeeds, fails or is cancelled.
+#[derive(Debug)]
+pub struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    /// Creates a new scratch directory in `base_dir`, named `purpose` after
+    /// `SCRATCH_DIR_PREFIX` with the process id and a counter, so that concurrent builds
+    /// sharing `base_dir` do not collide.
+    pub fn new(base_dir: &Path, purpose: &str) -> LinderaResult<ScratchDir> {
+        let path = base_dir.join(format!(
+            "{}-{}-{}-{}",
+            SCRATCH_DIR_PREFIX,
+            purpose,
+            std::process::id(),
+            NEXT_SCRATCH_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&path)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        Ok(ScratchDir { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The path of the file `name` in the scratch directory.
+    pub fn join(&self, name: &str) -> PathBuf {
+        self.path.join(name)
+    }
+
+    /// Writes the file `dest` atomically: `write` writes it to the scratch directory, from
+    /// where it is renamed to `dest`, or copied if they are on different filesystems, so
+    /// that `dest` is never left partially written.
+    pub fn write_atomic<F>(&self, dest: &Path, write: F) -> io::Result<()>
+    where
+        F: FnOnce(&Path) -> io::Result<()>,
+    {
+        let name = dest
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let tmp_path = self.path.join(format!("{}.tmp", name));
+        write(&tmp_path)?;
+        if fs::rename(&tmp_path, dest).is_err() {
+            // Copied aside of `dest` first, so that the final rename is atomic.
+            let copy_path = dest.with_file_name(format!(".{}.tmp", name));
+            fs::copy(&tmp_path, &copy_path)?;
+            fs::remove_file(&tmp_path)?;
+            fs::rename(&copy_path, dest)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+impl IpadicNeologdBuilder {
+    /// Creates a scratch directory for `purpose` in `BuilderOptions::scratch_dir`, or in
+    /// `output_dir` if it is not set.
+    pub(crate) fn scratch_dir(
+        &self,
+        output_dir: &Path,
+        purpose: &str,
+    ) -> LinderaResult<ScratchDir> {
+        ScratchDir::new(
+            self.options.scratch_dir.as_deref().unwrap_or(output_dir),
+            purpose,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io;
+
+    use super::{ScratchDir, SCRATCH_DIR_PREFIX};
+    use crate::fixtures::build_fixture;
+    use crate::options::BuilderOptions;
+    use crate::IpadicNeologdBuilder;
+
+    #[test]
+    fn removes_the_scratch_directory_when_dropped() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-scratch-{}",
+            std::process::id()
+        ));
+        let first = ScratchDir::new(&base_dir, "sort").unwrap();
+        let second = ScratchDir::new(&base_dir, "sort").unwrap();
+        assert_ne!(first.path(), second.path());
+        assert!(first
+            .path()
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with(&format!(
+                "{}-sort-{}-",
+                SCRATCH_DIR_PREFIX,
+                std::process::id()
+            )));
+
+        let dest = base_dir.join("dict.da");
+        fs::write(&dest, b"old").unwrap();
+        let err = first
+            .write_atomic(&dest, |path| {
+                fs::write(path, b"partial")?;
+                Err(io::Error::other("interrupted"))
+            })
+            .unwrap_err();
+        assert_eq!(err.to_string(), "interrupted");
+        assert_eq!(fs::read(&dest).unwrap(), b"old");
+        first
+            .write_atomic(&dest, |path| fs::write(path, b"new"))
+            .unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), b"new");
+
+        let (first_path, second_path) = (first.path().to_path_buf(), second.path().to_path_buf());
+        fs::write(first.join("run-0"), b"run").unwrap();
+        drop(first);
+        drop(second);
+        assert!(!first_path.exists());
+        assert!(!second_path.exists());
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn builds_with_a_configured_scratch_directory() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-scratch-build-{}",
+            std::process::id()
+        ));
+        let (output_dir, scratch_dir) = (base_dir.join("output"), base_dir.join("scratch"));
+        let options = BuilderOptions {
+            scratch_dir: Some(scratch_dir.clone()),
+            memory_budget: Some(1),
+            ..BuilderOptions::default()
+        };
+        let scratch = IpadicNeologdBuilder::with_options(options.clone())
+            .scratch_dir(&output_dir, "test")
+            .unwrap();
+        assert!(scratch.path().starts_with(&scratch_dir));
+        drop(scratch);
+
+        build_fixture(&base_dir.join("input"), &output_dir, options).unwrap();
+        let scratch_dirs: Vec<_> = fs::read_dir(&scratch_dir)
+            .unwrap()
+            .chain(fs::read_dir(&output_dir).unwrap())
+            .map(|entry| entry.unwrap().file_name())
+            .filter(|name| name.to_string_lossy().starts_with(SCRATCH_DIR_PREFIX))
+            .collect();
+        assert!(scratch_dirs.is_empty(), "{:?}", scratch_dirs);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// The snapshot dates of the NEologd seed files of a build, as `YYYYMMDD`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedDates {
+    pub oldest: String,
+    pub newest: String,
+}
+
+impl SeedDates {
+    /// The oldest and newest snapshot dates in the names of `files`, or `None` if no name
+    /// carries one.
+    pub fn from_files<P: AsRef<Path>>(files: &[P]) -> Option<SeedDates> {
+        let mut dates = files
+            .iter()
+            .filter_map(|file| file.as_ref().file_stem())
+            .filter_map(|stem| seed_date(&stem.to_string_lossy()).map(str::to_string));
+        let first = dates.next()?;
+        let mut seed_dates = SeedDates {
+            oldest: first.clone(),
+            newest: first,
+        };
+        for date in dates {
+            if date < seed_dates.oldest {
+                seed_dates.oldest = date;
+            } else if date > seed_dates.newest {
+                seed_dates.newest = date;
+            }
+        }
+        Some(seed_dates)
+    }
+
+    /// Whether the files come from more than one snapshot.
+    pub fn is_mixed(&self) -> bool {
+        self.oldest != self.newest
+    }
+}
+
+/// The snapshot date of a seed file name such as `neologd-adjective-std-dict-seed.20200910`
+/// or `neologd-adjective-exp-dict-seed-20200910`: the last run of eight digits forming a
+/// plausible `YYYYMMDD` date.
+pub fn seed_date(file_stem: &str) -> Option<&str> {
+    file_stem
+        .rsplit(|c: char| !c.is_ascii_digit())
+        .find(|digits| digits.len() == 8 && is_date(digits))
+}
+
+fn is_date(digits: &str) -> bool {
+    let year: u32 = digits[0..4].parse().unwrap_or(0);
+    let month: u32 = digits[4..6].parse().unwrap_or(0);
+    let day: u32 = digits[6..8].parse().unwrap_or(0);
+    (1900..=2999).contains(&year) && (1..=12).contains(&month) && (1..=31).contains(&day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{seed_date, SeedDates};
+
+    #[test]
+    fn finds_the_last_plausible_date() {
+        assert_eq!(
+            seed_date("neologd-adjective-std-dict-seed.20200910"),
+            Some("20200910")
+        );
+        assert_eq!(
+            seed_date("neologd-adjective-exp-dict-seed-20200910"),
+            Some("20200910")
+        );
+        assert_eq!(seed_date("seed-20190101-20200910"), Some("20200910"));
+        assert_eq!(seed_date("seed-20200910-12345678"), Some("20200910"));
+        assert_eq!(seed_date("seed-20201310"), None);
+        assert_eq!(seed_date("seed-202009100"), None);
+        assert_eq!(seed_date("seed"), None);
+    }
+
+    #[test]
+    fn spans_the_dates_of_the_files() {
+        let seed_dates = SeedDates::from_files(&[
+            "input/neologd-noun-dict-seed.20200910.csv",
+            "input/user.csv",
+            "input/neologd-adjective-std-dict-seed.20151126.csv",
+            "input/neologd-adverb-dict-seed.20150623.csv",
+        ])
+        .unwrap();
+        assert_eq!(seed_dates.oldest, "20150623");
+        assert_eq!(seed_dates.newest, "20200910");
+        assert!(seed_dates.is_mixed());
+
+        let seed_dates =
+            SeedDates::from_files(&["neologd-noun-dict-seed.20200910.csv", "user.csv"]).unwrap();
+        assert!(!seed_dates.is_mixed());
+        assert!(SeedDates::from_files(&["user.csv"]).is_none());
+    }
+}
@@ -0,0 +1,415 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use lindera_core::dictionary_builder::DictionaryBuilder;
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::file_util::find_input_dir;
+use crate::options::BuilderOptions;
+use crate::package::{self, package_output};
+use crate::IpadicNeologdBuilder;
+
+const USER_DICT_FILENAME: &str = "userdic.bin";
+
+/// The number of jobs built at once when `BuildServer::with_max_jobs` is not set.
+pub const DEFAULT_MAX_JOBS: usize = 2;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum JobStatus {
+    /// The job waits for one of the jobs built at once to end.
+    Queued,
+    Running,
+    Done {
+        #[serde(skip)]
+        artifact: PathBuf,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+enum JobKind {
+    Dictionary,
+    UserDictionary,
+}
+
+type Jobs = Arc<Mutex<HashMap<u64, JobStatus>>>;
+
+/// The number of jobs built at once, bounded by the maximum of the server.
+struct JobSlots {
+    running: Mutex<usize>,
+    freed: Condvar,
+    max_jobs: usize,
+}
+
+impl JobSlots {
+    /// Waits until fewer than the maximum number of jobs are built, and takes a slot.
+    fn acquire(&self) {
+        if let Ok(mut running) = self.running.lock() {
+            while *running >= self.max_jobs {
+                running = match self.freed.wait(running) {
+                    Ok(running) => running,
+                    Err(_) => return,
+                };
+            }
+            *running += 1;
+        }
+    }
+
+    fn release(&self) {
+        if let Ok(mut running) = self.running.lock() {
+            *running -= 1;
+        }
+        self.freed.notify_one();
+    }
+}
+
+/// A small HTTP service building dictionaries on request.
+///
+/// - `POST /dictionary` with a `.tar.gz` of the source directory starts a dictionary build.
+/// - `POST /user-dictionary` with a user dictionary CSV starts a user dictionary build.
+/// - `GET /jobs/<id>` returns the status of a job.
+/// - `GET /jobs/<id>/artifact` downloads the packaged dictionary or the user dictionary binary.
+///
+/// Each job is built in a directory of the work directory named after its id. The ids
+/// continue after those of the directories left by earlier runs of the server. At most
+/// `max_jobs` jobs are built at once, the others are queued.
+pub struct BuildServer {
+    options: BuilderOptions,
+    work_dir: PathBuf,
+    jobs: Jobs,
+    slots: Arc<JobSlots>,
+    next_id: u64,
+}
+
+impl BuildServer {
+    pub fn new(work_dir: &Path, options: BuilderOptions) -> Self {
+        BuildServer {
+            options,
+            work_dir: work_dir.to_path_buf(),
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            slots: Arc::new(JobSlots {
+                running: Mutex::new(0),
+                freed: Condvar::new(),
+                max_jobs: DEFAULT_MAX_JOBS,
+            }),
+            next_id: 0,
+        }
+    }
+
+    /// Sets the number of jobs built at once.
+    pub fn with_max_jobs(mut self, max_jobs: usize) -> Self {
+        self.slots = Arc::new(JobSlots {
+            running: Mutex::new(0),
+            freed: Condvar::new(),
+            max_jobs: max_jobs.max(1),
+        });
+        self
+    }
+
+    /// Listens on `addr` and serves requests until the process is stopped.
+    pub fn serve(&mut self, addr: &str) -> LinderaResult<()> {
+        let server = Server::http(addr)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err.to_string())))?;
+        println!("listening on {}", addr);
+        self.serve_requests(&server)
+    }
+
+    fn serve_requests(&mut self, server: &Server) -> LinderaResult<()> {
+        fs::create_dir_all(&self.work_dir)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        self.next_id = next_job_id(&self.work_dir)?;
+
+        for request in server.incoming_requests() {
+            let result = self.handle(request);
+            if let Err(err) = result {
+                println!("{}", err);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle(&mut self, request: Request) -> io::Result<()> {
+        let url = request.url().trim_end_matches('/').to_string();
+        let segments: Vec<&str> = url.split('/').filter(|s| !s.is_empty()).collect();
+
+        match (request.method(), segments.as_slice()) {
+            (Method::Post, ["dictionary"]) => self.respond_start_job(JobKind::Dictionary, request),
+            (Method::Post, ["user-dictionary"]) => {
+                self.respond_start_job(JobKind::UserDictionary, request)
+            }
+            (Method::Get, ["jobs", id]) => match self.job_status(id) {
+                Some(status) => respond_json(request, 200, &status),
+                None => request.respond(Response::empty(404)),
+            },
+            (Method::Get, ["jobs", id, "artifact"]) => match self.job_status(id) {
+                Some(JobStatus::Done { artifact }) => {
+                    let file = File::open(artifact)?;
+                    request.respond(Response::from_file(file))
+                }
+                Some(_) => request.respond(Response::empty(409)),
+                None => request.respond(Response::empty(404)),
+            },
+            _ => request.respond(Response::empty(404)),
+        }
+    }
+
+    /// Starts a job of the uploaded request body, responding with its id, or with the error
+    /// if the upload could not be stored.
+    fn respond_start_job(&mut self, kind: JobKind, mut request: Request) -> io::Result<()> {
+        match self.start_job(kind, request.as_reader()) {
+            Ok(id) => respond_json(request, 202, &serde_json::json!({ "id": id })),
+            Err(err) => respond_json(
+                request,
+                500,
+                &serde_json::json!({ "error": err.to_string() }),
+            ),
+        }
+    }
+
+    fn job_status(&self, id: &str) -> Option<JobStatus> {
+        let id = id.parse::<u64>().ok()?;
+        self.jobs.lock().ok()?.get(&id).cloned()
+    }
+
+    fn start_job(&mut self, kind: JobKind, body: &mut dyn Read) -> io::Result<u64> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        // A job never reuses the directory of another one.
+        let job_dir = self.work_dir.join(id.to_string());
+        fs::create_dir(&job_dir)?;
+        let upload_path = job_dir.join("upload");
+        let uploaded = File::create(&upload_path).and_then(|mut upload| {
+            io::copy(body, &mut upload)?;
+            upload.flush()
+        });
+        if let Err(err) = uploaded {
+            let _ = fs::remove_dir_all(&job_dir);
+            return Err(err);
+        }
+
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.insert(id, JobStatus::Queued);
+        }
+
+        let jobs = self.jobs.clone();
+        let slots = self.slots.clone();
+        let options = self.options.clone();
+        thread::spawn(move || {
+            slots.acquire();
+            if let Ok(mut jobs) = jobs.lock() {
+                jobs.insert(id, JobStatus::Running);
+            }
+            let builder = IpadicNeologdBuilder::with_options(options);
+            let result = match kind {
+                JobKind::Dictionary => build_dictionary_job(&builder, &job_dir, &upload_path),
+                JobKind::UserDictionary => {
+                    build_user_dictionary_job(&builder, &job_dir, &upload_path)
+                }
+            };
+            let status = match result {
+                Ok(artifact) => JobStatus::Done { artifact },
+                Err(err) => JobStatus::Failed {
+                    error: err.to_string(),
+                },
+            };
+            if let Ok(mut jobs) = jobs.lock() {
+                jobs.insert(id, status);
+            }
+            slots.release();
+        });
+
+        Ok(id)
+    }
+}
+
+/// The id following those of the job directories in `work_dir`.
+fn next_job_id(work_dir: &Path) -> LinderaResult<u64> {
+    let entries = fs::read_dir(work_dir)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    let mut next_id = 0;
+    for entry in entries {
+        let entry = entry.map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        if let Some(id) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<u64>().ok())
+        {
+            next_id = next_id.max(id + 1);
+        }
+    }
+    Ok(next_id)
+}
+
+fn respond_json<T: Serialize>(request: Request, status_code: u16, body: &T) -> io::Result<()> {
+    let body = serde_json::to_string(body).map_err(io::Error::other)?;
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .map_err(|_| io::Error::other("invalid header"))?;
+    request.respond(
+        Response::from_string(body)
+            .with_status_code(status_code)
+            .with_header(content_type),
+    )
+}
+
+fn build_dictionary_job(
+    builder: &IpadicNeologdBuilder,
+    job_dir: &Path,
+    archive_path: &Path,
+) -> LinderaResult<PathBuf> {
+    // The source is extracted to a scratch directory, removed once the job ends.
+    let source_dir = builder.scratch_dir(job_dir, "source")?;
+    let archive = File::open(archive_path)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    tar::Archive::new(GzDecoder::new(archive))
+        .unpack(source_dir.path())
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    let input_dir = find_input_dir(source_dir.path())?;
+
+    let output_dir = job_dir.join("output");
+    builder.build_dictionary(&input_dir, &output_dir)?;
+    package_output(
+        &output_dir,
+        builder.package_version(),
+        builder
+            .options
+            .compression_level
+            .unwrap_or(package::DEFAULT_COMPRESSION_LEVEL),
+    )
+}
+
+fn build_user_dictionary_job(
+    builder: &IpadicNeologdBuilder,
+    job_dir: &Path,
+    csv_path: &Path,
+) -> LinderaResult<PathBuf> {
+    let user_dict_path = job_dir.join(USER_DICT_FILENAME);
+    builder.build_user_dictionary(csv_path, &user_dict_path)?;
+    Ok(user_dict_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::thread;
+    use std::time::Duration;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use tiny_http::Server;
+
+    use super::BuildServer;
+    use crate::fixtures::write_fixture;
+    use crate::options::BuilderOptions;
+
+    /// Sends an HTTP/1.0 request and returns the status code and the body of the response.
+    fn request(addr: SocketAddr, method: &str, path: &str, body: &[u8]) -> (u16, Vec<u8>) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.0\r\nContent-Length: {}\r\n\r\n",
+            method,
+            path,
+            body.len()
+        )
+        .unwrap();
+        stream.write_all(body).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+
+        let header_end = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .unwrap();
+        let status_line = String::from_utf8_lossy(&response[..header_end]).to_string();
+        let status_code = status_line.split(' ').nth(1).unwrap().parse().unwrap();
+        (status_code, response[header_end + 4..].to_vec())
+    }
+
+    fn json(body: &[u8]) -> serde_json::Value {
+        serde_json::from_slice(body).unwrap()
+    }
+
+    /// Polls the status of the job `id` until it is no longer queued or running.
+    fn wait_for_job(addr: SocketAddr, id: u64) -> serde_json::Value {
+        for _ in 0..600 {
+            let (status_code, body) = request(addr, "GET", &format!("/jobs/{}", id), b"");
+            assert_eq!(status_code, 200);
+            let status = json(&body);
+            if status["status"] != "queued" && status["status"] != "running" {
+                return status;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("job {} did not end", id);
+    }
+
+    #[test]
+    fn builds_uploaded_sources_on_request() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-serve-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&base_dir);
+        let source_dir = base_dir.join("source");
+        write_fixture(&source_dir).unwrap();
+        let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        archive.append_dir_all("mecab-ipadic", &source_dir).unwrap();
+        let archive = archive.into_inner().unwrap().finish().unwrap();
+
+        // The directory of a job of an earlier run of the server is kept.
+        let work_dir = base_dir.join("work");
+        fs::create_dir_all(work_dir.join("4")).unwrap();
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let addr = server.server_addr().to_ip().unwrap();
+        thread::spawn(move || {
+            BuildServer::new(&work_dir, BuilderOptions::default())
+                .with_max_jobs(1)
+                .serve_requests(&server)
+        });
+
+        let (status_code, body) = request(addr, "POST", "/dictionary", &archive);
+        assert_eq!(status_code, 202);
+        let id = json(&body)["id"].as_u64().unwrap();
+        assert_eq!(id, 5);
+        let (status_code, body) = request(addr, "POST", "/user-dictionary", b"");
+        assert_eq!(status_code, 202);
+        let user_dict_id = json(&body)["id"].as_u64().unwrap();
+        assert_eq!(user_dict_id, 6);
+
+        assert_eq!(wait_for_job(addr, id)["status"], "done");
+        let (status_code, package) = request(addr, "GET", &format!("/jobs/{}/artifact", id), b"");
+        assert_eq!(status_code, 200);
+        assert_eq!(&package[..2], &[0x1f, 0x8b]);
+
+        // A user dictionary without entries fails.
+        let status = wait_for_job(addr, user_dict_id);
+        assert_eq!(status["status"], "failed");
+        assert!(status["error"].as_str().unwrap().contains("no entries"));
+        let (status_code, _) = request(
+            addr,
+            "GET",
+            &format!("/jobs/{}/artifact", user_dict_id),
+            b"",
+        );
+        assert_eq!(status_code, 409);
+        assert_eq!(request(addr, "GET", "/jobs/99", b"").0, 404);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
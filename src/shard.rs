@@ -0,0 +1,264 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::word_entry::WordEntry;
+use lindera_core::LinderaResult;
+
+use crate::layout::DictionaryLayout;
+use crate::CsvRow;
+
+pub const SHARD_INDEX_FILENAME: &str = "dict.shards.json";
+
+/// A shard holding the word details and the word entries of a contiguous word_id range.
+///
+/// Since word_ids are assigned in surface order, a shard also covers the surfaces from
+/// `first_surface` to `last_surface`. Offsets in the shard's `wordsidx` file are relative
+/// to the shard's `words` file, and `first_val_index` is the index of the shard's first
+/// entry in `dict.vals`, as referenced by the double array.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardEntry {
+    pub words: String,
+    pub wordsidx: String,
+    pub vals: String,
+    pub first_word_id: u32,
+    pub word_count: u32,
+    pub first_val_index: u32,
+    pub val_count: u32,
+    pub first_surface: String,
+    pub last_surface: String,
+}
+
+/// The index of the shards, written to `dict.shards.json` in the output directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardIndex {
+    pub shard_size: usize,
+    pub shards: Vec<ShardEntry>,
+}
+
+impl ShardIndex {
+    pub fn load(output_dir: &Path) -> LinderaResult<ShardIndex> {
+        let index_data = fs::read(output_dir.join(SHARD_INDEX_FILENAME))
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        serde_json::from_slice(&index_data)
+            .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))
+    }
+
+    /// The names of the index and of every shard file.
+    pub fn filenames(&self) -> Vec<String> {
+        let mut filenames = vec![SHARD_INDEX_FILENAME.to_string()];
+        for shard in &self.shards {
+            filenames.push(shard.words.clone());
+            filenames.push(shard.wordsidx.clone());
+            filenames.push(shard.vals.clone());
+        }
+        filenames
+    }
+}
+
+fn write_file(path: &Path, data: &[u8], print: &dyn Fn(&str)) -> LinderaResult<()> {
+    print(&format!("creating {:?}", path));
+    fs::write(path, data).map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))
+}
+
+/// Splits the word details and the word entries into shards of `shard_size` word_ids,
+/// named after the unsharded files of `layout`, and writes the shard index.
+///
+/// `rows` are the sorted rows, `words_data`/`words_offsets` their serialized details and
+/// `word_entry_map` the word entries grouped by surface. The files created are reported with
+/// `print`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn write_shards(
+    output_dir: &Path,
+    layout: &DictionaryLayout,
+    shard_size: usize,
+    rows: &[CsvRow],
+    words_data: &[u8],
+    words_offsets: &[u32],
+    word_entry_map: &BTreeMap<String, Vec<WordEntry>>,
+    print: &dyn Fn(&str),
+) -> LinderaResult<()> {
+    if shard_size == 0 {
+        return Err(
+            LinderaErrorKind::Args.with_error(anyhow::anyhow!("shard size must not be zero"))
+        );
+    }
+
+    let word_entries: Vec<&WordEntry> = word_entry_map.values().flatten().collect();
+    let mut val_index = 0;
+
+    let mut shards = Vec::new();
+    for (shard_id, first_word_id) in (0..rows.len()).step_by(shard_size).enumerate() {
+        let end_word_id = (first_word_id + shard_size).min(rows.len());
+
+        let words_start = words_offsets[first_word_id] as usize;
+        let words_end = words_offsets
+            .get(end_word_id)
+            .map(|offset| *offset as usize)
+            .unwrap_or_else(|| words_data.len());
+        let mut words_idx_data = Vec::new();
+        for offset in &words_offsets[first_word_id..end_word_id] {
+            words_idx_data
+                .write_u32::<LittleEndian>(*offset - words_start as u32)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        }
+
+        let first_val_index = val_index;
+        let mut vals_data = Vec::<u8>::new();
+        while let Some(word_entry) = word_entries.get(val_index) {
+            if word_entry.word_id.0 as usize >= end_word_id {
+                break;
+            }
+            WordEntry::serialize(word_entry, &mut vals_data)
+                .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+            val_index += 1;
+        }
+
+        let shard = ShardEntry {
+            words: format!("{}.{:03}", layout.dict_words, shard_id),
+            wordsidx: format!("{}.{:03}", layout.dict_wordsidx, shard_id),
+            vals: format!("{}.{:03}", layout.dict_vals, shard_id),
+            first_word_id: first_word_id as u32,
+            word_count: (end_word_id - first_word_id) as u32,
+            first_val_index: first_val_index as u32,
+            val_count: (val_index - first_val_index) as u32,
+            first_surface: rows[first_word_id].surface_form.to_string(),
+            last_surface: rows[end_word_id - 1].surface_form.to_string(),
+        };
+        write_file(
+            &output_dir.join(&shard.words),
+            &words_data[words_start..words_end],
+            print,
+        )?;
+        write_file(&output_dir.join(&shard.wordsidx), &words_idx_data, print)?;
+        write_file(&output_dir.join(&shard.vals), &vals_data, print)?;
+        shards.push(shard);
+    }
+
+    let index = ShardIndex { shard_size, shards };
+    let wtr_index_path = output_dir.join(Path::new(SHARD_INDEX_FILENAME));
+    print(&format!("creating {:?}", wtr_index_path));
+    let mut wtr_index = io::BufWriter::new(
+        File::create(wtr_index_path)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
+    );
+    serde_json::to_writer_pretty(&mut wtr_index, &index)
+        .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+    wtr_index
+        .flush()
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use byteorder::{ByteOrder, LittleEndian};
+
+    use super::{ShardIndex, SHARD_INDEX_FILENAME};
+    use crate::fixtures::build_fixture;
+    use crate::manifest::Manifest;
+    use crate::options::BuilderOptions;
+
+    #[test]
+    fn shards_concatenate_to_the_unsharded_files() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-shard-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        let options = BuilderOptions {
+            shard_size: Some(2),
+            ..BuilderOptions::default()
+        };
+        build_fixture(&input_dir, &output_dir, options).unwrap();
+
+        let index = ShardIndex::load(&output_dir).unwrap();
+        assert_eq!(index.shard_size, 2);
+        let ranges: Vec<(u32, u32, &str, &str)> = index
+            .shards
+            .iter()
+            .map(|shard| {
+                (
+                    shard.first_word_id,
+                    shard.word_count,
+                    shard.first_surface.as_str(),
+                    shard.last_surface.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (0, 2, "いく", "に"),
+                (2, 2, "東京", "東京スカイツリー"),
+                (4, 1, "行く", "行く"),
+            ]
+        );
+        assert_eq!(index.shards[0].words, "dict.words.000");
+        assert_eq!(index.shards[2].vals, "dict.vals.002");
+
+        let read = |name: &str| fs::read(output_dir.join(name)).unwrap();
+        let mut words = Vec::new();
+        let mut vals = Vec::new();
+        let mut val_index = 0;
+        for shard in &index.shards {
+            let shard_words = read(&shard.words);
+            let offsets: Vec<u32> = read(&shard.wordsidx)
+                .chunks(4)
+                .map(LittleEndian::read_u32)
+                .collect();
+            assert_eq!(offsets.len(), shard.word_count as usize);
+            assert_eq!(offsets[0], 0);
+            assert!(offsets
+                .iter()
+                .all(|offset| (*offset as usize) < shard_words.len()));
+            words.extend(shard_words);
+
+            assert_eq!(shard.first_val_index, val_index);
+            val_index += shard.val_count;
+            vals.extend(read(&shard.vals));
+        }
+        assert_eq!(words, read("dict.words"));
+        assert_eq!(vals, read("dict.vals"));
+
+        let manifest = Manifest::load(&output_dir).unwrap();
+        for name in index.filenames() {
+            assert!(
+                manifest
+                    .artifacts
+                    .iter()
+                    .any(|artifact| artifact.name == name),
+                "{} is missing from the manifest",
+                name
+            );
+        }
+        assert_eq!(index.filenames()[0], SHARD_INDEX_FILENAME);
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_zero_shard_size() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-shard-zero-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        let options = BuilderOptions {
+            shard_size: Some(0),
+            ..BuilderOptions::default()
+        };
+        let err = build_fixture(&input_dir, &output_dir, options).unwrap_err();
+        assert!(err.to_string().contains("shard size must not be zero"));
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
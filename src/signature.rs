@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use ed25519_dalek::{Digest, Keypair, PublicKey, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::Sha512;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::manifest::{Manifest, MANIFEST_FILENAME};
+
+pub const SIGNATURE_FILENAME: &str = "signature.json";
+
+/// Context string for the Ed25519ph signatures, so they can't be replayed in other protocols.
+const SIGNATURE_CONTEXT: &[u8] = b"lindera-ipadic-neologd";
+
+/// A 32-byte Ed25519 secret key, left out of the `Debug` output of the options so that it
+/// does not end up in logs.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SigningKey(pub Vec<u8>);
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SigningKey(<redacted>)")
+    }
+}
+
+/// Ed25519 signatures of the manifest and the artifacts of a built dictionary,
+/// written to `signature.json` in the output directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureFile {
+    /// Hex-encoded public key of the signer.
+    pub public_key: String,
+    /// Hex-encoded signatures keyed by file name.
+    pub signatures: BTreeMap<String, String>,
+}
+
+fn prehash_file(path: &Path) -> LinderaResult<Sha512> {
+    let mut file =
+        File::open(path).map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    let mut hasher = Sha512::new();
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        let len = file
+            .read(&mut buf)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        if len == 0 {
+            break;
+        }
+        hasher.update(&buf[..len]);
+    }
+    Ok(hasher)
+}
+
+fn signed_filenames(manifest: &Manifest) -> Vec<String> {
+    let mut filenames = vec![MANIFEST_FILENAME.to_string()];
+    filenames.extend(
+        manifest
+            .artifacts
+            .iter()
+            .map(|artifact| artifact.name.clone()),
+    );
+    filenames
+}
+
+/// Signs the manifest and every artifact listed in it with the given 32-byte Ed25519 secret key.
+pub fn sign_output(output_dir: &Path, secret_key: &[u8]) -> LinderaResult<()> {
+    let secret = SecretKey::from_bytes(secret_key)
+        .map_err(|err| LinderaErrorKind::Args.with_error(anyhow::anyhow!(err)))?;
+    let public = PublicKey::from(&secret);
+    let keypair = Keypair { secret, public };
+
+    let manifest = Manifest::load(output_dir)?;
+    let mut signatures = BTreeMap::new();
+    for filename in signed_filenames(&manifest) {
+        let prehashed = prehash_file(&output_dir.join(&filename))?;
+        let signature = keypair
+            .sign_prehashed(prehashed, Some(SIGNATURE_CONTEXT))
+            .map_err(|err| LinderaErrorKind::Content.with_error(anyhow::anyhow!(err)))?;
+        signatures.insert(filename, hex::encode(signature.to_bytes().as_ref()));
+    }
+
+    let signature_file = SignatureFile {
+        public_key: hex::encode(keypair.public.to_bytes()),
+        signatures,
+    };
+
+    let wtr_signature_path = output_dir.join(Path::new(SIGNATURE_FILENAME));
+    println!("creating {:?}", wtr_signature_path);
+    let mut wtr_signature = io::BufWriter::new(
+        File::create(wtr_signature_path)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
+    );
+    serde_json::to_writer_pretty(&mut wtr_signature, &signature_file)
+        .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+    wtr_signature
+        .flush()
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+    Ok(())
+}
+
+/// Verifies that the manifest and every artifact listed in it carry a valid signature
+/// made by the owner of `public_key`.
+pub fn verify_signature(output_dir: &Path, public_key: &[u8]) -> LinderaResult<()> {
+    let public = PublicKey::from_bytes(public_key)
+        .map_err(|err| LinderaErrorKind::Args.with_error(anyhow::anyhow!(err)))?;
+
+    let signature_data = fs::read(output_dir.join(SIGNATURE_FILENAME))
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    let signature_file: SignatureFile = serde_json::from_slice(&signature_data)
+        .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))?;
+
+    let manifest = Manifest::load(output_dir)?;
+    for filename in signed_filenames(&manifest) {
+        let signature_hex = signature_file.signatures.get(&filename).ok_or_else(|| {
+            LinderaErrorKind::Content.with_error(anyhow::anyhow!("{} is not signed", filename))
+        })?;
+        let signature_bytes = hex::decode(signature_hex)
+            .map_err(|err| LinderaErrorKind::Decode.with_error(anyhow::anyhow!(err)))?;
+        let signature = Signature::try_from(&signature_bytes[..])
+            .map_err(|err| LinderaErrorKind::Decode.with_error(anyhow::anyhow!(err)))?;
+
+        let prehashed = prehash_file(&output_dir.join(&filename))?;
+        public
+            .verify_prehashed(prehashed, Some(SIGNATURE_CONTEXT), &signature)
+            .map_err(|_err| {
+                LinderaErrorKind::Content
+                    .with_error(anyhow::anyhow!("invalid signature for {}", filename))
+            })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+
+    use ed25519_dalek::{PublicKey, SecretKey};
+
+    use super::{sign_output, verify_signature, SigningKey};
+    use crate::fixtures::build_fixture;
+    use crate::manifest::Manifest;
+    use crate::options::BuilderOptions;
+
+    const SECRET_KEY: [u8; 32] = [7; 32];
+
+    fn public_key(secret_key: &[u8]) -> Vec<u8> {
+        PublicKey::from(&SecretKey::from_bytes(secret_key).unwrap())
+            .to_bytes()
+            .to_vec()
+    }
+
+    #[test]
+    fn verifies_signed_output_and_detects_tampering() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-signature-{}",
+            std::process::id()
+        ));
+        let (input_dir, output_dir) = (base_dir.join("input"), base_dir.join("output"));
+        build_fixture(&input_dir, &output_dir, BuilderOptions::default()).unwrap();
+
+        sign_output(&output_dir, &SECRET_KEY).unwrap();
+        verify_signature(&output_dir, &public_key(&SECRET_KEY)).unwrap();
+        // Signatures of another key are rejected.
+        assert!(verify_signature(&output_dir, &public_key(&[8; 32])).is_err());
+
+        let manifest = Manifest::load(&output_dir).unwrap();
+        let artifact = output_dir.join(&manifest.artifacts[0].name);
+        OpenOptions::new()
+            .append(true)
+            .open(&artifact)
+            .unwrap()
+            .write_all(b"\0")
+            .unwrap();
+        assert!(verify_signature(&output_dir, &public_key(&SECRET_KEY)).is_err());
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_the_signing_key_out_of_the_options_output() {
+        let options = BuilderOptions {
+            signing_key: Some(SigningKey(vec![0xAB; 32])),
+            ..BuilderOptions::default()
+        };
+        let debug = format!("{:?}", options);
+        assert!(debug.contains("SigningKey(<redacted>)"));
+        assert!(!debug.contains(&0xABu8.to_string()));
+        let json = serde_json::to_string(&options).unwrap();
+        assert!(!json.contains("signing_key"));
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CsvRow, SKIP_WORDS};
+
+/// A rule leaving matching entries out of the dictionary, set with
+/// `BuilderOptions::skip_rules`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkipRule {
+    pub surface: String,
+    /// The leading POS levels the entry must have, e.g. `["名詞", "固有名詞"]`. Empty
+    /// matches any POS.
+    #[serde(default)]
+    pub pos: Vec<String>,
+    /// The reading the entry must have. `None` matches any reading.
+    #[serde(default)]
+    pub reading: Option<String>,
+}
+
+impl SkipRule {
+    /// A rule matching every entry of `surface`.
+    pub fn surface(surface: &str) -> Self {
+        SkipRule {
+            surface: surface.to_string(),
+            pos: Vec::new(),
+            reading: None,
+        }
+    }
+
+    pub(crate) fn matches(&self, row: &CsvRow) -> bool {
+        let row_pos = [
+            row.pos_level1,
+            row.pos_level2,
+            row.pos_level3,
+            row.pos_level4,
+        ];
+        self.surface == row.surface_form
+            && self.pos.len() <= row_pos.len()
+            && self
+                .pos
+                .iter()
+                .zip(row_pos.iter())
+                .all(|(pos, row_pos)| pos == row_pos)
+            && self
+                .reading
+                .as_ref()
+                .is_none_or(|reading| reading == row.reading)
+    }
+}
+
+impl fmt::Display for SkipRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.surface)?;
+        if !self.pos.is_empty() {
+            write!(f, " ({})", self.pos.join(","))?;
+        }
+        if let Some(reading) = &self.reading {
+            write!(f, " [{}]", reading)?;
+        }
+        Ok(())
+    }
+}
+
+/// The rules used without `BuilderOptions::skip_rules`: every entry of the surfaces of
+/// `SKIP_WORDS`.
+pub(crate) fn default_skip_rules() -> Vec<SkipRule> {
+    SKIP_WORDS
+        .iter()
+        .map(|surface| SkipRule::surface(surface))
+        .collect()
+}
+
+/// The index of the first rule matching `row`.
+pub(crate) fn find_rule(rules: &[SkipRule], row: &CsvRow) -> Option<usize> {
+    rules.iter().position(|rule| rule.matches(row))
+}
+
+/// The number of entries removed by each skip rule during a build.
+#[derive(Default)]
+pub(crate) struct SkipRuleCounter {
+    counts: Mutex<BTreeMap<String, usize>>,
+}
+
+impl SkipRuleCounter {
+    pub(crate) fn record(&self, rule: &SkipRule) {
+        if let Ok(mut counts) = self.counts.lock() {
+            *counts.entry(rule.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    pub(crate) fn counts(&self) -> BTreeMap<String, usize> {
+        self.counts
+            .lock()
+            .map(|counts| counts.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{default_skip_rules, find_rule, SkipRule, SkipRuleCounter};
+    use crate::fixtures::FIXTURE_ROWS;
+    use crate::CsvRow;
+
+    #[test]
+    fn matches_surface_pos_and_reading() {
+        let rules: Vec<SkipRule> = serde_json::from_str(
+            r#"[
+                {"surface": "東京", "pos": ["名詞", "一般"]},
+                {"surface": "東京", "pos": ["名詞", "固有名詞"], "reading": "トウキョウ"},
+                {"surface": "いく", "reading": "ユク"},
+                {"surface": "に"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(rules[3], SkipRule::surface("に"));
+        let rule_of = |line: &str| find_rule(&rules, &CsvRow::from_line(line).unwrap());
+        assert_eq!(rule_of(FIXTURE_ROWS[0]), Some(1));
+        assert_eq!(rule_of(FIXTURE_ROWS[1]), None);
+        assert_eq!(rule_of(FIXTURE_ROWS[2]), Some(3));
+        assert_eq!(rule_of(FIXTURE_ROWS[4]), None);
+
+        let too_deep = SkipRule {
+            pos: vec!["名詞", "固有名詞", "地域", "一般", "*"]
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            ..SkipRule::surface("東京")
+        };
+        assert!(!too_deep.matches(&CsvRow::from_line(FIXTURE_ROWS[0]).unwrap()));
+        assert_eq!(
+            default_skip_rules()[0],
+            SkipRule::surface("カブシキガイシャ")
+        );
+    }
+
+    #[test]
+    fn counts_entries_by_rule() {
+        let rule = SkipRule {
+            pos: vec!["名詞".to_string(), "固有名詞".to_string()],
+            reading: Some("トウキョウ".to_string()),
+            ..SkipRule::surface("東京")
+        };
+        assert_eq!(rule.to_string(), "東京 (名詞,固有名詞) [トウキョウ]");
+        let counter = SkipRuleCounter::default();
+        counter.record(&rule);
+        counter.record(&rule);
+        counter.record(&SkipRule::surface("に"));
+        let counts: Vec<(String, usize)> = counter.counts().into_iter().collect();
+        assert_eq!(
+            counts,
+            vec![
+                ("に".to_string(), 1),
+                ("東京 (名詞,固有名詞) [トウキョウ]".to_string(), 2)
+            ]
+        );
+    }
+}
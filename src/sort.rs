@@ -0,0 +1,244 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use crate::CsvRow;
+
+/// Inputs with more ascending runs than this are sorted from scratch instead of merged.
+const MAX_MERGE_RUNS: usize = 1024;
+
+/// The start of every maximal run of rows in ascending surface order.
+fn run_starts(rows: &[CsvRow]) -> Vec<usize> {
+    let mut starts = vec![0];
+    for (idx, pair) in rows.windows(2).enumerate() {
+        if pair[0].surface_form > pair[1].surface_form {
+            starts.push(idx + 1);
+        }
+    }
+    starts
+}
+
+fn is_sorted(rows: &[CsvRow]) -> bool {
+    rows.windows(2)
+        .all(|pair| pair[0].surface_form <= pair[1].surface_form)
+}
+
+/// Merges the ascending runs starting at `starts`. Rows with the same surface keep their
+/// input order, as with a stable sort.
+fn merge_runs<'a>(mut rows: Vec<CsvRow<'a>>, starts: &[usize]) -> Vec<CsvRow<'a>> {
+    let len = rows.len();
+    let mut runs = Vec::with_capacity(starts.len());
+    for start in starts.iter().rev() {
+        runs.push(rows.split_off(*start).into_iter());
+    }
+    runs.reverse();
+
+    let mut heads: Vec<Option<CsvRow<'a>>> = runs.iter_mut().map(|run| run.next()).collect();
+    let mut heap = BinaryHeap::with_capacity(heads.len());
+    for (run_idx, head) in heads.iter().enumerate() {
+        if let Some(row) = head {
+            heap.push(Reverse((row.surface_form, run_idx)));
+        }
+    }
+
+    let mut merged = Vec::with_capacity(len);
+    while let Some(Reverse((_, run_idx))) = heap.pop() {
+        if let Some(row) = heads[run_idx].take() {
+            merged.push(row);
+        }
+        heads[run_idx] = runs[run_idx].next();
+        if let Some(row) = &heads[run_idx] {
+            heap.push(Reverse((row.surface_form, run_idx)));
+        }
+    }
+    merged
+}
+
+/// Sorts the rows by surface, stably. Already sorted input is left as is and input made of
+/// few sorted runs, such as the concatenation of sorted seed files, is merged; the result
+/// of the merge is verified and the rows are fully sorted if it is out of order.
+pub(crate) fn sort_rows<'a>(mut rows: Vec<CsvRow<'a>>, print: &dyn Fn(&str)) -> Vec<CsvRow<'a>> {
+    let starts = run_starts(&rows);
+    if starts.len() == 1 {
+        print("entries already sorted");
+        return rows;
+    }
+    if starts.len() <= MAX_MERGE_RUNS {
+        print(&format!("merging {} sorted runs of entries", starts.len()));
+        rows = merge_runs(rows, &starts);
+        if is_sorted(&rows) {
+            return rows;
+        }
+        print("merged entries out of order, sorting");
+    } else {
+        print("sorting entries");
+    }
+    rows.sort_by_key(|row| row.surface_form);
+    rows
+}
+
+/// The order of entries with the same surface with `BuilderOptions::sort_homonyms`: by word
+/// cost, then by POS levels, conjugation type and form, then by reading. Entries equal in all
+/// of these keep their input order.
+fn homonym_order(a: &CsvRow, b: &CsvRow) -> Ordering {
+    a.word_cost
+        .cmp(&b.word_cost)
+        .then_with(|| {
+            [
+                a.pos_level1,
+                a.pos_level2,
+                a.pos_level3,
+                a.pos_level4,
+                a.conjugation_type,
+                a.conjugate_form,
+            ]
+            .cmp(&[
+                b.pos_level1,
+                b.pos_level2,
+                b.pos_level3,
+                b.pos_level4,
+                b.conjugation_type,
+                b.conjugate_form,
+            ])
+        })
+        .then_with(|| a.reading.cmp(b.reading))
+}
+
+/// Sorts the entries of each surface of the rows sorted by surface in `homonym_order`, so
+/// that the word_ids of homonyms do not depend on the order of the input files.
+pub(crate) fn sort_homonyms(rows: &mut [CsvRow]) {
+    for homonyms in rows.chunk_by_mut(|a, b| a.surface_form == b.surface_form) {
+        homonyms.sort_by(homonym_order);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::{is_sorted, merge_runs, sort_homonyms, sort_rows, MAX_MERGE_RUNS};
+    use crate::CsvRow;
+
+    /// Entry lines of the surfaces, whose word cost is their index, so that the order of
+    /// entries of the same surface shows in the costs.
+    fn lines(surfaces: &[&str]) -> Vec<String> {
+        surfaces
+            .iter()
+            .enumerate()
+            .map(|(idx, surface)| {
+                format!("{},1,1,{},名詞,一般,*,*,*,*,{},*,*", surface, idx, surface)
+            })
+            .collect()
+    }
+
+    fn rows(lines: &[String]) -> Vec<CsvRow<'_>> {
+        lines
+            .iter()
+            .map(|line| CsvRow::from_line(line).unwrap())
+            .collect()
+    }
+
+    fn keys<'a>(rows: &[CsvRow<'a>]) -> Vec<(&'a str, i32)> {
+        rows.iter()
+            .map(|row| (row.surface_form, row.word_cost))
+            .collect()
+    }
+
+    /// Sorts the rows with `sort_rows`, checking that the result is the one of a stable
+    /// sort by surface, and returns the messages printed.
+    fn assert_stable_sort(lines: &[String]) -> Vec<String> {
+        let mut expected = rows(lines);
+        expected.sort_by_key(|row| row.surface_form);
+        let messages = RefCell::new(Vec::new());
+        let sorted = sort_rows(rows(lines), &|message| {
+            messages.borrow_mut().push(message.to_string())
+        });
+        assert_eq!(keys(&sorted), keys(&expected));
+        messages.into_inner()
+    }
+
+    #[test]
+    fn leaves_sorted_input_as_is() {
+        let lines = lines(&["あ", "い", "い", "う", "東京"]);
+        assert_eq!(assert_stable_sort(&lines), vec!["entries already sorted"]);
+    }
+
+    #[test]
+    fn merges_sorted_files() {
+        // Three sorted files with surfaces in common, concatenated.
+        let lines = lines(&["い", "う", "東京", "あ", "い", "東京", "あ", "う"]);
+        assert_eq!(
+            assert_stable_sort(&lines),
+            vec!["merging 3 sorted runs of entries"]
+        );
+    }
+
+    #[test]
+    fn sorts_input_of_too_many_runs() {
+        let surfaces: Vec<&str> = ["い", "あ"]
+            .iter()
+            .copied()
+            .cycle()
+            .take(2 * (MAX_MERGE_RUNS + 1))
+            .collect();
+        assert_eq!(
+            assert_stable_sort(&lines(&surfaces)),
+            vec!["sorting entries"]
+        );
+    }
+
+    #[test]
+    fn detects_merges_out_of_order() {
+        // Merging rows which are not ascending runs leaves them out of order.
+        let lines = lines(&["う", "あ", "い", "あ"]);
+        let merged = merge_runs(rows(&lines), &[0, 2]);
+        assert!(!is_sorted(&merged));
+        let mut expected = rows(&lines);
+        expected.sort_by_key(|row| row.surface_form);
+        assert!(is_sorted(&expected));
+    }
+
+    #[test]
+    fn sorts_homonyms_by_cost() {
+        let lines = vec![
+            "あ,1,1,300,名詞,一般,*,*,*,*,あ,ア,ア".to_string(),
+            "あ,1,1,100,名詞,一般,*,*,*,*,あ,ア,ア".to_string(),
+            "あ,1,1,100,感動詞,*,*,*,*,*,あ,ア,ア".to_string(),
+            "い,1,1,200,名詞,一般,*,*,*,*,い,イ,イ".to_string(),
+        ];
+        let mut rows = rows(&lines);
+        sort_homonyms(&mut rows);
+        let order: Vec<(i32, &str)> = rows
+            .iter()
+            .map(|row| (row.word_cost, row.pos_level1))
+            .collect();
+        assert_eq!(
+            order,
+            vec![(100, "名詞"), (100, "感動詞"), (300, "名詞"), (200, "名詞")]
+        );
+    }
+
+    #[test]
+    fn orders_homonyms_independently_of_the_input_order() {
+        let lines = vec![
+            "あ,1,1,100,名詞,一般,*,*,*,*,あ,ア,ア".to_string(),
+            "あ,1,1,100,名詞,一般,*,*,*,*,あ,アー,アー".to_string(),
+            "あ,1,1,100,名詞,固有名詞,*,*,*,*,あ,ア,ア".to_string(),
+        ];
+        let mut reversed = lines.clone();
+        reversed.reverse();
+        let readings = |lines: &[String]| -> Vec<(String, String)> {
+            let mut rows = sort_rows(rows(lines), &|_| {});
+            sort_homonyms(&mut rows);
+            rows.iter()
+                .map(|row| (row.pos_level2.to_string(), row.reading.to_string()))
+                .collect()
+        };
+        let expected = vec![
+            ("一般".to_string(), "ア".to_string()),
+            ("一般".to_string(), "アー".to_string()),
+            ("固有名詞".to_string(), "ア".to_string()),
+        ];
+        assert_eq!(readings(&lines), expected);
+        assert_eq!(readings(&reversed), expected);
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use lindera_core::character_definition::{CharacterDefinitions, CharacterDefinitionsBuilder};
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::unknown_dictionary::{parse_unk, UnknownDictionary};
+use lindera_core::LinderaResult;
+
+use crate::chardef::check_char_def;
+use crate::file_util::{decode_source, source_lines};
+use crate::options::BuilderOptions;
+use crate::unk::check_unk_lines;
+use crate::{matrix, skip, CsvRow, MAX_HOMONYMS};
+
+/// The raw content of the dictionary source files.
+#[derive(Debug, Clone, Default)]
+pub struct SourceBytes<'a> {
+    pub char_def: &'a [u8],
+    pub unk_def: &'a [u8],
+    pub matrix_def: &'a [u8],
+    pub csv_files: Vec<&'a [u8]>,
+}
+
+/// The dictionary sources parsed by `parse_sources`.
+pub struct ParsedSources {
+    pub char_definitions: CharacterDefinitions,
+    pub unknown_dictionary: UnknownDictionary,
+    /// The cost array written to `matrix.mtx`: the forward and backward sizes followed by
+    /// the costs.
+    pub costs: Vec<i16>,
+    pub rows: usize,
+    pub surfaces: usize,
+}
+
+/// Parses and cross-checks the dictionary sources in memory, without touching the file system.
+/// The entries are normalized and the homonyms counted as the build with `options` does.
+///
+/// Every malformed input is reported as an error rather than a panic, which makes this the
+/// entry point for fuzzing the parsers.
+pub fn parse_sources(
+    sources: &SourceBytes,
+    options: &BuilderOptions,
+) -> LinderaResult<ParsedSources> {
+    let char_def = decode_source(Path::new("char.def"), sources.char_def.to_vec())?;
+    check_char_def(&char_def)?;
+    let mut char_definitions_builder = CharacterDefinitionsBuilder::default();
+    char_definitions_builder.parse(&char_def)?;
+    let char_definitions = char_definitions_builder.build();
+
+    let unk_def = decode_source(Path::new("unk.def"), sources.unk_def.to_vec())?;
+    let unk_lines = source_lines(&unk_def)
+        .map(|(_, line)| line)
+        .collect::<Vec<_>>();
+    check_unk_lines(&unk_lines, char_definitions.categories())?;
+    let unknown_dictionary = parse_unk(char_definitions.categories(), &unk_lines.join("\n"))?;
+
+    let costs = matrix::parse_matrix_from(
+        Path::new("matrix.def"),
+        sources.matrix_def,
+        matrix::max_cells(sources.matrix_def.len() as u64),
+        1,
+        matrix::BATCH_LINES,
+        &|_| {},
+        &|_| {},
+    )?;
+    let forward_size = costs.first().copied().unwrap_or_default() as u32;
+    let backward_size = costs.get(1).copied().unwrap_or_default() as u32;
+
+    let normalizer = options.line_normalizer()?;
+    let skip_rules = options.skip_rules();
+    let mut rows = 0;
+    let mut homonyms: BTreeMap<String, usize> = BTreeMap::new();
+    for (file_idx, csv_file) in sources.csv_files.iter().enumerate() {
+        let filename = format!("{}.csv", file_idx);
+        let content = decode_source(Path::new(&filename), csv_file.to_vec())?;
+        for (line_num, line) in source_lines(&content) {
+            let line_error = |message: String| {
+                LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                    "{}:{}: {}",
+                    filename,
+                    line_num,
+                    message
+                ))
+            };
+            let line = normalizer.normalize(line);
+            let row = CsvRow::from_line(&line).map_err(|err| line_error(err.to_string()))?;
+            if row.left_id >= backward_size {
+                return Err(line_error(format!(
+                    "left_id {} is out of range [0, {})",
+                    row.left_id, backward_size
+                )));
+            }
+            if row.right_id >= forward_size {
+                return Err(line_error(format!(
+                    "right_id {} is out of range [0, {})",
+                    row.right_id, forward_size
+                )));
+            }
+            rows += 1;
+
+            if skip::find_rule(&skip_rules, &row).is_none() {
+                let count = homonyms.entry(row.surface_form.to_string()).or_insert(0);
+                *count += 1;
+                if *count >= MAX_HOMONYMS {
+                    return Err(line_error(format!(
+                        "{} has {} entries. Too many. [{}]",
+                        row.surface_form, count, MAX_HOMONYMS
+                    )));
+                }
+            }
+        }
+    }
+
+    Ok(ParsedSources {
+        char_definitions,
+        unknown_dictionary,
+        costs,
+        rows,
+        surfaces: homonyms.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_sources, SourceBytes};
+    use crate::fixtures::{FIXTURE_CHAR_DEF, FIXTURE_MATRIX_DEF, FIXTURE_ROWS, FIXTURE_UNK_DEF};
+    use crate::options::BuilderOptions;
+
+    fn fixture_csv() -> String {
+        let mut csv = FIXTURE_ROWS.join("\n");
+        csv.push('\n');
+        csv
+    }
+
+    fn fixture_sources(csv: &str) -> SourceBytes<'_> {
+        SourceBytes {
+            char_def: FIXTURE_CHAR_DEF.as_bytes(),
+            unk_def: FIXTURE_UNK_DEF.as_bytes(),
+            matrix_def: FIXTURE_MATRIX_DEF.as_bytes(),
+            csv_files: vec![csv.as_bytes()],
+        }
+    }
+
+    #[test]
+    fn truncated_sources_are_errors_not_panics() {
+        let csv = fixture_csv();
+        let options = BuilderOptions::default();
+        for file_idx in 0..4 {
+            let sources = fixture_sources(&csv);
+            let data = match file_idx {
+                0 => sources.char_def,
+                1 => sources.unk_def,
+                2 => sources.matrix_def,
+                _ => sources.csv_files[0],
+            };
+            for len in 0..data.len() {
+                let mut sources = fixture_sources(&csv);
+                let truncated = &data[..len];
+                match file_idx {
+                    0 => sources.char_def = truncated,
+                    1 => sources.unk_def = truncated,
+                    2 => sources.matrix_def = truncated,
+                    _ => sources.csv_files[0] = truncated,
+                }
+                let _ = parse_sources(&sources, &options);
+            }
+        }
+    }
+
+    #[test]
+    fn reports_the_line_of_invalid_entries() {
+        let options = BuilderOptions::default();
+        let error = |row: &str| {
+            let csv = format!("{}{}\n", fixture_csv(), row);
+            match parse_sources(&fixture_sources(&csv), &options) {
+                Ok(_) => panic!("{} was accepted", row),
+                Err(err) => err.to_string(),
+            }
+        };
+        assert!(
+            error("渋谷,1,3,3000,名詞,固有名詞,地域,一般,*,*,渋谷,シブヤ,シブヤ")
+                .contains("0.csv:6: right_id 3 is out of range [0, 3)")
+        );
+        assert!(error("渋谷,1,1").contains("0.csv:6: "));
+
+        let sources = SourceBytes {
+            unk_def: "EMOJI,0,0,5000,記号,一般,*,*,*,*,*\n".as_bytes(),
+            ..fixture_sources("")
+        };
+        assert!(parse_sources(&sources, &options).is_err());
+    }
+}
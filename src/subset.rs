@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use yada::builder::DoubleArrayBuilder;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::word_entry::{WordEntry, WordId};
+use lindera_core::LinderaResult;
+
+use crate::checksum::write_checksums;
+use crate::file_util::{read_source_file, source_lines};
+use crate::header::strip_header;
+use crate::layout::DictionaryLayout;
+use crate::manifest::{Manifest, MANIFEST_FILENAME};
+use crate::normalize_line;
+use crate::packing::VALUE_TABLE_FILENAME;
+use crate::reader::DictionaryReader;
+
+/// Loads a list of surfaces, one per line. Blank lines and lines starting with `#` are
+/// ignored.
+pub fn load_terms(path: &Path) -> LinderaResult<Vec<String>> {
+    let content = read_source_file(path)?;
+    Ok(source_lines(&content)
+        .map(|(_, line)| line.trim())
+        .filter(|line| !line.starts_with('#'))
+        .map(normalize_line)
+        .collect())
+}
+
+/// Writes to `output_dir` the sub-dictionary of the dictionary built in `dict_dir` holding
+/// only the entries of `surfaces` and of the surfaces that are prefixes of them, with the
+/// character definitions, unknown words and cost matrix unchanged, for applications with
+/// a closed vocabulary.
+pub fn subset_dictionary(
+    dict_dir: &Path,
+    surfaces: &[String],
+    output_dir: &Path,
+) -> LinderaResult<()> {
+    let layout = DictionaryLayout::default();
+    let reader = DictionaryReader::open(dict_dir)?;
+    let manifest = if dict_dir.join(MANIFEST_FILENAME).exists() {
+        Some(Manifest::load(dict_dir)?)
+    } else {
+        None
+    };
+    let (detail_format, lindera_target, value_packing) = manifest
+        .as_ref()
+        .map(|manifest| {
+            (
+                manifest.detail_format,
+                manifest.lindera_target,
+                manifest.value_packing,
+            )
+        })
+        .unwrap_or_default();
+
+    let mut word_entry_map: BTreeMap<&str, Vec<WordEntry>> = BTreeMap::new();
+    let mut missing = 0;
+    for surface in surfaces {
+        let mut found = false;
+        for (len, word_entry) in reader.prefix_search(surface) {
+            found |= len == surface.len();
+            let entries = word_entry_map.entry(&surface[..len]).or_default();
+            if !entries
+                .iter()
+                .any(|entry| entry.word_id.0 == word_entry.word_id.0)
+            {
+                entries.push(word_entry);
+            }
+        }
+        if !found {
+            missing += 1;
+        }
+    }
+    println!(
+        "{} of {} surfaces have no entry, {} surfaces kept with their prefixes",
+        missing,
+        surfaces.len(),
+        word_entry_map.len()
+    );
+
+    fs::create_dir_all(output_dir)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+    let codec = lindera_target.detail_codec(detail_format);
+    let mut words = Vec::new();
+    let mut words_idx = Vec::new();
+    let mut vals = Vec::new();
+    let mut keyset: Vec<(&[u8], u32)> = Vec::with_capacity(word_entry_map.len());
+    let mut packer = value_packing.packer();
+    let mut id = 0u32;
+    for (surface, word_entries) in &word_entry_map {
+        let len = word_entries.len() as u32;
+        keyset.push((surface.as_bytes(), packer.pack(surface, id, len)?));
+        for word_entry in word_entries {
+            words_idx
+                .write_u32::<LittleEndian>(words.len() as u32)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            codec.encode(&reader.details(word_entry.word_id.0)?, &mut words)?;
+            WordEntry {
+                word_id: WordId(id, true),
+                word_cost: word_entry.word_cost,
+                cost_id: word_entry.cost_id,
+            }
+            .serialize(&mut vals)
+            .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+            id += 1;
+        }
+    }
+    let da = DoubleArrayBuilder::build(&keyset).ok_or_else(|| {
+        LinderaErrorKind::Io.with_error(anyhow::anyhow!("DoubleArray build error."))
+    })?;
+
+    for (name, bytes) in [
+        (&layout.dict_da, &da),
+        (&layout.dict_vals, &vals),
+        (&layout.dict_words, &words),
+        (&layout.dict_wordsidx, &words_idx),
+    ] {
+        let path = layout.artifact_path(output_dir, name)?;
+        println!("creating {:?}", path);
+        fs::write(path, bytes)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    }
+    if let Some(value_table) = packer.table() {
+        let path = output_dir.join(VALUE_TABLE_FILENAME);
+        println!("creating {:?}", path);
+        fs::write(path, value_table)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    }
+    for name in [&layout.char_def, &layout.unk, &layout.matrix] {
+        let path = layout.artifact_path(output_dir, name)?;
+        println!("creating {:?}", path);
+        let data = fs::read(dict_dir.join(name))
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        fs::write(path, strip_header(&data)?)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    }
+
+    let mut subset_manifest = Manifest::from_output_dir(output_dir, &layout)?;
+    subset_manifest.detail_format = detail_format;
+    subset_manifest.lindera_target = lindera_target;
+    subset_manifest.value_packing = value_packing;
+    if let Some(manifest) = manifest {
+        subset_manifest.dictionary_version = manifest.dictionary_version;
+        subset_manifest.seed_dates = manifest.seed_dates;
+        subset_manifest.detail_fields = manifest.detail_fields;
+    }
+    subset_manifest.write(output_dir)?;
+    write_checksums(output_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{load_terms, subset_dictionary};
+    use crate::checksum::verify_output;
+    use crate::fixtures::build_fixture;
+    use crate::options::BuilderOptions;
+    use crate::reader::DictionaryReader;
+
+    #[test]
+    fn keeps_the_terms_and_their_prefixes() {
+        let base_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-subset-{}",
+            std::process::id()
+        ));
+        let (input_dir, dict_dir) = (base_dir.join("input"), base_dir.join("dict"));
+        build_fixture(&input_dir, &dict_dir, BuilderOptions::default()).unwrap();
+        fs::write(
+            base_dir.join("terms.txt"),
+            "# closed vocabulary\n東京スカイツリー\r\n\n 渋谷 \n",
+        )
+        .unwrap();
+        let terms = load_terms(&base_dir.join("terms.txt")).unwrap();
+        assert_eq!(terms, vec!["東京スカイツリー", "渋谷"]);
+
+        let subset_dir = base_dir.join("subset");
+        subset_dictionary(&dict_dir, &terms, &subset_dir).unwrap();
+        verify_output(&subset_dir).unwrap();
+        let reader = DictionaryReader::open(&subset_dir).unwrap();
+        let dict_reader = DictionaryReader::open(&dict_dir).unwrap();
+        assert_eq!(reader.num_words(), 2);
+        for surface in ["東京", "東京スカイツリー"] {
+            let entries = reader.lookup(surface);
+            let dict_entries = dict_reader.lookup(surface);
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].word_cost, dict_entries[0].word_cost);
+            assert_eq!(
+                reader.details(entries[0].word_id.0).unwrap(),
+                dict_reader.details(dict_entries[0].word_id.0).unwrap()
+            );
+        }
+        assert!(reader.lookup("に").is_empty());
+        assert_eq!(
+            fs::read(subset_dir.join("matrix.mtx")).unwrap(),
+            fs::read(dict_dir.join("matrix.mtx")).unwrap()
+        );
+
+        fs::remove_dir_all(&base_dir).unwrap();
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+
+/// The name of the generated symbol entries, recorded as their provenance.
+pub const SYMBOLS_FILENAME: &str = "symbols.csv";
+
+/// The word cost of the generated symbol entries when `BuilderOptions::symbol_cost` is not
+/// set.
+pub const DEFAULT_SYMBOL_COST: i32 = 2000;
+
+/// The context id of `記号,一般` in the IPADIC `matrix.def`.
+const SYMBOL_CONTEXT_ID: u32 = 5;
+
+/// Code point ranges of the emoji blocks whose code points are all assigned emoji.
+const EMOJI_RANGES: [(u32, u32); 5] = [
+    (0x1F300, 0x1F5FF),
+    (0x1F600, 0x1F64F),
+    (0x1F680, 0x1F6C5),
+    (0x1F90C, 0x1F9FF),
+    (0x1FA70, 0x1FA7C),
+];
+
+/// Emoji taking a skin tone modifier, U+1F3FB to U+1F3FF.
+const SKIN_TONE_EMOJI: [char; 8] = ['👍', '👎', '👋', '👏', '🙏', '👌', '✌', '💪'];
+
+/// Regions whose flag, a pair of regional indicators, is generated.
+const FLAG_REGIONS: [&str; 20] = [
+    "JP", "US", "GB", "CN", "KR", "TW", "HK", "FR", "DE", "IT", "ES", "CA", "AU", "BR", "IN", "RU",
+    "TH", "VN", "PH", "ID",
+];
+
+/// Common kaomoji. Kaomoji with commas are left out, since they cannot be written as CSV
+/// entries.
+pub const KAOMOJI: [&str; 24] = [
+    "(^_^)",
+    "(^^)",
+    "(^o^)",
+    "(^^;",
+    "(^_^;)",
+    "(;_;)",
+    "(T_T)",
+    "(TдT)",
+    "(>_<)",
+    "(-_-)",
+    "(-_-;)",
+    "(*^^*)",
+    "(*´ω`*)",
+    "(´・ω・`)",
+    "(・∀・)",
+    "(´∀`)",
+    "(゜o゜)",
+    "(￣▽￣)",
+    "(^_-)-☆",
+    "m(_ _)m",
+    "orz",
+    "OTL",
+    "ヽ(´ー`)ノ",
+    "\\(^o^)/",
+];
+
+/// Single emoji, emoji with skin tones, keycaps and flags.
+pub fn emoji_sequences() -> Vec<String> {
+    let mut sequences: Vec<String> = EMOJI_RANGES
+        .iter()
+        .flat_map(|(low, high)| *low..=*high)
+        .filter_map(char::from_u32)
+        .map(String::from)
+        .collect();
+    for emoji in SKIN_TONE_EMOJI {
+        for modifier in 0x1F3FB..=0x1F3FF {
+            if let Some(modifier) = char::from_u32(modifier) {
+                sequences.push(format!("{}{}", emoji, modifier));
+            }
+        }
+    }
+    for keycap in "0123456789#*".chars() {
+        sequences.push(format!("{}\u{FE0F}\u{20E3}", keycap));
+    }
+    for region in FLAG_REGIONS {
+        let flag: Option<String> = region
+            .chars()
+            .map(|c| char::from_u32(0x1F1E6 + (c as u32 - 'A' as u32)))
+            .collect();
+        sequences.extend(flag);
+    }
+    sequences
+}
+
+/// The CSV entry lines of the emoji and kaomoji asked for, as `記号,一般` entries of the
+/// word cost `cost`. Surfaces with an entry in `surfaces` are left out.
+pub(crate) fn symbol_lines(
+    emoji: bool,
+    kaomoji: bool,
+    cost: i32,
+    surfaces: &HashSet<&str>,
+) -> Vec<String> {
+    let mut symbols = Vec::new();
+    if emoji {
+        symbols.extend(emoji_sequences());
+    }
+    if kaomoji {
+        symbols.extend(KAOMOJI.iter().map(|kaomoji| kaomoji.to_string()));
+    }
+    symbols
+        .into_iter()
+        .filter(|symbol| !surfaces.contains(symbol.as_str()))
+        .map(|symbol| {
+            format!(
+                "{},{},{},{},記号,一般,*,*,*,*,{},*,*",
+                symbol, SYMBOL_CONTEXT_ID, SYMBOL_CONTEXT_ID, cost, symbol
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{emoji_sequences, symbol_lines, KAOMOJI};
+    use crate::CsvRow;
+
+    #[test]
+    fn generates_emoji_sequences() {
+        let sequences = emoji_sequences();
+        for emoji in ["😀", "🚀", "👍\u{1F3FD}", "#\u{FE0F}\u{20E3}", "🇯🇵", "🇻🇳"]
+        {
+            assert!(
+                sequences.iter().any(|sequence| sequence == emoji),
+                "{}",
+                emoji
+            );
+        }
+        let distinct: HashSet<&String> = sequences.iter().collect();
+        assert_eq!(distinct.len(), sequences.len());
+    }
+
+    #[test]
+    fn writes_symbols_as_entries() {
+        let surfaces: HashSet<&str> = vec!["orz", "😀"].into_iter().collect();
+        let lines = symbol_lines(false, true, 2000, &surfaces);
+        assert_eq!(lines.len(), KAOMOJI.len() - 1);
+        assert_eq!(lines[0], "(^_^),5,5,2000,記号,一般,*,*,*,*,(^_^),*,*");
+        for line in symbol_lines(true, true, 2000, &surfaces) {
+            let row = CsvRow::from_line(&line).unwrap();
+            assert!(!surfaces.contains(row.surface_form), "{}", line);
+            assert_eq!(row.word_cost, 2000);
+        }
+        assert!(symbol_lines(false, false, 2000, &surfaces).is_empty());
+    }
+}
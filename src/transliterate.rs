@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+
+/// The script the readings are transliterated to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReadingScript {
+    Hiragana,
+    Katakana,
+    /// Hepburn romanization in lowercase ASCII, with long vowels written twice.
+    Romaji,
+}
+
+impl ReadingScript {
+    /// Transliterates the kana of `reading` to the script. Other characters, such as the `*`
+    /// placeholder, are kept as they are.
+    pub fn transliterate(&self, reading: &str) -> String {
+        match self {
+            ReadingScript::Hiragana => to_hiragana(reading),
+            ReadingScript::Katakana => to_katakana(reading),
+            ReadingScript::Romaji => to_romaji(reading),
+        }
+    }
+}
+
+const HIRAGANA_TO_KATAKANA: u32 = 0x60;
+
+/// Converts the katakana of `text` to hiragana.
+pub fn to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{30A1}'..='\u{30F6}' | 'ヽ' | 'ヾ' => {
+                char::from_u32(c as u32 - HIRAGANA_TO_KATAKANA).unwrap_or(c)
+            }
+            _ => c,
+        })
+        .collect()
+}
+
+/// Converts the hiragana of `text` to katakana.
+pub fn to_katakana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{3041}'..='\u{3096}' | 'ゝ' | 'ゞ' => {
+                char::from_u32(c as u32 + HIRAGANA_TO_KATAKANA).unwrap_or(c)
+            }
+            _ => c,
+        })
+        .collect()
+}
+
+/// The romanization of a katakana character on its own.
+fn kana_romaji(c: char) -> Option<&'static str> {
+    let romaji = match c {
+        'ア' | 'ァ' => "a",
+        'イ' | 'ィ' | 'ヰ' => "i",
+        'ウ' | 'ゥ' => "u",
+        'エ' | 'ェ' | 'ヱ' => "e",
+        'オ' | 'ォ' | 'ヲ' => "o",
+        'カ' | 'ヵ' => "ka",
+        'キ' => "ki",
+        'ク' => "ku",
+        'ケ' | 'ヶ' => "ke",
+        'コ' => "ko",
+        'ガ' => "ga",
+        'ギ' => "gi",
+        'グ' => "gu",
+        'ゲ' => "ge",
+        'ゴ' => "go",
+        'サ' => "sa",
+        'シ' => "shi",
+        'ス' => "su",
+        'セ' => "se",
+        'ソ' => "so",
+        'ザ' => "za",
+        'ジ' | 'ヂ' => "ji",
+        'ズ' | 'ヅ' => "zu",
+        'ゼ' => "ze",
+        'ゾ' => "zo",
+        'タ' => "ta",
+        'チ' => "chi",
+        'ツ' => "tsu",
+        'テ' => "te",
+        'ト' => "to",
+        'ダ' => "da",
+        'デ' => "de",
+        'ド' => "do",
+        'ナ' => "na",
+        'ニ' => "ni",
+        'ヌ' => "nu",
+        'ネ' => "ne",
+        'ノ' => "no",
+        'ハ' => "ha",
+        'ヒ' => "hi",
+        'フ' => "fu",
+        'ヘ' => "he",
+        'ホ' => "ho",
+        'バ' => "ba",
+        'ビ' => "bi",
+        'ブ' => "bu",
+        'ベ' => "be",
+        'ボ' => "bo",
+        'パ' => "pa",
+        'ピ' => "pi",
+        'プ' => "pu",
+        'ペ' => "pe",
+        'ポ' => "po",
+        'マ' => "ma",
+        'ミ' => "mi",
+        'ム' => "mu",
+        'メ' => "me",
+        'モ' => "mo",
+        'ヤ' | 'ャ' => "ya",
+        'ユ' | 'ュ' => "yu",
+        'ヨ' | 'ョ' => "yo",
+        'ラ' => "ra",
+        'リ' => "ri",
+        'ル' => "ru",
+        'レ' => "re",
+        'ロ' => "ro",
+        'ワ' | 'ヮ' => "wa",
+        'ン' => "n",
+        'ヴ' => "vu",
+        _ => return None,
+    };
+    Some(romaji)
+}
+
+/// The romanization of the sounds written with a small vowel, mostly in loanwords.
+fn extended_romaji(c: char, small: char) -> Option<&'static str> {
+    let romaji = match (c, small) {
+        ('フ', 'ァ') => "fa",
+        ('フ', 'ィ') => "fi",
+        ('フ', 'ェ') => "fe",
+        ('フ', 'ォ') => "fo",
+        ('ウ', 'ィ') => "wi",
+        ('ウ', 'ェ') => "we",
+        ('ウ', 'ォ') => "wo",
+        ('ヴ', 'ァ') => "va",
+        ('ヴ', 'ィ') => "vi",
+        ('ヴ', 'ェ') => "ve",
+        ('ヴ', 'ォ') => "vo",
+        ('テ', 'ィ') => "ti",
+        ('デ', 'ィ') => "di",
+        ('ト', 'ゥ') => "tu",
+        ('ド', 'ゥ') => "du",
+        ('シ', 'ェ') => "she",
+        ('ジ', 'ェ') => "je",
+        ('チ', 'ェ') => "che",
+        ('ツ', 'ァ') => "tsa",
+        ('ツ', 'ィ') => "tsi",
+        ('ツ', 'ェ') => "tse",
+        ('ツ', 'ォ') => "tso",
+        ('イ', 'ェ') => "ye",
+        ('ク', 'ァ') => "kwa",
+        ('グ', 'ァ') => "gwa",
+        _ => return None,
+    };
+    Some(romaji)
+}
+
+/// The romanization of a syllable of a kana ending in `i` followed by a small `ャ`, `ュ` or
+/// `ョ`, such as `kya` or `sho`.
+fn contracted_romaji(c: char, small: char) -> Option<String> {
+    let vowel = match small {
+        'ャ' => 'a',
+        'ュ' => 'u',
+        'ョ' => 'o',
+        _ => return None,
+    };
+    let stem = kana_romaji(c)?.strip_suffix('i')?;
+    if stem.is_empty() {
+        return None;
+    }
+    // shi, chi and ji drop their `i` (sha, cho, ju), the others take a `y` (kya, nyu).
+    if stem == "sh" || stem == "ch" || stem == "j" {
+        Some(format!("{}{}", stem, vowel))
+    } else {
+        Some(format!("{}y{}", stem, vowel))
+    }
+}
+
+pub(crate) fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'i' | 'u' | 'e' | 'o')
+}
+
+/// A unit of a kana text, as romanized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Syllable {
+    /// The Hepburn romanization of a kana or of a kana with a small kana, such as `kya`.
+    Kana(String),
+    /// `ッ`, lengthening the following consonant.
+    Sokuon,
+    /// `ー`, lengthening the previous vowel.
+    LongVowel,
+    /// A character that is not kana.
+    Other(char),
+}
+
+/// Splits the kana of `text` into syllables.
+pub(crate) fn syllables(text: &str) -> Vec<Syllable> {
+    let chars: Vec<char> = to_katakana(text).chars().collect();
+    let mut syllables = Vec::with_capacity(chars.len());
+    let mut idx = 0;
+    while idx < chars.len() {
+        let c = chars[idx];
+        if let Some(&next) = chars.get(idx + 1) {
+            let romaji = extended_romaji(c, next)
+                .map(str::to_string)
+                .or_else(|| contracted_romaji(c, next));
+            if let Some(romaji) = romaji {
+                syllables.push(Syllable::Kana(romaji));
+                idx += 2;
+                continue;
+            }
+        }
+        syllables.push(match c {
+            'ッ' => Syllable::Sokuon,
+            'ー' => Syllable::LongVowel,
+            c => match kana_romaji(c) {
+                Some(romaji) => Syllable::Kana(romaji.to_string()),
+                None => Syllable::Other(c),
+            },
+        });
+        idx += 1;
+    }
+    syllables
+}
+
+/// Romanizes the kana of `text` in the Hepburn system. The sokuon doubles the following
+/// consonant (`tch` before `ch`), `ー` repeats the previous vowel, and `n` is followed by an
+/// apostrophe before a vowel or `y`.
+pub fn to_romaji(text: &str) -> String {
+    let syllables = syllables(text);
+    let mut romaji = String::with_capacity(text.len());
+    for (idx, syllable) in syllables.iter().enumerate() {
+        let next = match syllables.get(idx + 1) {
+            Some(Syllable::Kana(next)) => Some(next.as_str()),
+            _ => None,
+        };
+        match syllable {
+            Syllable::Kana(kana) if kana == "n" => {
+                romaji.push('n');
+                if next.is_some_and(|next| next.starts_with(|c| is_vowel(c) || c == 'y')) {
+                    romaji.push('\'');
+                }
+            }
+            Syllable::Kana(kana) => romaji.push_str(kana),
+            Syllable::Sokuon => match next.and_then(|next| next.chars().next()) {
+                Some('c') => romaji.push('t'),
+                Some(consonant) if !is_vowel(consonant) => romaji.push(consonant),
+                _ => {}
+            },
+            Syllable::LongVowel => {
+                if let Some(vowel) = romaji.chars().last().filter(|c| is_vowel(*c)) {
+                    romaji.push(vowel);
+                }
+            }
+            Syllable::Other(c) => romaji.push(*c),
+        }
+    }
+    romaji
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_hiragana, to_katakana, to_romaji, ReadingScript};
+
+    #[test]
+    fn converts_between_hiragana_and_katakana() {
+        assert_eq!(to_hiragana("トウキョウ*"), "とうきょう*");
+        assert_eq!(to_katakana("とうきょうヴ"), "トウキョウヴ");
+        assert_eq!(to_hiragana("ヽヾー"), "ゝゞー");
+        assert_eq!(
+            ReadingScript::Katakana.transliterate("すかいつりー"),
+            "スカイツリー"
+        );
+    }
+
+    #[test]
+    fn romanizes_in_hepburn() {
+        assert_eq!(to_romaji("トウキョウ"), "toukyou");
+        assert_eq!(to_romaji("シンジュク"), "shinjuku");
+        assert_eq!(to_romaji("マッチャ"), "matcha");
+        assert_eq!(to_romaji("ガッコウ"), "gakkou");
+        assert_eq!(to_romaji("スカイツリー"), "sukaitsurii");
+        assert_eq!(to_romaji("キンエン"), "kin'en");
+        assert_eq!(to_romaji("ホンヤ"), "hon'ya");
+        assert_eq!(to_romaji("ファイル"), "fairu");
+        assert_eq!(to_romaji("ちょっと"), "chotto");
+        assert_eq!(ReadingScript::Romaji.transliterate("*"), "*");
+    }
+}
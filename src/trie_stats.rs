@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Serialize;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::IpadicNeologdBuilder;
+
+/// The number of most-branching prefixes reported.
+pub const TOP_BRANCHING_PREFIXES: usize = 10;
+
+/// A prefix of the surfaces with the number of distinct bytes following it, counting the end
+/// of a surface as one.
+#[derive(Debug, Clone, Serialize)]
+pub struct BranchingPrefix {
+    pub prefix: String,
+    pub branches: usize,
+}
+
+/// Statistics of the trie of the surfaces and of the double array encoding it.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrieStats {
+    pub keys: usize,
+    /// The total length of the surfaces in bytes.
+    pub key_bytes: u64,
+    /// The nodes of the trie: the root, a node per distinct prefix and a leaf per surface.
+    /// Each takes a unit of the double array.
+    pub nodes: u64,
+    /// The units of the double array, used or not.
+    pub units: u64,
+    pub da_bytes: u64,
+    pub longest_key: String,
+    pub longest_key_bytes: usize,
+    pub branching_prefixes: Vec<BranchingPrefix>,
+}
+
+impl TrieStats {
+    /// Computes the statistics of the trie of `keys`, in ascending byte order as inserted
+    /// into the double array, and of its double array of `da_bytes` bytes.
+    pub fn from_sorted_keys<'a, I>(keys: I, da_bytes: u64) -> TrieStats
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        let mut num_keys = 0;
+        let mut key_bytes = 0;
+        let mut nodes = 1;
+        let mut longest_key: &[u8] = &[];
+        // The branches beyond the first of each prefix, as a key diverging from the previous
+        // one at the end of a prefix adds a branch to it, while the nodes below are new.
+        let mut extra_branches: HashMap<&[u8], usize> = HashMap::new();
+        let mut prev: Option<&[u8]> = None;
+        for key in keys {
+            let common = prev.map_or(0, |prev| {
+                prev.iter()
+                    .zip(key.iter())
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            });
+            if prev.is_some() {
+                *extra_branches.entry(&key[..common]).or_default() += 1;
+            }
+            nodes += (key.len() - common) as u64 + 1;
+            num_keys += 1;
+            key_bytes += key.len() as u64;
+            if key.len() > longest_key.len() {
+                longest_key = key;
+            }
+            prev = Some(key);
+        }
+
+        let mut branching: Vec<(&[u8], usize)> = extra_branches
+            .into_iter()
+            .map(|(prefix, extra)| (prefix, extra + 1))
+            .collect();
+        branching.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        branching.truncate(TOP_BRANCHING_PREFIXES);
+
+        TrieStats {
+            keys: num_keys,
+            key_bytes,
+            nodes,
+            units: da_bytes / 4,
+            da_bytes,
+            longest_key: String::from_utf8_lossy(longest_key).to_string(),
+            longest_key_bytes: longest_key.len(),
+            branching_prefixes: branching
+                .into_iter()
+                .map(|(prefix, branches)| BranchingPrefix {
+                    prefix: String::from_utf8_lossy(prefix).to_string(),
+                    branches,
+                })
+                .collect(),
+        }
+    }
+
+    /// The share of the units of the double array holding a node.
+    pub fn fill_rate(&self) -> f64 {
+        if self.units == 0 {
+            0.0
+        } else {
+            self.nodes as f64 / self.units as f64
+        }
+    }
+
+    /// The size of the double array relative to the total length of the surfaces.
+    pub fn size_ratio(&self) -> f64 {
+        if self.key_bytes == 0 {
+            0.0
+        } else {
+            self.da_bytes as f64 / self.key_bytes as f64
+        }
+    }
+}
+
+impl IpadicNeologdBuilder {
+    /// Prints the statistics of the trie of the surfaces `keys`, in ascending byte order, and
+    /// of the double array written to `da_path`, if the options ask for them.
+    pub(crate) fn report_trie_stats<'a, I>(&self, keys: I, da_path: &Path) -> LinderaResult<()>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        if !self.options.trie_stats {
+            return Ok(());
+        }
+        let da_bytes = da_path
+            .metadata()
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?
+            .len();
+        let stats = TrieStats::from_sorted_keys(keys, da_bytes);
+        self.print(&format!(
+            "trie: {} keys of {} bytes, {} nodes in {} units ({:.1}% filled), {} bytes ({:.2}x the keys)",
+            stats.keys,
+            stats.key_bytes,
+            stats.nodes,
+            stats.units,
+            stats.fill_rate() * 100.0,
+            stats.da_bytes,
+            stats.size_ratio()
+        ));
+        self.print(&format!(
+            "longest key: {} ({} bytes)",
+            stats.longest_key, stats.longest_key_bytes
+        ));
+        for prefix in &stats.branching_prefixes {
+            self.print(&format!(
+                "{} branches after {:?}",
+                prefix.branches, prefix.prefix
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TrieStats;
+
+    #[test]
+    fn counts_the_nodes_and_branches_of_the_trie() {
+        let keys: [&[u8]; 4] = [b"a", b"ab", b"abc", b"b"];
+        let stats = TrieStats::from_sorted_keys(keys.iter().copied(), 64);
+        assert_eq!(stats.keys, 4);
+        assert_eq!(stats.key_bytes, 7);
+        // The root, a node per prefix (a, ab, abc, b) and a leaf per key.
+        assert_eq!(stats.nodes, 9);
+        assert_eq!(stats.units, 16);
+        assert_eq!(stats.fill_rate(), 9.0 / 16.0);
+        assert_eq!(stats.size_ratio(), 64.0 / 7.0);
+        assert_eq!(stats.longest_key, "abc");
+        assert_eq!(stats.longest_key_bytes, 3);
+        let branching: Vec<(&str, usize)> = stats
+            .branching_prefixes
+            .iter()
+            .map(|prefix| (prefix.prefix.as_str(), prefix.branches))
+            .collect();
+        assert_eq!(branching, vec![("", 2), ("a", 2), ("ab", 2)]);
+    }
+
+    #[test]
+    fn handles_an_empty_keyset() {
+        let stats = TrieStats::from_sorted_keys(Vec::new(), 0);
+        assert_eq!(stats.keys, 0);
+        assert_eq!(stats.nodes, 1);
+        assert_eq!(stats.fill_rate(), 0.0);
+        assert_eq!(stats.size_ratio(), 0.0);
+        assert!(stats.branching_prefixes.is_empty());
+    }
+}
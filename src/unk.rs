@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+/// An adjustment of the costs of the `unk.def` entries of a character category.
+/// `cost` replaces the stock cost, then `scale` multiplies it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnkCostAdjustment {
+    pub cost: Option<i32>,
+    pub scale: Option<f64>,
+}
+
+impl UnkCostAdjustment {
+    fn apply(&self, cost: i32) -> i32 {
+        let cost = self.cost.unwrap_or(cost);
+        let cost = match self.scale {
+            Some(scale) => (f64::from(cost) * scale).round() as i32,
+            None => cost,
+        };
+        cost.max(i32::from(i16::MIN)).min(i32::from(i16::MAX))
+    }
+}
+
+/// The cost of an `unk.def` entry adjusted by `BuilderOptions::unk_costs`, as built.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EffectiveUnkCost {
+    pub category: String,
+    pub left_id: u16,
+    pub right_id: u16,
+    /// The cost in `unk.def`.
+    pub cost: i32,
+    pub effective_cost: i32,
+}
+
+/// Checks that every `unk.def` line names a category of `categories` and has a valid
+/// left id, right id and cost, so that parsing it cannot fail on a short or malformed line.
+pub(crate) fn check_unk_lines(lines: &[&str], categories: &[String]) -> LinderaResult<()> {
+    for line in lines {
+        let fields: Vec<&str> = line.split(',').collect();
+        let line_error = |message: String| {
+            LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                "unk.def: {}: {:?}",
+                message,
+                line
+            ))
+        };
+        match fields.as_slice() {
+            [category, left_id, right_id, cost, ..] => {
+                if !categories.iter().any(|name| name == category) {
+                    return Err(line_error(format!("undefined category {}", category)));
+                }
+                if u16::from_str(left_id).is_err() || u16::from_str(right_id).is_err() {
+                    return Err(line_error("invalid context id".to_string()));
+                }
+                if i16::from_str(cost).is_err() {
+                    return Err(line_error("invalid cost".to_string()));
+                }
+            }
+            _ => {
+                return Err(line_error(format!(
+                    "expected at least 4 fields but found {}",
+                    fields.len()
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Applies the cost adjustments to the lines of `unk.def`, returning the adjusted lines and
+/// the effective costs of the adjusted entries. Adjustments of categories without entries
+/// are rejected.
+pub(crate) fn adjust_unk_costs(
+    lines: &[&str],
+    adjustments: &BTreeMap<String, UnkCostAdjustment>,
+) -> LinderaResult<(Vec<String>, Vec<EffectiveUnkCost>)> {
+    for category in adjustments.keys() {
+        if !lines
+            .iter()
+            .any(|line| line.split(',').next() == Some(category.as_str()))
+        {
+            return Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!(
+                "unk.def has no entry for category {}",
+                category
+            )));
+        }
+    }
+
+    let mut adjusted_lines = Vec::with_capacity(lines.len());
+    let mut effective_costs = Vec::new();
+    for line in lines {
+        let mut fields: Vec<String> = line.split(',').map(|field| field.to_string()).collect();
+        let adjustment = fields
+            .first()
+            .and_then(|category| adjustments.get(category.as_str()));
+        match (adjustment, fields.get(3)) {
+            (Some(adjustment), Some(cost)) => {
+                let cost = i32::from_str(cost).map_err(|_err| {
+                    LinderaErrorKind::Parse
+                        .with_error(anyhow::anyhow!("failed to parse word_cost of {}", line))
+                })?;
+                let context_id = |field: &String| {
+                    u16::from_str(field).map_err(|_err| {
+                        LinderaErrorKind::Parse
+                            .with_error(anyhow::anyhow!("failed to parse context id of {}", line))
+                    })
+                };
+                let effective_cost = adjustment.apply(cost);
+                effective_costs.push(EffectiveUnkCost {
+                    category: fields[0].clone(),
+                    left_id: context_id(&fields[1])?,
+                    right_id: context_id(&fields[2])?,
+                    cost,
+                    effective_cost,
+                });
+                fields[3] = effective_cost.to_string();
+                adjusted_lines.push(fields.join(","));
+            }
+            _ => adjusted_lines.push(line.to_string()),
+        }
+    }
+    Ok((adjusted_lines, effective_costs))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{adjust_unk_costs, check_unk_lines, EffectiveUnkCost, UnkCostAdjustment};
+
+    const LINES: [&str; 3] = [
+        "DEFAULT,5,5,4769,記号,一般,*,*,*,*,*",
+        "KANJI,1285,1285,11426,名詞,一般,*,*,*,*,*",
+        "KANJI,1293,1293,17290,名詞,固有名詞,地域,一般,*,*,*",
+    ];
+
+    #[test]
+    fn checks_the_fields_of_unk_lines() {
+        let categories = vec!["DEFAULT".to_string(), "KANJI".to_string()];
+        check_unk_lines(&LINES, &categories).unwrap();
+
+        let error = |line: &str| {
+            check_unk_lines(&[line], &categories)
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(error("ALPHA,1,1,100,名詞").contains("undefined category ALPHA"));
+        assert!(error("KANJI,-1,1,100,名詞").contains("invalid context id"));
+        assert!(error("KANJI,1,1,40000,名詞").contains("invalid cost"));
+        assert!(error("KANJI,1,1").contains("expected at least 4 fields but found 3"));
+    }
+
+    #[test]
+    fn adjusts_the_costs_of_categories() {
+        let mut adjustments = BTreeMap::new();
+        adjustments.insert(
+            "KANJI".to_string(),
+            UnkCostAdjustment {
+                cost: None,
+                scale: Some(2.0),
+            },
+        );
+        adjustments.insert(
+            "DEFAULT".to_string(),
+            UnkCostAdjustment {
+                cost: Some(1000),
+                scale: Some(0.5),
+            },
+        );
+        let (lines, effective_costs) = adjust_unk_costs(&LINES, &adjustments).unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                "DEFAULT,5,5,500,記号,一般,*,*,*,*,*",
+                "KANJI,1285,1285,22852,名詞,一般,*,*,*,*,*",
+                "KANJI,1293,1293,32767,名詞,固有名詞,地域,一般,*,*,*",
+            ]
+        );
+        assert_eq!(
+            effective_costs[2],
+            EffectiveUnkCost {
+                category: "KANJI".to_string(),
+                left_id: 1293,
+                right_id: 1293,
+                cost: 17290,
+                effective_cost: i32::from(i16::MAX),
+            }
+        );
+
+        let mut adjustments = BTreeMap::new();
+        adjustments.insert("ALPHA".to_string(), UnkCostAdjustment::default());
+        let err = adjust_unk_costs(&LINES, &adjustments).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("unk.def has no entry for category ALPHA"));
+    }
+}
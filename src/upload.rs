@@ -0,0 +1,199 @@
+//! Upload of the built dictionary to object storage.
+//!
+//! The files are uploaded once the build has written them to the output directory, which
+//! therefore needs the space of the whole dictionary: they are read back from disk in
+//! multipart chunks rather than streamed to the store as they are built.
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use object_store::path::Path as ObjectPath;
+use object_store::{parse_url_opts, ObjectStore, WriteMultipart};
+use url::Url;
+
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::LinderaResult;
+
+use crate::lock::LOCK_FILENAME;
+use crate::scratch::SCRATCH_DIR_PREFIX;
+
+/// Maximum number of parts uploaded concurrently per file.
+const MAX_CONCURRENCY: usize = 8;
+
+/// Uploads every file in the output directory and its subdirectories, such as `columns/`
+/// or the directories of a `DictionaryLayout`, but its lock file and scratch directories,
+/// to the object storage location given by `sink_url` (e.g. `s3://bucket/prefix`,
+/// `gs://bucket/prefix`, `az://container/prefix`), keeping their paths relative to the
+/// output directory. Credentials are taken from the usual `AWS_*`, `GOOGLE_*` and `AZURE_*`
+/// environment variables.
+pub fn upload_output(output_dir: &Path, sink_url: &str, print: &dyn Fn(&str)) -> LinderaResult<()> {
+    let url = Url::parse(sink_url)
+        .map_err(|err| LinderaErrorKind::Args.with_error(anyhow::anyhow!(err)))?;
+    let env_options = std::env::vars().map(|(key, value)| (key.to_ascii_lowercase(), value));
+    let (store, prefix) = parse_url_opts(&url, env_options)
+        .map_err(|err| LinderaErrorKind::Args.with_error(anyhow::anyhow!(err)))?;
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    runtime.block_on(upload_dir(store.as_ref(), &prefix, output_dir, print))
+}
+
+async fn upload_dir(
+    store: &dyn ObjectStore,
+    prefix: &ObjectPath,
+    output_dir: &Path,
+    print: &dyn Fn(&str),
+) -> LinderaResult<()> {
+    for (path, names) in output_files(output_dir)? {
+        let location = names.iter().fold(prefix.clone(), |location, name| {
+            location.child(name.as_str())
+        });
+        print(&format!("uploading {:?} to {}", path, location));
+        upload_file(store, &path, &location).await?;
+    }
+    Ok(())
+}
+
+/// The files of `output_dir` and its subdirectories, sorted by path, with the names of the
+/// components of their paths relative to `output_dir`.
+fn output_files(output_dir: &Path) -> LinderaResult<Vec<(PathBuf, Vec<String>)>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![(output_dir.to_path_buf(), Vec::new())];
+    while let Some((dir, parents)) = dirs.pop() {
+        let entries = fs::read_dir(&dir)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        for entry in entries {
+            let entry =
+                entry.map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            let filename = match entry.file_name().to_str() {
+                Some(filename) => filename.to_string(),
+                None => {
+                    return Err(
+                        LinderaErrorKind::Io.with_error(anyhow::anyhow!("failed to get filename"))
+                    )
+                }
+            };
+            if filename == LOCK_FILENAME || filename.starts_with(SCRATCH_DIR_PREFIX) {
+                continue;
+            }
+            let file_type = entry
+                .file_type()
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            let mut names: Vec<String> = parents.clone();
+            names.push(filename);
+            if file_type.is_dir() {
+                dirs.push((entry.path(), names));
+            } else if file_type.is_file() {
+                files.push((entry.path(), names));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+async fn upload_file(
+    store: &dyn ObjectStore,
+    path: &Path,
+    location: &ObjectPath,
+) -> LinderaResult<()> {
+    let mut file =
+        File::open(path).map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    let upload = store
+        .put_multipart(location)
+        .await
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    let mut writer = WriteMultipart::new(upload);
+
+    let mut buf = vec![0; 1 << 20];
+    loop {
+        let len = file
+            .read(&mut buf)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        if len == 0 {
+            break;
+        }
+        writer
+            .wait_for_capacity(MAX_CONCURRENCY)
+            .await
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        writer.write(&buf[..len]);
+    }
+    writer
+        .finish()
+        .await
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use object_store::memory::InMemory;
+    use object_store::path::Path as ObjectPath;
+    use object_store::ObjectStore;
+
+    use super::upload_dir;
+    use crate::lock::LOCK_FILENAME;
+    use crate::scratch::SCRATCH_DIR_PREFIX;
+
+    #[test]
+    fn uploads_subdirectories_with_relative_keys() {
+        let output_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-upload-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&output_dir);
+        fs::create_dir_all(output_dir.join("dict")).unwrap();
+        fs::create_dir_all(output_dir.join("columns")).unwrap();
+        fs::create_dir_all(output_dir.join(format!("{}-sort-1-0", SCRATCH_DIR_PREFIX))).unwrap();
+        fs::write(output_dir.join("metadata.json"), b"{}").unwrap();
+        fs::write(output_dir.join("dict").join("da.bin"), b"da").unwrap();
+        fs::write(output_dir.join("columns").join("pos.bin"), b"pos").unwrap();
+        fs::write(output_dir.join(LOCK_FILENAME), b"1").unwrap();
+
+        let store = InMemory::new();
+        let prefix = ObjectPath::from("dictionaries/neologd");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime
+            .block_on(upload_dir(&store, &prefix, &output_dir, &|_| {}))
+            .unwrap();
+
+        let listing = runtime
+            .block_on(store.list_with_delimiter(Some(&prefix)))
+            .unwrap();
+        let objects: Vec<String> = listing
+            .objects
+            .iter()
+            .map(|meta| meta.location.to_string())
+            .collect();
+        let mut dirs: Vec<String> = listing
+            .common_prefixes
+            .iter()
+            .map(|location| location.to_string())
+            .collect();
+        dirs.sort();
+        assert_eq!(objects, vec!["dictionaries/neologd/metadata.json"]);
+        assert_eq!(
+            dirs,
+            vec!["dictionaries/neologd/columns", "dictionaries/neologd/dict"]
+        );
+        let get = |location: &str| {
+            runtime.block_on(async { store.get(&ObjectPath::from(location)).await?.bytes().await })
+        };
+        assert_eq!(&get("dictionaries/neologd/dict/da.bin").unwrap()[..], b"da");
+        assert_eq!(
+            &get("dictionaries/neologd/columns/pos.bin").unwrap()[..],
+            b"pos"
+        );
+
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+}
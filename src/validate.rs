@@ -0,0 +1,327 @@
+use std::collections::{BTreeMap, HashSet};
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use lindera_core::character_definition::CharacterDefinitionsBuilder;
+use lindera_core::error::LinderaErrorKind;
+use lindera_core::unknown_dictionary::parse_unk;
+use lindera_core::LinderaResult;
+
+use crate::chardef::read_char_def;
+use crate::file_util::{decode_source, read_source_file, source_lines};
+use crate::lint::LintReport;
+use crate::packing::ValuePacking;
+use crate::{skip, CsvRow, IpadicNeologdBuilder, MAX_HOMONYMS};
+
+/// A problem found while validating the dictionary source files.
+#[derive(Debug, Clone)]
+pub struct ValidationIssue {
+    pub file: PathBuf,
+    /// 1-based line number, if the issue relates to a specific line.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// The result of `IpadicNeologdBuilder::validate`.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub csv_files: usize,
+    pub rows: usize,
+    pub surfaces: usize,
+    pub duplicate_rows: usize,
+    pub max_homonyms: usize,
+    pub issues: Vec<ValidationIssue>,
+    /// The findings of the lint rules, with `BuilderOptions::lint`. They do not make the
+    /// source invalid.
+    pub lint: Option<LintReport>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn add_issue(&mut self, file: &Path, line: Option<usize>, message: String) {
+        self.issues.push(ValidationIssue {
+            file: file.to_path_buf(),
+            line,
+            message,
+        });
+    }
+}
+
+impl IpadicNeologdBuilder {
+    /// Runs all parsing and cross-checks of the source files in `input_dir` without
+    /// building the double array or writing any output.
+    pub fn validate(&self, input_dir: &Path) -> LinderaResult<ValidationReport> {
+        let mut report = ValidationReport::default();
+        let normalizer = self.options.line_normalizer()?;
+
+        let char_def_path = input_dir.join("char.def");
+        let char_def = read_char_def(input_dir, self.options.char_overrides.as_deref())?;
+        let mut char_definitions_builder = CharacterDefinitionsBuilder::default();
+        let categories = match char_definitions_builder.parse(&char_def) {
+            Ok(()) => Some(char_definitions_builder.build()),
+            Err(err) => {
+                report.add_issue(&char_def_path, None, err.to_string());
+                None
+            }
+        };
+
+        let unk_data_path = input_dir.join("unk.def");
+        let unk_data = source_lines(&read_source_file(&unk_data_path)?)
+            .map(|(_, line)| line)
+            .collect::<Vec<_>>()
+            .join("\n");
+        if let Some(char_definitions) = &categories {
+            if let Err(err) = parse_unk(char_definitions.categories(), &unk_data) {
+                report.add_issue(&unk_data_path, None, err.to_string());
+            }
+        }
+
+        let matrix_data_path = input_dir.join("matrix.def");
+        let matrix_size = validate_matrix(&matrix_data_path, &mut report)?;
+
+        let mut seen_lines: HashSet<String> = HashSet::new();
+        let skip_rules = self.options.skip_rules();
+        let mut homonyms: BTreeMap<String, usize> = BTreeMap::new();
+        let mut lint_report = LintReport::default();
+        for (_, filename) in self.csv_sources(input_dir)? {
+            report.csv_files += 1;
+            lint_report.csv_files += 1;
+            let bytes = fs::read(&filename)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            let file_data = match decode_source(&filename, bytes) {
+                Ok(file_data) => file_data,
+                Err(err) => {
+                    report.add_issue(&filename, None, err.to_string());
+                    continue;
+                }
+            };
+
+            for (line_num, line) in self.csv_lines(&file_data) {
+                let line = normalizer.normalize(line).into_owned();
+                let row = match CsvRow::from_line(&line) {
+                    Ok(row) => row,
+                    Err(err) => {
+                        report.add_issue(&filename, Some(line_num), err.to_string());
+                        continue;
+                    }
+                };
+                report.rows += 1;
+                if self.options.lint {
+                    lint_report.rows += 1;
+                    self.lint_line(&filename, line_num, &line, &mut lint_report);
+                }
+
+                if let Some((forward_size, backward_size)) = matrix_size {
+                    if row.left_id >= backward_size {
+                        report.add_issue(
+                            &filename,
+                            Some(line_num),
+                            format!(
+                                "left_id {} is out of range [0, {})",
+                                row.left_id, backward_size
+                            ),
+                        );
+                    }
+                    if row.right_id >= forward_size {
+                        report.add_issue(
+                            &filename,
+                            Some(line_num),
+                            format!(
+                                "right_id {} is out of range [0, {})",
+                                row.right_id, forward_size
+                            ),
+                        );
+                    }
+                }
+
+                if skip::find_rule(&skip_rules, &row).is_none() {
+                    *homonyms.entry(row.surface_form.to_string()).or_insert(0) += 1;
+                }
+
+                if !seen_lines.insert(line.clone()) {
+                    report.duplicate_rows += 1;
+                }
+            }
+        }
+
+        if self.options.lint {
+            report.lint = Some(lint_report);
+        }
+        report.surfaces = homonyms.len();
+        for (surface, count) in homonyms {
+            report.max_homonyms = report.max_homonyms.max(count);
+            if count >= MAX_HOMONYMS && self.options.value_packing == ValuePacking::Compact {
+                report.add_issue(
+                    input_dir,
+                    None,
+                    format!(
+                        "{} has {} entries. Too many. [{}]",
+                        surface, count, MAX_HOMONYMS
+                    ),
+                );
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Checks the shape of matrix.def and returns its declared (forward, backward) sizes.
+fn validate_matrix(
+    matrix_data_path: &Path,
+    report: &mut ValidationReport,
+) -> LinderaResult<Option<(u32, u32)>> {
+    let matrix_data = read_source_file(matrix_data_path)?;
+    let mut lines = source_lines(&matrix_data);
+
+    let (header_line_num, header): (usize, Vec<u32>) = match lines.next() {
+        Some((line_num, line)) => match line.split_whitespace().map(u32::from_str).collect() {
+            Ok(header) => (line_num, header),
+            Err(err) => {
+                report.add_issue(matrix_data_path, Some(line_num), err.to_string());
+                return Ok(None);
+            }
+        },
+        None => {
+            report.add_issue(matrix_data_path, None, "empty matrix.def".to_string());
+            return Ok(None);
+        }
+    };
+    if header.len() != 2 {
+        report.add_issue(
+            matrix_data_path,
+            Some(header_line_num),
+            format!("expected 2 header fields but found {}", header.len()),
+        );
+        return Ok(None);
+    }
+    let (forward_size, backward_size) = (header[0], header[1]);
+
+    for (line_num, line) in lines {
+        let fields: Result<Vec<i32>, _> = line.split_whitespace().map(i32::from_str).collect();
+        match fields {
+            Ok(fields) if fields.len() == 3 => {
+                if fields[0] < 0
+                    || fields[0] as u32 >= forward_size
+                    || fields[1] < 0
+                    || fields[1] as u32 >= backward_size
+                {
+                    report.add_issue(
+                        matrix_data_path,
+                        Some(line_num),
+                        format!(
+                            "ids ({}, {}) are out of range ({}, {})",
+                            fields[0], fields[1], forward_size, backward_size
+                        ),
+                    );
+                }
+                if i16::try_from(fields[2]).is_err() {
+                    report.add_issue(
+                        matrix_data_path,
+                        Some(line_num),
+                        format!(
+                            "cost {} is out of the range {}..={}",
+                            fields[2],
+                            i16::MIN,
+                            i16::MAX
+                        ),
+                    );
+                }
+            }
+            Ok(fields) => report.add_issue(
+                matrix_data_path,
+                Some(line_num),
+                format!("expected 3 fields but found {}", fields.len()),
+            ),
+            Err(err) => report.add_issue(matrix_data_path, Some(line_num), err.to_string()),
+        }
+    }
+
+    Ok(Some((forward_size, backward_size)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::fixtures::{write_fixture, FIXTURE_CSV_FILENAME, FIXTURE_ROWS};
+    use crate::IpadicNeologdBuilder;
+
+    #[test]
+    fn validates_the_fixture() {
+        let input_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-validate-{}",
+            std::process::id()
+        ));
+        write_fixture(&input_dir).unwrap();
+
+        let report = IpadicNeologdBuilder::new().validate(&input_dir).unwrap();
+        assert!(report.is_valid(), "{:?}", report.issues);
+        assert_eq!(report.csv_files, 1);
+        assert_eq!(report.rows, FIXTURE_ROWS.len());
+        assert_eq!(report.surfaces, FIXTURE_ROWS.len());
+        assert_eq!(report.duplicate_rows, 0);
+        assert_eq!(report.max_homonyms, 1);
+        assert!(report.lint.is_none());
+        assert_eq!(fs::read_dir(&input_dir).unwrap().count(), 4);
+
+        fs::remove_dir_all(&input_dir).unwrap();
+    }
+
+    #[test]
+    fn reports_every_issue_with_its_line() {
+        let input_dir = std::env::temp_dir().join(format!(
+            "lindera-ipadic-neologd-validate-issues-{}",
+            std::process::id()
+        ));
+        write_fixture(&input_dir).unwrap();
+        fs::write(
+            input_dir.join("matrix.def"),
+            "3 3\n0 0 0\n3 0 100\n0 1 40000\n0 2\n",
+        )
+        .unwrap();
+        let mut csv = FIXTURE_ROWS.join("\n");
+        csv.push('\n');
+        csv.push_str(FIXTURE_ROWS[0]);
+        csv.push_str("\n渋谷,5,1,3000,名詞,固有名詞,地域,一般,*,*,渋谷,シブヤ,シブヤ\n渋谷,1\n");
+        fs::write(input_dir.join(FIXTURE_CSV_FILENAME), csv).unwrap();
+
+        let report = IpadicNeologdBuilder::new().validate(&input_dir).unwrap();
+        assert!(!report.is_valid());
+        let issues: Vec<(String, Option<usize>)> = report
+            .issues
+            .iter()
+            .map(|issue| {
+                (
+                    issue
+                        .file
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned(),
+                    issue.line,
+                )
+            })
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                ("matrix.def".to_string(), Some(3)),
+                ("matrix.def".to_string(), Some(4)),
+                ("matrix.def".to_string(), Some(5)),
+                (FIXTURE_CSV_FILENAME.to_string(), Some(7)),
+                (FIXTURE_CSV_FILENAME.to_string(), Some(8)),
+            ]
+        );
+        assert!(report.issues[3].message.contains("left_id 5"));
+        assert_eq!(report.rows, FIXTURE_ROWS.len() + 2);
+        assert_eq!(report.duplicate_rows, 1);
+
+        fs::remove_dir_all(&input_dir).unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use lindera_core::error::LinderaError;
+use lindera_core::LinderaResult;
+
+use crate::error::BuildError;
+use crate::file_util::{read_source_file, source_lines};
+use crate::normalize_line;
+
+/// A spelling variation: every occurrence of `from` in a surface is replaced with `to`,
+/// which may be empty to drop it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantRule {
+    pub from: String,
+    pub to: String,
+}
+
+/// Rules expanding the entries into the common spelling variations of their surface, such
+/// as ヴ to ブ or a dropped ー. The file lists one rule per line, the text to replace and its
+/// replacement separated by a tab; blank lines and lines starting with `#` are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct VariantRules {
+    pub rules: Vec<VariantRule>,
+}
+
+impl VariantRules {
+    pub fn load(path: &Path) -> LinderaResult<VariantRules> {
+        let content = read_source_file(path)?;
+        VariantRules::parse(&content)
+            .map_err(|err| LinderaError::from(err.at(&path.display().to_string(), None)))
+    }
+
+    pub fn parse(content: &str) -> Result<VariantRules, BuildError> {
+        let mut rules = Vec::new();
+        for (line_num, line) in source_lines(content) {
+            if line.starts_with('#') {
+                continue;
+            }
+            let (from, to) = line.split_once('\t').ok_or_else(|| BuildError::Parse {
+                file: None,
+                line: Some(line_num),
+                field: None,
+                message: format!("expected a tab-separated rule but found {:?}", line),
+            })?;
+            let from = normalize_line(from);
+            if from.is_empty() {
+                return Err(BuildError::Parse {
+                    file: None,
+                    line: Some(line_num),
+                    field: None,
+                    message: "the text to replace is empty".to_string(),
+                });
+            }
+            rules.push(VariantRule {
+                from,
+                to: normalize_line(to.trim_end()),
+            });
+        }
+        Ok(VariantRules { rules })
+    }
+
+    /// The distinct variant surfaces of `surface`, one per matching rule.
+    pub fn variants(&self, surface: &str) -> Vec<String> {
+        let mut variants: Vec<String> = Vec::new();
+        for rule in &self.rules {
+            if !surface.contains(rule.from.as_str()) {
+                continue;
+            }
+            let variant = surface.replace(rule.from.as_str(), &rule.to);
+            if !variant.is_empty() && variant != surface && !variants.contains(&variant) {
+                variants.push(variant);
+            }
+        }
+        variants
+    }
+}
+
+/// Generates the variant lines of the CSV entry `lines` whose variant surface has no entry in
+/// `surfaces`, sharing the details of the line they come from. Returns the index of that line
+/// with each variant line.
+pub(crate) fn variant_lines(
+    lines: &[String],
+    rules: &VariantRules,
+    surfaces: &HashSet<&str>,
+) -> Vec<(usize, String)> {
+    let mut generated = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        let (surface, rest) = match line.split_once(',') {
+            Some(fields) => fields,
+            None => continue,
+        };
+        for variant in rules.variants(surface) {
+            if !surfaces.contains(variant.as_str()) {
+                generated.push((idx, format!("{},{}", variant, rest)));
+            }
+        }
+    }
+    generated
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{variant_lines, VariantRule, VariantRules};
+
+    #[test]
+    fn parses_tab_separated_rules() {
+        let rules = VariantRules::parse("# spelling\r\nヴァ\tバ\r\n\nー\t\n").unwrap();
+        assert_eq!(
+            rules.rules,
+            vec![
+                VariantRule {
+                    from: "ヴァ".to_string(),
+                    to: "バ".to_string(),
+                },
+                VariantRule {
+                    from: "ー".to_string(),
+                    to: String::new(),
+                },
+            ]
+        );
+        assert_eq!(
+            VariantRules::parse("ヴァ\tバ\nヴィ ビ\n")
+                .unwrap_err()
+                .to_string(),
+            "expected a tab-separated rule but found \"ヴィ ビ\""
+        );
+        let err = VariantRules::parse("ヴァ\tバ\n\tビ\n").unwrap_err();
+        assert_eq!(
+            err.at("variants.tsv", None).to_string(),
+            "variants.tsv:2: the text to replace is empty"
+        );
+    }
+
+    #[test]
+    fn expands_entries_into_their_variants() {
+        let rules = VariantRules::parse("ヴァ\tバ\nー\t\nヴ\tブ\n").unwrap();
+        assert_eq!(
+            rules.variants("ヴァイオリン"),
+            vec!["バイオリン", "ブァイオリン"]
+        );
+        assert_eq!(rules.variants("サーバー"), vec!["サバ"]);
+        assert!(rules.variants("ー").is_empty());
+        assert!(rules.variants("東京").is_empty());
+
+        let lines = vec![
+            "サーバー,1,1,3000,名詞,一般,*,*,*,*,サーバー,サーバー,サーバー".to_string(),
+            "ヴァイオリン,1,1,3000,名詞,一般,*,*,*,*,ヴァイオリン,ヴァイオリン,ヴァイオリン"
+                .to_string(),
+        ];
+        let surfaces: HashSet<&str> = vec!["バイオリン"].into_iter().collect();
+        assert_eq!(
+            variant_lines(&lines, &rules, &surfaces),
+            vec![
+                (
+                    0,
+                    "サバ,1,1,3000,名詞,一般,*,*,*,*,サーバー,サーバー,サーバー".to_string()
+                ),
+                (
+                    1,
+                    "ブァイオリン,1,1,3000,名詞,一般,*,*,*,*,ヴァイオリン,ヴァイオリン,ヴァイオリン"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use lindera_core::error::{LinderaError, LinderaErrorKind};
+use lindera_core::LinderaResult;
+
+/// The category of a `BuildWarning`, used to configure how it is handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    DuplicateEntry,
+    ConflictingEntry,
+    TruncatedCost,
+    UnknownContextId,
+    NormalizationApplied,
+    MissingReading,
+    MixedSnapshots,
+    StalePatchOp,
+}
+
+impl WarningKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WarningKind::DuplicateEntry => "duplicate_entry",
+            WarningKind::ConflictingEntry => "conflicting_entry",
+            WarningKind::TruncatedCost => "truncated_cost",
+            WarningKind::UnknownContextId => "unknown_context_id",
+            WarningKind::NormalizationApplied => "normalization_applied",
+            WarningKind::MissingReading => "missing_reading",
+            WarningKind::MixedSnapshots => "mixed_snapshots",
+            WarningKind::StalePatchOp => "stale_patch_op",
+        }
+    }
+}
+
+impl FromStr for WarningKind {
+    type Err = LinderaError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "duplicate_entry" => Ok(WarningKind::DuplicateEntry),
+            "conflicting_entry" => Ok(WarningKind::ConflictingEntry),
+            "truncated_cost" => Ok(WarningKind::TruncatedCost),
+            "unknown_context_id" => Ok(WarningKind::UnknownContextId),
+            "normalization_applied" => Ok(WarningKind::NormalizationApplied),
+            "missing_reading" => Ok(WarningKind::MissingReading),
+            "mixed_snapshots" => Ok(WarningKind::MixedSnapshots),
+            "stale_patch_op" => Ok(WarningKind::StalePatchOp),
+            _ => Err(LinderaErrorKind::Args
+                .with_error(anyhow::anyhow!("unknown warning kind {}", value))),
+        }
+    }
+}
+
+/// How the warnings of a kind are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WarningAction {
+    /// The warnings are dropped.
+    Ignore,
+    /// The warnings are printed and collected.
+    #[default]
+    Warn,
+    /// The first warning fails the build.
+    Error,
+}
+
+impl FromStr for WarningAction {
+    type Err = LinderaError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "ignore" => Ok(WarningAction::Ignore),
+            "warn" => Ok(WarningAction::Warn),
+            "error" => Ok(WarningAction::Error),
+            _ => Err(LinderaErrorKind::Args
+                .with_error(anyhow::anyhow!("unknown warning action {}", value))),
+        }
+    }
+}
+
+/// A problem with the source entries that does not prevent building the dictionary.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BuildWarning {
+    /// An entry identical to a previous entry of the same surface.
+    DuplicateEntry { file: String, surface: String },
+    /// An entry identical to a previous entry of the same surface except for the word cost
+    /// or the reading, which makes the choice between them arbitrary.
+    ConflictingEntry {
+        file: String,
+        surface: String,
+        difference: String,
+    },
+    /// A word cost outside the `i16` range, clamped to it.
+    TruncatedCost {
+        file: String,
+        surface: String,
+        word_cost: i32,
+    },
+    /// A context id outside the sizes of `matrix.def`.
+    UnknownContextId {
+        file: String,
+        surface: String,
+        left_id: u32,
+        right_id: u32,
+    },
+    /// A line changed by the character normalization.
+    NormalizationApplied { file: String, line: String },
+    /// An entry whose reading could not be filled.
+    MissingReading { surface: String },
+    /// Seed files of different NEologd snapshots built together.
+    MixedSnapshots { oldest: String, newest: String },
+    /// A removal or cost change of a patch matching no entry, which usually means the patch
+    /// is stale.
+    StalePatchOp { patch: String, surface: String },
+}
+
+impl BuildWarning {
+    pub fn kind(&self) -> WarningKind {
+        match self {
+            BuildWarning::DuplicateEntry { .. } => WarningKind::DuplicateEntry,
+            BuildWarning::ConflictingEntry { .. } => WarningKind::ConflictingEntry,
+            BuildWarning::TruncatedCost { .. } => WarningKind::TruncatedCost,
+            BuildWarning::UnknownContextId { .. } => WarningKind::UnknownContextId,
+            BuildWarning::NormalizationApplied { .. } => WarningKind::NormalizationApplied,
+            BuildWarning::MissingReading { .. } => WarningKind::MissingReading,
+            BuildWarning::MixedSnapshots { .. } => WarningKind::MixedSnapshots,
+            BuildWarning::StalePatchOp { .. } => WarningKind::StalePatchOp,
+        }
+    }
+}
+
+impl fmt::Display for BuildWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildWarning::DuplicateEntry { file, surface } => {
+                write!(f, "{}: duplicate entry for {}", file, surface)
+            }
+            BuildWarning::ConflictingEntry {
+                file,
+                surface,
+                difference,
+            } => write!(
+                f,
+                "{}: conflicting entry for {}, {}",
+                file, surface, difference
+            ),
+            BuildWarning::TruncatedCost {
+                file,
+                surface,
+                word_cost,
+            } => write!(
+                f,
+                "{}: cost {} of {} is out of the range {}..={}",
+                file,
+                word_cost,
+                surface,
+                i16::MIN,
+                i16::MAX
+            ),
+            BuildWarning::UnknownContextId {
+                file,
+                surface,
+                left_id,
+                right_id,
+            } => write!(
+                f,
+                "{}: context ids ({}, {}) of {} are not in matrix.def",
+                file, left_id, right_id, surface
+            ),
+            BuildWarning::NormalizationApplied { file, line } => {
+                write!(f, "{}: normalized {}", file, line)
+            }
+            BuildWarning::MissingReading { surface } => {
+                write!(f, "no reading found for {}", surface)
+            }
+            BuildWarning::MixedSnapshots { oldest, newest } => write!(
+                f,
+                "seed files of NEologd snapshots from {} to {} are combined",
+                oldest, newest
+            ),
+            BuildWarning::StalePatchOp { patch, surface } => {
+                write!(f, "{}: no entry of {} to patch", patch, surface)
+            }
+        }
+    }
+}
+
+/// The warnings collected during a build, with the number reported of each kind.
+#[derive(Default)]
+pub(crate) struct WarningCollector {
+    warnings: Mutex<(Vec<BuildWarning>, BTreeMap<WarningKind, usize>)>,
+}
+
+impl WarningCollector {
+    /// Handles `warning` according to `action`. At most `limit` warnings of each kind are
+    /// printed with `print` and collected; the others are only counted. Returns whether it
+    /// was printed.
+    pub(crate) fn report(
+        &self,
+        warning: BuildWarning,
+        action: WarningAction,
+        limit: Option<usize>,
+        print: &dyn Fn(&str),
+    ) -> LinderaResult<bool> {
+        match action {
+            WarningAction::Ignore => return Ok(false),
+            WarningAction::Error => {
+                return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!("{}", warning)))
+            }
+            WarningAction::Warn => {}
+        }
+
+        let mut warnings = match self.warnings.lock() {
+            Ok(warnings) => warnings,
+            Err(_err) => return Ok(false),
+        };
+        let (collected, counts) = &mut *warnings;
+        let count = counts.entry(warning.kind()).or_insert(0);
+        *count += 1;
+        match limit {
+            Some(limit) if *count > limit => {
+                if *count == limit + 1 {
+                    print(&format!(
+                        "further {} warnings are not printed",
+                        warning.kind().name()
+                    ));
+                }
+                Ok(false)
+            }
+            _ => {
+                print(&warning.to_string());
+                collected.push(warning);
+                Ok(true)
+            }
+        }
+    }
+
+    pub(crate) fn warnings(&self) -> Vec<BuildWarning> {
+        self.warnings
+            .lock()
+            .map(|warnings| warnings.0.clone())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn counts(&self) -> BTreeMap<WarningKind, usize> {
+        self.warnings
+            .lock()
+            .map(|warnings| warnings.1.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::str::FromStr;
+
+    use super::{BuildWarning, WarningAction, WarningCollector, WarningKind};
+
+    fn duplicate(surface: &str) -> BuildWarning {
+        BuildWarning::DuplicateEntry {
+            file: "a.csv".to_string(),
+            surface: surface.to_string(),
+        }
+    }
+
+    #[test]
+    fn kinds_and_actions_parse_from_their_names() {
+        for kind in [
+            WarningKind::DuplicateEntry,
+            WarningKind::ConflictingEntry,
+            WarningKind::TruncatedCost,
+            WarningKind::UnknownContextId,
+            WarningKind::NormalizationApplied,
+            WarningKind::MissingReading,
+            WarningKind::MixedSnapshots,
+            WarningKind::StalePatchOp,
+        ] {
+            assert_eq!(WarningKind::from_str(kind.name()).unwrap(), kind);
+            assert_eq!(
+                serde_json::to_string(&kind).unwrap(),
+                format!("\"{}\"", kind.name())
+            );
+        }
+        assert!(WarningKind::from_str("duplicate").is_err());
+        assert_eq!(
+            WarningAction::from_str("error").unwrap(),
+            WarningAction::Error
+        );
+        assert!(WarningAction::from_str("Error").is_err());
+    }
+
+    #[test]
+    fn limits_the_warnings_printed_per_kind() {
+        let collector = WarningCollector::default();
+        let printed = RefCell::new(Vec::new());
+        let print = |message: &str| printed.borrow_mut().push(message.to_string());
+        for surface in ["東京", "大阪", "京都", "奈良"] {
+            collector
+                .report(duplicate(surface), WarningAction::Warn, Some(2), &print)
+                .unwrap();
+        }
+        let missing = BuildWarning::MissingReading {
+            surface: "渋谷".to_string(),
+        };
+        assert!(collector
+            .report(missing, WarningAction::Warn, Some(2), &print)
+            .unwrap());
+
+        assert_eq!(
+            printed.into_inner(),
+            vec![
+                "a.csv: duplicate entry for 東京",
+                "a.csv: duplicate entry for 大阪",
+                "further duplicate_entry warnings are not printed",
+                "no reading found for 渋谷",
+            ]
+        );
+        assert_eq!(collector.warnings().len(), 3);
+        let counts = collector.counts();
+        assert_eq!(counts[&WarningKind::DuplicateEntry], 4);
+        assert_eq!(counts[&WarningKind::MissingReading], 1);
+    }
+
+    #[test]
+    fn ignores_or_fails_on_warnings_by_action() {
+        let collector = WarningCollector::default();
+        assert!(!collector
+            .report(duplicate("東京"), WarningAction::Ignore, None, &|_| {})
+            .unwrap());
+        let err = collector
+            .report(duplicate("東京"), WarningAction::Error, None, &|_| {})
+            .unwrap_err();
+        assert!(err.to_string().contains("a.csv: duplicate entry for 東京"));
+        assert!(collector.warnings().is_empty());
+        assert!(collector.counts().is_empty());
+    }
+}